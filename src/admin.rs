@@ -0,0 +1,649 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use log::{info, warn, error, debug};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, Notify, Semaphore, mpsc};
+
+use crate::config::{Config, DisabledReason, UserSource, Users, WatchedUser};
+use crate::db::{TrackClaim, TrackDatabase};
+
+/// Shared state handed to every admin API handler
+#[derive(Clone)]
+pub struct AdminState {
+    pub config: Config,
+    pub db: Arc<Mutex<TrackDatabase>>,
+    pub users: Arc<Mutex<Users>>,
+    /// The API polls and archives go through
+    pub soundcloud_api: Arc<dyn crate::soundcloud_api::SoundCloudApi>,
+    /// Notified to request an immediate poll of all users
+    pub poll_trigger: Arc<Notify>,
+    /// When the watcher started, used for health checks before the first poll completes
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Result type returned by admin API handlers
+type ApiResult = Result<Json<Value>, (StatusCode, Json<Value>)>;
+
+/// Build a JSON error response
+fn api_error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": message.into() })))
+}
+
+/// Extra slack allowed on top of the poll interval before the watcher is considered stuck
+const HEALTH_STALE_GRACE_SECS: i64 = 600;
+
+/// How many submitted URLs can wait to be archived before new ones are rejected
+const SUBMIT_QUEUE_CAPACITY: usize = 100;
+
+/// State for the `/submit` endpoint
+#[derive(Clone)]
+struct SubmitState {
+    token: String,
+    queue: mpsc::Sender<String>,
+}
+
+/// Start the admin HTTP API in the background if it's enabled in config
+///
+/// The server is started when `admin_api_bind` is set. The unauthenticated
+/// `/healthz`, `/readyz` and `/metrics` endpoints are always served; the `/api` endpoints
+/// are only enabled when `admin_api_token` is also set, so an unauthenticated
+/// admin API is never exposed. The `/dashboard` web UI is served along with the `/api`
+/// endpoints. Likewise `/submit` is only enabled when `submit_token` is set.
+pub async fn start_admin_server(state: AdminState) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let bind = match &state.config.admin_api_bind {
+        Some(b) => b.clone(),
+        None => {
+            debug!("Admin API is disabled (admin_api_bind not set)");
+            return Ok(());
+        }
+    };
+
+    let listener = tokio::net::TcpListener::bind(&bind).await?;
+    let mut app = if state.config.admin_api_token.as_deref().unwrap_or("").is_empty() {
+        warn!("admin_api_token is not set, only health and metrics endpoints will be served on {}", bind);
+        health_router(state.clone())
+    } else {
+        health_router(state.clone())
+            .merge(build_router(state.clone()))
+            .merge(crate::dashboard::router(state.clone()))
+    };
+    if let Some(token) = state.config.submit_token.clone().filter(|t| !t.is_empty()) {
+        info!("Accepting track submissions on {}/submit", bind);
+        app = app.merge(submit_router(state, token));
+    }
+
+    info!("Admin API listening on {}", bind);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("Admin API server stopped: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Build the admin API router
+fn build_router(state: AdminState) -> Router {
+    Router::new()
+        .route("/api/poll", post(poll_all))
+        .route("/api/poll/{user_id}", post(poll_user))
+        .route("/api/users", get(list_users).post(add_user))
+        .route("/api/users/{user_id}", delete(remove_user))
+        .route("/api/users/{user_id}/reactivate", post(reactivate_user))
+        .route("/api/users/{user_id}/tracks", get(list_user_tracks))
+        .route("/api/archive", post(archive_url))
+        .route("/api/tracks", get(list_tracks))
+        .route("/api/tracks/{track_id}", get(get_track))
+        .route("/api/stats", get(stats))
+        .route("/api/db/flush", post(flush_db))
+        .route("/api/log-level", get(get_log_level).put(set_log_level))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Build the unauthenticated health check and metrics router
+fn health_router(state: AdminState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics))
+        .with_state(state)
+}
+
+/// Build the `/submit` router and start the worker that archives submitted tracks
+fn submit_router(state: AdminState, token: String) -> Router {
+    let (queue, receiver) = mpsc::channel(SUBMIT_QUEUE_CAPACITY);
+    tokio::spawn(process_submissions(state, receiver));
+
+    let submit_state = SubmitState { token, queue };
+    Router::new()
+        .route("/submit", post(submit))
+        .route_layer(middleware::from_fn_with_state(submit_state.clone(), require_submit_token))
+        .with_state(submit_state)
+}
+
+/// Collect the current health report
+///
+/// Returns the report along with whether the watcher is alive (polls are still
+/// completing) and ready (client ID valid and database writable).
+fn health_report(state: &AdminState) -> (Value, bool, bool) {
+    let now = chrono::Utc::now();
+    let last_poll = crate::metrics::last_poll_completed();
+    let client_id_valid = crate::soundcloud::client_id_valid();
+    let db_writable = TrackDatabase::is_path_writable(&state.config.tracks_file);
+
+    // Polls can legitimately take a while with large watchlists, so allow generous slack
+    let stale_after = 2 * state.config.poll_interval_sec as i64 + HEALTH_STALE_GRACE_SECS;
+    let since = last_poll.unwrap_or(state.started_at);
+    let alive = (now - since).num_seconds() <= stale_after;
+    let ready = client_id_valid && db_writable;
+
+    let report = json!({
+        "status": if alive && ready { "ok" } else { "degraded" },
+        "client_id_valid": client_id_valid,
+        "last_successful_poll": last_poll.map(|t| t.to_rfc3339()),
+        "seconds_since_last_poll": last_poll.map(|t| (now - t).num_seconds()),
+        "db_writable": db_writable,
+        "queue_depth": crate::metrics::queue_depth(),
+        "uptime_sec": (now - state.started_at).num_seconds(),
+    });
+
+    (report, alive, ready)
+}
+
+/// GET /healthz - liveness: fails if poll cycles have stopped completing
+async fn healthz(State(state): State<AdminState>) -> (StatusCode, Json<Value>) {
+    let (report, alive, _) = health_report(&state);
+    let status = if alive { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+
+/// GET /readyz - readiness: fails if the client ID is invalid or the database isn't writable
+async fn readyz(State(state): State<AdminState>) -> (StatusCode, Json<Value>) {
+    let (report, _, ready) = health_report(&state);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+
+/// GET /metrics - Prometheus metrics in the text exposition format
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        crate::metrics::render(),
+    )
+}
+
+/// Compare a token from a request with the configured one in constant time
+///
+/// Only the length can be told from how long the comparison takes, not how much of
+/// the token was right. An empty configured token never matches.
+pub(crate) fn token_matches(provided: &str, expected: &str) -> bool {
+    if expected.is_empty() || provided.len() != expected.len() {
+        return false;
+    }
+    provided.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Reject requests that don't carry the configured bearer token
+async fn require_token(State(state): State<AdminState>, request: Request, next: Next) -> Response {
+    let expected = state.config.admin_api_token.as_deref().unwrap_or("");
+    let provided = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if token_matches(token, expected) => next.run(request).await,
+        _ => {
+            warn!("Rejected unauthenticated admin API request to {}", request.uri().path());
+            api_error(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response()
+        }
+    }
+}
+
+/// Reject submissions that don't carry the submit token
+///
+/// The token can be sent as a bearer token or as a `token` query parameter, for clients
+/// like bookmarklets that can't set headers.
+async fn require_submit_token(
+    State(state): State<SubmitState>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next
+) -> Response {
+    let provided = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or(params.get("token").map(|t| t.as_str()));
+
+    match provided {
+        Some(token) if token_matches(token, &state.token) => next.run(request).await,
+        _ => {
+            warn!("Rejected unauthenticated track submission");
+            api_error(StatusCode::UNAUTHORIZED, "Missing or invalid submit token").into_response()
+        }
+    }
+}
+
+/// POST /submit - queue a track (ID or URL) to be archived
+///
+/// The body is either `{"url": "..."}` or the bare URL as plain text. Returns
+/// 202 as soon as the track is queued; it's archived in the background.
+async fn submit(State(state): State<SubmitState>, body: String) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let url = match serde_json::from_str::<ArchiveRequest>(&body) {
+        Ok(request) => request.url,
+        Err(_) => body.trim().to_string(),
+    };
+
+    let is_id = !url.is_empty() && url.chars().all(|c| c.is_ascii_digit());
+    let is_url = url.starts_with("http") && url.contains("soundcloud.com/");
+    if !is_id && !is_url {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Expected a SoundCloud track URL or track ID"));
+    }
+
+    match state.queue.try_send(url.clone()) {
+        Ok(()) => {
+            info!("Queued submitted track {}", url);
+            Ok((StatusCode::ACCEPTED, Json(json!({ "status": "queued", "url": url }))))
+        },
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            warn!("Submission queue is full, rejecting {}", url);
+            Err(api_error(StatusCode::SERVICE_UNAVAILABLE, "Submission queue is full, try again later"))
+        },
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            Err(api_error(StatusCode::SERVICE_UNAVAILABLE, "Submissions are not being processed"))
+        }
+    }
+}
+
+/// Archive submitted tracks one at a time, skipping ones that are already archived
+async fn process_submissions(state: AdminState, mut queue: mpsc::Receiver<String>) {
+    let post_processing = crate::soundcloud::PostProcessing::from_config(&state.config).with_api(state.soundcloud_api.clone());
+
+    while let Some(url) = queue.recv().await {
+        if crate::shutdown::is_requested() {
+            debug!("Shutdown requested, dropping {} queued submissions", queue.len() + 1);
+            break;
+        }
+
+        let (track_id, secret_token) = match state.soundcloud_api.resolve_track(&url).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                warn!("Failed to resolve submitted track {}: {}", url, e);
+                continue;
+            }
+        };
+        if post_processing.outbox.contains(&track_id) {
+            info!("Submitted track {} is already waiting in the Discord queue", track_id);
+            continue;
+        }
+        let Some(_claim) = state.db.lock().await.claim(&track_id) else {
+            info!("Submitted track {} is already archived or being processed", track_id);
+            continue;
+        };
+
+        let posted = match state.soundcloud_api.track_details(&track_id, secret_token.as_deref()).await {
+            Ok(track) => crate::soundcloud::post_track(
+                track,
+                &state.config.discord_webhook_url,
+                state.config.temp_dir.as_deref(),
+                None,
+                &post_processing,
+                None
+            ).await,
+            Err(e) => Err(e),
+        };
+        match posted {
+            Ok(posted) => {
+                let mut db = state.db.lock().await;
+                db.add_posted_track(&posted);
+                if let Err(e) = db.save() {
+                    warn!("Failed to save database after archiving submitted track {}: {}", track_id, e);
+                }
+                info!("Archived submitted track {}", track_id);
+            },
+            Err(e) if e.is::<crate::outbox::Queued>() => warn!("{}", e),
+            Err(e) if e.is::<crate::ignore::Ignored>() => {
+                if let Some(ignored) = e.downcast_ref::<crate::ignore::Ignored>() {
+                    state.db.lock().await.record_ignored(ignored);
+                }
+            },
+            Err(e) => {
+                error!("Failed to archive submitted track {}: {}", track_id, e);
+                post_processing.hooks.error(&e.to_string(), None, Some(&track_id));
+                state.db.lock().await.record_skip(&track_id, crate::db::SkipReason::Failed, &e.to_string(), None, None);
+            }
+        }
+    }
+}
+
+/// POST /api/poll - trigger an immediate poll of all users
+async fn poll_all(State(state): State<AdminState>) -> ApiResult {
+    info!("Admin API: immediate poll of all users requested");
+    state.poll_trigger.notify_one();
+    Ok(Json(json!({ "status": "poll requested" })))
+}
+
+/// POST /api/poll/{user_id} - poll a single user right now and wait for the result
+async fn poll_user(State(state): State<AdminState>, Path(user_id): Path<String>) -> ApiResult {
+    info!("Admin API: force-polling user {}", user_id);
+
+    let processing_semaphore = Arc::new(Semaphore::new(state.config.max_processing_parallelism));
+    let discord_semaphore = Arc::new(Semaphore::new(state.config.max_discord_parallelism));
+
+    let mut db = state.db.lock().await;
+    match db.poll_user(&state.soundcloud_api, &user_id, &state.config, &processing_semaphore, &discord_semaphore).await {
+        Ok(count) => Ok(Json(json!({ "user_id": user_id, "new_tracks": count }))),
+        Err(e) => {
+            error!("Admin API: failed to poll user {}: {}", user_id, e);
+            Err(api_error(StatusCode::BAD_GATEWAY, format!("Failed to poll user {}: {}", user_id, e)))
+        }
+    }
+}
+
+/// GET /api/users - list watched users
+async fn list_users(State(state): State<AdminState>) -> ApiResult {
+    let users = state.users.lock().await;
+    let db = state.db.lock().await;
+    let deactivated: Vec<&String> = crate::backoff::UserBackoff::deactivated_users(&db).into_iter()
+        .map(|(user_id, _)| user_id)
+        .filter(|user_id| users.contains(user_id))
+        .collect();
+    let disabled: BTreeMap<&String, DisabledReason> = users.users.iter()
+        .filter(|user| !user.enabled)
+        .map(|user| (&user.id, user.disabled_reason.unwrap_or(DisabledReason::Manual)))
+        .collect();
+    Ok(Json(json!({ "users": users.ids(), "entries": users.users, "deactivated": deactivated, "disabled": disabled })))
+}
+
+#[derive(Deserialize)]
+struct AddUserRequest {
+    /// User ID or profile URL
+    user: String,
+}
+
+/// POST /api/users - add a user (ID or profile URL) to the watch list
+async fn add_user(State(state): State<AdminState>, Json(request): Json<AddUserRequest>) -> ApiResult {
+    let user_id = match state.soundcloud_api.resolve_user_id(&request.user).await {
+        Ok(id) => id,
+        Err(e) => return Err(api_error(StatusCode::BAD_REQUEST, format!("Could not resolve user: {}", e))),
+    };
+
+    let mut users = state.users.lock().await;
+
+    // Merge any manual edits first so saving doesn't clobber them
+    if let Err(e) = users.reload_if_changed(&state.config.users_file) {
+        warn!("Failed to reload users file before adding user: {}", e);
+    }
+
+    if !users.add(WatchedUser::new(&user_id, UserSource::Manual, None)) {
+        return Ok(Json(json!({ "user_id": user_id, "added": false })));
+    }
+
+    if let Err(e) = users.save(&state.config.users_file) {
+        return Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save users file: {}", e)));
+    }
+
+    info!("Admin API: added user {} to watch list", user_id);
+    Ok(Json(json!({ "user_id": user_id, "added": true })))
+}
+
+/// DELETE /api/users/{user_id} - remove a user from the watch list
+async fn remove_user(State(state): State<AdminState>, Path(user_id): Path<String>) -> ApiResult {
+    let mut users = state.users.lock().await;
+
+    if let Err(e) = users.reload_if_changed(&state.config.users_file) {
+        warn!("Failed to reload users file before removing user: {}", e);
+    }
+
+    if !users.remove(&user_id) {
+        return Err(api_error(StatusCode::NOT_FOUND, format!("User {} is not being watched", user_id)));
+    }
+
+    if let Err(e) = users.save(&state.config.users_file) {
+        return Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save users file: {}", e)));
+    }
+
+    info!("Admin API: removed user {} from watch list", user_id);
+    Ok(Json(json!({ "user_id": user_id, "removed": true })))
+}
+
+/// POST /api/users/{user_id}/reactivate - resume polling a user that was deactivated
+async fn reactivate_user(State(state): State<AdminState>, Path(user_id): Path<String>) -> ApiResult {
+    let mut db = state.db.lock().await;
+    if !crate::backoff::UserBackoff::reactivate(&mut db, &user_id) {
+        return Err(api_error(StatusCode::NOT_FOUND, format!("User {} is not deactivated", user_id)));
+    }
+    if let Err(e) = db.save() {
+        return Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save database: {}", e)));
+    }
+
+    info!("Admin API: reactivated user {}", user_id);
+    Ok(Json(json!({ "user_id": user_id, "reactivated": true })))
+}
+
+#[derive(Deserialize)]
+struct ArchiveRequest {
+    /// Track ID or SoundCloud track URL
+    url: String,
+}
+
+/// POST /api/archive - archive and post a single track, recording it in the database
+async fn archive_url(State(state): State<AdminState>, Json(request): Json<ArchiveRequest>) -> ApiResult {
+    info!("Admin API: archiving {}", request.url);
+
+    let (track_id, secret_token) = match state.soundcloud_api.resolve_track(&request.url).await {
+        Ok(resolved) => resolved,
+        Err(e) => return Err(api_error(StatusCode::BAD_REQUEST, format!("Could not resolve track: {}", e))),
+    };
+    let db_path = state.db.lock().await.db_path.clone();
+    let Some(_claim) = TrackClaim::try_claim(&db_path, &track_id) else {
+        return Err(api_error(StatusCode::CONFLICT, format!("Track {} is already being processed", track_id)));
+    };
+
+    let posted = match state.soundcloud_api.track_details(&track_id, secret_token.as_deref()).await {
+        Ok(track) => crate::soundcloud::post_track(
+            track,
+            &state.config.discord_webhook_url,
+            state.config.temp_dir.as_deref(),
+            None,
+            &crate::soundcloud::PostProcessing::from_config(&state.config).with_api(state.soundcloud_api.clone()),
+            None
+        ).await,
+        Err(e) => Err(e),
+    };
+    let posted = match posted {
+        Ok(result) => result,
+        Err(e) if e.is::<crate::ignore::Ignored>() => {
+            if let Some(ignored) = e.downcast_ref::<crate::ignore::Ignored>() {
+                state.db.lock().await.record_ignored(ignored);
+            }
+            return Err(api_error(StatusCode::FORBIDDEN, e.to_string()));
+        },
+        Err(e) => {
+            error!("Admin API: failed to archive {}: {}", request.url, e);
+            return Err(api_error(StatusCode::BAD_GATEWAY, format!("Failed to archive {}: {}", request.url, e)));
+        }
+    };
+
+    let mut db = state.db.lock().await;
+    db.add_posted_track(&posted);
+    if let Err(e) = db.save() {
+        warn!("Admin API: failed to save database after archiving track {}: {}", posted.track_id, e);
+    }
+
+    Ok(Json(json!({
+        "track_id": posted.track_id,
+        "message_id": posted.response.message_id,
+        "channel_id": posted.response.channel_id,
+        "musicbrainz": posted.metadata,
+    })))
+}
+
+/// Default and maximum number of tracks returned by the track listing endpoints
+const DEFAULT_TRACK_LIMIT: usize = 100;
+const MAX_TRACK_LIMIT: usize = 1000;
+
+/// Everything the database knows about a track
+fn track_json(db: &TrackDatabase, track_id: &str) -> Value {
+    json!({
+        "track_id": track_id,
+        "details": db.archived_track(track_id),
+        "discord": db.get_discord_info(track_id),
+        "musicbrainz": db.track_metadata(track_id),
+        "stats": db.track_stats(track_id).last(),
+        "annotation": db.track_annotation(track_id),
+        "availability": db.track_availability(track_id),
+    })
+}
+
+/// Sort track IDs newest first: tracks with details by archive time, then the rest by ID
+fn sort_tracks_newest_first(db: &TrackDatabase, track_ids: &mut [String]) {
+    track_ids.sort_by_cached_key(|id| std::cmp::Reverse((
+        db.archived_track(id).map(|t| t.archived_at),
+        id.parse::<u64>().unwrap_or(0),
+    )));
+}
+
+#[derive(Deserialize)]
+struct TrackListQuery {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    /// Case-insensitive search in titles and usernames (only matches tracks with details),
+    /// or an exact operator tag
+    q: Option<String>,
+}
+
+/// Apply a listing query to track IDs and build the response
+fn track_list_response(db: &TrackDatabase, mut track_ids: Vec<String>, query: &TrackListQuery) -> Value {
+    if let Some(search) = query.q.as_deref().map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty()) {
+        track_ids.retain(|id| *id == search || db.archived_track(id).is_some_and(|t|
+            t.title.to_lowercase().contains(&search) || t.username.to_lowercase().contains(&search))
+            || db.track_annotation(id).is_some_and(|a| a.tags.iter().any(|tag| tag.to_lowercase() == search)));
+    }
+    sort_tracks_newest_first(db, &mut track_ids);
+
+    let limit = query.limit.unwrap_or(DEFAULT_TRACK_LIMIT).min(MAX_TRACK_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let tracks: Vec<Value> = track_ids.iter()
+        .skip(offset)
+        .take(limit)
+        .map(|id| track_json(db, id))
+        .collect();
+
+    json!({
+        "total": track_ids.len(),
+        "offset": offset,
+        "limit": limit,
+        "tracks": tracks,
+    })
+}
+
+/// GET /api/tracks - list archived tracks, newest first, with `limit`, `offset` and `q`
+async fn list_tracks(State(state): State<AdminState>, Query(query): Query<TrackListQuery>) -> ApiResult {
+    let db = state.db.lock().await;
+    let track_ids = db.get_all_tracks();
+    Ok(Json(track_list_response(&db, track_ids, &query)))
+}
+
+/// GET /api/tracks/{track_id} - look up a track in the database
+async fn get_track(State(state): State<AdminState>, Path(track_id): Path<String>) -> ApiResult {
+    let db = state.db.lock().await;
+    if !db.has_track(&track_id) {
+        return Err(api_error(StatusCode::NOT_FOUND, format!("Track {} is not in the database", track_id)));
+    }
+
+    let mut track = track_json(&db, &track_id);
+    track["stats_history"] = json!(db.track_stats(&track_id));
+    Ok(Json(track))
+}
+
+/// GET /api/users/{user_id}/tracks - tracks archived from a user, as uploader or while watching them
+async fn list_user_tracks(State(state): State<AdminState>, Path(user_id): Path<String>, Query(query): Query<TrackListQuery>) -> ApiResult {
+    let db = state.db.lock().await;
+    let mut track_ids = db.find_tracks_by_user(&user_id);
+    for (track_id, track) in db.archived_tracks() {
+        if track.user_id == user_id && !track_ids.contains(track_id) {
+            track_ids.push(track_id.clone());
+        }
+    }
+
+    Ok(Json(track_list_response(&db, track_ids, &query)))
+}
+
+/// GET /api/stats - archive totals and recent activity
+async fn stats(State(state): State<AdminState>) -> ApiResult {
+    let watched_users = state.users.lock().await.users.len();
+    let db = state.db.lock().await;
+    let now = chrono::Utc::now();
+    let archived_since = |hours: i64| db.archived_tracks().values()
+        .filter(|t| now - t.archived_at <= chrono::Duration::hours(hours))
+        .count();
+    let track_ids = db.get_all_tracks();
+    let with_discord = track_ids.iter()
+        .filter(|id| db.get_discord_info(id).is_some())
+        .count();
+    let musicbrainz_matched = track_ids.iter()
+        .filter(|id| db.track_metadata(id).is_some())
+        .count();
+
+    Ok(Json(json!({
+        "tracks": track_ids.len(),
+        "tracks_posted_to_discord": with_discord,
+        "tracks_with_details": db.archived_tracks().len(),
+        "tracks_matched_on_musicbrainz": musicbrainz_matched,
+        "archived_last_24h": archived_since(24),
+        "archived_last_7d": archived_since(24 * 7),
+        "watched_users": watched_users,
+        "paused_users": crate::backoff::UserBackoff::paused_users(&db).len(),
+        "last_successful_poll": crate::metrics::last_poll_completed().map(|t| t.to_rfc3339()),
+        "uptime_sec": (now - state.started_at).num_seconds(),
+    })))
+}
+
+/// POST /api/db/flush - save the database to disk now
+async fn flush_db(State(state): State<AdminState>) -> ApiResult {
+    let db = state.db.lock().await;
+    match db.save() {
+        Ok(()) => {
+            info!("Admin API: database flushed to disk");
+            Ok(Json(json!({ "status": "saved", "tracks": db.track_count() })))
+        },
+        Err(e) => Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save database: {}", e))),
+    }
+}
+
+/// GET /api/log-level - show the current console and file log filters
+async fn get_log_level() -> ApiResult {
+    match crate::loghandler::log_levels() {
+        Some((console, file)) => Ok(Json(json!({ "level": console, "file_level": file }))),
+        None => Err(api_error(StatusCode::SERVICE_UNAVAILABLE, "Logging is not initialized")),
+    }
+}
+
+#[derive(Deserialize)]
+struct LogLevelRequest {
+    /// trace, debug, info, warn or error
+    level: String,
+}
+
+/// PUT /api/log-level - change the log level without restarting
+async fn set_log_level(Json(request): Json<LogLevelRequest>) -> ApiResult {
+    if !crate::loghandler::is_valid_log_level(&request.level) {
+        return Err(api_error(StatusCode::BAD_REQUEST, format!("Invalid log level '{}', expected trace, debug, info, warn or error", request.level)));
+    }
+
+    info!("Admin API: setting log level to {}", request.level);
+    crate::loghandler::update_log_level(&request.level);
+    get_log_level().await
+}
@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use log::{info, warn, error, debug};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+use crate::db::{TrackClaim, TrackDatabase};
+use crate::soundcloud::{PostProcessing, Track, TrackUser};
+
+/// An album (or EP) a user published: a playlist SoundCloud marks with `is_album`
+#[derive(Debug, Clone)]
+pub struct Album {
+    pub id: String,
+    pub title: String,
+    pub permalink_url: String,
+    pub artwork_url: Option<String>,
+    /// "album", "ep", "single" or "compilation"
+    pub set_type: String,
+    pub user: TrackUser,
+    /// Track IDs in album order
+    pub track_ids: Vec<String>,
+}
+
+impl Album {
+    /// Read an album from a playlist in the API, or `None` if the playlist isn't an album
+    fn from_json(playlist: &Value) -> Option<Self> {
+        if !playlist.get("is_album").and_then(|a| a.as_bool()).unwrap_or(false) {
+            return None;
+        }
+        let str_field = |name: &str| playlist.get(name).and_then(|v| v.as_str()).map(|v| v.to_string());
+        // Only the first tracks are complete, the rest are just IDs, which is all that's needed
+        let track_ids = playlist.get("tracks")
+            .and_then(|t| t.as_array())
+            .map(|tracks| tracks.iter()
+                .filter_map(|t| t.get("id").and_then(|id| id.as_u64()))
+                .map(|id| id.to_string())
+                .collect())
+            .unwrap_or_default();
+        Some(Album {
+            id: playlist.get("id")?.as_u64()?.to_string(),
+            title: str_field("title").unwrap_or_else(|| "Untitled".to_string()),
+            permalink_url: str_field("permalink_url").unwrap_or_default(),
+            artwork_url: str_field("artwork_url")
+                .filter(|url| !url.is_empty())
+                .map(|url| crate::soundcloud::get_original_artwork_url(&url)),
+            set_type: str_field("set_type").filter(|t| !t.is_empty()).unwrap_or_else(|| "album".to_string()),
+            user: crate::soundcloud::parse_track_user(playlist),
+            track_ids,
+        })
+    }
+}
+
+/// An album recorded in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedAlbum {
+    pub title: String,
+    pub permalink_url: String,
+    pub track_ids: Vec<String>,
+    /// The album's post, or `None` if its tracks were all archived before it was seen
+    #[serde(default)]
+    pub discord: Option<crate::db::DiscordMessage>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Post a user's new albums as a whole if `archive_albums` is enabled
+///
+/// An album is new if it isn't in the database yet and has tracks that aren't either. Its
+/// tracks are downloaded one at a time and posted in a single post with the album's artwork
+/// and tracklist, spread over as many messages as the files need. Albums whose tracks were
+/// all archived before are only recorded.
+///
+/// Returns the IDs of all tracks in the user's albums that weren't archived before, so the
+/// caller doesn't post them one by one; tracks of an album that failed are tried again
+/// with the album on the next poll.
+pub async fn check_albums(
+    db: &mut TrackDatabase,
+    user_id: &str,
+    config: &Config,
+    post_processing: &PostProcessing,
+    processing_semaphore: &Arc<Semaphore>,
+    discord_semaphore: &Arc<Semaphore>
+) -> HashSet<String> {
+    let mut album_tracks = HashSet::new();
+    if !config.archive_albums {
+        return album_tracks;
+    }
+
+    let albums = match post_processing.api.user_albums(user_id).await {
+        Ok(albums) => albums.iter().filter_map(Album::from_json).collect::<Vec<_>>(),
+        Err(e) => {
+            warn!("Failed to fetch albums of user {}: {}", user_id, e);
+            return album_tracks;
+        }
+    };
+
+    for album in albums {
+        if db.has_album(&album.id) || album.track_ids.is_empty() {
+            continue;
+        }
+        let new_tracks: Vec<&String> = album.track_ids.iter().filter(|id| !db.has_track(id)).collect();
+        if new_tracks.is_empty() {
+            debug!("All tracks of album {} ({}) were archived before, only recording it", album.id, album.title);
+            db.add_album(&album.id, ArchivedAlbum {
+                title: album.title.clone(),
+                permalink_url: album.permalink_url.clone(),
+                track_ids: album.track_ids.clone(),
+                discord: None,
+                archived_at: Utc::now(),
+            }, &[]);
+            continue;
+        }
+        album_tracks.extend(new_tracks.into_iter().cloned());
+
+        let Some(_claim) = TrackClaim::try_claim(&db.db_path, &format!("album:{}", album.id)) else {
+            debug!("Album {} is already being processed elsewhere", album.id);
+            continue;
+        };
+        if crate::shutdown::is_requested() {
+            break;
+        }
+
+        info!("New {} by {}: {} ({} tracks)", album.set_type, album.user.username, album.title, album.track_ids.len());
+        match process_and_post_album(&album, config, post_processing, processing_semaphore, discord_semaphore).await {
+            Ok((message, tracks)) => {
+                db.add_album(&album.id, ArchivedAlbum {
+                    title: album.title.clone(),
+                    permalink_url: album.permalink_url.clone(),
+                    track_ids: album.track_ids.clone(),
+                    discord: Some(message),
+                    archived_at: Utc::now(),
+                }, &tracks);
+                // Albums only go to the main webhook, the other destinations get their tracks one by one
+                let destinations = post_processing.extra_webhook_names();
+                for track in &tracks {
+                    db.add_pending_destinations(&track.id, &destinations);
+                }
+                if let Err(e) = db.save() {
+                    error!("Failed to save database after posting album {}: {}", album.id, e);
+                }
+            },
+            Err(e) => {
+                error!("Failed to archive album {} ({}): {}", album.id, album.title, e);
+                post_processing.hooks.error(&e.to_string(), Some(user_id), None);
+            }
+        }
+    }
+    album_tracks
+}
+
+/// Download every track of an album and post them together
+async fn process_and_post_album(
+    album: &Album,
+    config: &Config,
+    post_processing: &PostProcessing,
+    processing_semaphore: &Arc<Semaphore>,
+    discord_semaphore: &Arc<Semaphore>
+) -> Result<(crate::db::DiscordMessage, Vec<Track>), Box<dyn std::error::Error + Send + Sync>> {
+    let mut tracks = Vec::new();
+    let mut files = Vec::new();
+    let mut temp_files = Vec::new();
+
+    let result = async {
+        for (i, track_id) in album.track_ids.iter().enumerate() {
+            let track = post_processing.api.track_details(track_id, None).await?;
+            // Ignored tracks are left out of the album post altogether
+            if let Err(ignored) = post_processing.ignore_list.check(&track) {
+                info!("{}, leaving it out of album {}", ignored, album.id);
+                continue;
+            }
+            let _permit = processing_semaphore.acquire().await?;
+            // Album posts only attach audio, so there's no point in drawing waveforms
+            match crate::audio::process_track_audio(
+                &track, config.temp_dir.as_deref(), post_processing.max_download_bytes, !post_processing.metadata_only, false, post_processing.replaygain
+            ).await {
+                Ok((audio_files, artwork, json, waveform)) => {
+                    // Only the preferred format of each track, numbered so they sort in album order
+                    if let Some((_, path)) = audio_files.first() {
+                        let extension = std::path::Path::new(path).extension()
+                            .map(|e| e.to_string_lossy().to_string())
+                            .unwrap_or_else(|| "mp3".to_string());
+                        let name = format!("{:02} - {}.{}", i + 1, crate::audio::sanitize_filename(&track.title), extension);
+                        files.push((path.clone(), name));
+                    }
+                    temp_files.extend(audio_files.into_iter().map(|(_, path)| (path, String::new())));
+                    temp_files.extend(artwork.into_iter().chain(json).chain(waveform).map(|path| (path, String::new())));
+                },
+                Err(e) => warn!("Failed to download track {} of album {}: {}", track_id, album.id, e),
+            }
+            tracks.push(track);
+        }
+
+        let content = post_processing.post_content.as_deref().map(|template| crate::discord::render_post_content(
+            template, &album.user.username, &album.title, &album.permalink_url));
+        let _permit = discord_semaphore.acquire().await?;
+        let response = crate::discord::send_album_post(
+            &config.discord_webhook_url, album, &tracks, &files, content.as_deref(), post_processing.metadata_only
+        ).await?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(crate::db::DiscordMessage {
+            id: response.message_id,
+            channel_id: response.channel_id,
+            destinations: Default::default(),
+            pending_destinations: Default::default(),
+            user_id: Some(album.user.id.clone()),
+        })
+    }.await;
+
+    for (path, _) in &temp_files {
+        if let Err(e) = crate::audio::delete_temp_file(path).await {
+            warn!("Failed to clean up temp file {}: {}", path, e);
+        }
+    }
+    result.map(|message| (message, tracks))
+}
@@ -0,0 +1,706 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use log::{info, warn, error, debug};
+use tokio::sync::{Mutex, Notify, Semaphore};
+
+use crate::autotune::ParallelismTuner;
+use crate::backoff::UserBackoff;
+use crate::config::{Config, Users};
+use crate::db::{TrackClaim, TrackDatabase};
+use crate::metrics::{increment_new_tracks, increment_error_count, increment_skipped_polls, record_poll_completed};
+use crate::supervisor::spawn_supervised;
+use crate::soundcloud::{PostProcessing, PostedTrack};
+use crate::soundcloud_api::{SoundCloudApi, SoundCloudClient};
+use crate::{audio, shutdown, soundcloud};
+
+/// Builder for an [`Archiver`]
+///
+/// Anything that isn't provided is loaded from the paths in the config, the same
+/// way the watcher does on startup.
+#[derive(Default)]
+pub struct ArchiverBuilder {
+    config: Option<Config>,
+    config_path: Option<String>,
+    users: Option<Users>,
+    database: Option<TrackDatabase>,
+    name: Option<String>,
+    soundcloud_tuner: Option<Arc<ParallelismTuner>>,
+    soundcloud_api: Option<Arc<dyn SoundCloudApi>>,
+}
+
+impl ArchiverBuilder {
+    /// Use an already loaded config instead of reading one from disk
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Load the config from this path (defaults to `config.json`)
+    pub fn config_path(mut self, path: impl Into<String>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Use this users list instead of loading `users_file`
+    pub fn users(mut self, users: Users) -> Self {
+        self.users = Some(users);
+        self
+    }
+
+    /// Use this tracks database instead of loading `tracks_file`
+    pub fn database(mut self, database: TrackDatabase) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Name used to tell this archiver's log lines apart when running several profiles
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Limit SoundCloud polls with a parallelism limit shared with other archivers
+    ///
+    /// Without this, each archiver polls up to `max_soundcloud_parallelism` users at once
+    /// on its own, so several archivers in one process would multiply the request rate.
+    pub fn soundcloud_tuner(mut self, tuner: Arc<ParallelismTuner>) -> Self {
+        self.soundcloud_tuner = Some(tuner);
+        self
+    }
+
+    /// Fetch users and tracks through this API instead of the live SoundCloud API
+    ///
+    /// Mostly useful to run polls against a [`crate::soundcloud_api::FixtureApi`].
+    pub fn soundcloud_api(mut self, api: Arc<dyn SoundCloudApi>) -> Self {
+        self.soundcloud_api = Some(api);
+        self
+    }
+
+    /// Load anything that wasn't provided and initialize the SoundCloud client
+    pub async fn build(self) -> Result<Archiver, Box<dyn std::error::Error + Send + Sync>> {
+        let config = match self.config {
+            Some(c) => c,
+            None => {
+                let path = self.config_path.as_deref().unwrap_or("config.json");
+                info!("Loading configuration from {}", path);
+                Config::load(path)?
+            }
+        };
+        Config::set_show_ffmpeg_output(config.show_ffmpeg_output);
+        crate::locale::init(config.locale_file.as_deref())?;
+
+        let users = match self.users {
+            Some(u) => u,
+            None => {
+                info!("Loading users from {}", config.users_file);
+                Users::load(&config.users_file)?
+            }
+        };
+        if users.users.is_empty() {
+            warn!("No users found in {}. Add some users to the file and restart!", config.users_file);
+        } else {
+            debug!("Loaded users: {:?}", users.ids());
+        }
+
+        if !audio::check_ffmpeg() {
+            warn!("ffmpeg not found in PATH, audio transcoding will not work!");
+            warn!("Please install ffmpeg and make sure it's in your PATH");
+        } else {
+            info!("ffmpeg found in PATH");
+        }
+
+        info!("Initializing SoundCloud client");
+        let soundcloud_api = self.soundcloud_api.unwrap_or_else(|| Arc::new(SoundCloudClient));
+        let database = match self.database {
+            Some(d) => {
+                soundcloud_api.initialize().await?;
+                d
+            },
+            None => {
+                // A large database takes a while to parse, so the client is set up meanwhile
+                info!("Initializing tracks database");
+                let tracks_file = config.tracks_file.clone();
+                let loading = tokio::task::spawn_blocking(move || TrackDatabase::load_or_create(tracks_file));
+                let (loaded, initialized) = tokio::join!(loading, soundcloud_api.initialize());
+                let d = loaded??;
+                info!("Tracks database initialized from {} with {} tracks", d.db_path, d.track_count());
+                initialized?;
+                d
+            }
+        };
+        info!("SoundCloud client initialized successfully");
+
+        let mut users = users;
+        users.resolve_entries(soundcloud_api.as_ref(), &config.users_file).await;
+
+        Ok(Archiver {
+            name: self.name,
+            soundcloud_tuner: self.soundcloud_tuner
+                .unwrap_or_else(|| Arc::new(ParallelismTuner::new(&config))),
+            post_processing: PostProcessing::from_config(&config).with_api(soundcloud_api.clone()),
+            soundcloud_api,
+            backoff: Mutex::new(UserBackoff::new(&config)),
+            discord_semaphore: Arc::new(Semaphore::new(config.max_discord_parallelism)),
+            warmup_done: AtomicBool::new(false),
+            db: Arc::new(Mutex::new(database)),
+            users: Arc::new(Mutex::new(users)),
+            poll_trigger: Arc::new(Notify::new()),
+            started_at: Utc::now(),
+            config,
+        })
+    }
+}
+
+/// Outcome of a single poll of all watched users
+#[derive(Debug, Clone, Default)]
+pub struct PollSummary {
+    /// Users that were polled (paused users aren't included)
+    pub users_polled: usize,
+    /// Users whose poll failed or panicked
+    pub users_failed: usize,
+    /// New tracks archived across all users
+    pub new_tracks: usize,
+}
+
+/// The archiver: watches users, archives their new tracks and posts them to Discord
+///
+/// Owns the config, users list, tracks database and user backoff state. The
+/// watcher binary is a thin wrapper around [`Archiver::run`]; embedders can
+/// also drive it one step at a time with [`Archiver::poll_once`] and
+/// [`Archiver::archive_track`].
+pub struct Archiver {
+    name: Option<String>,
+    config: Config,
+    db: Arc<Mutex<TrackDatabase>>,
+    users: Arc<Mutex<Users>>,
+    poll_trigger: Arc<Notify>,
+    started_at: DateTime<Utc>,
+    backoff: Mutex<UserBackoff>,
+    // Shared by one-off archives so they respect max_discord_parallelism
+    discord_semaphore: Arc<Semaphore>,
+    // Limits concurrent user polls, possibly across several archivers
+    soundcloud_tuner: Arc<ParallelismTuner>,
+    soundcloud_api: Arc<dyn SoundCloudApi>,
+    post_processing: PostProcessing,
+    // Set once the first poll has started, which is spread over startup_warmup_sec
+    warmup_done: AtomicBool,
+}
+
+impl Archiver {
+    pub fn builder() -> ArchiverBuilder {
+        ArchiverBuilder::default()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The profile name given to the builder, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Post a deactivated user to `alert_webhook_url`, if set, with how to reactivate them
+    async fn alert_user_deactivated(&self, user_id: &str) {
+        let Some(webhook_url) = self.config.alert_webhook_url.as_deref().filter(|u| !u.is_empty()) else {
+            return;
+        };
+        let username = self.users.lock().await.users.iter()
+            .find(|u| u.id == user_id)
+            .and_then(|u| u.username.clone());
+        let user = match username {
+            Some(username) => format!("**{}** ({})", username, user_id),
+            None => format!("**{}**", user_id),
+        };
+        let mut description = format!(
+            "SoundCloud has reported user {} missing or hidden for {} polls in a row, so they're no longer polled. \
+             Everything archived from them stays in the archive.\n\nIf the account comes back, resume polling it with \
+             `POST /api/users/{}/reactivate` on the admin API.",
+            user, self.config.user_deactivation_threshold, user_id
+        );
+        if self.config.admin_api_bind.is_none() {
+            description.push_str(" The admin API is off, set `admin_api_bind` to enable it.");
+        }
+        if let Err(e) = crate::discord::send_user_deactivated_notice(webhook_url, &description).await {
+            warn!("Failed to post the deactivation of user {} to the alert webhook: {}", user_id, e);
+        }
+    }
+
+    // Prefix for log lines that should say which profile they're about
+    fn log_prefix(&self) -> String {
+        self.name.as_ref().map(|n| format!("[{}] ", n)).unwrap_or_default()
+    }
+
+    /// Shared handle to the tracks database
+    pub fn database(&self) -> Arc<Mutex<TrackDatabase>> {
+        self.db.clone()
+    }
+
+    /// Shared handle to the watched users list
+    pub fn users(&self) -> Arc<Mutex<Users>> {
+        self.users.clone()
+    }
+
+    /// The API users and tracks are fetched through
+    pub fn soundcloud_api(&self) -> Arc<dyn SoundCloudApi> {
+        self.soundcloud_api.clone()
+    }
+
+    /// Handle that can be notified to make [`Archiver::run`] poll immediately
+    pub fn poll_trigger(&self) -> Arc<Notify> {
+        self.poll_trigger.clone()
+    }
+
+    /// When the archiver was built
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Archive a single track (ID or URL), post it to Discord and record it in the database
+    ///
+    /// Returns the track ID, the uploader's user ID, the Discord webhook response and any
+    /// MusicBrainz metadata found.
+    pub async fn archive_track(&self, id_or_url: &str) -> Result<PostedTrack, Box<dyn std::error::Error + Send + Sync>> {
+        let (track_id, secret_token) = self.soundcloud_api.resolve_track(id_or_url).await?;
+        let db_path = self.db.lock().await.db_path.clone();
+        let _claim = TrackClaim::try_claim(&db_path, &track_id)
+            .ok_or_else(|| format!("Track {} is already being processed", track_id))?;
+
+        let track = self.soundcloud_api.track_details(&track_id, secret_token.as_deref()).await?;
+        let posted = soundcloud::post_track(
+            track,
+            &self.config.discord_webhook_url,
+            self.config.temp_dir.as_deref(),
+            Some(&self.discord_semaphore),
+            &self.post_processing,
+            None
+        ).await;
+        let posted = match posted {
+            Ok(posted) => posted,
+            Err(e) => {
+                if let Some(ignored) = e.downcast_ref::<crate::ignore::Ignored>() {
+                    self.db.lock().await.record_ignored(ignored);
+                }
+                return Err(e);
+            }
+        };
+
+        let mut db = self.db.lock().await;
+        db.add_posted_track(&posted);
+        if let Err(e) = db.save() {
+            warn!("Failed to save database after archiving track {}: {}", posted.track_id, e);
+        }
+
+        Ok(posted)
+    }
+
+    /// Poll a single user right now, returning the number of new tracks
+    ///
+    /// This ignores any backoff pause and doesn't count towards it.
+    pub async fn poll_user(&self, user_id: &str) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        poll_user(&self.soundcloud_api, &self.config, user_id, &self.db).await
+    }
+
+    /// Poll all watched users once
+    ///
+    /// Posts tracks queued while Discord was unavailable, picks up edits to the users
+    /// file, skips paused users and polls the rest in batches of the SoundCloud
+    /// parallelism limit, adjusting the limit after each batch when autotuning is
+    /// enabled. Stops starting new users once a shutdown is requested, and abandons
+    /// in-flight polls after the drain timeout.
+    ///
+    /// With `startup_warmup_sec` set, the batches of the first poll are paced evenly
+    /// over that window instead of being started back to back.
+    pub async fn poll_once(&self) -> PollSummary {
+        let poll_started = Instant::now();
+
+        // Post anything that was queued while Discord was unavailable
+        self.post_processing.outbox.flush(&self.db, &self.post_processing).await;
+        // Catch the extra destinations up on tracks they're still missing
+        crate::destinations::retry_pending_destinations(&self.db, &self.post_processing).await;
+
+        let drain_timeout = Duration::from_secs(self.config.shutdown_drain_timeout_sec);
+        let mut backoff = self.backoff.lock().await;
+        let mut summary = PollSummary::default();
+
+        // Pick up manual edits to the users file without a restart
+        let (users_vec, playlists, file_overrides) = {
+            let mut users_guard = self.users.lock().await;
+            match users_guard.reload_if_changed(&self.config.users_file) {
+                Ok(true) => {
+                    users_guard.resolve_entries(self.soundcloud_api.as_ref(), &self.config.users_file).await;
+                },
+                Ok(false) => {},
+                Err(e) => warn!("Failed to check users file {} for changes: {}", self.config.users_file, e),
+            }
+            (users_guard.active(), users_guard.playlists.clone(), users_guard.overrides())
+        };
+
+        // Skip users that are paused after repeated failures
+        let users_vec: Vec<String> = {
+            let db_guard = self.db.lock().await;
+            users_vec.into_iter()
+                .filter(|user_id| backoff.should_poll(&db_guard, user_id))
+                .collect()
+        };
+
+        // Spread the first poll after startup out instead of bursting every user at once
+        let warmup = (!self.warmup_done.swap(true, Ordering::SeqCst) && self.config.startup_warmup_sec > 0)
+            .then(|| Duration::from_secs(self.config.startup_warmup_sec));
+        if let Some(window) = warmup {
+            info!("{}Warming up: spreading the first poll of {} users over {} seconds",
+                  self.log_prefix(), users_vec.len(), window.as_secs());
+        }
+
+        // Process users in batches with SoundCloud parallelism limit
+        let mut users_processed = 0;
+        while users_processed < users_vec.len() {
+            // Start each warm-up batch at its share of the window
+            if let (Some(window), true) = (warmup, users_processed > 0) {
+                let due = window.mul_f64(users_processed as f64 / users_vec.len() as f64);
+                let wait = due.saturating_sub(poll_started.elapsed());
+                if !wait.is_zero() {
+                    debug!("Warm-up: waiting {:.1}s before polling the next {} users", wait.as_secs_f64(),
+                           self.soundcloud_tuner.limit().min(users_vec.len() - users_processed));
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {},
+                        _ = shutdown::requested() => {},
+                    }
+                }
+            }
+
+            // Don't start polling any more users once a shutdown is requested
+            if shutdown::is_requested() {
+                info!("Shutdown requested, skipping the remaining {} users in this poll",
+                      users_vec.len() - users_processed);
+                break;
+            }
+
+            let batch_size = std::cmp::min(self.soundcloud_tuner.limit(), users_vec.len() - users_processed);
+            let batch = &users_vec[users_processed..users_processed + batch_size];
+
+            let mut tasks = Vec::new();
+
+            // Create tasks for each user in the batch
+            for user_id in batch {
+                let mut config = self.config.clone();
+                // Overrides in the users file take precedence over those in the config
+                if let Some(overrides) = file_overrides.get(user_id) {
+                    config.user_overrides.entry(user_id.clone()).or_default().merge(overrides);
+                }
+                let user_id = user_id.clone();
+                let db = self.db.clone();
+                let api = self.soundcloud_api.clone();
+                let semaphore = self.soundcloud_tuner.semaphore();
+
+                let task = spawn_supervised(format!("poll user {}", user_id), {
+                    let user_id = user_id.clone();
+                    async move {
+                        let _permit = semaphore.acquire_owned().await?;
+                        match poll_user(&api, &config, &user_id, &db).await {
+                            Ok(count) => {
+                                increment_new_tracks(count as u64);
+                                Ok(count)
+                            },
+                            Err(e) => {
+                                error!("Error polling user {}: {}", user_id, e);
+                                increment_error_count();
+                                Err(e)
+                            }
+                        }
+                    }
+                });
+
+                tasks.push((user_id, task));
+            }
+
+            // Wait for all tasks in the batch to complete, giving up on them if a
+            // shutdown was requested and the drain timeout has passed
+            let mut drain_timed_out = false;
+            for (user_id, task) in tasks {
+                if drain_timed_out {
+                    task.abort();
+                    continue;
+                }
+
+                let abort_handle = task.abort_handle();
+                let result = tokio::select! {
+                    result = task.join() => result,
+                    _ = shutdown::drain_expired(drain_timeout) => {
+                        abort_handle.abort();
+                        warn!("Drain timeout of {} seconds reached, abandoning in-flight poll of user {}",
+                              drain_timeout.as_secs(), user_id);
+                        drain_timed_out = true;
+                        continue;
+                    }
+                };
+
+                summary.users_polled += 1;
+                match result {
+                    Ok(Ok(count)) => {
+                        backoff.record_success(&mut *self.db.lock().await, &user_id);
+                        summary.new_tracks += count;
+                    },
+                    Ok(Err(e)) => {
+                        // Error already logged in poll_user
+                        let deactivated = {
+                            let mut db = self.db.lock().await;
+                            backoff.record_failure(&mut db, &user_id, &e.to_string());
+                            self.post_processing.hooks.error(&e.to_string(), Some(&user_id), None);
+                            e.is::<crate::soundcloud::UserUnavailable>() && backoff.record_unavailable(&mut db, &user_id)
+                        };
+                        if deactivated {
+                            // Their archived tracks are all that's left of the catalog now
+                            let message = format!("User {} looks deactivated on SoundCloud, no longer polling them until they're reactivated", user_id);
+                            error!("{}", message);
+                            self.post_processing.hooks.error(&message, Some(&user_id), None);
+                            self.alert_user_deactivated(&user_id).await;
+                        }
+                        summary.users_failed += 1;
+                    },
+                    Err(failure) => {
+                        // Panic already logged by the supervisor, count it against the user
+                        backoff.record_failure(&mut *self.db.lock().await, &user_id, &failure.to_string());
+                        self.post_processing.hooks.error(&failure.to_string(), Some(&user_id), None);
+                        summary.users_failed += 1;
+                    }
+                }
+            }
+
+            users_processed += batch_size;
+            self.soundcloud_tuner.adjust();
+        }
+
+        // Post the tracks added to watched playlists
+        if !playlists.is_empty() && !shutdown::is_requested() {
+            let count = crate::playlists::poll_playlists(&self.db, &playlists, &self.config, &self.post_processing).await;
+            increment_new_tracks(count as u64);
+            summary.new_tracks += count;
+        }
+
+        record_poll_completed(poll_started.elapsed());
+        backoff.log_summary(&*self.db.lock().await);
+        drop(backoff);
+
+        // Re-check a few archived tracks for deletion
+        if !shutdown::is_requested() {
+            crate::deletions::check_deleted_tracks(&self.db, self.soundcloud_api.as_ref(), &self.config).await;
+        }
+
+        summary
+    }
+
+    /// Check the auto-follow source for new followings and add them to the watch list
+    ///
+    /// Returns the number of users added. Does nothing if `auto_follow_source` isn't set.
+    /// If a user is unfollowed by the source, they remain in the users list, disabled if
+    /// `auto_follow_remove` is set.
+    pub async fn update_followings(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let source = match &self.config.auto_follow_source {
+            Some(s) => s,
+            None => {
+                debug!("No auto-follow source configured, skipping followings update");
+                return Ok(0);
+            }
+        };
+
+        self.users.lock().await.update_followings_from_source(
+            self.soundcloud_api.as_ref(), source, &self.config.users_file, self.config.auto_follow_remove
+        ).await
+    }
+
+    /// Poll all users on the configured interval until a shutdown is requested
+    ///
+    /// Also checks for new followings every `auto_follow_interval` polls, saves the
+    /// database according to `db_save_tracks` / `db_save_interval`, and saves it
+    /// once more before returning. Notify [`Archiver::poll_trigger`] to poll early,
+    /// and call [`shutdown::request`] to stop.
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let config = &self.config;
+
+        // If auto-follow is enabled, check for new followings on startup
+        if config.auto_follow_source.is_some() {
+            info!("Auto-follow is enabled, checking for new followings on startup");
+            match self.update_followings().await {
+                Ok(count) => {
+                    if count > 0 {
+                        info!("Added {} new users to watch from auto-follow source during startup", count);
+                    } else {
+                        info!("No new followings found from auto-follow source during startup");
+                    }
+                },
+                Err(e) => {
+                    warn!("Failed to update followings from source during startup: {}", e);
+                }
+            }
+        }
+
+        // Create scheduler interval
+        //
+        // Polls fire at a fixed cadence measured from startup. If a poll overruns the
+        // interval, the missed ticks are skipped (and logged) rather than fired back to
+        // back, so the schedule doesn't drift and doesn't burst after a slow poll.
+        let poll_interval = Duration::from_secs(config.poll_interval_sec);
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        // Start main polling loop
+        info!("{}Starting polling loop with interval of {} seconds", self.log_prefix(), config.poll_interval_sec);
+
+        // Initialize counters
+        let mut total_polls = 0;
+        let mut follow_check_counter = 0;
+        let mut db_save_counter = 0;
+        let mut tracks_since_last_save = 0;
+        let mut db_needs_saving = false;
+
+        // Main polling loop
+        loop {
+            // Wait for either the next tick or a shutdown signal
+            let should_shutdown = tokio::select! {
+                scheduled = interval.tick() => {
+                    // Account for any ticks that were skipped because the last poll overran
+                    let behind = tokio::time::Instant::now().saturating_duration_since(scheduled);
+                    let skipped = (behind.as_secs_f64() / poll_interval.as_secs_f64()) as u64;
+                    if skipped > 0 {
+                        warn!("Previous poll overran the {} second interval, skipped {} scheduled poll(s)",
+                              config.poll_interval_sec, skipped);
+                        increment_skipped_polls(skipped);
+                    }
+                    false
+                },
+                _ = self.poll_trigger.notified() => {
+                    info!("Immediate poll requested");
+                    false
+                },
+                _ = shutdown::requested() => true,
+            };
+
+            if should_shutdown {
+                info!("Shutdown signal received, performing clean shutdown");
+
+                // Saving the database gets as long as in-flight work does to drain
+                let shutdown_timeout = Duration::from_secs(config.shutdown_drain_timeout_sec);
+
+                // Create a timeout for the shutdown process
+                let shutdown_result = tokio::time::timeout(shutdown_timeout, async {
+                    // Save the database
+                    {
+                        let db_guard = self.db.lock().await;
+                        if let Err(e) = db_guard.shutdown() {
+                            error!("Error during database shutdown: {}", e);
+                        }
+                    }
+
+                    // Small delay to ensure all resources are freed
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }).await;
+
+                match shutdown_result {
+                    Ok(_) => info!("Application shutdown completed successfully"),
+                    Err(_) => warn!("Application shutdown timed out after {} seconds", shutdown_timeout.as_secs()),
+                }
+
+                break;
+            }
+
+            total_polls += 1;
+            info!("{}Starting poll #{}", self.log_prefix(), total_polls);
+
+            // Check if it's time to update followings
+            if config.auto_follow_source.is_some() {
+                follow_check_counter += 1;
+
+                if follow_check_counter >= config.auto_follow_interval {
+                    info!("Auto-follow interval reached ({} polls), checking for new followings",
+                          config.auto_follow_interval);
+
+                    match self.update_followings().await {
+                        Ok(count) => {
+                            if count > 0 {
+                                info!("Added {} new users to watch from auto-follow source", count);
+                            } else {
+                                debug!("No new followings found from auto-follow source");
+                            }
+                        },
+                        Err(e) => {
+                            warn!("Failed to update followings from source: {}", e);
+                        }
+                    }
+
+                    // Reset counter
+                    follow_check_counter = 0;
+                }
+            }
+
+            let summary = self.poll_once().await;
+            tracks_since_last_save += summary.new_tracks;
+            if summary.new_tracks > 0 {
+                db_needs_saving = true;
+            }
+
+            // Increment the database save counter
+            db_save_counter += 1;
+
+            // Save the database if:
+            // 1. We found new tracks and reached the track threshold OR
+            // 2. It's time for a scheduled save based on poll cycles
+            let save_by_tracks = db_needs_saving && tracks_since_last_save >= config.db_save_tracks;
+            let save_by_interval = db_save_counter >= config.db_save_interval;
+
+            if save_by_tracks || save_by_interval {
+                let save_reason = if save_by_tracks {
+                    format!("processed {} new tracks (threshold: {})",
+                           tracks_since_last_save, config.db_save_tracks)
+                } else {
+                    format!("reached poll interval {} (current: {})",
+                           config.db_save_interval, db_save_counter)
+                };
+
+                info!("Saving database: {}", save_reason);
+
+                // Hold the mutex lock for the entire save operation
+                let db_guard = self.db.lock().await;
+                if let Err(e) = db_guard.save() {
+                    error!("Failed to save tracks database: {}", e);
+                } else {
+                    info!("Database saved successfully with {} tracks ({})",
+                         db_guard.track_count(), save_reason);
+                }
+
+                // Reset the counter and flag
+                db_save_counter = 0;
+                tracks_since_last_save = 0;
+                db_needs_saving = false;
+            }
+
+            if summary.new_tracks > 0 {
+                info!("{}Poll #{} completed: {} new tracks found", self.log_prefix(), total_polls, summary.new_tracks);
+            } else {
+                debug!("{}Poll #{} completed: no new tracks", self.log_prefix(), total_polls);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Poll a user for new tracks, process them, and send to Discord
+async fn poll_user(
+    api: &Arc<dyn SoundCloudApi>,
+    config: &Config,
+    user_id: &str,
+    db: &Arc<Mutex<TrackDatabase>>,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    // Create semaphores for limiting concurrency
+    let processing_semaphore = Arc::new(Semaphore::new(config.max_processing_parallelism));
+    let discord_semaphore = Arc::new(Semaphore::new(config.max_discord_parallelism));
+
+    // Get mutable access to the database
+    let mut db_guard = db.lock().await;
+
+    // Use the poll_user method with both semaphores
+    db_guard.poll_user(api, user_id, config, &processing_semaphore, &discord_semaphore).await
+}
@@ -5,21 +5,34 @@ use std::env;
 use log::{info, warn, error, debug};
 use tokio::process::Command as TokioCommand;
 use tokio::fs::File as TokioFile;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use uuid::Uuid;
 use crate::soundcloud::{Track, get_stream_url};
-use lazy_static;
 use serde_json::Value;
 
+/// Downloaded audio files as (format_info, file_path), plus artwork, JSON metadata and waveform paths
+pub type ProcessedAudio = (Vec<(String, String)>, Option<String>, Option<String>, Vec<String>);
+
 /// Download and preserve original audio from a SoundCloud track
 /// Returns a tuple containing:
 /// - Vec of (format_info, file_path) for all downloaded audio files
 /// - Option<String> for artwork file path
 /// - Option<String> for JSON metadata file path
+/// - Vec of waveform file paths (the waveform's JSON, and an SVG with `render_waveform`)
+///
+/// With `max_download_bytes` set, no further formats are fetched once the formats
+/// downloaded so far add up to more than that. Without `download_audio`, only the
+/// artwork, JSON metadata and waveform are saved. With `replaygain`, every audio file
+/// gets ReplayGain tags (see [`write_replaygain_tags`]).
+#[tracing::instrument(name = "download", skip_all, fields(track_id = %track.id))]
 pub async fn process_track_audio(
     track: &Track,
-    temp_dir: Option<&str>
-) -> Result<(Vec<(String, String)>, Option<String>, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    temp_dir: Option<&str>,
+    max_download_bytes: Option<u64>,
+    download_audio: bool,
+    render_waveform: bool,
+    replaygain: bool
+) -> Result<ProcessedAudio, Box<dyn std::error::Error + Send + Sync>> {
     // Get the base temp directory
     let base_dir = match temp_dir {
         Some(dir) => {
@@ -41,35 +54,77 @@ pub async fn process_track_audio(
     
     info!("Processing audio for track '{}' (ID: {}) in {}", track.title, track.id, work_dir.display());
     
-    // Save raw track data as JSON
-    let mut json_result = None;
+    // The artwork and JSON don't depend on the audio, so fetch them alongside it
     let sanitized_title = sanitize_filename(&track.title);
-    let json_path = work_dir.join(format!("{}_data.json", sanitized_title));
+    let (mut downloaded_files, artwork_result, json_result, waveform_files) = tokio::join!(
+        download_audio_formats(track, &work_dir, &sanitized_title, max_download_bytes, download_audio),
+        save_artwork_file(track, &work_dir, &sanitized_title),
+        save_json_file(track, &work_dir, &sanitized_title),
+        save_waveform_files(track, &work_dir, &sanitized_title, render_waveform)
+    );
     
-    match save_track_json(track, &json_path).await {
-        Ok(()) => {
-            let file_size = match fs::metadata(&json_path) {
-                Ok(metadata) => metadata.len(),
-                Err(_) => 0,
-            };
-            
-            json_result = Some(json_path.to_string_lossy().to_string());
-            info!("Saved track data as JSON: {} ({} bytes)", json_path.display(), file_size);
-        },
-        Err(e) => {
-            warn!("Failed to save track data as JSON: {}", e);
+    // If we have no audio files, return error
+    if downloaded_files.is_empty() && json_result.is_none() && artwork_result.is_none() {
+        error!("No valid audio URLs or data found for track {}", track.id);
+        cleanup_temp_dir(&work_dir).await?;
+        return Err("No valid audio URLs or data found for track".into());
+    }
+    
+    // Sort files by preference for primary/secondary output
+    downloaded_files.sort_by(|(format_a, _), (format_b, _)| {
+        // Prioritize formats based on quality/preference
+        let priority_a = get_format_priority(format_a);
+        let priority_b = get_format_priority(format_b);
+        priority_a.cmp(&priority_b)
+    });
+    
+    if replaygain {
+        for (format_info, path) in &downloaded_files {
+            if let Err(e) = write_replaygain_tags(Path::new(path)).await {
+                warn!("Failed to write ReplayGain tags to the {} file of track {}: {}", format_info, track.id, e);
+            }
         }
     }
     
+    // Return all downloaded files instead of just primary/secondary
+    info!("Processing completed for track '{}' (ID: {})", track.title, track.id);
+    debug!("Downloaded {} audio files", downloaded_files.len());
+    
+    Ok((downloaded_files, artwork_result, json_result, waveform_files))
+}
+
+/// Download a track's audio formats, best first, falling back to its HLS and stream URLs
+///
+/// Returns (format_info, file_path) for every format that was downloaded.
+async fn download_audio_formats(
+    track: &Track,
+    work_dir: &Path,
+    sanitized_title: &str,
+    max_download_bytes: Option<u64>,
+    download_audio: bool
+) -> Vec<(String, String)> {
     // Extract all available formats from the raw data
-    let available_formats = extract_available_formats(track);
+    let available_formats = if download_audio {
+        extract_available_formats(track)
+    } else {
+        debug!("Not downloading audio for track {} (metadata only)", track.id);
+        Vec::new()
+    };
     debug!("Found {} available formats for track {}", available_formats.len(), track.id);
     
     // First try to download all available formats in their original format
     let mut downloaded_files = Vec::new();
+    let mut downloaded_bytes = 0u64;
     
     // If we have raw transcodings data, use it
     for (format_info, url) in available_formats {
+        // Formats are sorted best first, so the ones left out are the least preferred
+        if let Some(max) = max_download_bytes.filter(|max| downloaded_bytes > *max) {
+            info!("Downloaded {} bytes of track {}, over the limit of {} bytes, not fetching {} or any further formats",
+                  downloaded_bytes, track.id, max, format_info);
+            break;
+        }
+        
         debug!("Attempting to download format: {} at {}", format_info, url);
         
         // Determine file extension based on format info
@@ -89,6 +144,7 @@ pub async fn process_track_audio(
                 
                 info!("Successfully downloaded {} format: {} ({} bytes)", 
                       format_info, output_path.display(), file_size);
+                downloaded_bytes += file_size;
                 downloaded_files.push((format_info, output_path.to_string_lossy().to_string()));
             },
             Err(e) => {
@@ -99,36 +155,21 @@ pub async fn process_track_audio(
     }
     
     // Fallback: Use our existing HLS and stream_url fields if we didn't get anything
-    if downloaded_files.is_empty() {
+    if downloaded_files.is_empty() && download_audio {
         debug!("No formats downloaded from transcodings, falling back to HLS/stream URLs");
         
-        // Resolve the HLS URL if we have one
-        let hls_url = match &track.hls_url {
-            Some(url) => {
-                debug!("Resolving HLS URL for track {}", track.id);
-                match get_stream_url(url).await {
-                    Ok(resolved) => {
-                        info!("Successfully resolved HLS URL for track {}", track.id);
-                        Some(resolved)
-                    },
-                    Err(e) => {
-                        warn!("Failed to resolve HLS URL for track {}: {}", track.id, e);
-                        None
-                    }
-                }
-            },
-            None => {
-                warn!("No HLS URL available for track {}, checking other streams", track.id);
-                None
-            }
-        };
+        // get_track_details already resolved the HLS transcoding to its playlist URL
+        let hls_url = track.hls_url.clone();
+        if hls_url.is_none() {
+            warn!("No HLS URL available for track {}, checking other streams", track.id);
+        }
         
         // Resolve the stream URL if we have one (and no HLS)
         let stream_url = if downloaded_files.is_empty() {
             match &track.stream_url {
                 Some(url) => {
                     debug!("Resolving stream URL for track {}", track.id);
-                    match get_stream_url(url).await {
+                    match get_stream_url(&crate::soundcloud::with_secret_token(url, track.secret_token.as_deref())).await {
                         Ok(resolved) => {
                             info!("Successfully resolved stream URL for track {}", track.id);
                             Some(resolved)
@@ -153,7 +194,7 @@ pub async fn process_track_audio(
             let output_path = work_dir.join(format!("{}_hls.m4a", sanitized_title));
             debug!("Downloading HLS stream to: {}", output_path.display());
             
-            match download_stream(url, &output_path).await {
+            match download_stream(url, &output_path, false).await {
                 Ok(()) => {
                     let file_size = match fs::metadata(&output_path) {
                         Ok(metadata) => metadata.len(),
@@ -189,12 +230,11 @@ pub async fn process_track_audio(
         }
         
         // Try to download from stream URL if we don't have anything yet
-        if downloaded_files.is_empty() && stream_url.is_some() {
-            let url = stream_url.as_ref().unwrap();
+        if let (true, Some(url)) = (downloaded_files.is_empty(), &stream_url) {
             let output_path = work_dir.join(format!("{}_stream.mp3", sanitized_title));
             debug!("Downloading progressive stream to: {}", output_path.display());
             
-            match download_stream(url, &output_path).await {
+            match download_stream(url, &output_path, true).await {
                 Ok(()) => {
                     let file_size = match fs::metadata(&output_path) {
                         Ok(metadata) => metadata.len(),
@@ -260,54 +300,87 @@ pub async fn process_track_audio(
         }
     }
     
-    // Download artwork if available
-    let mut artwork_result = None;
-    if let Some(artwork_url) = &track.artwork_url {
-        if !artwork_url.is_empty() {
-            // Get the original high-res image URL
-            info!("Downloading original artwork from: {}", artwork_url);
-            
-            // Create file path for artwork
-            let artwork_path = work_dir.join(format!("{}_cover.jpg", sanitized_title));
+    downloaded_files
+}
+
+/// Download a track's artwork, or the artist's avatar as the cover instead
+async fn save_artwork_file(track: &Track, work_dir: &Path, sanitized_title: &str) -> Option<String> {
+    let artwork_url = crate::soundcloud::cover_art_url(track)?;
+    info!("Downloading original artwork from: {}", artwork_url);
+    
+    // Create file path for artwork
+    let artwork_path = work_dir.join(format!("{}_cover.jpg", sanitized_title));
+    
+    // Download the artwork
+    match download_artwork(artwork_url, &artwork_path).await {
+        Ok(()) => {
+            let file_size = match fs::metadata(&artwork_path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            };
             
-            // Download the artwork
-            match download_artwork(&artwork_url, &artwork_path).await {
-                Ok(()) => {
-                    let file_size = match fs::metadata(&artwork_path) {
-                        Ok(metadata) => metadata.len(),
-                        Err(_) => 0,
-                    };
-                    
-                    artwork_result = Some(artwork_path.to_string_lossy().to_string());
-                    info!("Successfully downloaded artwork: {} ({} bytes)", artwork_path.display(), file_size);
-                },
-                Err(e) => {
-                    warn!("Failed to download artwork: {}", e);
-                }
-            }
+            info!("Successfully downloaded artwork: {} ({} bytes)", artwork_path.display(), file_size);
+            Some(artwork_path.to_string_lossy().to_string())
+        },
+        Err(e) => {
+            warn!("Failed to download artwork: {}", e);
+            None
         }
     }
+}
+
+/// Save a track's raw data as JSON
+async fn save_json_file(track: &Track, work_dir: &Path, sanitized_title: &str) -> Option<String> {
+    let json_path = work_dir.join(format!("{}_data.json", sanitized_title));
     
-    // If we have no audio files, return error
-    if downloaded_files.is_empty() && json_result.is_none() && artwork_result.is_none() {
-        error!("No valid audio URLs or data found for track {}", track.id);
-        cleanup_temp_dir(&work_dir).await?;
-        return Err("No valid audio URLs or data found for track".into());
+    match save_track_json(track, &json_path).await {
+        Ok(()) => {
+            let file_size = match fs::metadata(&json_path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            };
+            
+            info!("Saved track data as JSON: {} ({} bytes)", json_path.display(), file_size);
+            Some(json_path.to_string_lossy().to_string())
+        },
+        Err(e) => {
+            warn!("Failed to save track data as JSON: {}", e);
+            None
+        }
     }
+}
+
+/// Save a track's waveform as JSON, and as an SVG image if `render` is set
+async fn save_waveform_files(track: &Track, work_dir: &Path, sanitized_title: &str, render: bool) -> Vec<String> {
+    let mut files = Vec::new();
+    let Some(waveform) = &track.waveform else {
+        return files;
+    };
     
-    // Sort files by preference for primary/secondary output
-    downloaded_files.sort_by(|(format_a, _), (format_b, _)| {
-        // Prioritize formats based on quality/preference
-        let priority_a = get_format_priority(format_a);
-        let priority_b = get_format_priority(format_b);
-        priority_a.cmp(&priority_b)
-    });
-    
-    // Return all downloaded files instead of just primary/secondary
-    info!("Processing completed for track '{}' (ID: {})", track.title, track.id);
-    debug!("Downloaded {} audio files", downloaded_files.len());
+    let json_path = work_dir.join(format!("{}_waveform.json", sanitized_title));
+    let result = match serde_json::to_vec(waveform) {
+        Ok(json) => tokio::fs::write(&json_path, json).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match result {
+        Ok(()) => {
+            debug!("Saved waveform data: {}", json_path.display());
+            files.push(json_path.to_string_lossy().to_string());
+        },
+        Err(e) => warn!("Failed to save waveform data: {}", e),
+    }
     
-    Ok((downloaded_files, artwork_result, json_result))
+    if render {
+        let svg_path = work_dir.join(format!("{}_waveform.svg", sanitized_title));
+        match tokio::fs::write(&svg_path, waveform.render_svg()).await {
+            Ok(()) => {
+                debug!("Rendered waveform image: {}", svg_path.display());
+                files.push(svg_path.to_string_lossy().to_string());
+            },
+            Err(e) => warn!("Failed to save waveform image: {}", e),
+        }
+    }
+    files
 }
 
 /// Extract all available streaming formats from track data
@@ -348,7 +421,9 @@ fn extract_available_formats(track: &Track) -> Vec<(String, String)> {
                     // Get URL
                     if let Some(url) = transcoding.get("url").and_then(Value::as_str) {
                         debug!("Found format: {} at URL: {}", format_string, url);
-                        formats.push((format_string, url.to_string()));
+                        // Private tracks only stream with their secret token
+                        let url = crate::soundcloud::with_secret_token(url, track.secret_token.as_deref());
+                        formats.push((format_string, url));
                     }
                 }
             }
@@ -459,26 +534,157 @@ fn get_format_priority(format_info: &str) -> i32 {
     }
 }
 
+/// ffmpeg was refused with HTTP 403, usually because the resolved CDN URL expired
+#[derive(Debug)]
+struct StreamUrlExpired;
+
+impl std::fmt::Display for StreamUrlExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the stream URL was rejected with HTTP 403, it has probably expired")
+    }
+}
+
+impl std::error::Error for StreamUrlExpired {}
+
+/// Most of ffmpeg's error output kept to look for HTTP errors
+const FFMPEG_STDERR_TAIL: usize = 16 * 1024;
+
+/// Run ffmpeg, showing its output if configured, and report whether the server
+/// answered with HTTP 403
+///
+/// stderr is always read so the 403 can be spotted; with `show_ffmpeg_output`
+/// it's passed through as it arrives.
+async fn run_ffmpeg(cmd: &mut TokioCommand, show_output: bool) -> std::io::Result<(std::process::ExitStatus, bool)> {
+    if !show_output {
+        // Silence ffmpeg output
+        cmd.stdout(std::process::Stdio::null());
+    }
+    cmd.stderr(std::process::Stdio::piped());
+    
+    let mut child = cmd.spawn()?;
+    let mut tail = Vec::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = stderr.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            if show_output {
+                let mut out = tokio::io::stderr();
+                let _ = out.write_all(&buffer[..read]).await;
+            }
+            tail.extend_from_slice(&buffer[..read]);
+            if tail.len() > FFMPEG_STDERR_TAIL {
+                tail.drain(..tail.len() - FFMPEG_STDERR_TAIL);
+            }
+        }
+    }
+    
+    let status = child.wait().await?;
+    let forbidden = !status.success() && String::from_utf8_lossy(&tail).contains("403 Forbidden");
+    Ok((status, forbidden))
+}
+
 /// Download a stream directly
-async fn download_stream(url: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///
+/// Progressive streams are plain files, so they're fetched over HTTP and resumed with range
+/// requests when the connection drops. Other streams (HLS) are downloaded with ffmpeg.
+async fn download_stream(url: &str, output_path: &Path, progressive: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if progressive {
+        debug!("Downloading progressive stream over HTTP from {}", url);
+        return download_with_resume(url, output_path).await;
+    }
+    
     // For streaming URLs, direct downloads often produce incomplete files
-    // Instead, always use ffmpeg to properly download and process streams
+    // Instead, use ffmpeg to properly download and process streams
     debug!("Using ffmpeg to download stream from {}", url);
     
     // Use ffmpeg with stream copy to preserve original quality
     ffmpeg_stream_copy(url, output_path).await
 }
 
+/// How many times a dropped progressive download is resumed before giving up
+const MAX_DOWNLOAD_RESUMES: u32 = 5;
+
+/// Download a file over HTTP, resuming from the last byte written if the connection drops
+#[tracing::instrument(name = "http_download", skip_all)]
+async fn download_with_resume(url: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut file = TokioFile::create(output_path).await?;
+    let mut written = 0u64;
+    let mut total: Option<u64> = None;
+    let mut resumes = 0;
+    
+    loop {
+        let mut request = HTTP_CLIENT.get(url).header("User-Agent", "Mozilla/5.0");
+        if written > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", written));
+        }
+        
+        let dropped = match request.send().await {
+            Ok(mut response) => {
+                let status = response.status();
+                if status == reqwest::StatusCode::FORBIDDEN {
+                    return Err(Box::new(StreamUrlExpired));
+                }
+                if !status.is_success() {
+                    return Err(format!("Download failed: HTTP {}", status).into());
+                }
+                if written > 0 && status != reqwest::StatusCode::PARTIAL_CONTENT {
+                    // The server ignored the range, so the body starts from the beginning again
+                    warn!("Server doesn't support resuming downloads, starting over");
+                    file = TokioFile::create(output_path).await?;
+                    written = 0;
+                }
+                if let Some(len) = response.content_length() {
+                    total = Some(written + len);
+                }
+                
+                loop {
+                    match response.chunk().await {
+                        Ok(Some(chunk)) => {
+                            file.write_all(&chunk).await?;
+                            written += chunk.len() as u64;
+                            crate::metrics::add_bytes_downloaded(chunk.len() as u64);
+                        },
+                        Ok(None) => break None,
+                        Err(e) => break Some(e.to_string()),
+                    }
+                }
+            },
+            Err(e) => Some(e.to_string()),
+        };
+        
+        // A body that ends early without an error was cut off too
+        let dropped = dropped.or_else(|| match total {
+            Some(total) if written < total => Some(format!("connection closed after {} of {} bytes", written, total)),
+            _ => None,
+        });
+        let Some(reason) = dropped else {
+            break;
+        };
+        
+        resumes += 1;
+        if resumes > MAX_DOWNLOAD_RESUMES {
+            return Err(format!("Download failed after {} resumes: {}", MAX_DOWNLOAD_RESUMES, reason).into());
+        }
+        warn!("Download dropped at {} bytes ({}), resuming ({}/{})", written, reason, resumes, MAX_DOWNLOAD_RESUMES);
+        tokio::time::sleep(std::time::Duration::from_secs(resumes as u64)).await;
+    }
+    
+    file.flush().await?;
+    debug!("Downloaded {} bytes to {}", written, output_path.display());
+    Ok(())
+}
+
 /// Use ffmpeg to copy the stream without transcoding
+#[tracing::instrument(name = "ffmpeg_stream_copy", skip_all)]
 async fn ffmpeg_stream_copy(url: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     debug!("Executing ffmpeg stream copy command");
     let mut cmd = TokioCommand::new("ffmpeg");
     
     // Check if we should show ffmpeg output
-    let show_output = match crate::config::Config::show_ffmpeg_output() {
-        Some(true) => true,
-        _ => false,
-    };
+    let show_output = matches!(crate::config::Config::show_ffmpeg_output(), Some(true));
     
     cmd.arg("-i")
         .arg(url)
@@ -487,13 +693,6 @@ async fn ffmpeg_stream_copy(url: &str, output_path: &Path) -> Result<(), Box<dyn
         .arg("-y")  // Overwrite output
         .kill_on_drop(true);  // Ensure process is killed if parent process exits
     
-    // Configure stdout/stderr redirection based on config
-    if !show_output {
-        // Silence ffmpeg output
-        cmd.stdout(std::process::Stdio::null())
-           .stderr(std::process::Stdio::null());
-    }
-    
     // Add output path
     cmd.arg(output_path);
     
@@ -502,7 +701,13 @@ async fn ffmpeg_stream_copy(url: &str, output_path: &Path) -> Result<(), Box<dyn
           output_path.display());
     
     // Execute command
-    let status = cmd.status().await?;
+    let (status, forbidden) = run_ffmpeg(&mut cmd, show_output).await?;
+    
+    if forbidden {
+        // Picking other codecs won't help, the URL has to be resolved again
+        warn!("ffmpeg stream copy was refused with HTTP 403");
+        return Err(Box::new(StreamUrlExpired));
+    }
     
     if !status.success() {
         error!("ffmpeg stream copy failed with exit code: {}", status);
@@ -517,19 +722,17 @@ async fn ffmpeg_stream_copy(url: &str, output_path: &Path) -> Result<(), Box<dyn
             .arg("-y")  // Overwrite output
             .kill_on_drop(true);  // Ensure process is killed if parent process exits
         
-        // Configure stdout/stderr redirection based on config
-        if !show_output {
-            // Silence ffmpeg output
-            cmd2.stdout(std::process::Stdio::null())
-               .stderr(std::process::Stdio::null());
-        }
-        
         // Add output path
         cmd2.arg(output_path);
         
         debug!("ffmpeg retry command: -i [url] -y {}", output_path.display());
         
-        let retry_status = cmd2.status().await?;
+        let (retry_status, forbidden) = run_ffmpeg(&mut cmd2, show_output).await?;
+        
+        if forbidden {
+            warn!("ffmpeg retry was refused with HTTP 403");
+            return Err(Box::new(StreamUrlExpired));
+        }
         
         if !retry_status.success() {
             error!("ffmpeg retry failed with exit code: {}", retry_status);
@@ -537,20 +740,26 @@ async fn ffmpeg_stream_copy(url: &str, output_path: &Path) -> Result<(), Box<dyn
         }
     }
     
+    record_downloaded_bytes(output_path);
     debug!("ffmpeg stream copy completed successfully");
     Ok(())
 }
 
+/// Add the size of a file written by ffmpeg to the downloaded bytes metric
+fn record_downloaded_bytes(path: &Path) {
+    if let Ok(metadata) = std::fs::metadata(path) {
+        crate::metrics::add_bytes_downloaded(metadata.len());
+    }
+}
+
 /// Transcode a URL to MP3 using ffmpeg (fallback method)
+#[tracing::instrument(name = "ffmpeg_transcode", skip_all)]
 async fn transcode_to_mp3(url: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     debug!("Executing ffmpeg MP3 transcoding command");
     let mut cmd = TokioCommand::new("ffmpeg");
     
     // Check if we should show ffmpeg output
-    let show_output = match crate::config::Config::show_ffmpeg_output() {
-        Some(true) => true,
-        _ => false,
-    };
+    let show_output = matches!(crate::config::Config::show_ffmpeg_output(), Some(true));
     
     cmd.arg("-i")
         .arg(url)
@@ -583,10 +792,104 @@ async fn transcode_to_mp3(url: &str, output_path: &Path) -> Result<(), Box<dyn s
         return Err(format!("ffmpeg failed with exit code: {}", status).into());
     }
     
+    record_downloaded_bytes(output_path);
     debug!("ffmpeg MP3 transcoding completed successfully");
     Ok(())
 }
 
+/// ReplayGain 2.0 reference loudness, in LUFS
+const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// EBU R128 reference loudness used by `R128_TRACK_GAIN` in Opus files, in LUFS
+const R128_REFERENCE_LUFS: f64 = -23.0;
+
+/// Measure a file's loudness and write it into its tags as ReplayGain
+///
+/// The integrated loudness and true peak are measured with ffmpeg's `loudnorm` filter, then
+/// the file is remuxed with stream copy, so the audio itself is untouched.
+/// `REPLAYGAIN_TRACK_GAIN` and `REPLAYGAIN_TRACK_PEAK` are written to every file, and Opus
+/// files also get `R128_TRACK_GAIN`, which is what Opus players read.
+#[tracing::instrument(name = "replaygain", skip_all)]
+pub async fn write_replaygain_tags(path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (loudness, true_peak) = measure_loudness(path).await?;
+    let gain = REPLAYGAIN_REFERENCE_LUFS - loudness;
+    let peak = 10f64.powf(true_peak / 20.0);
+    debug!("{} has a loudness of {:.2} LUFS and a true peak of {:.2} dBTP, ReplayGain {:+.2} dB",
+           path.display(), loudness, true_peak, gain);
+
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mut tags = vec![
+        ("REPLAYGAIN_TRACK_GAIN", format!("{:+.2} dB", gain)),
+        ("REPLAYGAIN_TRACK_PEAK", format!("{:.6}", peak)),
+    ];
+    if extension == "opus" {
+        // Q7.8 fixed point, relative to R128's reference rather than ReplayGain's
+        let r128_gain = ((R128_REFERENCE_LUFS - loudness) * 256.0).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        tags.push(("R128_TRACK_GAIN", r128_gain.to_string()));
+    }
+
+    // Keep the extension so ffmpeg picks the same container
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tagged_path = path.with_file_name(format!("replaygain_{}", file_name));
+    let mut cmd = TokioCommand::new("ffmpeg");
+    cmd.arg("-i").arg(path)
+        .arg("-map").arg("0")
+        .arg("-c").arg("copy")
+        .kill_on_drop(true);
+    // Ogg keeps its comments per stream, the other containers in the file's global tags
+    let metadata_arg = if extension == "opus" || extension == "ogg" { "-metadata:s:a:0" } else { "-metadata" };
+    for (key, value) in &tags {
+        cmd.arg(metadata_arg).arg(format!("{}={}", key, value));
+    }
+    if extension == "m4a" {
+        // MP4 drops tags it has no atom for unless told otherwise
+        cmd.arg("-movflags").arg("use_metadata_tags");
+    }
+    cmd.arg("-y").arg(&tagged_path);
+
+    let show_output = matches!(crate::config::Config::show_ffmpeg_output(), Some(true));
+    let (status, _) = run_ffmpeg(&mut cmd, show_output).await?;
+    if !status.success() {
+        let _ = fs::remove_file(&tagged_path);
+        return Err(format!("ffmpeg failed to write the tags with exit code: {}", status).into());
+    }
+    fs::rename(&tagged_path, path)?;
+    Ok(())
+}
+
+/// Measure a file's integrated loudness (LUFS) and true peak (dBTP) with ffmpeg
+async fn measure_loudness(path: &Path) -> Result<(f64, f64), Box<dyn std::error::Error + Send + Sync>> {
+    let output = TokioCommand::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-i").arg(path)
+        .arg("-af").arg("loudnorm=print_format=json")
+        .arg("-f").arg("null")
+        .arg("-")
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg failed to measure the loudness with exit code: {}", output.status).into());
+    }
+
+    // loudnorm prints its measurements as the last JSON object on stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let json = stderr.rfind('{')
+        .and_then(|start| stderr[start..].rfind('}').map(|end| &stderr[start..=start + end]))
+        .ok_or("ffmpeg printed no loudness measurements")?;
+    let measurements: Value = serde_json::from_str(json)?;
+    let value = |name: &str| measurements.get(name)
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite());
+    match (value("input_i"), value("input_tp")) {
+        (Some(loudness), Some(true_peak)) => Ok((loudness, true_peak)),
+        // Silence measures as -inf, which has no meaningful gain
+        _ => Err("the file is silent or its loudness couldn't be measured".into()),
+    }
+}
+
 /// Clean up temporary files after processing
 pub async fn cleanup_temp_dir(dir: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if dir.exists() && dir.is_dir() {
@@ -617,7 +920,7 @@ pub async fn delete_temp_file(path: &str) -> Result<(), Box<dyn std::error::Erro
 }
 
 /// Sanitize a filename to be safe for the file system
-fn sanitize_filename(filename: &str) -> String {
+pub(crate) fn sanitize_filename(filename: &str) -> String {
     // Replace invalid characters with underscores
     let sanitized = filename
         .chars()
@@ -647,6 +950,7 @@ pub fn check_ffmpeg() -> bool {
 }
 
 /// Download artwork from URL
+#[tracing::instrument(name = "download_artwork", skip_all)]
 async fn download_artwork(url: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     debug!("Downloading artwork from URL");
     
@@ -665,6 +969,7 @@ async fn download_artwork(url: &str, output_path: &Path) -> Result<(), Box<dyn s
     
     // Get the image data
     let image_data = response.bytes().await?;
+    crate::metrics::add_bytes_downloaded(image_data.len() as u64);
     
     // Save to file
     let mut file = TokioFile::create(output_path).await?;
@@ -708,7 +1013,20 @@ async fn resolve_and_download_format(
     match get_stream_url(url).await {
         Ok(resolved_url) => {
             // Download the stream
-            match download_stream(&resolved_url, output_path).await {
+            let progressive = format_info.starts_with("progressive");
+            let mut result = download_stream(&resolved_url, output_path, progressive).await;
+            
+            // Long downloads can outlive the resolved URL, so resolve it again and restart once
+            if result.as_ref().is_err_and(|e| e.is::<StreamUrlExpired>()) {
+                warn!("Stream URL for {} expired during the download, resolving it again", format_info);
+                crate::soundcloud::forget_stream_url(url);
+                result = match get_stream_url(url).await {
+                    Ok(resolved_url) => download_stream(&resolved_url, output_path, progressive).await,
+                    Err(e) => Err(e),
+                };
+            }
+            
+            match result {
                 Ok(()) => {
                     // Check if file is large enough to be a valid audio file
                     let file_size = match fs::metadata(output_path) {
@@ -723,7 +1041,11 @@ async fn resolve_and_download_format(
                     debug!("Successfully downloaded {} format: {} bytes", format_info, file_size);
                     Ok(())
                 },
-                Err(e) => Err(e)
+                Err(e) => {
+                    // Resolve it again next time in case the cached URL went stale
+                    crate::soundcloud::forget_stream_url(url);
+                    Err(e)
+                }
             }
         },
         Err(e) => {
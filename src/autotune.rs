@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use log::{info, debug};
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+
+/// Batches in a row without throttling before the limit is raised again
+const HEALTHY_BATCHES_BEFORE_RAISE: u32 = 3;
+
+/// Limits how many users are polled from SoundCloud at once, optionally adjusting
+/// the limit to how SoundCloud responds
+///
+/// With `autotune_soundcloud_parallelism` enabled, the limit is halved whenever a
+/// batch of polls got HTTP 429 or 403 responses, and raised by one after a few
+/// batches without any, staying between `min_soundcloud_parallelism` and
+/// `max_soundcloud_parallelism`. Otherwise it stays at `max_soundcloud_parallelism`.
+#[derive(Debug)]
+pub struct ParallelismTuner {
+    semaphore: Arc<Semaphore>,
+    enabled: bool,
+    min: usize,
+    max: usize,
+    state: Mutex<TunerState>,
+}
+
+#[derive(Debug)]
+struct TunerState {
+    /// Current limit
+    limit: usize,
+    /// Permits still to be taken out of the semaphore once they're released
+    owed: usize,
+    /// Throttled response count at the last adjustment
+    last_throttled: u64,
+    healthy_batches: u32,
+}
+
+impl ParallelismTuner {
+    pub fn new(config: &Config) -> Self {
+        let max = config.max_soundcloud_parallelism;
+        crate::metrics::set_soundcloud_parallelism(max);
+        ParallelismTuner {
+            semaphore: Arc::new(Semaphore::new(max)),
+            enabled: config.autotune_soundcloud_parallelism,
+            min: config.min_soundcloud_parallelism.clamp(1, max.max(1)),
+            max,
+            state: Mutex::new(TunerState {
+                limit: max,
+                owed: 0,
+                last_throttled: crate::metrics::soundcloud_throttled(),
+                healthy_batches: 0,
+            }),
+        }
+    }
+
+    /// Semaphore to acquire a permit from before polling a user
+    pub fn semaphore(&self) -> Arc<Semaphore> {
+        self.semaphore.clone()
+    }
+
+    /// Current number of users that may be polled at once
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).limit
+    }
+
+    /// Adjust the limit to the SoundCloud responses since the last call
+    ///
+    /// Called after each batch of user polls; does nothing unless autotuning is enabled.
+    pub fn adjust(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let throttled = crate::metrics::soundcloud_throttled();
+        let new_throttled = throttled.saturating_sub(state.last_throttled);
+        state.last_throttled = throttled;
+
+        let old_limit = state.limit;
+        if new_throttled > 0 {
+            state.healthy_batches = 0;
+            state.limit = (state.limit / 2).max(self.min);
+            if state.limit < old_limit {
+                info!("SoundCloud throttled {} requests, lowering parallelism from {} to {}",
+                      new_throttled, old_limit, state.limit);
+                state.owed += old_limit - state.limit;
+            }
+        } else {
+            state.healthy_batches += 1;
+            if state.healthy_batches >= HEALTHY_BATCHES_BEFORE_RAISE && state.limit < self.max {
+                state.healthy_batches = 0;
+                state.limit += 1;
+                info!("SoundCloud looks healthy, raising parallelism from {} to {}", old_limit, state.limit);
+                // Cancel a pending reduction before handing out new permits
+                if state.owed > 0 {
+                    state.owed -= 1;
+                } else {
+                    self.semaphore.add_permits(1);
+                }
+            }
+        }
+
+        if state.owed > 0 {
+            state.owed -= self.semaphore.forget_permits(state.owed);
+            if state.owed > 0 {
+                debug!("{} SoundCloud permits are still in use, they'll be removed later", state.owed);
+            }
+        }
+        crate::metrics::set_soundcloud_parallelism(state.limit);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::{info, warn, debug};
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+use crate::db::TrackDatabase;
+use crate::soundcloud::{PostProcessing, PostedTrack, Provenance, Track};
+
+/// Post a newly watched user's tracks in batches if `backfill_batch_size` is set
+///
+/// On a user's first poll, every track within `max_tracks_per_user` (and their likes) is
+/// new. Instead of a post per track, their embeds are posted `backfill_batch_size` to a
+/// message, oldest first, without downloading audio. Returns `None` if the tracks aren't
+/// posted in batches, otherwise the number of tracks posted. All tracks are kept as pending
+/// until they're posted, so the tracks of a batch that failed, or that wasn't posted before
+/// a shutdown, are posted one by one on the next poll.
+pub async fn post_backfill(
+    db: &mut TrackDatabase,
+    user_id: &str,
+    tracks: &[Track],
+    upload_ids: &HashSet<String>,
+    config: &Config,
+    post_processing: &PostProcessing,
+    discord_semaphore: &Arc<Semaphore>
+) -> Option<usize> {
+    let batch_size = config.backfill_batch_size;
+    if batch_size < 2 || tracks.len() < 2 {
+        return None;
+    }
+
+    let mut tracks = tracks.to_vec();
+    tracks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    info!("Backfilling {} tracks of newly watched user {} in batches of {}", tracks.len(), user_id, batch_size);
+
+    let provenance_of = |track: &Track| if upload_ids.contains(&track.id) { Provenance::Upload } else { Provenance::Like };
+    let pending: Vec<_> = tracks.iter().map(|t| (t.clone(), provenance_of(t))).collect();
+    if db.add_pending_tracks(user_id, &pending) {
+        if let Err(e) = db.save() {
+            warn!("Failed to save database with the pending tracks of user {}: {}", user_id, e);
+        }
+    }
+
+    let mut posted = 0;
+    for batch in tracks.chunks(batch_size) {
+        if crate::shutdown::is_requested() {
+            debug!("Shutdown requested, leaving the rest of user {}'s backfill for the next poll", user_id);
+            break;
+        }
+
+        // Tracks whose embed couldn't be built stay pending and are posted on their own later
+        let mut prepared = Vec::new();
+        let mut embeds = Vec::new();
+        for track in batch {
+            let badge = Some(provenance_of(track)).filter(|_| post_processing.provenance_badge).map(|p| p.badge());
+            match crate::discord::prepare_track_post(&*post_processing.api, track, None, badge.as_deref(), true, post_processing.embed_fields, &post_processing.plugins).await {
+                Ok((embed, _)) => {
+                    prepared.push(track.clone());
+                    embeds.push(embed);
+                },
+                Err(e) => warn!("Failed to prepare the embed of track {}, it's posted on the next poll: {}", track.id, e),
+            }
+        }
+        if prepared.is_empty() {
+            continue;
+        }
+
+        let response = {
+            let _permit = match discord_semaphore.acquire().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    warn!("Failed to acquire Discord semaphore for backfill of user {}: {}", user_id, e);
+                    break;
+                }
+            };
+            crate::discord::send_track_batch(&config.discord_webhook_url, &prepared, embeds, post_processing.webhook_retry).await
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to post a backfill batch of user {}, its tracks are posted one by one on the next poll: {}", user_id, e);
+                post_processing.hooks.error(&e.to_string(), Some(user_id), None);
+                continue;
+            }
+        };
+
+        for track in &prepared {
+            db.add_posted_track(&PostedTrack {
+                track_id: track.id.clone(),
+                user_id: track.user.id.clone(),
+                response: response.clone(),
+                track: track.clone(),
+                metadata: None,
+                skip: None,
+                artwork_source: None,
+                destinations: Vec::new(),
+                // Batches only go to the main webhook, the other destinations get the tracks one by one
+                pending_destinations: post_processing.extra_webhook_names(),
+            });
+            post_processing.hooks.track_archived(track, &response, &[]).await;
+            posted += 1;
+        }
+    }
+
+    if posted > 0 {
+        crate::metrics::increment_total_tracks(posted as u64);
+        if let Err(e) = db.save() {
+            warn!("Failed to save database after backfilling user {}: {}", user_id, e);
+        }
+    }
+    Some(posted)
+}
@@ -0,0 +1,164 @@
+use chrono::{Duration, Utc};
+use log::{info, warn};
+
+use crate::config::Config;
+use crate::db::{TrackDatabase, UserPollState};
+
+/// Pauses users whose polls fail repeatedly
+///
+/// Once a user fails `user_failure_threshold` polls in a row they're paused, and
+/// re-checked after `user_backoff_base_sec`. Each further failed re-check doubles
+/// the pause, up to `user_backoff_max_sec`. A single successful poll resets the user.
+///
+/// Users SoundCloud reports as missing or hidden for `user_deactivation_threshold` polls
+/// in a row are deactivated instead: they aren't polled at all until re-enabled.
+///
+/// The failure counts and pauses are kept in the tracks database so they survive restarts.
+#[derive(Debug, Clone)]
+pub struct UserBackoff {
+    threshold: u32,
+    deactivation_threshold: u32,
+    base_sec: u64,
+    max_sec: u64,
+    // Paused user IDs as of the last logged summary
+    last_summary: Vec<String>,
+}
+
+impl UserBackoff {
+    pub fn new(config: &Config) -> Self {
+        UserBackoff {
+            threshold: config.user_failure_threshold,
+            deactivation_threshold: config.user_deactivation_threshold,
+            base_sec: config.user_backoff_base_sec,
+            max_sec: config.user_backoff_max_sec,
+            last_summary: Vec::new(),
+        }
+    }
+
+    /// Check whether a user should be polled right now
+    pub fn should_poll(&self, db: &TrackDatabase, user_id: &str) -> bool {
+        let Some(state) = db.user_state(user_id) else {
+            return true;
+        };
+        if state.deactivated_at.is_some() {
+            return false;
+        }
+        match state.paused_until {
+            Some(until) => Utc::now() >= until,
+            None => true,
+        }
+    }
+
+    /// Record a successful poll, clearing any failure state for the user
+    pub fn record_success(&self, db: &mut TrackDatabase, user_id: &str) {
+        let state = db.user_state_mut(user_id);
+        if state.paused_until.is_some() {
+            info!("User {} polled successfully again, resuming normal polling after {} consecutive failures",
+                  user_id, state.consecutive_errors);
+        }
+        state.consecutive_errors = 0;
+        state.consecutive_unavailable = 0;
+        state.paused_until = None;
+        state.last_error = None;
+    }
+
+    /// Record a poll where SoundCloud said the user doesn't exist or is hidden
+    ///
+    /// Call this in addition to [`record_failure`](Self::record_failure). Returns true if
+    /// the user was deactivated just now.
+    pub fn record_unavailable(&self, db: &mut TrackDatabase, user_id: &str) -> bool {
+        let state = db.user_state_mut(user_id);
+        state.consecutive_unavailable += 1;
+        if self.deactivation_threshold == 0
+            || state.consecutive_unavailable < self.deactivation_threshold
+            || state.deactivated_at.is_some() {
+            return false;
+        }
+        state.deactivated_at = Some(Utc::now());
+        state.paused_until = None;
+        warn!("User {} has been unavailable on SoundCloud for {} polls in a row, deactivating them",
+              user_id, state.consecutive_unavailable);
+        true
+    }
+
+    /// Re-enable polling of a deactivated user, returning false if they weren't deactivated
+    pub fn reactivate(db: &mut TrackDatabase, user_id: &str) -> bool {
+        let Some(state) = db.user_state(user_id).filter(|s| s.deactivated_at.is_some()) else {
+            return false;
+        };
+        info!("Reactivating user {} (deactivated since {})", user_id,
+              state.deactivated_at.map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_default());
+        let state = db.user_state_mut(user_id);
+        state.deactivated_at = None;
+        state.consecutive_unavailable = 0;
+        state.consecutive_errors = 0;
+        state.paused_until = None;
+        true
+    }
+
+    /// Get all deactivated users with their polling state, most recently deactivated last
+    pub fn deactivated_users(db: &TrackDatabase) -> Vec<(&String, &UserPollState)> {
+        let mut deactivated: Vec<_> = db.user_states().iter()
+            .filter(|(_, state)| state.deactivated_at.is_some())
+            .collect();
+        deactivated.sort_by_key(|(_, state)| state.deactivated_at);
+        deactivated
+    }
+
+    /// Record a failed poll, pausing the user if they've hit the failure threshold
+    pub fn record_failure(&self, db: &mut TrackDatabase, user_id: &str, error: &str) {
+        let state = db.user_state_mut(user_id);
+        state.consecutive_errors += 1;
+        state.last_error = Some(error.to_string());
+
+        // A threshold of 0 disables pausing
+        if self.threshold == 0 || state.consecutive_errors < self.threshold {
+            return;
+        }
+
+        // Double the pause for every failed re-check after the threshold was reached
+        let exponent = (state.consecutive_errors - self.threshold).min(32);
+        let pause_sec = self.base_sec.saturating_mul(1u64 << exponent).min(self.max_sec);
+        let until = Utc::now() + Duration::seconds(pause_sec as i64);
+        state.paused_until = Some(until);
+
+        warn!("User {} has failed {} polls in a row, pausing polling for {} seconds (until {}): {}",
+              user_id, state.consecutive_errors, pause_sec, until.format("%Y-%m-%d %H:%M:%S UTC"), error);
+    }
+
+    /// Get all paused users with their polling state
+    ///
+    /// Users stay paused until they poll successfully, even while a re-check is due.
+    pub fn paused_users(db: &TrackDatabase) -> Vec<(&String, &UserPollState)> {
+        let mut paused: Vec<_> = db.user_states().iter()
+            .filter(|(_, state)| state.paused_until.is_some())
+            .collect();
+        paused.sort_by_key(|(_, state)| state.paused_until);
+        paused
+    }
+
+    /// Log a summary of paused users whenever the set of paused users changes
+    pub fn log_summary(&mut self, db: &TrackDatabase) {
+        let paused = Self::paused_users(db);
+        let mut paused_ids: Vec<String> = paused.iter().map(|(id, _)| id.to_string()).collect();
+        paused_ids.sort();
+        if paused_ids == self.last_summary {
+            return;
+        }
+
+        if paused.is_empty() {
+            info!("No users are paused anymore");
+        } else {
+            info!("{} user(s) are paused due to repeated failures:", paused.len());
+            for (user_id, state) in &paused {
+                info!("  {} - {} consecutive failures, next check at {}, last error: {}",
+                      user_id,
+                      state.consecutive_errors,
+                      state.paused_until.map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_default(),
+                      state.last_error.as_deref().unwrap_or("unknown"));
+            }
+        }
+
+        self.last_summary = paused_ids;
+    }
+}
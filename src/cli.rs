@@ -1,10 +1,13 @@
+use std::collections::BTreeMap;
 use std::io::{self, Write, BufRead};
 use log::{info, warn, error, debug};
 use std::sync::Arc;
 
-use crate::config::{Config, Users};
-use crate::db::TrackDatabase;
+use crate::config::{Config, DisabledReason, UserSource, Users, WatchedUser};
+use crate::backoff::UserBackoff;
+use crate::db::{SkipReason, TrackDatabase};
 use crate::soundcloud;
+use crate::soundcloud_api::SoundCloudApi;
 use crate::loghandler::update_log_level;
 
 /// Display help information to the console
@@ -15,9 +18,27 @@ pub fn show_help() {
     println!("  archiver_webhook --init-tracks   - Initialize tracks database with existing tracks");
     println!("  archiver_webhook --post-track ID - Post a specific track to webhook (bypass database)");
     println!("                               - Can be a track ID or a SoundCloud URL");
+    println!("  archiver_webhook --post-tracks --ids-file FILE - Archive every track ID or URL listed in FILE");
+    println!("                               - (one per line), skipping tracks already in the database");
     println!("  archiver_webhook --lookup-discord-id ID - Look up a track by Discord message ID");
+    println!("  archiver_webhook --tag-track ID [--add TAG] [--remove TAG] [--note TEXT] [--clear-note]");
+    println!("                               - Edit the tags and note of an archived track, or show them");
+    println!("  archiver_webhook --mark-seen --user ID [--user ID...] [--before DATE]");
+    println!("                               - Record the users' existing tracks (uploaded before DATE, if given)");
+    println!("                               - as archived without downloading or posting them");
     println!("  archiver_webhook --generate-config URL - Generate config.json and users.json files");
     println!("                               - URL should be a SoundCloud user profile");
+    println!("  archiver_webhook --user-status   - Show polling state of watched users, including paused users");
+    println!("  archiver_webhook --show-config   - Print the effective configuration (file + environment + defaults)");
+    println!("                               - Secrets are redacted");
+    println!("  archiver_webhook --export-users csv|opml [FILE] - Export watched users with usernames, URLs");
+    println!("                               - and archived track counts (to users.csv/users.opml by default)");
+    println!("  archiver_webhook --skipped-report - Count tracks skipped (too large, premium only, geo-blocked,");
+    println!("                               - failed) per reason and per artist");
+    println!("  archiver_webhook --retry-restricted - Post geo-blocked and premium only tracks again if their");
+    println!("                               - full audio is available now, e.g. from a different region");
+    println!("  archiver_webhook --stats-chart ID [FILE] - Chart the recorded plays, likes and reposts of a");
+    println!("                               - track, or of all of a user's tracks (to stats_ID.svg by default)");
     println!("  archiver_webhook --help          - Show this help");
 }
 
@@ -54,7 +75,7 @@ pub async fn resolve_soundcloud_url(url: &str) -> Result<(), Box<dyn std::error:
 }
 
 /// Initialize tracks database with all existing tracks from all users
-pub async fn initialize_tracks_database() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn initialize_tracks_database(api: &Arc<dyn SoundCloudApi>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load config
     let config_path = "config.json";
     info!("Loading configuration from {}", config_path);
@@ -76,7 +97,7 @@ pub async fn initialize_tracks_database() -> Result<(), Box<dyn std::error::Erro
     
     // Load users
     info!("Loading users from {}", config.users_file);
-    let users = match Users::load(&config.users_file) {
+    let mut users = match Users::load(&config.users_file) {
         Ok(u) => {
             info!("Loaded {} users", u.users.len());
             u
@@ -107,7 +128,7 @@ pub async fn initialize_tracks_database() -> Result<(), Box<dyn std::error::Erro
     
     // Initialize SoundCloud client
     info!("Initializing SoundCloud client");
-    match soundcloud::initialize().await {
+    match api.initialize().await {
         Ok(_) => info!("SoundCloud client initialized successfully"),
         Err(e) => {
             error!("Failed to initialize SoundCloud client: {}", e);
@@ -115,14 +136,16 @@ pub async fn initialize_tracks_database() -> Result<(), Box<dyn std::error::Erro
         }
     }
     
+    users.resolve_entries(api.as_ref(), &config.users_file).await;
+    
     // Use our new method to initialize the database with tracks from users
-    info!("Initializing database with tracks from {} users", users.users.len());
+    let active_users = users.active();
+    info!("Initializing database with tracks from {} users", active_users.len());
     let (total_users_processed, total_tracks_added) = match db.initialize_with_tracks_from_users(
-        &users.users,
-        config.max_tracks_per_user,
-        config.pagination_size,
-        config.scrape_user_likes,
-        config.max_likes_per_user
+        api,
+        &active_users,
+        &config,
+        &Arc::new(tokio::sync::Semaphore::new(config.max_soundcloud_parallelism))
     ).await {
         Ok(result) => result,
         Err(e) => {
@@ -140,13 +163,13 @@ pub async fn initialize_tracks_database() -> Result<(), Box<dyn std::error::Erro
     println!("\nInitialization complete!");
     println!("Processed {} users", total_users_processed);
     println!("Added {} tracks to database", total_tracks_added);
-    println!("Total tracks in database: {}", db.get_all_tracks().len());
+    println!("Total tracks in database: {}", db.track_count());
     
     Ok(())
 }
 
 /// Post a single track to the webhook without checking the database
-pub async fn post_single_track(id_or_url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn post_single_track(api: &Arc<dyn SoundCloudApi>, id_or_url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load config
     let config_path = "config.json";
     info!("Loading configuration from {}", config_path);
@@ -165,6 +188,11 @@ pub async fn post_single_track(id_or_url: &str) -> Result<(), Box<dyn std::error
         }
     };
     
+    if let Err(e) = crate::locale::init(config.locale_file.as_deref()) {
+        error!("Failed to load locale: {}", e);
+        return Err(e);
+    }
+    
     // Initialize database to store the Discord message ID
     let tracks_db_path = config.tracks_file.clone();
     let mut db = match TrackDatabase::load_or_create(tracks_db_path) {
@@ -180,7 +208,7 @@ pub async fn post_single_track(id_or_url: &str) -> Result<(), Box<dyn std::error
     
     // Initialize SoundCloud client
     info!("Initializing SoundCloud client");
-    match soundcloud::initialize().await {
+    match api.initialize().await {
         Ok(_) => info!("SoundCloud client initialized successfully"),
         Err(e) => {
             error!("Failed to initialize SoundCloud client: {}", e);
@@ -191,45 +219,243 @@ pub async fn post_single_track(id_or_url: &str) -> Result<(), Box<dyn std::error
     // Create Discord semaphore
     let discord_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_discord_parallelism));
     
-    // Use our modularized function to process and post the track
-    let result = match soundcloud::process_and_post_track(
-        id_or_url, 
+    // Fetch the track and process and post it
+    let (track_id, secret_token) = api.resolve_track(id_or_url).await?;
+    let track = api.track_details(&track_id, secret_token.as_deref()).await?;
+    let result = match soundcloud::post_track(
+        track,
         &config.discord_webhook_url, 
         config.temp_dir.as_deref(),
-        Some(&discord_semaphore)
+        Some(&discord_semaphore),
+        &soundcloud::PostProcessing::from_config(&config),
+        None
     ).await {
-        Ok((track_id, user_id, webhook_response)) => {
+        Ok(posted) => {
             // Store the Discord message ID in the database
-            db.add_track_with_discord_info(
-                &track_id,
-                webhook_response.message_id.clone(),
-                webhook_response.channel_id.clone(),
-                Some(user_id)
-            );
+            db.add_posted_track(&posted);
             
             // Save the database
             if let Err(e) = db.save() {
                 warn!("Failed to save track with Discord message ID to database: {}", e);
             } else {
                 info!("Stored track {} with Discord message ID {} in database", 
-                     track_id, webhook_response.message_id);
+                     posted.track_id, posted.response.message_id);
             }
             
             Ok(())
         },
-        Err(e) => Err(e),
+        Err(e) => {
+            if let Some(ignored) = e.downcast_ref::<crate::ignore::Ignored>() {
+                db.record_ignored(ignored);
+                if let Err(e) = db.save() {
+                    warn!("Failed to save database: {}", e);
+                }
+            }
+            Err(e)
+        },
     };
     
     result
 }
 
+/// Archive the tracks listed in a file, one ID or URL per line, and record them in the database
+///
+/// Blank lines and lines starting with `#` are ignored. Tracks already in the database
+/// are skipped and the database is saved after each track, so an interrupted run can
+/// simply be started again.
+pub async fn post_tracks_from_file(api: &Arc<dyn SoundCloudApi>, ids_file: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let ids = std::fs::read_to_string(ids_file)
+        .map_err(|e| format!("Failed to read {}: {}", ids_file, e))?;
+    let entries: Vec<&str> = ids.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    
+    // Load config
+    let config_path = "config.json";
+    info!("Loading configuration from {}", config_path);
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    crate::locale::init(config.locale_file.as_deref())?;
+    
+    let mut db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    let mut soundcloud_initialized = false;
+    
+    let discord_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_discord_parallelism));
+    let post_processing = soundcloud::PostProcessing::from_config(&config);
+    
+    println!("Archiving {} tracks from {}", entries.len(), ids_file);
+    let (mut posted, mut already_archived, mut queued, mut ignored, mut failed) = (0, 0, 0, 0, 0);
+    for (i, entry) in entries.iter().enumerate() {
+        let (track_id, secret_token) = match api.resolve_track(entry).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                println!("[{}/{}] {}: couldn't resolve track: {}", i + 1, entries.len(), entry, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if post_processing.outbox.contains(&track_id) || !db.try_claim(&track_id) {
+            debug!("Track {} is already archived, queued or being processed, skipping it", track_id);
+            already_archived += 1;
+            continue;
+        }
+        
+        // Only needed once there's something left to archive
+        if !soundcloud_initialized {
+            api.initialize().await?;
+            soundcloud_initialized = true;
+        }
+        
+        let result = match api.track_details(&track_id, secret_token.as_deref()).await {
+            Ok(track) => soundcloud::post_track(
+                track,
+                &config.discord_webhook_url,
+                config.temp_dir.as_deref(),
+                Some(&discord_semaphore),
+                &post_processing,
+                None
+            ).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(track) => {
+                println!("[{}/{}] {}: posted {} by {}", i + 1, entries.len(), track_id, track.track.title, track.track.user.username);
+                db.add_posted_track(&track);
+                posted += 1;
+            },
+            Err(e) if e.is::<crate::outbox::Queued>() => {
+                println!("[{}/{}] {}: queued until Discord is reachable", i + 1, entries.len(), track_id);
+                queued += 1;
+            },
+            Err(e) if e.is::<crate::ignore::Ignored>() => {
+                println!("[{}/{}] {}: on the ignore list, skipped", i + 1, entries.len(), track_id);
+                if let Some(ignored) = e.downcast_ref::<crate::ignore::Ignored>() {
+                    db.record_ignored(ignored);
+                }
+                ignored += 1;
+            },
+            Err(e) => {
+                println!("[{}/{}] {}: failed: {}", i + 1, entries.len(), track_id, e);
+                db.record_skip(&track_id, SkipReason::Failed, &e.to_string(), None, None);
+                failed += 1;
+            }
+        }
+        db.release(&track_id);
+        
+        // Save after every track so an interrupted run picks up where it stopped
+        if let Err(e) = db.save() {
+            warn!("Failed to save database: {}", e);
+        }
+    }
+    
+    println!("Done: {} posted, {} already archived, {} queued, {} ignored, {} failed", posted, already_archived, queued, ignored, failed);
+    if failed > 0 {
+        println!("Run the same command again to retry the failed tracks.");
+    }
+    Ok(())
+}
+
+/// Archive again the tracks whose audio SoundCloud restricted when they were posted
+///
+/// Goes through the tracks recorded as skipped for being geo-blocked or premium only, and
+/// posts each one whose audio is no longer restricted again, with its full audio, e.g.
+/// when run from a different region. Tracks that are still restricted are left as they are.
+pub async fn retry_restricted(api: &Arc<dyn SoundCloudApi>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = "config.json";
+    info!("Loading configuration from {}", config_path);
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    crate::locale::init(config.locale_file.as_deref())?;
+    
+    let mut db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    let mut track_ids: Vec<String> = db.skipped_tracks().iter()
+        .filter(|(_, skip)| matches!(skip.reason, SkipReason::GeoBlocked | SkipReason::PremiumOnly))
+        .map(|(track_id, _)| track_id.clone())
+        .collect();
+    track_ids.sort();
+    if track_ids.is_empty() {
+        println!("No geo-blocked or premium only tracks recorded.");
+        return Ok(());
+    }
+    api.initialize().await?;
+    
+    let discord_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_discord_parallelism));
+    let post_processing = soundcloud::PostProcessing::from_config(&config);
+    
+    println!("Retrying {} restricted tracks", track_ids.len());
+    let (mut posted, mut restricted, mut failed) = (0, 0, 0);
+    for (i, track_id) in track_ids.iter().enumerate() {
+        let track = match api.track_details(track_id, None).await {
+            Ok(track) => track,
+            Err(e) => {
+                println!("[{}/{}] {}: couldn't fetch track: {}", i + 1, track_ids.len(), track_id, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if let Some(restriction) = soundcloud::track_restriction(&track) {
+            println!("[{}/{}] {}: still restricted ({}), skipping it", i + 1, track_ids.len(), track_id, restriction);
+            restricted += 1;
+            continue;
+        }
+        
+        match soundcloud::post_track(
+            track,
+            &config.discord_webhook_url,
+            config.temp_dir.as_deref(),
+            Some(&discord_semaphore),
+            &post_processing,
+            None
+        ).await {
+            Ok(track) => {
+                println!("[{}/{}] {}: posted {} by {}", i + 1, track_ids.len(), track_id, track.track.title, track.track.user.username);
+                db.add_posted_track(&track);
+                posted += 1;
+            },
+            Err(e) if e.is::<crate::outbox::Queued>() => {
+                println!("[{}/{}] {}: queued until Discord is reachable", i + 1, track_ids.len(), track_id);
+                posted += 1;
+            },
+            Err(e) => {
+                println!("[{}/{}] {}: failed: {}", i + 1, track_ids.len(), track_id, e);
+                failed += 1;
+            }
+        }
+        
+        // Save after every track so an interrupted run picks up where it stopped
+        if let Err(e) = db.save() {
+            warn!("Failed to save database: {}", e);
+        }
+    }
+    
+    println!("Done: {} posted, {} still restricted, {} failed", posted, restricted, failed);
+    Ok(())
+}
+
 /// Generate config.json and users.json files interactively based on a SoundCloud user's followings
-pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn generate_config(api: &Arc<dyn SoundCloudApi>, url: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("Generating configuration based on SoundCloud user: {}", url);
     
     // Initialize SoundCloud client
     info!("Initializing SoundCloud client");
-    match soundcloud::initialize().await {
+    match api.initialize().await {
         Ok(_) => info!("SoundCloud client initialized successfully"),
         Err(e) => {
             error!("Failed to initialize SoundCloud client: {}", e);
@@ -237,37 +463,25 @@ pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error
         }
     }
     
-    // Resolve the URL to get the user ID
+    // Resolve the URL to get the user ID (this fails if it isn't a user's profile)
     info!("Resolving SoundCloud URL: {}", url);
-    let resolved = match soundcloud::resolve_url(url).await {
-        Ok(data) => data,
+    let user_id = match api.resolve_user_id(url).await {
+        Ok(id) => id,
         Err(e) => {
             error!("Failed to resolve URL: {}", e);
             return Err(e);
         }
     };
     
-    // Check if this is a user
-    if let Some(kind) = resolved.get("kind").and_then(|v| v.as_str()) {
-        if kind != "user" {
-            error!("The URL does not point to a user account (found: {})", kind);
-            return Err(format!("URL points to a {}, not a user", kind).into());
-        }
-    } else {
-        error!("Could not determine object type from resolved URL");
-        return Err("Could not determine object type from resolved URL".into());
-    }
-    
-    // Get the user ID and username
-    let user_id = match resolved.get("id").and_then(|v| v.as_u64()) {
-        Some(id) => id.to_string(),
-        None => {
-            error!("Could not extract user ID from resolved URL");
-            return Err("Could not extract user ID from resolved URL".into());
+    // Get the username
+    let user = match api.user_details(&user_id).await {
+        Ok(details) => details,
+        Err(e) => {
+            error!("Failed to fetch user {}: {}", user_id, e);
+            return Err(e);
         }
     };
-    
-    let username = resolved.get("username")
+    let username = user.get("username")
         .and_then(|v| v.as_str())
         .unwrap_or("Unknown");
     
@@ -281,7 +495,7 @@ pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error
     
     // Fetch the user's followings
     println!("\nFetching users that {} follows...", username);
-    let followings = match soundcloud::get_user_followings(&user_id, None).await {
+    let followings = match api.user_followings(&user_id, None).await {
         Ok(f) => f,
         Err(e) => {
             error!("Failed to fetch followings: {}", e);
@@ -292,14 +506,14 @@ pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error
     println!("Found {} users that {} follows.", followings.len(), username);
     
     // Extract user IDs and usernames
-    let mut user_ids = Vec::new();
+    let mut watched = Vec::new();
     if include_user {
-        user_ids.push(user_id.clone());
+        watched.push(WatchedUser::new(&user_id, UserSource::Manual, Some(username.to_string())));
     }
     
     // Calculate the maximum username length for formatting
     let max_username_len = followings.iter()
-        .filter_map(|u| u.get("username").and_then(|v| v.as_str()).map(|s| s.len()))
+        .filter_map(|u| u.username.as_ref().map(|s| s.len()))
         .max()
         .unwrap_or(10);
     
@@ -310,14 +524,14 @@ pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error
     println!("{}", "-".repeat(5 + 1 + max_username_len + 10 + 12 + 3));
     
     for (i, user) in followings.iter().enumerate() {
-        let following_id = user.get("id").and_then(|v| v.as_u64()).map(|id| id.to_string());
-        let following_username = user.get("username").and_then(|v| v.as_str()).unwrap_or("Unknown");
-        let track_count = user.get("track_count").and_then(|v| v.as_u64()).unwrap_or(0);
+        let following_id = user.id.map(|id| id.to_string());
+        let following_username = user.username.as_deref().unwrap_or("Unknown");
+        let track_count = user.track_count.unwrap_or(0);
         
         if let Some(id) = &following_id {
             println!("{:<5} {:<1} {:<width$} {:<10} {:<12}", 
                      i+1, "", following_username, track_count, id, width=max_username_len);
-            user_ids.push(id.clone());
+            watched.push(WatchedUser::new(id, UserSource::Manual, user.username.clone()));
         }
     }
     
@@ -442,12 +656,12 @@ pub async fn generate_config(url: &str) -> Result<(), Box<dyn std::error::Error
         db_save_tracks,
         show_ffmpeg_output,
         log_file,
+        ..Config::default()
     };
     
     // Create the users
-    let users = Users {
-        users: user_ids,
-    };
+    let mut users = Users::default();
+    users.users = watched;
     
     // Save config.json
     let config_json = serde_json::to_string_pretty(&config)?;
@@ -499,7 +713,7 @@ pub fn read_line_with_default(default: &str) -> String {
 }
 
 /// Look up a track by its Discord message ID
-pub async fn lookup_by_discord_id(discord_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn lookup_by_discord_id(api: &Arc<dyn SoundCloudApi>, discord_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load config
     let config_path = "config.json";
     info!("Loading configuration from {}", config_path);
@@ -545,11 +759,16 @@ pub async fn lookup_by_discord_id(discord_id: &str) -> Result<(), Box<dyn std::e
             if let Some(user_id) = discord_info.user_id {
                 println!("- Posted by user ID: {}", user_id);
             }
+            for (name, message) in &discord_info.destinations {
+                println!("- Message in destination {}: {}{}", name, message.id,
+                         message.channel_id.as_deref().map(|c| format!(" (channel {})", c)).unwrap_or_default());
+            }
         }
+        print_track_annotation(db.track_annotation(&track_id));
         
         // Initialize SoundCloud client to get track details
         info!("Initializing SoundCloud client to get track details");
-        match soundcloud::initialize().await {
+        match api.initialize().await {
             Ok(_) => info!("SoundCloud client initialized successfully"),
             Err(e) => {
                 error!("Failed to initialize SoundCloud client: {}", e);
@@ -558,7 +777,7 @@ pub async fn lookup_by_discord_id(discord_id: &str) -> Result<(), Box<dyn std::e
         }
         
         // Get track details
-        match soundcloud::get_track_details(&track_id).await {
+        match api.track_details(&track_id, None).await {
             Ok(track) => {
                 println!("\nTrack details:");
                 println!("- Title: {}", track.title);
@@ -604,4 +823,480 @@ pub async fn lookup_by_discord_id(discord_id: &str) -> Result<(), Box<dyn std::e
         println!("No track found with Discord message ID: {}", discord_id);
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+/// Edit the operator tags and note of a track in the database
+///
+/// `options` are `--add TAG`, `--remove TAG`, `--note TEXT` and `--clear-note`, and
+/// can be repeated. Without options, the current tags and note are shown.
+pub async fn tag_track(api: &Arc<dyn SoundCloudApi>, id_or_url: &str, options: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    
+    let mut db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    let (track_id, _) = api.resolve_track(id_or_url).await?;
+    if !db.has_track(&track_id) {
+        return Err(format!("Track {} is not in the database", track_id).into());
+    }
+    
+    let mut annotation = db.track_annotation(&track_id).cloned().unwrap_or_default();
+    let mut options = options.iter();
+    let mut changed = false;
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--add" | "--remove" | "--note" => {
+                let value = options.next()
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .ok_or_else(|| format!("{} needs a value", option))?;
+                match option.as_str() {
+                    "--add" => { annotation.tags.insert(value); },
+                    "--remove" => { annotation.tags.remove(&value); },
+                    _ => annotation.note = Some(value),
+                }
+            },
+            "--clear-note" => annotation.note = None,
+            other => return Err(format!("Unknown option for --tag-track: {}", other).into()),
+        }
+        changed = true;
+    }
+    
+    if changed {
+        db.set_track_annotation(&track_id, annotation);
+        db.save()?;
+        println!("Updated track {}:", track_id);
+    } else {
+        println!("Track {}:", track_id);
+    }
+    if let Some(track) = db.archived_track(&track_id) {
+        println!("- {} by {}", track.title, track.username);
+    }
+    match db.track_annotation(&track_id) {
+        Some(annotation) => print_track_annotation(Some(annotation)),
+        None => println!("- No tags or note"),
+    }
+    Ok(())
+}
+
+/// Print a track's operator tags and note, if it has any
+fn print_track_annotation(annotation: Option<&crate::db::TrackAnnotation>) {
+    let Some(annotation) = annotation else {
+        return;
+    };
+    if !annotation.tags.is_empty() {
+        println!("- Tags: {}", annotation.tags.iter().cloned().collect::<Vec<_>>().join(", "));
+    }
+    if let Some(note) = &annotation.note {
+        println!("- Note: {}", note);
+    }
+}
+
+/// Record users' existing tracks as archived without downloading or posting them
+///
+/// Takes one or more `--user ID` (or profile URL) and an optional `--before DATE`
+/// (YYYY-MM-DD or RFC 3339); only tracks uploaded before that date are recorded, all
+/// of them otherwise. Likes are included for users whose likes are polled.
+pub async fn mark_seen(api: &Arc<dyn SoundCloudApi>, options: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut users = Vec::new();
+    let mut before = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| format!("{} needs a value", option))?;
+        match option.as_str() {
+            "--user" => users.push(value.to_string()),
+            "--before" => before = Some(parse_date(value)
+                .ok_or_else(|| format!("Invalid date for --before: {} (use YYYY-MM-DD)", value))?),
+            other => return Err(format!("Unknown option for --mark-seen: {}", other).into()),
+        }
+    }
+    if users.is_empty() {
+        return Err("--mark-seen needs at least one --user".into());
+    }
+    
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    let mut db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    api.initialize().await?;
+    
+    let mut total_added = 0;
+    for user in &users {
+        let user_id = api.resolve_user_id(user).await?;
+        let user_config = config.for_user(&user_id);
+        let mut tracks = api.user_tracks(&user_id, user_config.max_tracks_per_user, user_config.pagination_size).await?.tracks;
+        if user_config.scrape_user_likes {
+            let likes = api.user_likes(&user_id, user_config.max_likes_per_user, user_config.pagination_size, None).await?;
+            tracks.extend(soundcloud::extract_tracks_from_likes(&likes));
+        }
+        
+        let found = tracks.len();
+        let track_ids: Vec<String> = tracks.into_iter()
+            .filter(|t| match before {
+                Some(before) => chrono::DateTime::parse_from_rfc3339(&t.created_at).is_ok_and(|d| d < before),
+                None => true,
+            })
+            .map(|t| t.id)
+            .collect();
+        let matching = track_ids.len();
+        let added = db.add_tracks(&track_ids).len();
+        total_added += added;
+        
+        match before {
+            Some(before) => println!("User {}: {} of {} tracks uploaded before {}, {} newly marked as seen",
+                                     user_id, matching, found, before.format("%Y-%m-%d %H:%M"), added),
+            None => println!("User {}: {} tracks, {} newly marked as seen", user_id, found, added),
+        }
+    }
+    
+    db.save()?;
+    println!("Marked {} tracks as seen; they won't be downloaded or posted", total_added);
+    Ok(())
+}
+
+/// Parse a date given on the command line, as a day (midnight UTC) or an RFC 3339 timestamp
+fn parse_date(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+/// Print the persisted polling state of all watched users
+pub async fn show_user_status() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Load config
+    let config_path = "config.json";
+    info!("Loading configuration from {}", config_path);
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            debug!("Configuration loaded successfully");
+            debug!("Tracks file: {}", c.tracks_file);
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    
+    let users = Users::load(&config.users_file)?;
+    let db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    
+    let format_time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+        t.map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "never".to_string())
+    };
+    
+    println!("\nPolling state for {} watched users:", users.users.len());
+    for user in &users.users {
+        let user_id = &user.id;
+        if !user.enabled {
+            let reason = match user.disabled_reason {
+                Some(DisabledReason::Unfollowed) => "disabled, unfollowed by the auto-follow source",
+                _ => "disabled",
+            };
+            println!("- {}: {}, last polled {}", user_id, reason, format_time(db.user_state(user_id).and_then(|s| s.last_polled)));
+            continue;
+        }
+        match db.user_state(user_id) {
+            Some(state) if state.deactivated_at.is_some() => {
+                println!("- {}: deactivated since {}, last polled {}, newest upload {}",
+                         user_id,
+                         format_time(state.deactivated_at),
+                         format_time(state.last_polled),
+                         format_time(state.last_track_date));
+            },
+            Some(state) => {
+                println!("- {}: last polled {}, newest upload {}, newest like {}, {} consecutive errors",
+                         user_id,
+                         format_time(state.last_polled),
+                         format_time(state.last_track_date),
+                         format_time(state.last_like_date),
+                         state.consecutive_errors);
+            },
+            None => println!("- {}: not polled yet", user_id),
+        }
+    }
+    
+    if db.pending_count() > 0 {
+        println!("\n{} tracks were found but not posted yet, they're tried again on the next poll.", db.pending_count());
+    }
+    
+    let paused = UserBackoff::paused_users(&db);
+    if paused.is_empty() {
+        println!("\nNo users are paused.");
+    } else {
+        println!("\n{} paused users:", paused.len());
+        for (user_id, state) in paused {
+            println!("- {}: {} consecutive failures, next check at {}, last error: {}",
+                     user_id,
+                     state.consecutive_errors,
+                     format_time(state.paused_until),
+                     state.last_error.as_deref().unwrap_or("unknown"));
+        }
+    }
+    
+    let deactivated = UserBackoff::deactivated_users(&db);
+    if !deactivated.is_empty() {
+        println!("\n{} deactivated users (not polled until reactivated through the admin API):", deactivated.len());
+        for (user_id, state) in deactivated {
+            println!("- {}: since {}, last error: {}",
+                     user_id,
+                     format_time(state.deactivated_at),
+                     state.last_error.as_deref().unwrap_or("unknown"));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print the fully resolved configuration with secrets redacted
+pub fn show_config() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+    
+    println!("{}", serde_json::to_string_pretty(&config.redacted()?)?);
+    Ok(())
+}
+
+/// A watched user as written by `--export-users`
+struct ExportedUser {
+    id: String,
+    username: String,
+    url: String,
+    archived_tracks: usize,
+}
+
+/// Export the watched users as CSV or OPML
+///
+/// Written to `users.<format>` unless another file is given, since the console log
+/// would otherwise end up mixed into the export.
+pub async fn export_users(api: &Arc<dyn SoundCloudApi>, format: &str, output: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if format != "csv" && format != "opml" {
+        return Err(format!("Unknown export format '{}', expected csv or opml", format).into());
+    }
+
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+
+    let users = Users::load(&config.users_file)?;
+    let db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+
+    // Usernames and URLs come from SoundCloud; without it the export still has IDs and counts
+    let online = match api.initialize().await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("Failed to initialize SoundCloud client, exporting without usernames: {}", e);
+            false
+        }
+    };
+
+    let mut exported = Vec::new();
+    for user in &users.users {
+        let user_id = &user.id;
+        let details = if online {
+            match api.user_details(user_id).await {
+                Ok(details) => Some(details),
+                Err(e) => {
+                    warn!("Failed to fetch details for user {}: {}", user_id, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let field = |name: &str| details.as_ref()
+            .and_then(|d| d.get(name))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let username = match field("username") {
+            name if name.is_empty() => user.username.clone().unwrap_or_default(),
+            name => name,
+        };
+
+        exported.push(ExportedUser {
+            id: user_id.clone(),
+            username,
+            url: field("permalink_url"),
+            archived_tracks: db.find_tracks_by_user(user_id).len(),
+        });
+    }
+
+    let rendered = if format == "csv" {
+        render_users_csv(&exported)
+    } else {
+        render_users_opml(&exported)
+    };
+
+    let path = output.map(|p| p.to_string()).unwrap_or_else(|| format!("users.{}", format));
+    std::fs::write(&path, rendered)?;
+    println!("Exported {} users to {}", exported.len(), path);
+    Ok(())
+}
+
+/// Render users as CSV with a header row
+fn render_users_csv(users: &[ExportedUser]) -> String {
+    let escape = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+
+    let mut csv = String::from("user_id,username,url,archived_tracks\n");
+    for user in users {
+        csv.push_str(&format!("{},{},{},{}\n", escape(&user.id), escape(&user.username), escape(&user.url), user.archived_tracks));
+    }
+    csv
+}
+
+/// Render users as an OPML outline, one `link` outline per user
+fn render_users_opml(users: &[ExportedUser]) -> String {
+    let escape = |text: &str| text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+    opml.push_str("  <head>\n    <title>SoundCloud Archiver watched users</title>\n");
+    opml.push_str(&format!("    <dateCreated>{}</dateCreated>\n  </head>\n  <body>\n", chrono::Utc::now().to_rfc2822()));
+    for user in users {
+        let name = if user.username.is_empty() { &user.id } else { &user.username };
+        opml.push_str(&format!(
+            "    <outline type=\"link\" text=\"{}\" url=\"{}\" soundcloudUserId=\"{}\" archivedTracks=\"{}\"/>\n",
+            escape(name), escape(&user.url), escape(&user.id), user.archived_tracks));
+    }
+    opml.push_str("  </body>\n</opml>\n");
+    opml
+}
+
+/// Render a growth chart from the stats recorded for a track or a user's tracks
+///
+/// Written as SVG to `stats_<id>.svg` unless another file is given.
+pub fn stats_chart(id: &str, output: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+
+    let db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    let Some(series) = crate::stats::series_for(&db, id) else {
+        if config.stats_snapshot_interval_hours == 0 {
+            println!("No stats recorded for {}. Set stats_snapshot_interval_hours in config.json to start recording them.", id);
+        } else {
+            println!("No stats recorded for {} yet. Stats are recorded for archived tracks while polling.", id);
+        }
+        return Ok(());
+    };
+
+    let path = output.map(|p| p.to_string()).unwrap_or_else(|| format!("stats_{}.svg", id));
+    std::fs::write(&path, crate::stats::render_svg(&series))?;
+    println!("Wrote chart of {} snapshots for {} to {}", series.snapshots.len(), series.title, path);
+    if series.snapshots.len() < 2 {
+        println!("Only one snapshot so far, the chart will show growth once more are recorded.");
+    }
+    Ok(())
+}
+
+/// Summarize why tracks were skipped, per reason and per artist
+pub fn skipped_report() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = "config.json";
+    let config = match Config::load(config_path) {
+        Ok(c) => {
+            update_log_level(&c.log_level);
+            c
+        },
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            return Err(e);
+        }
+    };
+
+    let db = TrackDatabase::load_or_create(config.tracks_file.clone())?;
+    let skipped = db.skipped_tracks();
+    if skipped.is_empty() {
+        println!("No skipped tracks recorded.");
+        return Ok(());
+    }
+
+    let mut per_reason: BTreeMap<SkipReason, usize> = BTreeMap::new();
+    // Artist (user ID) -> name and counts per reason
+    let mut per_artist: BTreeMap<String, (String, BTreeMap<SkipReason, usize>)> = BTreeMap::new();
+    for skip in skipped.values() {
+        *per_reason.entry(skip.reason).or_default() += 1;
+        let user_id = skip.user_id.clone().unwrap_or_else(|| "unknown".to_string());
+        let (name, counts) = per_artist.entry(user_id.clone())
+            .or_insert_with(|| (user_id.clone(), BTreeMap::new()));
+        if let Some(username) = &skip.username {
+            *name = username.clone();
+        }
+        *counts.entry(skip.reason).or_default() += 1;
+    }
+
+    println!("Skipped tracks: {}", skipped.len());
+    println!();
+    println!("By reason:");
+    for (reason, count) in &per_reason {
+        println!("  {:<14} {}", reason.to_string(), count);
+    }
+
+    let mut artists: Vec<_> = per_artist.into_iter().collect();
+    artists.sort_by_key(|(_, (_, counts))| std::cmp::Reverse(counts.values().sum::<usize>()));
+    println!();
+    println!("By artist:");
+    for (user_id, (name, counts)) in artists {
+        let breakdown: Vec<String> = counts.iter().map(|(reason, count)| format!("{} {}", count, reason)).collect();
+        let label = if name == user_id { user_id } else { format!("{} ({})", name, user_id) };
+        println!("  {:<40} {}", label, breakdown.join(", "));
+    }
+
+    Ok(())
+}
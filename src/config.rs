@@ -1,11 +1,63 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
 use log::{info, warn, debug, error};
 use serde_json::Value;
 use std::fs;
-use lazy_static;
+use std::time::SystemTime;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use chrono::{DateTime, Utc};
+
+/// Settings that can be overridden for an individual user
+///
+/// Any field left unset falls back to the global setting of the same name.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct UserOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tracks_per_user: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_user_likes: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_likes_per_user: Option<usize>,
+    /// Post this user's tracks to a different webhook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discord_webhook_url: Option<String>,
+    /// Post this user's tracks without downloading their audio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub likes_since_watched: Option<bool>,
+}
+
+impl UserOverrides {
+    /// Whether no setting is overridden
+    pub fn is_empty(&self) -> bool {
+        *self == UserOverrides::default()
+    }
+
+    /// Apply the settings `other` overrides on top of these
+    pub fn merge(&mut self, other: &UserOverrides) {
+        if other.max_tracks_per_user.is_some() {
+            self.max_tracks_per_user = other.max_tracks_per_user;
+        }
+        if other.scrape_user_likes.is_some() {
+            self.scrape_user_likes = other.scrape_user_likes;
+        }
+        if other.max_likes_per_user.is_some() {
+            self.max_likes_per_user = other.max_likes_per_user;
+        }
+        if other.discord_webhook_url.is_some() {
+            self.discord_webhook_url = other.discord_webhook_url.clone();
+        }
+        if other.metadata_only.is_some() {
+            self.metadata_only = other.metadata_only;
+        }
+        if other.likes_since_watched.is_some() {
+            self.likes_since_watched = other.likes_since_watched;
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
@@ -63,6 +115,222 @@ pub struct Config {
     /// Path to log file (defaults to latest.log)
     #[serde(default = "default_log_file")]
     pub log_file: String,
+    /// Address to bind the admin HTTP API to (e.g. 127.0.0.1:8080), disabled if not set
+    pub admin_api_bind: Option<String>,
+    /// Bearer token required for admin HTTP API requests
+    pub admin_api_token: Option<String>,
+    /// OTLP gRPC endpoint to export trace spans to (requires the `otel` feature)
+    pub otlp_endpoint: Option<String>,
+    /// Service name reported with exported trace spans
+    #[serde(default = "default_otlp_service_name")]
+    pub otlp_service_name: String,
+    /// Consecutive failed polls before a user is paused (0 disables pausing)
+    #[serde(default = "default_user_failure_threshold")]
+    pub user_failure_threshold: u32,
+    /// Initial pause (in seconds) for a user that hit the failure threshold
+    #[serde(default = "default_user_backoff_base_sec")]
+    pub user_backoff_base_sec: u64,
+    /// Maximum pause (in seconds) for a failing user
+    #[serde(default = "default_user_backoff_max_sec")]
+    pub user_backoff_max_sec: u64,
+    /// Maximum time (in seconds) to wait for in-flight work to finish on shutdown
+    #[serde(default = "default_shutdown_drain_timeout_sec")]
+    pub shutdown_drain_timeout_sec: u64,
+    /// Per-user settings merged over the global ones, keyed by user ID
+    #[serde(default)]
+    pub user_overrides: HashMap<String, UserOverrides>,
+    /// Named profiles run side by side in one process, each merged over these settings
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Value>,
+    /// JSON file with translations of the text in Discord posts
+    pub locale_file: Option<String>,
+    /// Rotate the log file once it reaches this size in MB (0 disables)
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u64,
+    /// Rotate the log file at midnight in `timezone`
+    #[serde(default = "default_log_rotate_daily")]
+    pub log_rotate_daily: bool,
+    /// Number of rotated log files to keep
+    #[serde(default = "default_log_retain_files")]
+    pub log_retain_files: usize,
+    /// Gzip rotated log files
+    #[serde(default = "default_log_compress")]
+    pub log_compress: bool,
+    /// Log output format, "text" or "json"
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+    /// Log level for the log file, defaults to log_level
+    pub log_file_level: Option<String>,
+    /// Sentry DSN to report errors and panics to (requires the `sentry` feature)
+    pub sentry_dsn: Option<String>,
+    /// Environment name reported to Sentry (e.g. production)
+    pub sentry_environment: Option<String>,
+    /// Extra log file that only receives warnings and errors
+    pub error_log_file: Option<String>,
+    /// Show live stats in the terminal title
+    #[serde(default = "default_console_title")]
+    pub console_title: bool,
+    /// Command to run after each track is archived, with the track as JSON on stdin
+    pub on_track_archived: Option<String>,
+    /// Command to run when a track or user poll fails, with the error as JSON on stdin
+    pub on_error: Option<String>,
+    /// How long a hook or plugin command may run before it is killed
+    #[serde(default = "default_hook_timeout_sec")]
+    pub hook_timeout_sec: u64,
+    /// Commands that can change each track's embed and attachments before it is posted
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Token that clients must send to submit tracks on the admin server's /submit endpoint
+    pub submit_token: Option<String>,
+    /// Directory to keep a local copy of every archived track in, with M3U playlists
+    pub archive_dir: Option<String>,
+    /// Look archived tracks up on MusicBrainz and tag their files with the match
+    #[serde(default = "default_musicbrainz_enrichment")]
+    pub musicbrainz_enrichment: bool,
+    /// AcoustID API key, to match tracks by audio fingerprint when enrichment is enabled
+    pub acoustid_api_key: Option<String>,
+    /// Lower the SoundCloud parallelism when requests get rate limited and raise it back when they stop
+    #[serde(default = "default_autotune_soundcloud_parallelism")]
+    pub autotune_soundcloud_parallelism: bool,
+    /// Lowest SoundCloud parallelism autotuning may go down to
+    #[serde(default = "default_min_soundcloud_parallelism")]
+    pub min_soundcloud_parallelism: usize,
+    /// Directory where tracks are queued while Discord is unreachable, posted once it recovers
+    pub discord_queue_dir: Option<String>,
+    /// Plain text line above each post's embed, with `{artist}`, `{title}` and `{url}` filled in
+    pub post_content: Option<String>,
+    /// Hours between snapshots of archived tracks' play, like and repost counts (0 = disabled)
+    #[serde(default = "default_stats_snapshot_interval_hours")]
+    pub stats_snapshot_interval_hours: u64,
+    /// Minimum seconds between fetches of a user's likes (0 = every poll)
+    #[serde(default = "default_likes_poll_interval_sec")]
+    pub likes_poll_interval_sec: u64,
+    /// How often to record each user's profile spotlight and popular tracks, in hours (0 = disabled)
+    #[serde(default = "default_profile_check_interval_hours")]
+    pub profile_check_interval_hours: u64,
+    /// Spread the first poll after startup over this many seconds instead of polling everyone at once (0 = disabled)
+    #[serde(default = "default_startup_warmup_sec")]
+    pub startup_warmup_sec: u64,
+    /// Attach the track's comments as comments.json to each post
+    #[serde(default = "default_archive_comments")]
+    pub archive_comments: bool,
+    /// Show whether a polled track is a new upload or a like in front of the embed footer
+    #[serde(default = "default_provenance_badge")]
+    pub provenance_badge: bool,
+    /// Stop downloading further formats of a track once its downloads add up to more than this (0 = unlimited)
+    #[serde(default = "default_max_track_download_mb")]
+    pub max_track_download_mb: u64,
+    /// Attach a snapshot of the track's SoundCloud page as page.html to each post
+    #[serde(default = "default_archive_page_html")]
+    pub archive_page_html: bool,
+    /// Re-check each archived track for deletion at most this often (0 = disabled)
+    #[serde(default = "default_deleted_check_interval_hours")]
+    pub deleted_check_interval_hours: u64,
+    /// Maximum number of archived tracks checked for deletion after each poll
+    #[serde(default = "default_deleted_check_batch_size")]
+    pub deleted_check_batch_size: usize,
+    /// Post a note linking to the original post when an archived track is deleted
+    #[serde(default = "default_notify_deleted_tracks")]
+    pub notify_deleted_tracks: bool,
+    /// Post the changes when an archived track's title, description, artwork or tags are edited
+    #[serde(default = "default_detect_track_edits")]
+    pub detect_track_edits: bool,
+    /// Post tracks with their artwork and metadata but without downloading audio
+    #[serde(default = "default_metadata_only")]
+    pub metadata_only: bool,
+    /// Post new albums and EPs as a whole instead of track by track
+    #[serde(default = "default_archive_albums")]
+    pub archive_albums: bool,
+    /// Replace the SoundCloud client ID this often, in hours, before it expires (0 = only when it's rejected)
+    #[serde(default = "default_client_id_refresh_hours")]
+    pub client_id_refresh_hours: u64,
+    /// Most SoundCloud API requests per minute across all users and profiles (0 = unlimited)
+    #[serde(default = "default_soundcloud_requests_per_minute")]
+    pub soundcloud_requests_per_minute: u64,
+    /// Times a track post is retried when Discord can't be reached or has a server error
+    #[serde(default = "default_webhook_retries")]
+    pub webhook_retries: u64,
+    /// Delay before the first webhook retry in milliseconds, doubled for every further retry
+    #[serde(default = "default_webhook_retry_base_ms")]
+    pub webhook_retry_base_ms: u64,
+    /// Polls in a row SoundCloud must report a user missing (404) or hidden (403) before they're deactivated (0 = never)
+    #[serde(default = "default_user_deactivation_threshold")]
+    pub user_deactivation_threshold: u32,
+    /// Post a newly watched user's tracks this many to a message, without audio, instead of one post each (0 = disabled, at most 10)
+    #[serde(default = "default_backfill_batch_size")]
+    pub backfill_batch_size: usize,
+    /// Attach the track's waveform as an SVG image next to its JSON
+    #[serde(default = "default_render_waveform")]
+    pub render_waveform: bool,
+    /// Check for a newer release at startup
+    #[serde(default = "default_update_check")]
+    pub update_check: bool,
+    /// Discord webhook for notes meant for the operator rather than the archive, such as new releases
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
+    /// Retries of a failed SoundCloud API request after the first attempt
+    #[serde(default = "default_soundcloud_retries")]
+    pub soundcloud_retries: u64,
+    /// Delay before the first SoundCloud API retry in milliseconds, doubled for every further retry
+    #[serde(default = "default_soundcloud_retry_base_ms")]
+    pub soundcloud_retry_base_ms: u64,
+    /// Longest delay between two SoundCloud API attempts in milliseconds, including a Retry-After
+    #[serde(default = "default_soundcloud_retry_max_ms")]
+    pub soundcloud_retry_max_ms: u64,
+    /// Track IDs or permalink URLs that are never archived or posted
+    #[serde(default)]
+    pub ignored_tracks: Vec<String>,
+    /// Timezone for log timestamps and daily log rotation: "local", "UTC" or an offset like "+02:00"
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Raise an alert when SoundCloud requests have been failing because of the client ID for this many minutes (0 = never)
+    #[serde(default = "default_client_id_alert_minutes")]
+    pub client_id_alert_minutes: u64,
+    /// Only archive likes made after the user was first polled, rather than their whole like history
+    #[serde(default = "default_likes_since_watched")]
+    pub likes_since_watched: bool,
+    /// Attach the animated cover (GIF or video) of tracks that have one
+    #[serde(default = "default_archive_animated_artwork")]
+    pub archive_animated_artwork: bool,
+    /// Ask SoundCloud whether users' track listings changed (ETag / Last-Modified) and reuse the last listing when they didn't
+    #[serde(default = "default_conditional_requests")]
+    pub conditional_requests: bool,
+    /// Measure the loudness of downloaded audio and write it into its tags as ReplayGain
+    #[serde(default = "default_replaygain")]
+    pub replaygain: bool,
+    /// Most MB of the temp directory that tracks being processed may take at once (0 = unlimited)
+    #[serde(default = "default_max_temp_mb")]
+    pub max_temp_mb: u64,
+    /// Disable watched users the auto-follow source unfollows, and enable them again if it follows them again
+    #[serde(default = "default_auto_follow_remove")]
+    pub auto_follow_remove: bool,
+    /// Show the track description in embeds
+    #[serde(default = "default_embed_description")]
+    pub embed_description: bool,
+    /// Show the track length in embeds
+    #[serde(default = "default_embed_duration")]
+    pub embed_duration: bool,
+    /// Show the genre in embeds
+    #[serde(default = "default_embed_genre")]
+    pub embed_genre: bool,
+    /// Show the tags in embeds
+    #[serde(default = "default_embed_tags")]
+    pub embed_tags: bool,
+    /// Show play, like, repost and comment counts in embeds
+    #[serde(default = "default_embed_stats")]
+    pub embed_stats: bool,
+    /// Show the footer text in embeds
+    #[serde(default = "default_embed_footer")]
+    pub embed_footer: bool,
+    /// Show the artist's avatar next to their name in embeds
+    #[serde(default = "default_embed_author_icon")]
+    pub embed_author_icon: bool,
+    /// More Discord webhooks every track is posted to besides `discord_webhook_url`, by destination name
+    #[serde(default)]
+    pub extra_webhooks: BTreeMap<String, String>,
+    /// How long a user's profile is trusted to tell whether their tracks changed, 0 = always fetch tracks
+    #[serde(default = "default_user_cache_ttl_sec")]
+    pub user_cache_ttl_sec: u64,
 }
 
 fn default_poll_interval() -> u64 {
@@ -141,6 +409,280 @@ fn default_log_file() -> String {
     "latest.log".to_string()
 }
 
+/// Default OTLP service name
+fn default_otlp_service_name() -> String {
+    "archiver_webhook".to_string()
+}
+
+/// Default number of consecutive failures before pausing a user
+fn default_user_failure_threshold() -> u32 {
+    5 // Pause after 5 failed polls in a row
+}
+
+/// Default initial pause for failing users
+fn default_user_backoff_base_sec() -> u64 {
+    300 // Re-check after 5 minutes
+}
+
+/// Default maximum pause for failing users
+fn default_user_backoff_max_sec() -> u64 {
+    86400 // Re-check failing users at least once a day
+}
+
+/// Default drain timeout on shutdown
+fn default_shutdown_drain_timeout_sec() -> u64 {
+    120 // Give in-flight downloads and posts up to 2 minutes to finish
+}
+
+/// Default log size limit before rotation
+fn default_log_max_size_mb() -> u64 {
+    10 // Rotate at 10 MB
+}
+
+/// Default for daily log rotation
+fn default_log_rotate_daily() -> bool {
+    false // Only rotate by size
+}
+
+/// Default number of rotated log files to keep
+fn default_log_retain_files() -> usize {
+    5 // Keep 5 old log files
+}
+
+/// Default for compressing rotated logs
+fn default_log_compress() -> bool {
+    false // Keep rotated logs as plain text
+}
+
+/// Default log output format
+fn default_log_format() -> String {
+    "text".to_string() // Human-readable lines
+}
+
+/// Default for the console title updater
+fn default_console_title() -> bool {
+    true // Update the title when running in a terminal
+}
+
+/// Default hook timeout
+fn default_hook_timeout_sec() -> u64 {
+    300 // Allow hooks up to 5 minutes
+}
+
+/// Default MusicBrainz enrichment setting
+fn default_musicbrainz_enrichment() -> bool {
+    false // Off by default, most SoundCloud uploads aren't on MusicBrainz
+}
+
+/// Default: keep the SoundCloud parallelism fixed
+fn default_autotune_soundcloud_parallelism() -> bool {
+    false
+}
+
+/// Default minimum SoundCloud parallelism when autotuning
+fn default_min_soundcloud_parallelism() -> usize {
+    1
+}
+
+/// Default: don't record track stats
+fn default_stats_snapshot_interval_hours() -> u64 {
+    0
+}
+
+/// Default: fetch likes on every poll
+fn default_likes_poll_interval_sec() -> u64 {
+    0
+}
+
+/// Default: profile checks disabled
+fn default_profile_check_interval_hours() -> u64 {
+    0
+}
+
+/// Default: no warm-up
+fn default_startup_warmup_sec() -> u64 {
+    0
+}
+
+/// Default: comments are not archived
+fn default_archive_comments() -> bool {
+    false
+}
+
+/// Default: the badge is shown
+fn default_provenance_badge() -> bool {
+    true
+}
+
+/// Default: no limit
+fn default_max_track_download_mb() -> u64 {
+    0
+}
+
+/// Default: the page is not archived
+fn default_archive_page_html() -> bool {
+    false
+}
+
+/// Default: deleted tracks are not detected
+fn default_deleted_check_interval_hours() -> u64 {
+    0
+}
+
+/// Default number of tracks checked for deletion per poll
+fn default_deleted_check_batch_size() -> usize {
+    50
+}
+
+/// Default: deletions are posted
+fn default_notify_deleted_tracks() -> bool {
+    true
+}
+
+/// Default: edits are not detected
+fn default_detect_track_edits() -> bool {
+    false
+}
+
+/// Default: audio is archived
+fn default_metadata_only() -> bool {
+    false
+}
+
+fn default_archive_albums() -> bool {
+    false
+}
+
+fn default_client_id_refresh_hours() -> u64 {
+    12
+}
+
+fn default_soundcloud_requests_per_minute() -> u64 {
+    0
+}
+
+fn default_webhook_retries() -> u64 {
+    3
+}
+
+fn default_webhook_retry_base_ms() -> u64 {
+    1000
+}
+
+fn default_user_deactivation_threshold() -> u32 {
+    3
+}
+
+/// Default backfill batch size (disabled)
+fn default_backfill_batch_size() -> usize {
+    0
+}
+
+/// Default for rendering waveforms (off)
+fn default_render_waveform() -> bool {
+    false
+}
+
+/// Default value for update_check
+fn default_update_check() -> bool {
+    false // Opt-in, it calls out to GitHub
+}
+
+/// Default value for soundcloud_retries
+fn default_soundcloud_retries() -> u64 {
+    2
+}
+
+/// Default value for soundcloud_retry_base_ms
+fn default_soundcloud_retry_base_ms() -> u64 {
+    2000
+}
+
+/// Default value for soundcloud_retry_max_ms
+fn default_soundcloud_retry_max_ms() -> u64 {
+    30000
+}
+
+/// Default value for timezone (the host's)
+fn default_timezone() -> String {
+    "local".to_string()
+}
+
+/// Default value for client_id_alert_minutes
+fn default_client_id_alert_minutes() -> u64 {
+    5
+}
+
+/// Default value for likes_since_watched
+fn default_likes_since_watched() -> bool {
+    true
+}
+
+/// Default value for archive_animated_artwork
+fn default_archive_animated_artwork() -> bool {
+    true
+}
+
+/// Default value for conditional_requests
+fn default_conditional_requests() -> bool {
+    true
+}
+
+/// Default value for replaygain
+fn default_replaygain() -> bool {
+    false
+}
+
+/// Default value for max_temp_mb
+fn default_max_temp_mb() -> u64 {
+    0
+}
+
+/// Default value for auto_follow_remove
+fn default_auto_follow_remove() -> bool {
+    false
+}
+
+/// Default value for embed_description
+fn default_embed_description() -> bool {
+    true
+}
+
+/// Default value for embed_duration
+fn default_embed_duration() -> bool {
+    true
+}
+
+/// Default value for embed_genre
+fn default_embed_genre() -> bool {
+    true
+}
+
+/// Default value for embed_tags
+fn default_embed_tags() -> bool {
+    true
+}
+
+/// Default value for embed_stats
+fn default_embed_stats() -> bool {
+    false
+}
+
+/// Default value for embed_footer
+fn default_embed_footer() -> bool {
+    true
+}
+
+/// Default value for embed_author_icon
+fn default_embed_author_icon() -> bool {
+    true
+}
+
+/// Default value for user_cache_ttl_sec
+fn default_user_cache_ttl_sec() -> u64 {
+    3600
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -163,30 +705,393 @@ impl Default for Config {
             db_save_tracks: default_db_save_tracks(),
             show_ffmpeg_output: default_show_ffmpeg_output(),
             log_file: default_log_file(),
+            admin_api_bind: None,
+            admin_api_token: None,
+            otlp_endpoint: None,
+            otlp_service_name: default_otlp_service_name(),
+            user_failure_threshold: default_user_failure_threshold(),
+            user_backoff_base_sec: default_user_backoff_base_sec(),
+            user_backoff_max_sec: default_user_backoff_max_sec(),
+            shutdown_drain_timeout_sec: default_shutdown_drain_timeout_sec(),
+            user_overrides: HashMap::new(),
+            profiles: BTreeMap::new(),
+            locale_file: None,
+            log_max_size_mb: default_log_max_size_mb(),
+            log_rotate_daily: default_log_rotate_daily(),
+            log_retain_files: default_log_retain_files(),
+            log_compress: default_log_compress(),
+            log_format: default_log_format(),
+            log_file_level: None,
+            sentry_dsn: None,
+            sentry_environment: None,
+            error_log_file: None,
+            console_title: default_console_title(),
+            on_track_archived: None,
+            on_error: None,
+            hook_timeout_sec: default_hook_timeout_sec(),
+            plugins: Vec::new(),
+            submit_token: None,
+            archive_dir: None,
+            musicbrainz_enrichment: default_musicbrainz_enrichment(),
+            acoustid_api_key: None,
+            autotune_soundcloud_parallelism: default_autotune_soundcloud_parallelism(),
+            min_soundcloud_parallelism: default_min_soundcloud_parallelism(),
+            discord_queue_dir: None,
+            post_content: None,
+            stats_snapshot_interval_hours: default_stats_snapshot_interval_hours(),
+            likes_poll_interval_sec: default_likes_poll_interval_sec(),
+            profile_check_interval_hours: default_profile_check_interval_hours(),
+            startup_warmup_sec: default_startup_warmup_sec(),
+            archive_comments: default_archive_comments(),
+            provenance_badge: default_provenance_badge(),
+            max_track_download_mb: default_max_track_download_mb(),
+            archive_page_html: default_archive_page_html(),
+            deleted_check_interval_hours: default_deleted_check_interval_hours(),
+            deleted_check_batch_size: default_deleted_check_batch_size(),
+            notify_deleted_tracks: default_notify_deleted_tracks(),
+            detect_track_edits: default_detect_track_edits(),
+            metadata_only: default_metadata_only(),
+            archive_albums: default_archive_albums(),
+            client_id_refresh_hours: default_client_id_refresh_hours(),
+            soundcloud_requests_per_minute: default_soundcloud_requests_per_minute(),
+            webhook_retries: default_webhook_retries(),
+            webhook_retry_base_ms: default_webhook_retry_base_ms(),
+            user_deactivation_threshold: default_user_deactivation_threshold(),
+            backfill_batch_size: default_backfill_batch_size(),
+            render_waveform: default_render_waveform(),
+            update_check: default_update_check(),
+            alert_webhook_url: None,
+            soundcloud_retries: default_soundcloud_retries(),
+            soundcloud_retry_base_ms: default_soundcloud_retry_base_ms(),
+            soundcloud_retry_max_ms: default_soundcloud_retry_max_ms(),
+            ignored_tracks: Vec::new(),
+            timezone: default_timezone(),
+            client_id_alert_minutes: default_client_id_alert_minutes(),
+            likes_since_watched: default_likes_since_watched(),
+            archive_animated_artwork: default_archive_animated_artwork(),
+            conditional_requests: default_conditional_requests(),
+            replaygain: default_replaygain(),
+            max_temp_mb: default_max_temp_mb(),
+            auto_follow_remove: default_auto_follow_remove(),
+            embed_description: default_embed_description(),
+            embed_duration: default_embed_duration(),
+            embed_genre: default_embed_genre(),
+            embed_tags: default_embed_tags(),
+            embed_stats: default_embed_stats(),
+            embed_footer: default_embed_footer(),
+            embed_author_icon: default_embed_author_icon(),
+            extra_webhooks: BTreeMap::new(),
+            user_cache_ttl_sec: default_user_cache_ttl_sec(),
+        }
+    }
+}
+
+/// Why a user in the users file isn't polled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisabledReason {
+    /// Disabled by hand
+    Manual,
+    /// The auto-follow source unfollowed them (see `auto_follow_remove`)
+    Unfollowed,
+}
+
+/// How a user ended up in the users file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSource {
+    /// Added by hand, through the admin API or by `--generate-config`
+    #[default]
+    Manual,
+    /// Followed by the auto-follow source
+    AutoFollow,
+}
+
+/// Format version written to the users file
+///
+/// Version 1 files (no `version` field) list users as bare IDs and keep disabled users in a
+/// separate `disabled` map. They're migrated when loaded.
+pub const USERS_FILE_VERSION: u32 = 2;
+
+fn default_user_enabled() -> bool {
+    true
+}
+
+/// A user in the users file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedUser {
+    /// User ID; a profile URL or username until resolved (see [`Users::resolve_entries`])
+    pub id: String,
+    /// Username when the user was added, to make the file easier to read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// When the user was added (unknown for users migrated from a version 1 file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub source: UserSource,
+    /// Disabled users stay in the list but aren't polled
+    #[serde(default = "default_user_enabled")]
+    pub enabled: bool,
+    /// Why the user is disabled; unset means they were disabled by hand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_reason: Option<DisabledReason>,
+    /// Settings for this user only, applied on top of `user_overrides` in the config
+    #[serde(default, skip_serializing_if = "UserOverrides::is_empty")]
+    pub overrides: UserOverrides,
+}
+
+impl WatchedUser {
+    /// A user being added now
+    pub fn new(id: &str, source: UserSource, username: Option<String>) -> Self {
+        WatchedUser {
+            username,
+            added_at: Some(Utc::now()),
+            source,
+            ..WatchedUser::from_id(id.to_string())
+        }
+    }
+
+    /// An entry with nothing but the ID known, as read from a version 1 file
+    fn from_id(id: String) -> Self {
+        WatchedUser {
+            id,
+            username: None,
+            added_at: None,
+            source: UserSource::Manual,
+            enabled: true,
+            disabled_reason: None,
+            overrides: UserOverrides::default(),
         }
     }
 }
 
+/// Read the user list, accepting bare IDs (version 1) as well as full entries
+fn deserialize_user_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<WatchedUser>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Id(String),
+        User(WatchedUser),
+    }
+    
+    Ok(Vec::<Entry>::deserialize(deserializer)?
+        .into_iter()
+        .map(|entry| match entry {
+            Entry::Id(id) => WatchedUser::from_id(id),
+            Entry::User(user) => user,
+        })
+        .collect())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Users {
-    pub users: Vec<String>,
+    /// Format version of the file, 0 when read from a file that predates versioning
+    #[serde(default)]
+    pub version: u32,
+    /// Watched users; bare IDs, profile URLs and usernames are accepted as entries too
+    #[serde(deserialize_with = "deserialize_user_entries")]
+    pub users: Vec<WatchedUser>,
+    /// Playlists (IDs or URLs) whose newly added tracks are archived
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub playlists: Vec<String>,
+    /// Disabled users from a version 1 file, folded into their entries on load
+    #[serde(default, skip_serializing)]
+    disabled: BTreeMap<String, DisabledReason>,
+    /// Watched users the auto-follow source followed at its last check, so an unfollow can
+    /// be told apart from a user that was added by hand (only kept with `auto_follow_remove`)
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub source_followings: BTreeSet<String>,
+    /// Modification time of the users file when it was last loaded or saved
+    #[serde(skip)]
+    pub loaded_mtime: Option<SystemTime>,
+}
+
+impl Default for Users {
+    fn default() -> Self {
+        Users {
+            version: USERS_FILE_VERSION,
+            users: Vec::new(),
+            playlists: Vec::new(),
+            disabled: BTreeMap::new(),
+            source_followings: BTreeSet::new(),
+            loaded_mtime: None,
+        }
+    }
+}
+
+/// Whether a users file entry is a numeric user ID
+fn is_user_id(entry: &str) -> bool {
+    !entry.is_empty() && entry.chars().all(|c| c.is_ascii_digit())
+}
+
+/// The profile URL for a users file entry given as a URL (with or without the scheme) or a
+/// bare username
+fn profile_url(entry: &str) -> String {
+    let entry = entry.trim().trim_start_matches('@');
+    if entry.starts_with("http://") || entry.starts_with("https://") {
+        entry.to_string()
+    } else if entry.contains("soundcloud.com/") {
+        format!("https://{}", entry)
+    } else {
+        format!("https://soundcloud.com/{}", entry)
+    }
+}
+
+/// Get the modification time of a file, if it can be determined
+fn file_mtime(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Prefix for environment variables that override config fields
+const ENV_PREFIX: &str = "ARCHIVER_";
+
+/// Find the known config key closest to an unknown one, to suggest a fix for typos
+fn closest_key<'a>(key: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    known
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= 3)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+/// Config fields holding secrets, which can also be read from a file via `<field>_file`
+pub const SECRET_FIELDS: &[&str] = &["discord_webhook_url", "admin_api_token", "sentry_dsn", "submit_token", "acoustid_api_key", "alert_webhook_url"];
+
+/// Resolve `<field>_file` indirection for secret fields
+///
+/// For each secret field, a path can be given with the `<field>_file` key in the config
+/// or the `ARCHIVER_<FIELD>_FILE` environment variable (e.g. a Docker/Kubernetes secret
+/// mount), and the field's value is read from that file with surrounding whitespace
+/// trimmed. A value given directly via `ARCHIVER_<FIELD>` still takes precedence.
+fn apply_secret_files(config_json: &mut Value) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(target) = config_json.as_object_mut() else {
+        return Ok(0);
+    };
+    
+    let mut applied = 0;
+    for field in SECRET_FIELDS {
+        // A direct environment variable wins over any file
+        if std::env::var(format!("{}{}", ENV_PREFIX, field.to_uppercase())).is_ok() {
+            continue;
+        }
+        
+        let file_key = format!("{}_file", field);
+        let path = match std::env::var(format!("{}{}", ENV_PREFIX, file_key.to_uppercase())) {
+            Ok(p) => p,
+            Err(_) => match target.get(&file_key).and_then(|v| v.as_str()) {
+                Some(p) => p.to_string(),
+                None => continue,
+            },
+        };
+        
+        let secret = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {} from {}: {}", field, path, e))?;
+        debug!("Read config field '{}' from {}", field, path);
+        target.insert(field.to_string(), Value::String(secret.trim().to_string()));
+        applied += 1;
+    }
+    
+    Ok(applied)
+}
+
+/// Overlay `ARCHIVER_<FIELD>` environment variables onto the raw config JSON
+///
+/// Every config field can be overridden, e.g. `ARCHIVER_DISCORD_WEBHOOK_URL` or
+/// `ARCHIVER_POLL_INTERVAL_SEC`. Values are converted to the type of the field's
+/// default: numbers, booleans and JSON maps/lists are parsed, everything else is taken as a string,
+/// and an empty value clears an optional field. Returns the number of overrides applied.
+fn apply_env_overrides(config_json: &mut Value) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let defaults = serde_json::to_value(Config::default())?;
+    let (Some(defaults), Some(target)) = (defaults.as_object(), config_json.as_object_mut()) else {
+        return Ok(0);
+    };
+    
+    let mut applied = 0;
+    for (key, default) in defaults {
+        let env_name = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+        let raw = match std::env::var(&env_name) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        
+        let value = match default {
+            Value::Bool(_) => match raw.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "0" | "false" | "no" | "off" => Value::Bool(false),
+                _ => return Err(format!("{} must be a boolean, got '{}'", env_name, raw).into()),
+            },
+            Value::Number(_) => match serde_json::from_str::<Value>(&raw) {
+                Ok(v @ Value::Number(_)) => v,
+                _ => return Err(format!("{} must be a number, got '{}'", env_name, raw).into()),
+            },
+            Value::Object(_) | Value::Array(_) => serde_json::from_str(&raw)
+                .map_err(|e| format!("{} must be valid JSON: {}", env_name, e))?,
+            Value::Null if raw.is_empty() => Value::Null,
+            _ => Value::String(raw),
+        };
+        
+        // Don't log the value, it may well be a secret
+        info!("Config field '{}' overridden by environment variable {}", key, env_name);
+        target.insert(key.clone(), value);
+        applied += 1;
+    }
+    
+    Ok(applied)
 }
 
 impl Config {
     pub fn load(config_path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        if !Path::new(config_path).exists() {
+        let file_exists = Path::new(config_path).exists();
+        if !file_exists {
             warn!("Config file not found at {}, creating default config", config_path);
             let default_config = Config::default();
             let json = serde_json::to_string_pretty(&default_config)?;
             std::fs::write(config_path, json)?;
-            return Ok(default_config);
         }
 
         // Read the file as raw JSON Value first
-        let file = File::open(config_path)?;
-        let reader = BufReader::new(file);
-        let config_json: Value = serde_json::from_reader(reader)?;
+        let mut config_json: Value = if file_exists {
+            let file = File::open(config_path)?;
+            let reader = BufReader::new(file);
+            serde_json::from_reader(reader)?
+        } else {
+            Value::Object(serde_json::Map::new())
+        };
+        
+        // Environment variables take precedence over the file, and secrets can be
+        // read from separate files so they don't have to live in config.json
+        let overrides = apply_env_overrides(&mut config_json)? + apply_secret_files(&mut config_json)?;
+        if !file_exists && overrides == 0 {
+            return Ok(Config::default());
+        }
         
+        let config = Config::from_json(&config_json)?;
+        
+        info!("Loaded configuration from {}", config_path);
+        debug!("Config: log_level={}, poll_interval={}s, max_tracks={}, scrape_likes={}, max_concurrent_processing={}",
+               config.log_level, config.poll_interval_sec, config.max_tracks_per_user, 
+               config.scrape_user_likes, config.max_processing_parallelism);
+        Ok(config)
+    }
+    
+    /// Build a config from raw config JSON, starting from the defaults
+    fn from_json(config_json: &Value) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Start with the default config
         let mut config = Config::default();
         
@@ -275,79 +1180,907 @@ impl Config {
             config.log_file = log_file.to_string();
         }
         
-        // Validate required fields
-        if config.discord_webhook_url.is_empty() {
-            return Err("discord_webhook_url is required in config.json".into());
+        if let Some(admin_bind) = config_json.get("admin_api_bind") {
+            if admin_bind.is_null() {
+                config.admin_api_bind = None;
+            } else if let Some(value) = admin_bind.as_str() {
+                config.admin_api_bind = Some(value.to_string());
+            }
         }
         
-        info!("Loaded configuration from {}", config_path);
-        debug!("Config: log_level={}, poll_interval={}s, max_tracks={}, scrape_likes={}, max_concurrent_processing={}",
-               config.log_level, config.poll_interval_sec, config.max_tracks_per_user, 
-               config.scrape_user_likes, config.max_processing_parallelism);
-        Ok(config)
-    }
-    
-    /// Static access to show_ffmpeg_output setting
-    /// Used in audio.rs to check if ffmpeg output should be shown
-    pub fn show_ffmpeg_output() -> Option<bool> {
-        lazy_static::lazy_static! {
-            static ref CONFIG_VALUE: std::sync::Mutex<Option<bool>> = std::sync::Mutex::new(None);
+        if let Some(admin_token) = config_json.get("admin_api_token") {
+            if admin_token.is_null() {
+                config.admin_api_token = None;
+            } else if let Some(value) = admin_token.as_str() {
+                config.admin_api_token = Some(value.to_string());
+            }
         }
         
-        let lock = CONFIG_VALUE.lock().unwrap();
-        *lock
-    }
-    
-    /// Set the value for the static show_ffmpeg_output access
-    pub fn set_show_ffmpeg_output(value: bool) {
-        lazy_static::lazy_static! {
-            static ref CONFIG_VALUE: std::sync::Mutex<Option<bool>> = std::sync::Mutex::new(None);
+        if let Some(otlp_endpoint) = config_json.get("otlp_endpoint") {
+            if otlp_endpoint.is_null() {
+                config.otlp_endpoint = None;
+            } else if let Some(value) = otlp_endpoint.as_str() {
+                config.otlp_endpoint = Some(value.to_string());
+            }
         }
         
-        let mut lock = CONFIG_VALUE.lock().unwrap();
-        *lock = Some(value);
-    }
-}
-
-impl Users {
-    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        if !Path::new(path).exists() {
-            warn!("Users file not found at {}, creating empty list", path);
-            let empty_users = Users { users: Vec::new() };
-            let json = serde_json::to_string_pretty(&empty_users)?;
-            std::fs::write(path, json)?;
-            return Ok(empty_users);
+        if let Some(otlp_service_name) = config_json.get("otlp_service_name").and_then(|v| v.as_str()) {
+            config.otlp_service_name = otlp_service_name.to_string();
         }
-
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let users: Users = serde_json::from_reader(reader)?;
         
-        info!("Loaded {} users from {}", users.users.len(), path);
-        Ok(users)
-    }
-
-    /// Save users list to a file
-    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        debug!("Saving {} users to file: {}", self.users.len(), path);
+        if let Some(user_failure_threshold) = config_json.get("user_failure_threshold").and_then(|v| v.as_u64()) {
+            config.user_failure_threshold = user_failure_threshold as u32;
+        }
         
-        // First, create a backup of the existing file if it exists
-        let backup_path = format!("{}.bak", path);
-        if Path::new(path).exists() {
-            debug!("Creating backup of existing users file");
-            match fs::copy(path, &backup_path) {
-                Ok(_) => debug!("Created backup at {}", backup_path),
-                Err(e) => warn!("Failed to create backup file {}: {}", backup_path, e),
-            }
+        if let Some(user_backoff_base_sec) = config_json.get("user_backoff_base_sec").and_then(|v| v.as_u64()) {
+            config.user_backoff_base_sec = user_backoff_base_sec;
         }
         
-        // Write directly to target file
-        let file = match File::create(path) {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Failed to create users file {}: {}", path, e);
-                return Err(e.into());
-            }
+        if let Some(user_backoff_max_sec) = config_json.get("user_backoff_max_sec").and_then(|v| v.as_u64()) {
+            config.user_backoff_max_sec = user_backoff_max_sec;
+        }
+        
+        if let Some(shutdown_drain_timeout_sec) = config_json.get("shutdown_drain_timeout_sec").and_then(|v| v.as_u64()) {
+            config.shutdown_drain_timeout_sec = shutdown_drain_timeout_sec;
+        }
+        
+        if let Some(user_overrides) = config_json.get("user_overrides") {
+            config.user_overrides = serde_json::from_value(user_overrides.clone())
+                .map_err(|e| format!("Invalid user_overrides: {}", e))?;
+        }
+        
+        if let Some(profiles) = config_json.get("profiles").and_then(|v| v.as_object()) {
+            config.profiles = profiles.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        }
+        
+        if let Some(locale_file) = config_json.get("locale_file") {
+            if locale_file.is_null() {
+                config.locale_file = None;
+            } else if let Some(value) = locale_file.as_str() {
+                config.locale_file = Some(value.to_string());
+            }
+        }
+        
+        if let Some(log_max_size_mb) = config_json.get("log_max_size_mb").and_then(|v| v.as_u64()) {
+            config.log_max_size_mb = log_max_size_mb;
+        }
+        
+        if let Some(log_rotate_daily) = config_json.get("log_rotate_daily").and_then(|v| v.as_bool()) {
+            config.log_rotate_daily = log_rotate_daily;
+        }
+        
+        if let Some(log_retain_files) = config_json.get("log_retain_files").and_then(|v| v.as_u64()) {
+            config.log_retain_files = log_retain_files as usize;
+        }
+        
+        if let Some(log_compress) = config_json.get("log_compress").and_then(|v| v.as_bool()) {
+            config.log_compress = log_compress;
+        }
+        
+        if let Some(log_format) = config_json.get("log_format").and_then(|v| v.as_str()) {
+            config.log_format = log_format.to_string();
+        }
+        
+        if let Some(log_file_level) = config_json.get("log_file_level") {
+            if log_file_level.is_null() {
+                config.log_file_level = None;
+            } else if let Some(value) = log_file_level.as_str() {
+                config.log_file_level = Some(value.to_string());
+            }
+        }
+        
+        if let Some(sentry_dsn) = config_json.get("sentry_dsn") {
+            if sentry_dsn.is_null() {
+                config.sentry_dsn = None;
+            } else if let Some(value) = sentry_dsn.as_str() {
+                config.sentry_dsn = Some(value.to_string());
+            }
+        }
+        
+        if let Some(sentry_environment) = config_json.get("sentry_environment") {
+            if sentry_environment.is_null() {
+                config.sentry_environment = None;
+            } else if let Some(value) = sentry_environment.as_str() {
+                config.sentry_environment = Some(value.to_string());
+            }
+        }
+        
+        if let Some(error_log_file) = config_json.get("error_log_file") {
+            if error_log_file.is_null() {
+                config.error_log_file = None;
+            } else if let Some(value) = error_log_file.as_str() {
+                config.error_log_file = Some(value.to_string());
+            }
+        }
+        
+        if let Some(console_title) = config_json.get("console_title").and_then(|v| v.as_bool()) {
+            config.console_title = console_title;
+        }
+        
+        if let Some(on_track_archived) = config_json.get("on_track_archived") {
+            if on_track_archived.is_null() {
+                config.on_track_archived = None;
+            } else if let Some(value) = on_track_archived.as_str() {
+                config.on_track_archived = Some(value.to_string());
+            }
+        }
+        
+        if let Some(on_error) = config_json.get("on_error") {
+            if on_error.is_null() {
+                config.on_error = None;
+            } else if let Some(value) = on_error.as_str() {
+                config.on_error = Some(value.to_string());
+            }
+        }
+        
+        if let Some(hook_timeout_sec) = config_json.get("hook_timeout_sec").and_then(|v| v.as_u64()) {
+            config.hook_timeout_sec = hook_timeout_sec;
+        }
+        
+        if let Some(plugins) = config_json.get("plugins") {
+            config.plugins = serde_json::from_value(plugins.clone())
+                .map_err(|e| format!("Invalid plugins: {}", e))?;
+        }
+        
+        if let Some(submit_token) = config_json.get("submit_token") {
+            if submit_token.is_null() {
+                config.submit_token = None;
+            } else if let Some(value) = submit_token.as_str() {
+                config.submit_token = Some(value.to_string());
+            }
+        }
+        
+        if let Some(archive_dir) = config_json.get("archive_dir") {
+            if archive_dir.is_null() {
+                config.archive_dir = None;
+            } else if let Some(value) = archive_dir.as_str() {
+                config.archive_dir = Some(value.to_string());
+            }
+        }
+        
+        if let Some(musicbrainz_enrichment) = config_json.get("musicbrainz_enrichment").and_then(|v| v.as_bool()) {
+            config.musicbrainz_enrichment = musicbrainz_enrichment;
+        }
+        
+        if let Some(acoustid_api_key) = config_json.get("acoustid_api_key") {
+            if acoustid_api_key.is_null() {
+                config.acoustid_api_key = None;
+            } else if let Some(value) = acoustid_api_key.as_str() {
+                config.acoustid_api_key = Some(value.to_string());
+            }
+        }
+        
+        if let Some(autotune) = config_json.get("autotune_soundcloud_parallelism").and_then(|v| v.as_bool()) {
+            config.autotune_soundcloud_parallelism = autotune;
+        }
+        
+        if let Some(min_parallelism) = config_json.get("min_soundcloud_parallelism").and_then(|v| v.as_u64()) {
+            config.min_soundcloud_parallelism = min_parallelism as usize;
+        }
+        
+        if let Some(discord_queue_dir) = config_json.get("discord_queue_dir") {
+            if discord_queue_dir.is_null() {
+                config.discord_queue_dir = None;
+            } else if let Some(value) = discord_queue_dir.as_str() {
+                config.discord_queue_dir = Some(value.to_string());
+            }
+        }
+        
+        if let Some(post_content) = config_json.get("post_content") {
+            if post_content.is_null() {
+                config.post_content = None;
+            } else if let Some(value) = post_content.as_str() {
+                config.post_content = Some(value.to_string());
+            }
+        }
+        
+        if let Some(stats_interval) = config_json.get("stats_snapshot_interval_hours").and_then(|v| v.as_u64()) {
+            config.stats_snapshot_interval_hours = stats_interval;
+        }
+        
+        if let Some(likes_interval) = config_json.get("likes_poll_interval_sec").and_then(|v| v.as_u64()) {
+            config.likes_poll_interval_sec = likes_interval;
+        }
+        
+        if let Some(profile_interval) = config_json.get("profile_check_interval_hours").and_then(|v| v.as_u64()) {
+            config.profile_check_interval_hours = profile_interval;
+        }
+        
+        if let Some(warmup) = config_json.get("startup_warmup_sec").and_then(|v| v.as_u64()) {
+            config.startup_warmup_sec = warmup;
+        }
+        
+        if let Some(archive_comments) = config_json.get("archive_comments").and_then(|v| v.as_bool()) {
+            config.archive_comments = archive_comments;
+        }
+        
+        if let Some(provenance_badge) = config_json.get("provenance_badge").and_then(|v| v.as_bool()) {
+            config.provenance_badge = provenance_badge;
+        }
+        
+        if let Some(max_track_download_mb) = config_json.get("max_track_download_mb").and_then(|v| v.as_u64()) {
+            config.max_track_download_mb = max_track_download_mb;
+        }
+        
+        if let Some(archive_page_html) = config_json.get("archive_page_html").and_then(|v| v.as_bool()) {
+            config.archive_page_html = archive_page_html;
+        }
+        
+        if let Some(deleted_check_interval_hours) = config_json.get("deleted_check_interval_hours").and_then(|v| v.as_u64()) {
+            config.deleted_check_interval_hours = deleted_check_interval_hours;
+        }
+        
+        if let Some(deleted_check_batch_size) = config_json.get("deleted_check_batch_size").and_then(|v| v.as_u64()) {
+            config.deleted_check_batch_size = deleted_check_batch_size as usize;
+        }
+        
+        if let Some(notify_deleted_tracks) = config_json.get("notify_deleted_tracks").and_then(|v| v.as_bool()) {
+            config.notify_deleted_tracks = notify_deleted_tracks;
+        }
+        
+        if let Some(detect_track_edits) = config_json.get("detect_track_edits").and_then(|v| v.as_bool()) {
+            config.detect_track_edits = detect_track_edits;
+        }
+        
+        if let Some(metadata_only) = config_json.get("metadata_only").and_then(|v| v.as_bool()) {
+            config.metadata_only = metadata_only;
+        }
+        
+        if let Some(archive_albums) = config_json.get("archive_albums").and_then(|v| v.as_bool()) {
+            config.archive_albums = archive_albums;
+        }
+        
+        if let Some(client_id_refresh_hours) = config_json.get("client_id_refresh_hours").and_then(|v| v.as_u64()) {
+            config.client_id_refresh_hours = client_id_refresh_hours;
+        }
+        
+        if let Some(soundcloud_requests_per_minute) = config_json.get("soundcloud_requests_per_minute").and_then(|v| v.as_u64()) {
+            config.soundcloud_requests_per_minute = soundcloud_requests_per_minute;
+        }
+        
+        if let Some(webhook_retries) = config_json.get("webhook_retries").and_then(|v| v.as_u64()) {
+            config.webhook_retries = webhook_retries;
+        }
+        
+        if let Some(webhook_retry_base_ms) = config_json.get("webhook_retry_base_ms").and_then(|v| v.as_u64()) {
+            config.webhook_retry_base_ms = webhook_retry_base_ms;
+        }
+        
+        if let Some(user_deactivation_threshold) = config_json.get("user_deactivation_threshold").and_then(|v| v.as_u64()) {
+            config.user_deactivation_threshold = user_deactivation_threshold as u32;
+        }
+        
+        if let Some(backfill_batch_size) = config_json.get("backfill_batch_size").and_then(|v| v.as_u64()) {
+            config.backfill_batch_size = backfill_batch_size as usize;
+        }
+        
+        if let Some(render_waveform) = config_json.get("render_waveform").and_then(|v| v.as_bool()) {
+            config.render_waveform = render_waveform;
+        }
+        
+        if let Some(update_check) = config_json.get("update_check").and_then(|v| v.as_bool()) {
+            config.update_check = update_check;
+        }
+        
+        if let Some(alert_webhook_url) = config_json.get("alert_webhook_url") {
+            if alert_webhook_url.is_null() {
+                config.alert_webhook_url = None;
+            } else if let Some(value) = alert_webhook_url.as_str() {
+                config.alert_webhook_url = Some(value.to_string());
+            }
+        }
+        
+        if let Some(soundcloud_retries) = config_json.get("soundcloud_retries").and_then(|v| v.as_u64()) {
+            config.soundcloud_retries = soundcloud_retries;
+        }
+        
+        if let Some(soundcloud_retry_base_ms) = config_json.get("soundcloud_retry_base_ms").and_then(|v| v.as_u64()) {
+            config.soundcloud_retry_base_ms = soundcloud_retry_base_ms;
+        }
+        
+        if let Some(soundcloud_retry_max_ms) = config_json.get("soundcloud_retry_max_ms").and_then(|v| v.as_u64()) {
+            config.soundcloud_retry_max_ms = soundcloud_retry_max_ms;
+        }
+        
+        if let Some(ignored_tracks) = config_json.get("ignored_tracks") {
+            config.ignored_tracks = serde_json::from_value(ignored_tracks.clone())
+                .map_err(|e| format!("Invalid ignored_tracks: {}", e))?;
+        }
+        
+        if let Some(timezone) = config_json.get("timezone").and_then(|v| v.as_str()) {
+            config.timezone = timezone.to_string();
+        }
+        
+        if let Some(client_id_alert_minutes) = config_json.get("client_id_alert_minutes").and_then(|v| v.as_u64()) {
+            config.client_id_alert_minutes = client_id_alert_minutes;
+        }
+        
+        if let Some(likes_since_watched) = config_json.get("likes_since_watched").and_then(|v| v.as_bool()) {
+            config.likes_since_watched = likes_since_watched;
+        }
+        
+        if let Some(archive_animated_artwork) = config_json.get("archive_animated_artwork").and_then(|v| v.as_bool()) {
+            config.archive_animated_artwork = archive_animated_artwork;
+        }
+        
+        if let Some(conditional_requests) = config_json.get("conditional_requests").and_then(|v| v.as_bool()) {
+            config.conditional_requests = conditional_requests;
+        }
+        
+        if let Some(replaygain) = config_json.get("replaygain").and_then(|v| v.as_bool()) {
+            config.replaygain = replaygain;
+        }
+        
+        if let Some(max_temp_mb) = config_json.get("max_temp_mb").and_then(|v| v.as_u64()) {
+            config.max_temp_mb = max_temp_mb;
+        }
+        
+        if let Some(auto_follow_remove) = config_json.get("auto_follow_remove").and_then(|v| v.as_bool()) {
+            config.auto_follow_remove = auto_follow_remove;
+        }
+        
+        if let Some(embed_description) = config_json.get("embed_description").and_then(|v| v.as_bool()) {
+            config.embed_description = embed_description;
+        }
+        
+        if let Some(embed_duration) = config_json.get("embed_duration").and_then(|v| v.as_bool()) {
+            config.embed_duration = embed_duration;
+        }
+        
+        if let Some(embed_genre) = config_json.get("embed_genre").and_then(|v| v.as_bool()) {
+            config.embed_genre = embed_genre;
+        }
+        
+        if let Some(embed_tags) = config_json.get("embed_tags").and_then(|v| v.as_bool()) {
+            config.embed_tags = embed_tags;
+        }
+        
+        if let Some(embed_stats) = config_json.get("embed_stats").and_then(|v| v.as_bool()) {
+            config.embed_stats = embed_stats;
+        }
+        
+        if let Some(embed_footer) = config_json.get("embed_footer").and_then(|v| v.as_bool()) {
+            config.embed_footer = embed_footer;
+        }
+        
+        if let Some(embed_author_icon) = config_json.get("embed_author_icon").and_then(|v| v.as_bool()) {
+            config.embed_author_icon = embed_author_icon;
+        }
+        
+        if let Some(extra_webhooks) = config_json.get("extra_webhooks") {
+            config.extra_webhooks = serde_json::from_value(extra_webhooks.clone())
+                .map_err(|e| format!("Invalid extra_webhooks: {}", e))?;
+        }
+        
+        if let Some(user_cache_ttl_sec) = config_json.get("user_cache_ttl_sec").and_then(|v| v.as_u64()) {
+            config.user_cache_ttl_sec = user_cache_ttl_sec;
+        }
+        
+        // Validate everything and report all problems at once
+        config.validate(config_json)?;
+        
+        Ok(config)
+    }
+    
+    /// Check the raw config JSON and the resulting config for problems
+    ///
+    /// Unknown keys (usually typos, since unknown keys would otherwise be silently
+    /// ignored) are logged as warnings. Values of the wrong type and out-of-range
+    /// settings are errors, and all of them are reported together.
+    fn validate(&self, config_json: &Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let defaults = serde_json::to_value(Config::default())?;
+        let defaults = defaults.as_object().cloned().unwrap_or_default();
+        let mut errors: Vec<String> = Vec::new();
+        
+        if let Some(fields) = config_json.as_object() {
+            for (key, value) in fields {
+                let is_secret_file = key.strip_suffix("_file").is_some_and(|f| SECRET_FIELDS.contains(&f));
+                let default = match defaults.get(key) {
+                    Some(d) => d,
+                    None if is_secret_file => continue,
+                    None => {
+                        match closest_key(key, defaults.keys()) {
+                            Some(suggestion) => warn!("Unknown config key '{}' will be ignored (did you mean '{}'?)", key, suggestion),
+                            None => warn!("Unknown config key '{}' will be ignored", key),
+                        }
+                        continue;
+                    }
+                };
+                
+                // Optional fields accept null, everything else must match the default's type
+                let type_ok = match default {
+                    Value::Bool(_) => value.is_boolean(),
+                    Value::Number(_) => value.is_u64(),
+                    Value::String(_) => value.is_string(),
+                    Value::Null => value.is_null() || value.is_string(),
+                    Value::Object(_) => value.is_object(),
+                    Value::Array(_) => value.is_array(),
+                };
+                if !type_ok {
+                    let expected = match default {
+                        Value::Bool(_) => "a boolean",
+                        Value::Number(_) => "a non-negative integer",
+                        Value::String(_) => "a string",
+                        Value::Null => "a string or null",
+                        Value::Object(_) => "an object",
+                        Value::Array(_) => "a list",
+                    };
+                    errors.push(format!("'{}' must be {}, got {}", key, expected, value));
+                }
+            }
+        }
+        
+        // With profiles, each profile is checked for its own webhook instead
+        if self.discord_webhook_url.is_empty() && self.profiles.is_empty() {
+            errors.push("discord_webhook_url is required (set it in config.json, via discord_webhook_url_file, or ARCHIVER_DISCORD_WEBHOOK_URL)".to_string());
+        } else if !self.discord_webhook_url.is_empty() && !self.discord_webhook_url.starts_with("http") {
+            errors.push("discord_webhook_url must be an http(s) URL".to_string());
+        }
+        if self.alert_webhook_url.as_deref().is_some_and(|u| !u.is_empty() && !u.starts_with("http")) {
+            errors.push("alert_webhook_url must be an http(s) URL".to_string());
+        }
+        
+        if !["trace", "debug", "info", "warn", "error"].contains(&self.log_level.to_lowercase().as_str()) {
+            errors.push(format!("log_level must be one of trace, debug, info, warn, error (got '{}')", self.log_level));
+        }
+        
+        if let Some(level) = &self.log_file_level {
+            if !["trace", "debug", "info", "warn", "error"].contains(&level.to_lowercase().as_str()) {
+                errors.push(format!("log_file_level must be one of trace, debug, info, warn, error (got '{}')", level));
+            }
+        }
+        
+        if !["text", "json"].contains(&self.log_format.as_str()) {
+            errors.push(format!("log_format must be text or json (got '{}')", self.log_format));
+        }
+        if let Err(e) = crate::timezone::Timezone::parse(&self.timezone) {
+            errors.push(format!("timezone: {}", e));
+        }
+        
+        let must_be_positive: [(&str, u64); 11] = [
+            ("poll_interval_sec", self.poll_interval_sec),
+            ("pagination_size", self.pagination_size as u64),
+            ("max_soundcloud_parallelism", self.max_soundcloud_parallelism as u64),
+            ("min_soundcloud_parallelism", self.min_soundcloud_parallelism as u64),
+            ("max_discord_parallelism", self.max_discord_parallelism as u64),
+            ("max_processing_parallelism", self.max_processing_parallelism as u64),
+            ("auto_follow_interval", self.auto_follow_interval as u64),
+            ("db_save_interval", self.db_save_interval as u64),
+            ("db_save_tracks", self.db_save_tracks as u64),
+            ("user_backoff_base_sec", self.user_backoff_base_sec),
+            ("deleted_check_batch_size", self.deleted_check_batch_size as u64),
+        ];
+        for (name, value) in must_be_positive {
+            if value == 0 {
+                errors.push(format!("{} must be at least 1", name));
+            }
+        }
+        
+        if self.min_soundcloud_parallelism > self.max_soundcloud_parallelism {
+            errors.push("min_soundcloud_parallelism must not be more than max_soundcloud_parallelism".to_string());
+        }
+        
+        if self.startup_warmup_sec > 0 && self.startup_warmup_sec >= self.poll_interval_sec {
+            errors.push("startup_warmup_sec must be less than poll_interval_sec".to_string());
+        }
+        if self.user_backoff_max_sec < self.user_backoff_base_sec {
+            errors.push("user_backoff_max_sec must not be less than user_backoff_base_sec".to_string());
+        }
+        if self.soundcloud_retry_max_ms < self.soundcloud_retry_base_ms {
+            errors.push("soundcloud_retry_max_ms must not be less than soundcloud_retry_base_ms".to_string());
+        }
+        if self.backfill_batch_size > crate::discord::MAX_EMBEDS_PER_MESSAGE {
+            errors.push(format!("backfill_batch_size must not be more than {}, Discord's limit of embeds per message",
+                                crate::discord::MAX_EMBEDS_PER_MESSAGE));
+        }
+        
+        for (name, url) in &self.extra_webhooks {
+            if name.trim().is_empty() {
+                errors.push("extra_webhooks names must not be empty".to_string());
+            } else if !url.starts_with("http") {
+                errors.push(format!("extra_webhooks.{} must be an http(s) URL", name));
+            }
+        }
+        
+        for (user_id, overrides) in &self.user_overrides {
+            if overrides.discord_webhook_url.as_deref().is_some_and(|u| !u.starts_with("http")) {
+                errors.push(format!("user_overrides.{}.discord_webhook_url must be an http(s) URL", user_id));
+            }
+        }
+        
+        for (name, profile) in &self.profiles {
+            if !profile.is_object() {
+                errors.push(format!("profiles.{} must be an object", name));
+            } else if profile.get("profiles").is_some() {
+                errors.push(format!("profiles.{} can't contain its own profiles", name));
+            }
+        }
+        
+        if errors.is_empty() {
+            return Ok(());
+        }
+        
+        for e in &errors {
+            error!("Config error: {}", e);
+        }
+        Err(format!("Invalid configuration ({} problem(s)): {}", errors.len(), errors.join("; ")).into())
+    }
+    
+    /// Get the config as JSON with all secret values replaced by `<redacted>`
+    pub fn redacted(&self) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        const REDACTED: &str = "<redacted>";
+        let mut json = serde_json::to_value(self)?;
+        
+        for field in SECRET_FIELDS {
+            if let Some(value) = json.get_mut(*field) {
+                if value.as_str().is_some_and(|v| !v.is_empty()) {
+                    *value = Value::String(REDACTED.to_string());
+                }
+            }
+        }
+        
+        // Per-user webhook URLs are just as sensitive as the global one
+        if let Some(overrides) = json.get_mut("user_overrides").and_then(|v| v.as_object_mut()) {
+            for user in overrides.values_mut() {
+                if let Some(webhook) = user.get_mut("discord_webhook_url") {
+                    *webhook = Value::String(REDACTED.to_string());
+                }
+            }
+        }
+        
+        if let Some(webhooks) = json.get_mut("extra_webhooks").and_then(|v| v.as_object_mut()) {
+            for webhook in webhooks.values_mut() {
+                *webhook = Value::String(REDACTED.to_string());
+            }
+        }
+        
+        // Profiles can carry their own webhook URL and API token
+        if let Some(profiles) = json.get_mut("profiles").and_then(|v| v.as_object_mut()) {
+            for profile in profiles.values_mut() {
+                for field in SECRET_FIELDS {
+                    if let Some(value) = profile.get_mut(*field) {
+                        *value = Value::String(REDACTED.to_string());
+                    }
+                }
+            }
+        }
+        
+        Ok(json)
+    }
+    
+    /// Get the effective config of every profile, or an empty list if none are defined
+    ///
+    /// Each profile's settings are merged over this config, so shared settings only need
+    /// to be set once at the top level. Secrets can be read from files with `<field>_file`
+    /// keys inside the profile. Profiles must not share a users file or tracks database.
+    pub fn profiles(&self) -> Result<Vec<(String, Config)>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut base = serde_json::to_value(self)?;
+        if let Some(fields) = base.as_object_mut() {
+            fields.remove("profiles");
+        }
+        
+        let mut profiles = Vec::new();
+        for (name, overrides) in &self.profiles {
+            let mut profile_json = base.clone();
+            if let (Some(target), Some(overrides)) = (profile_json.as_object_mut(), overrides.as_object()) {
+                for (key, value) in overrides {
+                    target.insert(key.clone(), value.clone());
+                }
+                
+                for field in SECRET_FIELDS {
+                    let file_key = format!("{}_file", field);
+                    if let Some(path) = overrides.get(&file_key).and_then(|v| v.as_str()) {
+                        let secret = fs::read_to_string(path)
+                            .map_err(|e| format!("Failed to read {} for profile {} from {}: {}", field, name, path, e))?;
+                        target.insert(field.to_string(), Value::String(secret.trim().to_string()));
+                    }
+                }
+            }
+            
+            let config = Config::from_json(&profile_json)
+                .map_err(|e| format!("Profile {}: {}", name, e))?;
+            profiles.push((name.clone(), config));
+        }
+        
+        // Profiles sharing these files would clobber each other's state
+        for (i, (name, config)) in profiles.iter().enumerate() {
+            for (other_name, other) in &profiles[i + 1..] {
+                if config.users_file == other.users_file {
+                    return Err(format!("Profiles {} and {} both use users file {}", name, other_name, config.users_file).into());
+                }
+                if config.tracks_file == other.tracks_file {
+                    return Err(format!("Profiles {} and {} both use tracks file {}", name, other_name, config.tracks_file).into());
+                }
+            }
+        }
+        
+        Ok(profiles)
+    }
+    
+    /// Get the effective config for polling a user, with their overrides applied
+    pub fn for_user(&self, user_id: &str) -> Config {
+        let mut config = self.clone();
+        if let Some(overrides) = self.user_overrides.get(user_id) {
+            if let Some(max_tracks) = overrides.max_tracks_per_user {
+                config.max_tracks_per_user = max_tracks;
+            }
+            if let Some(scrape_likes) = overrides.scrape_user_likes {
+                config.scrape_user_likes = scrape_likes;
+            }
+            if let Some(max_likes) = overrides.max_likes_per_user {
+                config.max_likes_per_user = max_likes;
+            }
+            if let Some(webhook_url) = &overrides.discord_webhook_url {
+                config.discord_webhook_url = webhook_url.clone();
+            }
+            if let Some(metadata_only) = overrides.metadata_only {
+                config.metadata_only = metadata_only;
+            }
+            if let Some(likes_since_watched) = overrides.likes_since_watched {
+                config.likes_since_watched = likes_since_watched;
+            }
+        }
+        config
+    }
+    
+    /// Static access to show_ffmpeg_output setting
+    /// Used in audio.rs to check if ffmpeg output should be shown
+    pub fn show_ffmpeg_output() -> Option<bool> {
+        lazy_static::lazy_static! {
+            static ref CONFIG_VALUE: std::sync::Mutex<Option<bool>> = std::sync::Mutex::new(None);
+        }
+        
+        let lock = CONFIG_VALUE.lock().unwrap();
+        *lock
+    }
+    
+    /// Set the value for the static show_ffmpeg_output access
+    pub fn set_show_ffmpeg_output(value: bool) {
+        lazy_static::lazy_static! {
+            static ref CONFIG_VALUE: std::sync::Mutex<Option<bool>> = std::sync::Mutex::new(None);
+        }
+        
+        let mut lock = CONFIG_VALUE.lock().unwrap();
+        *lock = Some(value);
+    }
+}
+
+impl Users {
+    /// Users that are polled: everyone in the list that isn't disabled
+    ///
+    /// Entries that couldn't be resolved to a user ID yet are left out.
+    pub fn active(&self) -> Vec<String> {
+        self.users.iter()
+            .filter(|user| user.enabled && is_user_id(&user.id))
+            .map(|user| user.id.clone())
+            .collect()
+    }
+
+    /// IDs of every user in the list, disabled or not
+    pub fn ids(&self) -> Vec<String> {
+        self.users.iter().map(|user| user.id.clone()).collect()
+    }
+
+    /// Whether a user is in the list
+    pub fn contains(&self, id: &str) -> bool {
+        self.users.iter().any(|user| user.id == id)
+    }
+
+    /// Add a user to the list, returning false if they're already in it
+    pub fn add(&mut self, user: WatchedUser) -> bool {
+        if self.contains(&user.id) {
+            return false;
+        }
+        self.users.push(user);
+        true
+    }
+
+    /// Remove a user from the list, returning false if they weren't in it
+    pub fn remove(&mut self, id: &str) -> bool {
+        let count_before = self.users.len();
+        self.users.retain(|user| user.id != id);
+        self.source_followings.remove(id);
+        self.users.len() != count_before
+    }
+
+    /// Per-user overrides set in the users file, by user ID
+    pub fn overrides(&self) -> HashMap<String, UserOverrides> {
+        self.users.iter()
+            .filter(|user| !user.overrides.is_empty())
+            .map(|user| (user.id.clone(), user.overrides.clone()))
+            .collect()
+    }
+
+    /// Entries that are profile URLs or usernames rather than user IDs
+    pub fn unresolved(&self) -> Vec<String> {
+        self.users.iter().filter(|user| !is_user_id(&user.id)).map(|user| user.id.clone()).collect()
+    }
+
+    /// Replace profile URLs and usernames in the list with the users' IDs, and save the
+    /// file if any were resolved
+    ///
+    /// `soundcloud.com/artistname`, `https://soundcloud.com/artistname` and a bare
+    /// `artistname` all work. Entries that can't be resolved are kept as they are and
+    /// tried again after the next change to the file; they aren't polled meanwhile.
+    /// Returns the number of entries resolved.
+    pub async fn resolve_entries(&mut self, api: &dyn crate::soundcloud_api::SoundCloudApi, path: &str) -> usize {
+        let unresolved = self.unresolved();
+        if unresolved.is_empty() {
+            return 0;
+        }
+        info!("Resolving {} users given by URL or username in {}", unresolved.len(), path);
+        
+        let mut resolved = 0;
+        for entry in unresolved {
+            let user_id = match api.resolve_user_id(&profile_url(&entry)).await {
+                Ok(id) if is_user_id(&id) => id,
+                Ok(id) => {
+                    warn!("User {} in {} resolved to {}, which isn't a user ID, keeping it as is", entry, path, id);
+                    continue;
+                },
+                Err(e) => {
+                    warn!("Failed to resolve user {} in {}, it won't be polled until it can be: {}", entry, path, e);
+                    continue;
+                }
+            };
+            info!("Resolved user {} to ID {}", entry, user_id);
+            
+            // Keep the entry's place in the list, and don't watch anyone twice
+            if self.contains(&user_id) {
+                self.users.retain(|user| user.id != entry);
+            } else if let Some(user) = self.users.iter_mut().find(|user| user.id == entry) {
+                user.id = user_id;
+            }
+            resolved += 1;
+        }
+        
+        if resolved > 0 {
+            if let Err(e) = self.save(path) {
+                warn!("Failed to save resolved user IDs to {}: {}", path, e);
+            }
+        }
+        resolved
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if !Path::new(path).exists() {
+            warn!("Users file not found at {}, creating empty list", path);
+            let mut empty_users = Users::default();
+            let json = serde_json::to_string_pretty(&empty_users)?;
+            std::fs::write(path, json)?;
+            empty_users.loaded_mtime = file_mtime(path);
+            return Ok(empty_users);
+        }
+
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut users: Users = serde_json::from_reader(reader)?;
+        users.loaded_mtime = file_mtime(path);
+        if users.migrate() {
+            users.save_migrated(path);
+        }
+        
+        info!("Loaded {} users from {}", users.users.len(), path);
+        let unresolved = users.unresolved().len();
+        if unresolved > 0 {
+            info!("{} of them are given by URL or username and are resolved to IDs once the SoundCloud client is ready", unresolved);
+        }
+        let disabled = users.users.iter().filter(|user| !user.enabled).count();
+        if disabled > 0 {
+            info!("{} of them are disabled and won't be polled", disabled);
+        }
+        if !users.playlists.is_empty() {
+            info!("Watching {} playlists from {}", users.playlists.len(), path);
+        }
+        Ok(users)
+    }
+
+    /// Reload the users list if the file has been modified since it was last loaded or saved
+    /// 
+    /// This picks up manual edits to the users file without requiring a restart.
+    /// Returns true if the list was reloaded. If the file can't be parsed (e.g. it's
+    /// mid-edit), the current list is kept and the reload is retried on the next check.
+    pub fn reload_if_changed(&mut self, path: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let current_mtime = match file_mtime(path) {
+            Some(t) => t,
+            None => {
+                debug!("Could not read modification time of {}, skipping reload check", path);
+                return Ok(false);
+            }
+        };
+        
+        if self.loaded_mtime == Some(current_mtime) {
+            return Ok(false);
+        }
+        
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut reloaded: Users = match serde_json::from_reader(reader) {
+            Ok(u) => u,
+            Err(e) => {
+                warn!("Users file {} changed but could not be parsed, keeping current list: {}", path, e);
+                return Ok(false);
+            }
+        };
+        let migrated = reloaded.migrate();
+        
+        let added = reloaded.users.iter().filter(|user| !self.contains(&user.id)).count();
+        let removed = self.users.iter().filter(|user| !reloaded.contains(&user.id)).count();
+        
+        self.version = reloaded.version;
+        self.users = reloaded.users;
+        self.playlists = reloaded.playlists;
+        self.disabled = reloaded.disabled;
+        self.source_followings = reloaded.source_followings;
+        self.loaded_mtime = Some(current_mtime);
+        if migrated {
+            self.save_migrated(path);
+        }
+        
+        info!("Reloaded users file {}: {} users ({} added, {} removed), {} playlists", 
+             path, self.users.len(), added, removed, self.playlists.len());
+        Ok(true)
+    }
+
+    /// Bring a list read from an older file format up to the current one, returning
+    /// whether anything changed
+    fn migrate(&mut self) -> bool {
+        if self.version >= USERS_FILE_VERSION && self.disabled.is_empty() {
+            return false;
+        }
+        
+        for (id, reason) in std::mem::take(&mut self.disabled) {
+            if let Some(user) = self.users.iter_mut().find(|user| user.id == id) {
+                user.enabled = false;
+                user.disabled_reason = Some(reason);
+            }
+        }
+        if self.version < USERS_FILE_VERSION {
+            for user in self.users.iter_mut().filter(|user| self.source_followings.contains(&user.id)) {
+                user.source = UserSource::AutoFollow;
+            }
+        }
+        self.version = USERS_FILE_VERSION;
+        true
+    }
+
+    /// Write a migrated list back in the current format, keeping the old file next to it
+    ///
+    /// If the old file can't be copied it's left untouched; the list is still used as
+    /// migrated and written out with the next change.
+    fn save_migrated(&mut self, path: &str) {
+        let old_path = format!("{}.v1", path);
+        if let Err(e) = fs::copy(path, &old_path) {
+            warn!("Failed to keep a copy of users file {} at {}, not migrating it yet: {}", path, old_path, e);
+            return;
+        }
+        match self.save(path) {
+            Ok(_) => info!("Migrated users file {} to version {}, the old file was kept at {}", path, USERS_FILE_VERSION, old_path),
+            Err(e) => warn!("Failed to save migrated users file {}: {}", path, e),
+        }
+    }
+
+    /// Save users list to a file
+    pub fn save(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Saving {} users to file: {}", self.users.len(), path);
+        
+        // First, create a backup of the existing file if it exists
+        let backup_path = format!("{}.bak", path);
+        if Path::new(path).exists() {
+            debug!("Creating backup of existing users file");
+            match fs::copy(path, &backup_path) {
+                Ok(_) => debug!("Created backup at {}", backup_path),
+                Err(e) => warn!("Failed to create backup file {}: {}", backup_path, e),
+            }
+        }
+        
+        // Write directly to target file
+        let file = match File::create(path) {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Failed to create users file {}: {}", path, e);
+                return Err(e.into());
+            }
         };
         
         let writer = BufWriter::new(file);
@@ -375,6 +2108,8 @@ impl Users {
             }
         }
         
+        self.loaded_mtime = file_mtime(path);
+        
         info!("Successfully saved {} users to {}", self.users.len(), path);
         Ok(())
     }
@@ -383,17 +2118,23 @@ impl Users {
     /// 
     /// This method fetches followings from a SoundCloud user and adds
     /// any new followings to the users list, then saves the changes.
+    ///
+    /// With `remove_unfollowed`, users the source followed at the last check and no longer
+    /// does are disabled rather than deleted, and enabled again if the source follows them
+    /// again. Users added by hand are left alone.
     pub async fn update_followings_from_source(
         &mut self,
+        api: &dyn crate::soundcloud_api::SoundCloudApi,
         source: &str,
-        users_file: &str
+        users_file: &str,
+        remove_unfollowed: bool
     ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
         info!("Checking for new users followed by source: {}", source);
         
         // Initialize SoundCloud client if not already done
         if crate::soundcloud::get_client_id().is_none() {
             info!("Initializing SoundCloud client");
-            match crate::soundcloud::initialize().await {
+            match api.initialize().await {
                 Ok(_) => info!("SoundCloud client initialized successfully"),
                 Err(e) => {
                     error!("Failed to initialize SoundCloud client: {}", e);
@@ -403,42 +2144,11 @@ impl Users {
         }
         
         // Determine if the source is an ID or URL
-        let user_id = if source.contains("soundcloud.com") || source.contains("http") {
-            // It's a URL, resolve it
-            info!("Resolving URL to user ID: {}", source);
-            match crate::soundcloud::resolve_url(source).await {
-                Ok(data) => {
-                    if let Some(kind) = data.get("kind").and_then(|v| v.as_str()) {
-                        if kind == "user" {
-                            match data.get("id").and_then(|v| v.as_u64()) {
-                                Some(id) => id.to_string(),
-                                None => {
-                                    error!("Could not extract user ID from resolved URL data");
-                                    return Err("Missing user ID in resolved data".into());
-                                }
-                            }
-                        } else {
-                            error!("URL resolved to non-user kind: {}", kind);
-                            return Err(format!("URL resolved to non-user kind: {}", kind).into());
-                        }
-                    } else {
-                        error!("URL resolved to object with missing kind");
-                        return Err("URL resolved to object with missing kind".into());
-                    }
-                },
-                Err(e) => {
-                    error!("Failed to resolve URL {}: {}", source, e);
-                    return Err(e);
-                }
-            }
-        } else {
-            // It's already an ID
-            source.to_string()
-        };
+        let user_id = api.resolve_user_id(source).await?;
         
         // Fetch the user's followings
         info!("Fetching followings for user ID: {}", user_id);
-        let followings = match crate::soundcloud::get_user_followings(&user_id, None).await {
+        let followings = match api.user_followings(&user_id, None).await {
             Ok(f) => f,
             Err(e) => {
                 error!("Failed to fetch followings: {}", e);
@@ -448,44 +2158,200 @@ impl Users {
         
         info!("Found {} followings for source user", followings.len());
         
+        // Pick up any manual edits before merging, so saving doesn't clobber them
+        if let Err(e) = self.reload_if_changed(users_file) {
+            warn!("Failed to reload users file {} before auto-follow update: {}", users_file, e);
+        }
+        
         // Extract user IDs from followings
         let following_ids: Vec<String> = followings.iter()
-            .filter_map(|f| f.get("id").and_then(|v| v.as_u64()).map(|id| id.to_string()))
+            .filter_map(|f| f.id.map(|id| id.to_string()))
             .collect();
         
         // Find new followings not already in users list
         let new_followings: Vec<String> = following_ids.iter()
-            .filter(|id| !self.users.contains(id))
+            .filter(|id| !self.contains(id))
             .cloned()
             .collect();
         
         let count = new_followings.len();
+        // Extract username if available, kept with the entry and used for logging
+        let known_username = |id: &str| followings.iter()
+            .find(|u| u.id.map(|i| i.to_string()).as_deref() == Some(id))
+            .and_then(|u| u.username.clone());
+        let username = |id: &str| known_username(id).unwrap_or_else(|| "Unknown".to_string());
+        
+        let mut changed = false;
+        if remove_unfollowed {
+            changed = self.mirror_unfollows(&following_ids, &username);
+        } else if !self.source_followings.is_empty() {
+            self.source_followings.clear();
+            changed = true;
+        }
         
         if count > 0 {
             info!("Adding {} new followings to users list", count);
             for id in &new_followings {
-                // Extract username if available for logging
-                let username = followings.iter()
-                    .find(|u| u.get("id").and_then(|v| v.as_u64()).map(|i| i.to_string()) == Some(id.clone()))
-                    .and_then(|u| u.get("username").and_then(|v| v.as_str()))
-                    .unwrap_or("Unknown");
-                
-                info!("Adding new user to watch: {} ({})", username, id);
-                self.users.push(id.clone());
+                info!("Adding new user to watch: {} ({})", username(id), id);
+                self.add(WatchedUser::new(id, UserSource::AutoFollow, known_username(id)));
+                if remove_unfollowed {
+                    self.source_followings.insert(id.clone());
+                }
             }
-            
+        } else {
+            debug!("No new followings found for user {}", user_id);
+        }
+        
+        if count > 0 || changed {
             // Save updated users file
             match self.save(users_file) {
-                Ok(_) => info!("Successfully saved {} new users to {}", count, users_file),
+                Ok(_) => info!("Successfully saved users file {} with {} new users", users_file, count),
                 Err(e) => {
                     error!("Failed to save updated users file: {}", e);
                     return Err(e);
                 }
             }
-        } else {
-            debug!("No new followings found for user {}", user_id);
         }
         
         Ok(count)
     }
-} 
\ No newline at end of file
+
+    /// Disable watched users the source unfollowed since the last check, and enable the
+    /// ones it followed again, returning whether anything changed
+    fn mirror_unfollows(&mut self, following_ids: &[String], username: &dyn Fn(&str) -> String) -> bool {
+        let following: BTreeSet<&String> = following_ids.iter().collect();
+        let mut changed = false;
+        
+        // An empty list is far more likely a failed fetch than an unfollow of everyone
+        if !following.is_empty() {
+            for user in self.users.iter_mut() {
+                if user.enabled && self.source_followings.contains(&user.id) && !following.contains(&user.id) {
+                    info!("Source unfollowed user {}, disabling them (set \"enabled\" back to true in the users file to poll them again)", user.id);
+                    user.enabled = false;
+                    user.disabled_reason = Some(DisabledReason::Unfollowed);
+                    changed = true;
+                }
+            }
+        }
+        
+        for user in self.users.iter_mut() {
+            if !user.enabled && user.disabled_reason == Some(DisabledReason::Unfollowed) && following.contains(&user.id) {
+                info!("Source follows {} ({}) again, enabling them", username(&user.id), user.id);
+                user.enabled = true;
+                user.disabled_reason = None;
+                changed = true;
+            }
+        }
+        
+        let watched: BTreeSet<String> = following_ids.iter()
+            .filter(|id| self.contains(id))
+            .cloned()
+            .collect();
+        if !following.is_empty() && watched != self.source_followings {
+            self.source_followings = watched;
+            changed = true;
+        }
+        changed
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// The default config with the one setting it can't do without
+    fn valid_config() -> Config {
+        Config { discord_webhook_url: "https://discord.com/api/webhooks/1/abc".to_string(), ..Config::default() }
+    }
+
+    fn assert_rejected(config: &Config, config_json: &Value, problem: &str) {
+        let error = config.validate(config_json).expect_err(problem).to_string();
+        assert!(error.contains(problem), "expected '{}' in: {}", problem, error);
+    }
+
+    #[test]
+    fn default_config_passes() {
+        let config = valid_config();
+        config.validate(&json!({})).unwrap();
+        // A config file spelling out every default is just as valid
+        config.validate(&serde_json::to_value(&config).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rejects_wrong_value_types() {
+        let config = valid_config();
+        assert_rejected(&config, &json!({ "poll_interval_sec": "60" }), "'poll_interval_sec' must be a non-negative integer");
+        assert_rejected(&config, &json!({ "poll_interval_sec": -1 }), "'poll_interval_sec' must be a non-negative integer");
+        assert_rejected(&config, &json!({ "log_level": 3 }), "'log_level' must be a string");
+        assert_rejected(&config, &json!({ "temp_dir": false }), "'temp_dir' must be a string or null");
+        assert_rejected(&config, &json!({ "extra_webhooks": [] }), "'extra_webhooks' must be an object");
+        // Unknown keys only warn
+        config.validate(&json!({ "pol_interval_sec": 60 })).unwrap();
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_webhooks() {
+        assert_rejected(&Config::default(), &json!({}), "discord_webhook_url is required");
+        let profiles = Config { profiles: BTreeMap::from([("main".to_string(), json!({}))]), ..Config::default() };
+        profiles.validate(&json!({})).unwrap();
+
+        let config = Config { discord_webhook_url: "discord.com/api/webhooks/1/abc".to_string(), ..Config::default() };
+        assert_rejected(&config, &json!({}), "discord_webhook_url must be an http(s) URL");
+        let config = Config { alert_webhook_url: Some("alerts".to_string()), ..valid_config() };
+        assert_rejected(&config, &json!({}), "alert_webhook_url must be an http(s) URL");
+        let config = Config { extra_webhooks: BTreeMap::from([(" ".to_string(), "https://example.com".to_string())]), ..valid_config() };
+        assert_rejected(&config, &json!({}), "extra_webhooks names must not be empty");
+        let config = Config { extra_webhooks: BTreeMap::from([("mirror".to_string(), "example.com".to_string())]), ..valid_config() };
+        assert_rejected(&config, &json!({}), "extra_webhooks.mirror must be an http(s) URL");
+        let overrides = UserOverrides { discord_webhook_url: Some("example.com".to_string()), ..UserOverrides::default() };
+        let config = Config { user_overrides: HashMap::from([("123".to_string(), overrides)]), ..valid_config() };
+        assert_rejected(&config, &json!({}), "user_overrides.123.discord_webhook_url must be an http(s) URL");
+    }
+
+    #[test]
+    fn rejects_invalid_logging_and_timezone() {
+        let config = Config { log_level: "verbose".to_string(), ..valid_config() };
+        assert_rejected(&config, &json!({}), "log_level must be one of");
+        let config = Config { log_file_level: Some("loud".to_string()), ..valid_config() };
+        assert_rejected(&config, &json!({}), "log_file_level must be one of");
+        let config = Config { log_format: "xml".to_string(), ..valid_config() };
+        assert_rejected(&config, &json!({}), "log_format must be text or json");
+        let config = Config { timezone: "Not/AZone".to_string(), ..valid_config() };
+        assert_rejected(&config, &json!({}), "timezone:");
+    }
+
+    #[test]
+    fn rejects_zero_counts_and_intervals() {
+        let config = Config { poll_interval_sec: 0, ..valid_config() };
+        assert_rejected(&config, &json!({}), "poll_interval_sec must be at least 1");
+        let config = Config { pagination_size: 0, ..valid_config() };
+        assert_rejected(&config, &json!({}), "pagination_size must be at least 1");
+        let config = Config { max_processing_parallelism: 0, ..valid_config() };
+        assert_rejected(&config, &json!({}), "max_processing_parallelism must be at least 1");
+        let config = Config { deleted_check_batch_size: 0, ..valid_config() };
+        assert_rejected(&config, &json!({}), "deleted_check_batch_size must be at least 1");
+    }
+
+    #[test]
+    fn rejects_inconsistent_limits() {
+        let config = Config { min_soundcloud_parallelism: 5, max_soundcloud_parallelism: 2, ..valid_config() };
+        assert_rejected(&config, &json!({}), "min_soundcloud_parallelism must not be more than max_soundcloud_parallelism");
+        let config = Config { startup_warmup_sec: 60, poll_interval_sec: 60, ..valid_config() };
+        assert_rejected(&config, &json!({}), "startup_warmup_sec must be less than poll_interval_sec");
+        let config = Config { user_backoff_base_sec: 600, user_backoff_max_sec: 60, ..valid_config() };
+        assert_rejected(&config, &json!({}), "user_backoff_max_sec must not be less than user_backoff_base_sec");
+        let config = Config { soundcloud_retry_base_ms: 5000, soundcloud_retry_max_ms: 1000, ..valid_config() };
+        assert_rejected(&config, &json!({}), "soundcloud_retry_max_ms must not be less than soundcloud_retry_base_ms");
+        let config = Config { backfill_batch_size: crate::discord::MAX_EMBEDS_PER_MESSAGE + 1, ..valid_config() };
+        assert_rejected(&config, &json!({}), "backfill_batch_size must not be more than");
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let config = Config { profiles: BTreeMap::from([("main".to_string(), json!("config.json"))]), ..valid_config() };
+        assert_rejected(&config, &json!({}), "profiles.main must be an object");
+        let config = Config { profiles: BTreeMap::from([("main".to_string(), json!({ "profiles": {} }))]), ..valid_config() };
+        assert_rejected(&config, &json!({}), "profiles.main can't contain its own profiles");
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::path::Path;
+use axum::{
+    Router,
+    extract::{Path as UrlPath, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use base64::Engine;
+use log::{warn, debug};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use tower_http::services::ServeDir;
+
+use crate::admin::AdminState;
+use crate::db::{ArchivedTrack, TrackDatabase};
+
+/// Tracks shown per page
+const PAGE_SIZE: usize = 50;
+
+/// Server the webhook posts to, looked up once to build links to Discord messages
+static GUILD_ID: OnceCell<Option<String>> = OnceCell::const_new();
+
+/// Build the read-only dashboard router
+///
+/// Served under `/dashboard` next to the admin API and protected by the same token,
+/// sent either as a bearer token or as the password of a browser login (any username).
+/// Files in `archive_dir` are served under `/dashboard/files` when it's set.
+pub fn router(state: AdminState) -> Router {
+    let mut router = Router::new()
+        .route("/dashboard", get(recent))
+        .route("/dashboard/artists/{user_id}", get(artist));
+    if let Some(dir) = state.config.archive_dir.as_deref().filter(|d| !d.is_empty()) {
+        router = router.nest_service("/dashboard/files", ServeDir::new(dir));
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), require_login))
+        .with_state(state)
+}
+
+/// Ask the browser to log in unless the request carries the admin token
+async fn require_login(State(state): State<AdminState>, request: Request, next: Next) -> Response {
+    let expected = state.config.admin_api_token.as_deref().unwrap_or("");
+    let authorized = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            if let Some(token) = v.strip_prefix("Bearer ") {
+                crate::admin::token_matches(token, expected)
+            } else if let Some(credentials) = v.strip_prefix("Basic ") {
+                base64::engine::general_purpose::STANDARD.decode(credentials).ok()
+                    .and_then(|c| String::from_utf8(c).ok())
+                    .and_then(|c| c.split_once(':').map(|(_, password)| crate::admin::token_matches(password, expected)))
+                    .unwrap_or(false)
+            } else {
+                false
+            }
+        });
+
+    if authorized {
+        next.run(request).await
+    } else {
+        debug!("Asking for dashboard login for {}", request.uri().path());
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"SoundCloud Archiver\"")],
+            "Log in with any username and the admin API token as the password",
+        ).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// Case-insensitive search in titles and usernames
+    q: Option<String>,
+    /// 1-based page number
+    page: Option<usize>,
+}
+
+/// GET /dashboard - recently archived tracks, newest first
+async fn recent(State(state): State<AdminState>, Query(query): Query<ListQuery>) -> Html<String> {
+    let guild_id = guild_id(&state).await;
+    let db = state.db.lock().await;
+    let total = db.track_count();
+    let without_details = total.saturating_sub(db.archived_tracks().len());
+
+    let mut body = search_form("/dashboard", query.q.as_deref());
+    body.push_str(&track_list(&state, &db, guild_id, "/dashboard", &query, |_| true));
+    if without_details > 0 {
+        body.push_str(&format!(
+            "<p class=\"note\">{} of {} tracks were archived before track details were recorded and aren't listed.</p>",
+            without_details, total));
+    }
+
+    page("Archive", &body)
+}
+
+/// GET /dashboard/artists/{user_id} - tracks archived from one uploader
+async fn artist(State(state): State<AdminState>, UrlPath(user_id): UrlPath<String>, Query(query): Query<ListQuery>) -> Html<String> {
+    let guild_id = guild_id(&state).await;
+    let db = state.db.lock().await;
+    let username = db.archived_tracks().values()
+        .find(|t| t.user_id == user_id)
+        .map(|t| t.username.clone())
+        .unwrap_or_else(|| user_id.clone());
+
+    let base = format!("/dashboard/artists/{}", encode_path(&user_id));
+    let mut body = format!("<p><a href=\"/dashboard\">&larr; All tracks</a></p>{}", search_form(&base, query.q.as_deref()));
+    body.push_str(&track_list(&state, &db, guild_id, &base, &query, |t| t.user_id == user_id));
+
+    page(&username, &body)
+}
+
+/// The server the webhook posts to, looked up on first use
+async fn guild_id(state: &AdminState) -> Option<&'static str> {
+    GUILD_ID.get_or_init(|| async {
+        match crate::discord::get_webhook_guild_id(&state.config.discord_webhook_url).await {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Failed to look up the webhook's server, Discord links won't be shown: {}", e);
+                None
+            }
+        }
+    }).await.as_deref()
+}
+
+/// Render one page of the archived tracks matching `filter` and the search query
+fn track_list(
+    state: &AdminState,
+    db: &TrackDatabase,
+    guild_id: Option<&str>,
+    base: &str,
+    query: &ListQuery,
+    filter: impl Fn(&ArchivedTrack) -> bool
+) -> String {
+    let search = query.q.as_deref().unwrap_or("").trim().to_lowercase();
+    let mut tracks: Vec<(&String, &ArchivedTrack)> = db.archived_tracks().iter()
+        .filter(|(_, t)| filter(t))
+        .filter(|(id, t)| search.is_empty()
+            || t.title.to_lowercase().contains(&search)
+            || t.username.to_lowercase().contains(&search)
+            || id.as_str() == search)
+        .collect();
+    tracks.sort_by_key(|(_, t)| std::cmp::Reverse(t.archived_at));
+
+    if tracks.is_empty() {
+        return "<p>No archived tracks found.</p>".to_string();
+    }
+
+    let pages = tracks.len().div_ceil(PAGE_SIZE);
+    let current = query.page.unwrap_or(1).clamp(1, pages);
+    let mut archived_files = ArchivedFiles::new(state.config.archive_dir.as_deref());
+
+    let mut html = format!("<p>{} tracks</p><ul class=\"tracks\">", tracks.len());
+    for (track_id, track) in tracks.iter().skip((current - 1) * PAGE_SIZE).take(PAGE_SIZE) {
+        let artwork = match &track.artwork_url {
+            Some(url) => format!("<img src=\"{}\" alt=\"\" loading=\"lazy\">", escape(url)),
+            None => "<div class=\"no-art\"></div>".to_string(),
+        };
+
+        let mut links = vec![format!("<a href=\"{}\">SoundCloud</a>", escape(&track.permalink_url))];
+        if let (Some(guild), Some(discord)) = (guild_id, db.get_discord_info(track_id)) {
+            if let Some(channel) = &discord.channel_id {
+                links.push(format!("<a href=\"https://discord.com/channels/{}/{}/{}\">Discord</a>",
+                                   escape(guild), escape(channel), escape(&discord.id)));
+            }
+        }
+        for (name, href) in archived_files.for_track(track_id, track) {
+            links.push(format!("<a href=\"{}\">{}</a>", href, escape(&name)));
+        }
+
+        let musicbrainz = match db.track_metadata(track_id) {
+            Some(m) => format!(" &middot; <a href=\"https://musicbrainz.org/recording/{}\">MusicBrainz: {} - {}</a>",
+                               escape(&m.recording_id), escape(&m.artist), escape(&m.title)),
+            None => String::new(),
+        };
+
+        html.push_str(&format!(
+            "<li>{}<div><strong>{}</strong><br><a href=\"/dashboard/artists/{}\">{}</a> &middot; archived {}{}<br><span class=\"links\">{}</span></div></li>",
+            artwork,
+            escape(&track.title),
+            encode_path(&track.user_id),
+            escape(&track.username),
+            track.archived_at.format("%Y-%m-%d %H:%M UTC"),
+            musicbrainz,
+            links.join(" &middot; "),
+        ));
+    }
+    html.push_str("</ul>");
+
+    if pages > 1 {
+        let q = query.q.as_deref().map(|q| format!("&q={}", encode_path(q))).unwrap_or_default();
+        html.push_str("<p class=\"pages\">");
+        if current > 1 {
+            html.push_str(&format!("<a href=\"{}?page={}{}\">&larr; Newer</a> ", base, current - 1, q));
+        }
+        html.push_str(&format!("Page {} of {}", current, pages));
+        if current < pages {
+            html.push_str(&format!(" <a href=\"{}?page={}{}\">Older &rarr;</a>", base, current + 1, q));
+        }
+        html.push_str("</p>");
+    }
+
+    html
+}
+
+/// Finds a track's files in the local archive, listing each artist folder only once
+struct ArchivedFiles<'a> {
+    dir: Option<&'a str>,
+    folders: HashMap<String, Vec<String>>,
+}
+
+impl<'a> ArchivedFiles<'a> {
+    fn new(dir: Option<&'a str>) -> Self {
+        ArchivedFiles { dir: dir.filter(|d| !d.is_empty()), folders: HashMap::new() }
+    }
+
+    /// The track's files as (name, link) pairs
+    fn for_track(&mut self, track_id: &str, track: &ArchivedTrack) -> Vec<(String, String)> {
+        let Some(dir) = self.dir else {
+            return Vec::new();
+        };
+
+        let folder = crate::audio::sanitize_filename(&track.username);
+        let files = self.folders.entry(folder.clone()).or_insert_with(|| {
+            std::fs::read_dir(Path::new(dir).join(&folder))
+                .map(|entries| entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect())
+                .unwrap_or_default()
+        });
+
+        let prefix = format!("{}_", track_id);
+        let mut found: Vec<(String, String)> = files.iter()
+            .filter_map(|f| f.strip_prefix(&prefix).map(|name| (name.to_string(),
+                format!("/dashboard/files/{}/{}", encode_path(&folder), encode_path(f)))))
+            .collect();
+        found.sort();
+        found
+    }
+}
+
+/// Search box that submits back to `action`
+fn search_form(action: &str, q: Option<&str>) -> String {
+    format!(
+        "<form action=\"{}\"><input type=\"search\" name=\"q\" value=\"{}\" placeholder=\"Search titles and artists\"> <button>Search</button></form>",
+        action, escape(q.unwrap_or("")))
+}
+
+/// Wrap the body in the page layout
+fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} - SoundCloud Archiver</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+a {{ color: #f50; }}
+ul.tracks {{ list-style: none; padding: 0; }}
+ul.tracks li {{ display: flex; gap: 1rem; padding: .75rem 0; border-bottom: 1px solid #eee; }}
+ul.tracks img, .no-art {{ width: 80px; height: 80px; flex-shrink: 0; object-fit: cover; background: #eee; }}
+.links, .note, .pages {{ font-size: .9rem; color: #666; }}
+input[type=search] {{ width: 20rem; max-width: 70%; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}
+</body>
+</html>
+"#, title = escape(title), body = body))
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Percent-encode a single URL path segment or query value
+fn encode_path(segment: &str) -> String {
+    segment.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_quotes_and_ampersands_in_attributes() {
+        let html = format!("<a title=\"{}\">", escape(r#"Tom & Jerry's "Remix" <live>"#));
+        assert_eq!(html, "<a title=\"Tom &amp; Jerry&#39;s &quot;Remix&quot; &lt;live&gt;\">");
+        // Already-escaped text is escaped again rather than passed through
+        assert_eq!(escape("&amp;"), "&amp;amp;");
+    }
+
+    #[test]
+    fn percent_encodes_multi_byte_utf8() {
+        assert_eq!(encode_path("café"), "caf%C3%A9");
+        assert_eq!(encode_path("音楽"), "%E9%9F%B3%E6%A5%BD");
+        assert_eq!(encode_path("a-b_c.d~e"), "a-b_c.d~e");
+    }
+
+    #[test]
+    fn encodes_slash_in_username_folder() {
+        assert_eq!(encode_path("AC/DC"), "AC%2FDC");
+        assert_eq!(encode_path("a b&c=d?"), "a%20b%26c%3Dd%3F");
+    }
+}
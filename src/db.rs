@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::{File, copy, remove_file};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read};
 use std::path::Path;
 use log::{info, debug, trace, error, warn};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use serde_json::Value;
+use chrono::{DateTime, Utc};
+use tracing::Instrument;
+use crate::musicbrainz::TrackMetadata;
+use crate::soundcloud_api::SoundCloudApi;
+use crate::supervisor::spawn_supervised;
 
 /// Discord message information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +21,270 @@ pub struct DiscordMessage {
     pub channel_id: Option<String>,
     /// User who originally posted the track
     pub user_id: Option<String>,
+    /// The track's messages in the `extra_webhooks` destinations, by destination name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub destinations: BTreeMap<String, DestinationMessage>,
+    /// `extra_webhooks` destinations the track still has to be posted to, retried every poll
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub pending_destinations: BTreeSet<String>,
+}
+
+/// A track's message in one of the `extra_webhooks` destinations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationMessage {
+    pub id: String,
+    pub channel_id: Option<String>,
+}
+
+/// Summary of an archived track, kept so the archive can be browsed without the SoundCloud API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTrack {
+    pub title: String,
+    /// The uploader's user ID
+    pub user_id: String,
+    pub username: String,
+    pub permalink_url: String,
+    #[serde(default)]
+    pub artwork_url: Option<String>,
+    /// Where the cover art came from (not recorded for older tracks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_source: Option<crate::soundcloud::ArtworkSource>,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl ArchivedTrack {
+    pub fn from_track(track: &crate::soundcloud::Track) -> Self {
+        ArchivedTrack {
+            title: track.title.clone(),
+            user_id: track.user.id.clone(),
+            username: track.user.username.clone(),
+            permalink_url: track.permalink_url.clone(),
+            artwork_url: crate::soundcloud::cover_art_url(track).map(|url| url.to_string()),
+            artwork_source: None,
+            archived_at: Utc::now(),
+        }
+    }
+}
+
+/// Tags and a note attached to a track by an operator
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackAnnotation {
+    #[serde(default, skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub tags: std::collections::BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl TrackAnnotation {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.note.is_none()
+    }
+}
+
+/// Whether an archived track was still on SoundCloud when it was last checked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackAvailability {
+    pub checked_at: DateTime<Utc>,
+    /// When the track was first found to be gone, if it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A track found while polling that hasn't been posted yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTrack {
+    /// The watched user it was found for
+    pub user_id: String,
+    pub provenance: crate::soundcloud::Provenance,
+    pub discovered_at: DateTime<Utc>,
+    /// The track as listed, enough to process it again
+    pub track: crate::soundcloud::Track,
+}
+
+/// How long a pending track that keeps failing is tried again before it's given up on
+const PENDING_TRACK_RETENTION_DAYS: i64 = 7;
+
+/// Play, like, repost and comment counts of a track at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub at: DateTime<Utc>,
+    pub plays: u64,
+    pub likes: u64,
+    pub reposts: u64,
+    pub comments: u64,
+}
+
+/// Why a track, or its audio, wasn't archived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Audio files were over Discord's upload limit and weren't attached
+    TooLarge,
+    /// Only a preview is available without SoundCloud Go+
+    PremiumOnly,
+    /// SoundCloud blocks the track in the archiver's region
+    GeoBlocked,
+    /// Processing or posting the track failed
+    Failed,
+    /// The track is on the ignore list
+    Ignored,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SkipReason::TooLarge => "too large",
+            SkipReason::PremiumOnly => "premium only",
+            SkipReason::GeoBlocked => "geo-blocked",
+            SkipReason::Failed => "failed",
+            SkipReason::Ignored => "ignored",
+        })
+    }
+}
+
+/// The most recent skip recorded for a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedTrack {
+    pub reason: SkipReason,
+    /// More about what was skipped, e.g. the error message
+    pub detail: String,
+    /// The uploader, or the watched user the track was found through, if known
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    pub at: DateTime<Utc>,
+    /// Number of times in a row the track was skipped for this reason
+    pub count: u32,
+}
+
+/// Persisted polling state for a watched user
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserPollState {
+    /// When the user was last polled successfully
+    #[serde(default)]
+    pub last_polled: Option<DateTime<Utc>>,
+    /// Upload date of the newest track seen for the user
+    #[serde(default)]
+    pub last_track_date: Option<DateTime<Utc>>,
+    /// When the user's likes were last fetched successfully
+    #[serde(default)]
+    pub last_likes_polled: Option<DateTime<Utc>>,
+    /// When the newest like seen for the user was made; likes are only fetched back to here
+    #[serde(default)]
+    pub last_like_date: Option<DateTime<Utc>>,
+    /// When the user was first polled, or, for users watched before this was recorded,
+    /// polled after that
+    #[serde(default)]
+    pub watched_since: Option<DateTime<Utc>>,
+    /// Number of polls in a row that failed for the user
+    #[serde(default)]
+    pub consecutive_errors: u32,
+    /// Polling is paused for the user until this time after repeated failures
+    #[serde(default)]
+    pub paused_until: Option<DateTime<Utc>>,
+    /// Most recent poll error
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// What the user featured on their profile at the last check
+    #[serde(default)]
+    pub profile: Option<crate::profile::ProfileSnapshot>,
+    /// Number of polls in a row SoundCloud said the user doesn't exist or is hidden
+    #[serde(default)]
+    pub consecutive_unavailable: u32,
+    /// When the user was found deactivated; they aren't polled until re-enabled
+    #[serde(default)]
+    pub deactivated_at: Option<DateTime<Utc>>,
+}
+
+lazy_static::lazy_static! {
+    /// Tracks currently being processed anywhere in the process, by database path and track ID
+    static ref IN_FLIGHT_TRACKS: Mutex<std::collections::HashSet<(String, String)>> = Mutex::new(std::collections::HashSet::new());
+}
+
+/// Mark a track as being processed for a database, returning false if it already was
+///
+/// Each profile has its own database, so a track one profile is processing is still new
+/// to the others.
+fn claim_in_flight(db_path: &str, track_id: &str) -> bool {
+    IN_FLIGHT_TRACKS.lock().unwrap_or_else(|e| e.into_inner()).insert((db_path.to_string(), track_id.to_string()))
+}
+
+/// Mark a track as no longer being processed for a database
+fn release_in_flight(db_path: &str, track_id: &str) {
+    IN_FLIGHT_TRACKS.lock().unwrap_or_else(|e| e.into_inner()).remove(&(db_path.to_string(), track_id.to_string()));
+}
+
+/// Exclusive claim on processing a track, released when dropped
+///
+/// The same track can be found by several pollers at once (e.g. uploaded by one
+/// watched user and liked by another), so every path that posts tracks claims them
+/// first and keeps the claim until the track is recorded in the database.
+#[derive(Debug)]
+pub struct TrackClaim {
+    db_path: String,
+    track_id: String,
+}
+
+impl TrackClaim {
+    /// Claim a track for the database at `db_path`, or return `None` if it's already being
+    /// processed for it
+    ///
+    /// Doesn't check whether the track was archived before; use [`TrackDatabase::claim`]
+    /// for new tracks.
+    pub fn try_claim(db_path: &str, track_id: &str) -> Option<Self> {
+        claim_in_flight(db_path, track_id).then(|| TrackClaim {
+            db_path: db_path.to_string(),
+            track_id: track_id.to_string(),
+        })
+    }
+}
+
+impl Drop for TrackClaim {
+    fn drop(&mut self) {
+        release_in_flight(&self.db_path, &self.track_id);
+    }
+}
+
+/// Tracks that were successfully posted during a poll
+type PostedTracks = Arc<Mutex<Vec<crate::soundcloud::PostedTrack>>>;
+
+/// Tracks that failed during a poll, with the error
+type FailedTracks = Arc<Mutex<Vec<(crate::soundcloud::Track, String)>>>;
+
+/// Tracks that turned out to be on the ignore list once their details were fetched
+type IgnoredTracks = Arc<Mutex<Vec<crate::ignore::Ignored>>>;
+
+/// Read buffer for parsing the database file, large enough that parsing isn't held up by reads
+const LOAD_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// How much of a large database file is read between progress messages
+const LOAD_PROGRESS_STEP: u64 = 64 * 1024 * 1024;
+
+/// Reads the database file while it's parsed, reporting how far along it is
+struct LoadProgress<R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    next_report: u64,
+}
+
+impl<R: Read> LoadProgress<R> {
+    fn new(inner: R, total: u64) -> Self {
+        LoadProgress { inner, read: 0, total, next_report: LOAD_PROGRESS_STEP }
+    }
+}
+
+impl<R: Read> Read for LoadProgress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        if self.read >= self.next_report && self.read < self.total {
+            info!("Loading tracks database: {} of {} MB read", self.read / (1024 * 1024), self.total / (1024 * 1024));
+            self.next_report += LOAD_PROGRESS_STEP;
+        }
+        Ok(read)
+    }
 }
 
 /// Simple database to store known track IDs
@@ -24,6 +293,39 @@ pub struct TrackDatabase {
     // Map of track_ids to Discord message info
     #[serde(default)]
     tracks: HashMap<String, Option<DiscordMessage>>,
+    // Map of user IDs to their polling state
+    #[serde(default)]
+    users: HashMap<String, UserPollState>,
+    // Map of track IDs to their MusicBrainz metadata (only matched tracks)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<String, TrackMetadata>,
+    // Map of track IDs to their details (only tracks archived since details were recorded)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    archived: HashMap<String, ArchivedTrack>,
+    // Map of track IDs to their stats over time, oldest first
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    stats: HashMap<String, Vec<StatsSnapshot>>,
+    // Map of track IDs to why they (or their audio) were last skipped
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    skipped: HashMap<String, SkippedTrack>,
+    // Map of track IDs to their operator-added tags and note
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    annotations: HashMap<String, TrackAnnotation>,
+    // Map of track IDs to whether they were still on SoundCloud at the last check
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    availability: HashMap<String, TrackAvailability>,
+    // Map of track IDs to their editable metadata as last seen, to notice edits
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    snapshots: HashMap<String, crate::edits::TrackSnapshot>,
+    // Map of album (playlist) IDs to the albums recorded or posted as a whole
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    albums: HashMap<String, crate::albums::ArchivedAlbum>,
+    // Map of track IDs to tracks found while polling that haven't been posted yet
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pending: HashMap<String, PendingTrack>,
+    // Map of watched playlist IDs to the tracks in them at the last poll
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    playlists: HashMap<String, crate::playlists::PlaylistState>,
     // Path to the database file (if persistent)
     #[serde(skip)]
     pub db_path: String,
@@ -34,105 +336,115 @@ impl TrackDatabase {
     pub fn new(db_path: String) -> Self {
         TrackDatabase {
             tracks: HashMap::new(),
+            users: HashMap::new(),
+            metadata: HashMap::new(),
+            archived: HashMap::new(),
+            stats: HashMap::new(),
+            skipped: HashMap::new(),
+            annotations: HashMap::new(),
+            availability: HashMap::new(),
+            snapshots: HashMap::new(),
+            albums: HashMap::new(),
+            pending: HashMap::new(),
+            playlists: HashMap::new(),
             db_path,
         }
     }
     
-    /// Attempt to migrate from an older database format if needed
+    /// Migrate a database file in an older format, or return `None` if it isn't one
     /// 
-    /// This function safely handles migration from the old HashSet format to the new HashMap format.
-    /// It's called during database loading to ensure backward compatibility.
+    /// Older versions stored the tracks as an array of IDs rather than a map. Only tried
+    /// when the file doesn't load in the current format, so the usual startup path reads
+    /// the file just once.
     fn migrate_from_old_format(file_path: &str) -> Result<Option<Self>, Box<dyn std::error::Error + Send + Sync>> {
-        // Try to open the file
-        let file = match File::open(file_path) {
-            Ok(f) => f,
-            Err(e) => return Err(e.into()),
-        };
-        
-        let reader = BufReader::new(file);
+        #[derive(Deserialize)]
+        struct OldFormat {
+            tracks: Vec<Value>,
+        }
         
-        // First, try to parse as a raw JSON Value to check the structure
-        let json_value: Value = match serde_json::from_reader(reader) {
-            Ok(v) => v,
-            Err(e) => return Err(e.into()),
+        // Check if this is the old format (array of track IDs)
+        let reader = BufReader::with_capacity(LOAD_BUFFER_SIZE, File::open(file_path)?);
+        let Ok(old) = serde_json::from_reader::<_, OldFormat>(reader) else {
+            return Ok(None);
         };
+        info!("Detected old database format with {} tracks. Migrating to new format...", old.tracks.len());
         
-        // Check if this is the old format (array of track IDs)
-        if let Some(tracks_array) = json_value.get("tracks").and_then(|t| t.as_array()) {
-            info!("Detected old database format with {} tracks. Migrating to new format...", tracks_array.len());
-            
-            // Create a new database with the new format
-            let mut new_db = TrackDatabase::new(file_path.to_string());
-            
-            // Convert each track ID to the new format
-            for track_id in tracks_array {
-                if let Some(id) = track_id.as_str() {
-                    new_db.tracks.insert(id.to_string(), None);
-                } else if let Some(id) = track_id.as_u64() {
-                    new_db.tracks.insert(id.to_string(), None);
-                }
-            }
-            
-            info!("Migration complete. Converted {} tracks to new format.", new_db.tracks.len());
-            
-            // Create a backup of the old file
-            let backup_path = format!("{}.old_format.bak", file_path);
-            match copy(file_path, &backup_path) {
-                Ok(_) => info!("Created backup of old database format at {}", backup_path),
-                Err(e) => warn!("Failed to create backup of old database format: {}", e),
-            }
-            
-            // Save the new format
-            if let Err(e) = new_db.save() {
-                error!("Failed to save migrated database: {}", e);
-                return Err(e.into());
+        // Create a new database with the new format
+        let mut new_db = TrackDatabase::new(file_path.to_string());
+        
+        // Convert each track ID to the new format
+        for track_id in old.tracks {
+            if let Some(id) = track_id.as_str() {
+                new_db.tracks.insert(id.to_string(), None);
+            } else if let Some(id) = track_id.as_u64() {
+                new_db.tracks.insert(id.to_string(), None);
             }
-            
-            return Ok(Some(new_db));
         }
         
-        // Not an old format, or couldn't determine
-        Ok(None)
+        info!("Migration complete. Converted {} tracks to new format.", new_db.tracks.len());
+        
+        // Create a backup of the old file
+        let backup_path = format!("{}.old_format.bak", file_path);
+        match copy(file_path, &backup_path) {
+            Ok(_) => info!("Created backup of old database format at {}", backup_path),
+            Err(e) => warn!("Failed to create backup of old database format: {}", e),
+        }
+        
+        // Save the new format
+        if let Err(e) = new_db.save() {
+            error!("Failed to save migrated database: {}", e);
+            return Err(e);
+        }
+        
+        Ok(Some(new_db))
     }
     
     /// Load from file or create a new instance
+    ///
+    /// The file is parsed as it's read, entry by entry straight into the maps, so loading
+    /// never holds the file's contents in memory on top of the parsed database. Progress is
+    /// logged for large files, and the load runs alongside the SoundCloud client setup.
     pub fn load_or_create(db_path: String) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         if Path::new(&db_path).exists() {
-            // Try to migrate from an old format first
-            if let Ok(Some(migrated_db)) = Self::migrate_from_old_format(&db_path) {
-                return Ok(migrated_db);
-            }
-            
-            // Load database from file with the current format
             debug!("Loading tracks database from {}", db_path);
+            let started = std::time::Instant::now();
             let file = File::open(&db_path)?;
-            let reader = BufReader::new(file);
+            let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+            let reader = BufReader::with_capacity(LOAD_BUFFER_SIZE, LoadProgress::new(file, size));
             
             // Try to deserialize with the current format
-            match serde_json::from_reader::<_, TrackDatabase>(reader) {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            let parsed = TrackDatabase::deserialize(&mut deserializer)
+                .and_then(|db| deserializer.end().map(|_| db));
+            let error = match parsed {
                 Ok(mut db) => {
                     db.db_path = db_path;
-                    let track_count = db.tracks.len();
-                    info!("Loaded tracks database with {} tracks", track_count);
-                    Ok(db)
+                    info!("Loaded tracks database with {} tracks ({} MB) in {:.1}s",
+                          db.tracks.len(), size / (1024 * 1024), started.elapsed().as_secs_f64());
+                    return Ok(db);
                 },
-                Err(e) => {
-                    error!("Failed to deserialize tracks database: {}", e);
-                    
-                    // Create a backup of the corrupted file
-                    let backup_path = format!("{}.corrupted.bak", db_path);
-                    match copy(&db_path, &backup_path) {
-                        Ok(_) => info!("Created backup of corrupted database at {}", backup_path),
-                        Err(e) => warn!("Failed to create backup of corrupted database: {}", e),
-                    }
-                    
-                    // Create a new empty database as fallback
-                    warn!("Creating new empty database due to loading error");
-                    let db = TrackDatabase::new(db_path);
-                    db.save()?;
-                    Ok(db)
-                }
+                Err(e) => e,
+            };
+            
+            // Not the current format, it may be an old one
+            if let Ok(Some(migrated_db)) = Self::migrate_from_old_format(&db_path) {
+                return Ok(migrated_db);
             }
+            
+            error!("Failed to deserialize tracks database: {}", error);
+            
+            // Create a backup of the corrupted file
+            let backup_path = format!("{}.corrupted.bak", db_path);
+            match copy(&db_path, &backup_path) {
+                Ok(_) => info!("Created backup of corrupted database at {}", backup_path),
+                Err(e) => warn!("Failed to create backup of corrupted database: {}", e),
+            }
+            
+            // Create a new empty database as fallback
+            warn!("Creating new empty database due to loading error");
+            let db = TrackDatabase::new(db_path);
+            db.save()?;
+            Ok(db)
         } else {
             // Create a new database and save it to file
             debug!("Tracks database file not found, creating new one at {}", db_path);
@@ -210,6 +522,11 @@ impl TrackDatabase {
         tracks
     }
     
+    /// Number of tracks in the database
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+    
     /// Check if a track is already in the database
     pub fn has_track(&self, track_id: &str) -> bool {
         let has = self.tracks.contains_key(track_id);
@@ -217,6 +534,29 @@ impl TrackDatabase {
         has
     }
     
+    /// Claim a track for processing unless it's archived already or being processed elsewhere
+    ///
+    /// Checking the database and claiming happen in one step, so two pollers or commands
+    /// that find the same track can't both go on to post it. Returns whether the track was
+    /// claimed; the claim is held until [`TrackDatabase::release`], which should come after
+    /// the track is recorded.
+    pub fn try_claim(&self, track_id: &str) -> bool {
+        !self.has_track(track_id) && claim_in_flight(&self.db_path, track_id)
+    }
+    
+    /// Give up a claim taken with [`TrackDatabase::try_claim`]
+    pub fn release(&self, track_id: &str) {
+        release_in_flight(&self.db_path, track_id);
+    }
+    
+    /// [`TrackDatabase::try_claim`], with the claim released when the returned guard is dropped
+    pub fn claim(&self, track_id: &str) -> Option<TrackClaim> {
+        self.try_claim(track_id).then(|| TrackClaim {
+            db_path: self.db_path.clone(),
+            track_id: track_id.to_string(),
+        })
+    }
+    
     /// Add new tracks and return which ones were newly added
     /// 
     /// This method adds tracks to the in-memory database but does not automatically save to disk.
@@ -234,6 +574,7 @@ impl TrackDatabase {
             // Add the new tracks
             for track_id in &new_tracks {
                 self.tracks.insert(track_id.clone(), None);
+                self.pending.remove(track_id);
                 trace!("Added new track {} to database", track_id);
             }
             
@@ -258,12 +599,296 @@ impl TrackDatabase {
             id: discord_id,
             channel_id,
             user_id,
+            destinations: BTreeMap::new(),
+            pending_destinations: BTreeSet::new(),
         };
         
         self.tracks.insert(track_id.to_string(), Some(discord_info));
+        self.pending.remove(track_id);
         debug!("Added track {} with Discord message info", track_id);
     }
     
+    /// Record a track's messages in the `extra_webhooks` destinations it was posted to
+    pub fn set_destination_messages(&mut self, track_id: &str, responses: &[(String, crate::discord::WebhookResponse)]) {
+        if let Some(Some(info)) = self.tracks.get_mut(track_id) {
+            for (name, response) in responses {
+                info.pending_destinations.remove(name);
+                info.destinations.insert(name.clone(), DestinationMessage {
+                    id: response.message_id.clone(),
+                    channel_id: response.channel_id.clone(),
+                });
+            }
+        }
+    }
+    
+    /// Mark `extra_webhooks` destinations a posted track still has to go to
+    pub fn add_pending_destinations(&mut self, track_id: &str, names: &[String]) {
+        if let Some(Some(info)) = self.tracks.get_mut(track_id) {
+            info.pending_destinations.extend(names.iter().filter(|name| !info.destinations.contains_key(*name)).cloned());
+        }
+    }
+    
+    /// Stop trying to post a track to the given destinations
+    pub fn remove_pending_destinations(&mut self, track_id: &str, names: &[String]) {
+        if let Some(Some(info)) = self.tracks.get_mut(track_id) {
+            for name in names {
+                info.pending_destinations.remove(name);
+            }
+        }
+    }
+    
+    /// Up to `limit` tracks with destinations they still have to be posted to, oldest
+    /// message first
+    pub fn tracks_with_pending_destinations(&self, limit: usize) -> Vec<(String, Vec<String>)> {
+        let mut pending: Vec<(&String, &DiscordMessage)> = self.tracks.iter()
+            .filter_map(|(id, info)| info.as_ref().filter(|i| !i.pending_destinations.is_empty()).map(|i| (id, i)))
+            .collect();
+        // Discord message IDs are snowflakes, so they sort by time once they're the same length
+        pending.sort_by(|(_, a), (_, b)| (a.id.len(), &a.id).cmp(&(b.id.len(), &b.id)));
+        pending.into_iter()
+            .take(limit)
+            .map(|(id, info)| (id.clone(), info.pending_destinations.iter().cloned().collect()))
+            .collect()
+    }
+    
+    /// Record a track posted by `process_and_post_track`, with its uploader and metadata
+    pub fn add_posted_track(&mut self, posted: &crate::soundcloud::PostedTrack) {
+        self.add_track_with_discord_info(
+            &posted.track_id,
+            posted.response.message_id.clone(),
+            posted.response.channel_id.clone(),
+            Some(posted.user_id.clone())
+        );
+        self.set_destination_messages(&posted.track_id, &posted.destinations);
+        self.add_pending_destinations(&posted.track_id, &posted.pending_destinations);
+        let mut archived = ArchivedTrack::from_track(&posted.track);
+        archived.artwork_source = posted.artwork_source;
+        self.archived.insert(posted.track_id.clone(), archived);
+        if let Some(metadata) = &posted.metadata {
+            self.set_track_metadata(&posted.track_id, metadata.clone());
+        }
+        self.record_posted_skip(posted);
+    }
+    
+    /// Record why part of a posted track was skipped, or clear an earlier skip if nothing was
+    fn record_posted_skip(&mut self, posted: &crate::soundcloud::PostedTrack) {
+        match &posted.skip {
+            Some((reason, detail)) => self.record_skip(
+                &posted.track_id, *reason, detail, Some(&posted.track.user.id), Some(&posted.track.user.username)),
+            None => {
+                self.skipped.remove(&posted.track_id);
+            }
+        }
+    }
+    
+    /// Record that a track, or its audio, was skipped
+    pub fn record_skip(&mut self, track_id: &str, reason: SkipReason, detail: &str, user_id: Option<&str>, username: Option<&str>) {
+        let count = match self.skipped.get(track_id) {
+            Some(previous) if previous.reason == reason => previous.count + 1,
+            _ => 1,
+        };
+        self.skipped.insert(track_id.to_string(), SkippedTrack {
+            reason,
+            detail: detail.to_string(),
+            user_id: user_id.map(|u| u.to_string()),
+            username: username.map(|u| u.to_string()),
+            at: Utc::now(),
+            count,
+        });
+    }
+    
+    /// Record that a track on the ignore list was skipped
+    ///
+    /// Only the first skip in a row is logged above debug level, since ignored tracks keep
+    /// showing up in every poll; the database keeps count of all of them.
+    pub fn record_ignored(&mut self, ignored: &crate::ignore::Ignored) {
+        let repeated = self.skipped.get(&ignored.track_id).is_some_and(|s| s.reason == SkipReason::Ignored);
+        if repeated {
+            debug!("{}", ignored);
+        } else {
+            info!("{} (uploaded by {})", ignored, ignored.username);
+        }
+        self.record_skip(&ignored.track_id, SkipReason::Ignored, &format!("on the ignore list as {}", ignored.entry),
+                         Some(&ignored.user_id), Some(&ignored.username));
+    }
+    
+    /// All tracks with a recorded skip, by track ID
+    pub fn skipped_tracks(&self) -> &HashMap<String, SkippedTrack> {
+        &self.skipped
+    }
+    
+    /// Get the details recorded when a track was archived
+    pub fn archived_track(&self, track_id: &str) -> Option<&ArchivedTrack> {
+        self.archived.get(track_id)
+    }
+    
+    /// All tracks with recorded details, by track ID
+    pub fn archived_tracks(&self) -> &HashMap<String, ArchivedTrack> {
+        &self.archived
+    }
+    
+    /// Store the MusicBrainz metadata found for a track
+    pub fn set_track_metadata(&mut self, track_id: &str, metadata: TrackMetadata) {
+        self.metadata.insert(track_id.to_string(), metadata);
+    }
+    
+    /// Get the MusicBrainz metadata for a track, if it was matched
+    pub fn track_metadata(&self, track_id: &str) -> Option<&TrackMetadata> {
+        self.metadata.get(track_id)
+    }
+    
+    /// Record a track's current counts, unless the last snapshot is newer than `interval`
+    ///
+    /// Returns whether a snapshot was added.
+    pub fn record_stats(&mut self, track: &crate::soundcloud::Track, interval: chrono::Duration) -> bool {
+        let snapshots = self.stats.entry(track.id.clone()).or_default();
+        let now = Utc::now();
+        if snapshots.last().is_some_and(|last| now - last.at < interval) {
+            return false;
+        }
+        snapshots.push(StatsSnapshot {
+            at: now,
+            plays: track.playback_count.unwrap_or(0),
+            likes: track.likes_count.unwrap_or(0),
+            reposts: track.reposts_count.unwrap_or(0),
+            comments: track.comment_count.unwrap_or(0),
+        });
+        true
+    }
+    
+    /// Get the tags and note attached to a track, if any
+    pub fn track_annotation(&self, track_id: &str) -> Option<&TrackAnnotation> {
+        self.annotations.get(track_id)
+    }
+    
+    /// Replace the tags and note of a track, removing the entry if both are empty
+    pub fn set_track_annotation(&mut self, track_id: &str, annotation: TrackAnnotation) {
+        if annotation.is_empty() {
+            self.annotations.remove(track_id);
+        } else {
+            self.annotations.insert(track_id.to_string(), annotation);
+        }
+    }
+    
+    /// Whether a track was still on SoundCloud at the last check, if it was checked
+    pub fn track_availability(&self, track_id: &str) -> Option<&TrackAvailability> {
+        self.availability.get(track_id)
+    }
+    
+    /// Record whether a track is still on SoundCloud
+    ///
+    /// Keeps the time a deleted track was first found to be gone.
+    pub fn record_availability(&mut self, track_id: &str, exists: bool) {
+        let now = Utc::now();
+        let entry = self.availability.entry(track_id.to_string())
+            .or_insert(TrackAvailability { checked_at: now, deleted_at: None });
+        entry.checked_at = now;
+        entry.deleted_at = if exists { None } else { entry.deleted_at.or(Some(now)) };
+    }
+    
+    /// Archived tracks that haven't been checked for deletion within `interval`
+    ///
+    /// Never-checked tracks come first, then the ones checked longest ago. Tracks already
+    /// known to be deleted aren't included.
+    pub fn tracks_due_for_availability_check(&self, interval: chrono::Duration, limit: usize) -> Vec<String> {
+        let now = Utc::now();
+        let mut due: Vec<(Option<DateTime<Utc>>, &String)> = self.tracks.keys()
+            .filter_map(|id| match self.availability.get(id) {
+                None => Some((None, id)),
+                Some(a) if a.deleted_at.is_none() && now - a.checked_at >= interval => Some((Some(a.checked_at), id)),
+                Some(_) => None,
+            })
+            .collect();
+        due.sort();
+        due.into_iter().take(limit).map(|(_, id)| id.clone()).collect()
+    }
+    
+    /// A track's title, description, artwork and tags as last seen, if recorded
+    pub fn track_snapshot(&self, track_id: &str) -> Option<&crate::edits::TrackSnapshot> {
+        self.snapshots.get(track_id)
+    }
+    
+    /// Record a track's title, description, artwork and tags as seen now
+    pub fn set_track_snapshot(&mut self, track_id: &str, snapshot: crate::edits::TrackSnapshot) {
+        self.snapshots.insert(track_id.to_string(), snapshot);
+    }
+    
+    /// Check if an album was already recorded
+    pub fn has_album(&self, album_id: &str) -> bool {
+        self.albums.contains_key(album_id)
+    }
+    
+    /// Record an album and the tracks posted with it, which all share the album's message
+    pub fn add_album(&mut self, album_id: &str, album: crate::albums::ArchivedAlbum, tracks: &[crate::soundcloud::Track]) {
+        for track in tracks {
+            self.tracks.insert(track.id.clone(), album.discord.clone());
+            self.pending.remove(&track.id);
+            self.archived.insert(track.id.clone(), ArchivedTrack::from_track(track));
+        }
+        debug!("Added album {} ({}) with {} tracks", album_id, album.title, tracks.len());
+        self.albums.insert(album_id.to_string(), album);
+    }
+    
+    /// A watched playlist as of its last poll, or `None` if it wasn't polled yet
+    pub fn playlist_state(&self, playlist_id: &str) -> Option<&crate::playlists::PlaylistState> {
+        self.playlists.get(playlist_id)
+    }
+    
+    /// Record a watched playlist as polled now
+    pub fn set_playlist_state(&mut self, playlist_id: &str, state: crate::playlists::PlaylistState) {
+        self.playlists.insert(playlist_id.to_string(), state);
+    }
+    
+    /// Remember tracks found for a user until they're posted, so they're still processed
+    /// after a restart even if they've dropped out of the user's listings by then
+    ///
+    /// Returns whether any of them weren't pending yet.
+    pub fn add_pending_tracks(&mut self, user_id: &str, tracks: &[(crate::soundcloud::Track, crate::soundcloud::Provenance)]) -> bool {
+        let mut added = false;
+        for (track, provenance) in tracks {
+            if !self.pending.contains_key(&track.id) {
+                self.pending.insert(track.id.clone(), PendingTrack {
+                    user_id: user_id.to_string(),
+                    provenance: provenance.clone(),
+                    discovered_at: Utc::now(),
+                    track: track.clone(),
+                });
+                added = true;
+            }
+        }
+        added
+    }
+    
+    /// Tracks found for a user that still haven't been posted
+    ///
+    /// Drops tracks that were archived since, and those that kept failing for longer
+    /// than a week.
+    pub fn pending_tracks(&mut self, user_id: &str) -> Vec<PendingTrack> {
+        let cutoff = Utc::now() - chrono::Duration::days(PENDING_TRACK_RETENTION_DAYS);
+        let tracks = &self.tracks;
+        self.pending.retain(|track_id, pending| {
+            if pending.discovered_at < cutoff && !tracks.contains_key(track_id) {
+                warn!("Giving up on track {} ({}), it couldn't be posted for {} days",
+                      track_id, pending.track.title, PENDING_TRACK_RETENTION_DAYS);
+            }
+            pending.discovered_at >= cutoff && !tracks.contains_key(track_id)
+        });
+        self.pending.values()
+            .filter(|pending| pending.user_id == user_id)
+            .cloned()
+            .collect()
+    }
+    
+    /// Number of tracks found while polling that haven't been posted yet
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+    
+    /// Get a track's recorded stats, oldest first
+    pub fn track_stats(&self, track_id: &str) -> &[StatsSnapshot] {
+        self.stats.get(track_id).map(|s| s.as_slice()).unwrap_or_default()
+    }
+    
     /// Get Discord message info for a track if it exists
     pub fn get_discord_info(&self, track_id: &str) -> Option<DiscordMessage> {
         match self.tracks.get(track_id) {
@@ -275,11 +900,11 @@ impl TrackDatabase {
     /// Find a track ID by its Discord message ID
     /// 
     /// This allows reverse lookup when you have a Discord message ID but need to find
-    /// the associated SoundCloud track ID.
+    /// the associated SoundCloud track ID. Messages in every destination are searched.
     pub fn find_track_by_discord_id(&self, discord_id: &str) -> Option<String> {
         for (track_id, discord_info) in &self.tracks {
             if let Some(info) = discord_info {
-                if info.id == discord_id {
+                if info.id == discord_id || info.destinations.values().any(|m| m.id == discord_id) {
                     return Some(track_id.clone());
                 }
             }
@@ -312,10 +937,9 @@ impl TrackDatabase {
     pub fn get_all_discord_ids(&self) -> Vec<String> {
         let mut result = Vec::new();
         
-        for (_track_id, discord_info) in &self.tracks {
-            if let Some(info) = discord_info {
-                result.push(info.id.clone());
-            }
+        for info in self.tracks.values().flatten() {
+            result.push(info.id.clone());
+            result.extend(info.destinations.values().map(|m| m.id.clone()));
         }
         
         result
@@ -354,6 +978,38 @@ impl TrackDatabase {
         Ok(new_tracks)
     }
     
+    /// Get the polling state for a user, if any has been recorded
+    pub fn user_state(&self, user_id: &str) -> Option<&UserPollState> {
+        self.users.get(user_id)
+    }
+    
+    /// Get the polling state for a user for updating, creating it if needed
+    pub fn user_state_mut(&mut self, user_id: &str) -> &mut UserPollState {
+        self.users.entry(user_id.to_string()).or_default()
+    }
+    
+    /// Get the polling state of all users
+    pub fn user_states(&self) -> &HashMap<String, UserPollState> {
+        &self.users
+    }
+    
+    /// Check whether a database file at the given path can be written
+    /// 
+    /// Opens the file for appending without modifying it (or checks the parent
+    /// directory if the file doesn't exist yet), so it's safe to call at any time.
+    pub fn is_path_writable(db_path: &str) -> bool {
+        let path = Path::new(db_path);
+        if path.exists() {
+            return std::fs::OpenOptions::new().append(true).open(path).is_ok();
+        }
+        
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        std::fs::metadata(parent).map(|m| m.is_dir() && !m.permissions().readonly()).unwrap_or(false)
+    }
+    
     /// Perform a clean shutdown, ensuring all data is saved
     pub fn shutdown(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Performing clean database shutdown");
@@ -363,54 +1019,50 @@ impl TrackDatabase {
     }
 
     /// Initialize database with tracks from multiple users
+    ///
+    /// Users are fetched in parallel, as many at a time as `soundcloud_semaphore` allows.
+    /// Each user's tracks are saved as soon as they're fetched, so an interrupted run
+    /// keeps the progress made so far.
     pub async fn initialize_with_tracks_from_users(
         &mut self, 
+        api: &Arc<dyn SoundCloudApi>,
         users: &[String], 
-        max_tracks_per_user: usize,
-        pagination_size: usize,
-        scrape_likes: bool,
-        max_likes_per_user: usize,
+        config: &crate::config::Config,
+        soundcloud_semaphore: &Arc<tokio::sync::Semaphore>,
     ) -> Result<(usize, usize), Box<dyn std::error::Error + Send + Sync>> {
+        let (max_tracks_per_user, pagination_size) = (config.max_tracks_per_user, config.pagination_size);
+        let (scrape_likes, max_likes_per_user) = (config.scrape_user_likes, config.max_likes_per_user);
         let mut total_users_processed = 0;
         let mut total_tracks_added = 0;
         
-        // Process each user
+        // Fetch each user's tracks in the background
+        let mut fetches = tokio::task::JoinSet::new();
         for user_id in users {
-            info!("Fetching tracks for user {}", user_id);
-            
-            // Collect all tracks from this user
-            let mut all_tracks = Vec::new();
-            
-            // Get uploaded tracks
-            match crate::soundcloud::get_user_tracks(user_id, max_tracks_per_user, pagination_size).await {
-                Ok(tracks) => {
-                    info!("Found {} uploaded tracks for user {}", tracks.len(), user_id);
-                    all_tracks.extend(tracks);
+            let user_id = user_id.clone();
+            let semaphore = soundcloud_semaphore.clone();
+            let api = api.clone();
+            fetches.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let result = fetch_user_track_ids(
+                    api.as_ref(), &user_id, max_tracks_per_user, pagination_size, scrape_likes, max_likes_per_user
+                ).await;
+                (user_id, result)
+            });
+        }
+        
+        // Add each user's tracks as they come in
+        while let Some(joined) = fetches.join_next().await {
+            let (user_id, track_ids) = match joined {
+                Ok((user_id, Ok(track_ids))) => (user_id, track_ids),
+                Ok((user_id, Err(e))) => {
+                    error!("Failed to fetch tracks for user {}: {}", user_id, e);
+                    continue;
                 },
                 Err(e) => {
-                    error!("Failed to fetch tracks for user {}: {}", user_id, e);
+                    error!("Track fetch task failed: {}", e);
                     continue;
                 }
-            }
-            
-            // If enabled, get liked tracks too
-            if scrape_likes {
-                info!("Fetching likes for user {} (enabled in config)", user_id);
-                match crate::soundcloud::get_user_likes(user_id, max_likes_per_user, pagination_size).await {
-                    Ok(likes) => {
-                        let liked_tracks = crate::soundcloud::extract_tracks_from_likes(&likes);
-                        info!("Found {} liked tracks for user {}", liked_tracks.len(), user_id);
-                        all_tracks.extend(liked_tracks);
-                    },
-                    Err(e) => {
-                        warn!("Failed to fetch likes for user {}: {}", user_id, e);
-                    }
-                }
-            }
-            
-            // Extract track IDs
-            let track_ids: Vec<String> = all_tracks.iter().map(|t| t.id.clone()).collect();
-            info!("Total tracks for user {}: {}", user_id, track_ids.len());
+            };
             
             // Add to database
             let current_count = self.tracks.len();
@@ -422,24 +1074,30 @@ impl TrackDatabase {
             
             let added = new_count - current_count;
             total_tracks_added += added;
-            
-            info!("Added {} new tracks for user {} to database", added, user_id);
             total_users_processed += 1;
+            
+            info!("Added {} new tracks for user {} to database ({}/{} users done)", 
+                 added, user_id, total_users_processed, users.len());
         }
         
         Ok((total_users_processed, total_tracks_added))
     }
 
     /// Poll a user for new tracks and process them
+    #[tracing::instrument(name = "poll_user", skip_all, fields(user_id = %user_id))]
     pub async fn poll_user(
         &mut self,
+        api: &Arc<dyn SoundCloudApi>,
         user_id: &str,
         config: &crate::config::Config,
         processing_semaphore: &Arc<tokio::sync::Semaphore>,
         discord_semaphore: &Arc<tokio::sync::Semaphore>
     ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        // Apply any per-user overrides over the global settings
+        let config = &config.for_user(user_id);
+        
         // Fetch latest tracks from SoundCloud
-        let tracks = match crate::soundcloud::get_user_tracks(user_id, config.max_tracks_per_user, config.pagination_size).await {
+        let crate::soundcloud::UserTracks { tracks, reused } = match api.user_tracks(user_id, config.max_tracks_per_user, config.pagination_size).await {
             Ok(t) => t,
             Err(e) => {
                 error!("Failed to fetch tracks for user {}: {}", user_id, e);
@@ -449,14 +1107,50 @@ impl TrackDatabase {
         
         debug!("Fetched {} tracks for user {}", tracks.len(), user_id);
         
-        // If enabled, fetch user likes as well
-        let mut all_tracks = tracks.clone();
+        // Record the successful poll and the newest upload we've seen
+        let newest_upload = tracks.iter()
+            .filter_map(|t| DateTime::parse_from_rfc3339(&t.created_at).ok())
+            .map(|d| d.with_timezone(&Utc))
+            .max();
+        let state = self.user_state_mut(user_id);
+        let first_poll = state.last_polled.is_none();
+        state.watched_since = state.watched_since.or(state.last_polled).or_else(|| Some(Utc::now()));
+        state.last_polled = Some(Utc::now());
+        if newest_upload > state.last_track_date {
+            state.last_track_date = newest_upload;
+        }
+        
+        // Keep track of what the user pins to their profile
+        let user = tracks.iter().find(|t| t.user.id == user_id).map(|t| t.user.clone());
+        crate::profile::check_profile(self, api.as_ref(), user_id, user.as_ref(), config).await;
+        
+        // If enabled, fetch user likes as well. Anything that isn't an upload came from them.
+        let mut upload_ids: std::collections::HashSet<String> = tracks.iter().map(|t| t.id.clone()).collect();
+        // A reused listing may be behind on edits and stats, so only the likes are fresh
+        let fresh_from = if reused { tracks.len() } else { 0 };
+        let mut all_tracks = tracks;
         
-        if config.scrape_user_likes {
+        // Likes change often, so they can be fetched less often than uploads
+        let likes_due = self.user_state(user_id)
+            .and_then(|s| s.last_likes_polled)
+            .is_none_or(|last| Utc::now() - last >= chrono::Duration::seconds(config.likes_poll_interval_sec as i64));
+        if config.scrape_user_likes && !likes_due {
+            debug!("Not fetching likes for user {} yet (likes_poll_interval_sec is {})", user_id, config.likes_poll_interval_sec);
+        } else if config.scrape_user_likes {
             debug!("Fetching likes for user {} (enabled in config)", user_id);
-            match crate::soundcloud::get_user_likes(user_id, config.max_likes_per_user, config.pagination_size).await {
+            // Likes from before the user was watched are history rather than news
+            let state = self.user_state(user_id);
+            let since = state.and_then(|s| s.last_like_date)
+                .max(state.and_then(|s| s.watched_since).filter(|_| config.likes_since_watched));
+            match api.user_likes(user_id, config.max_likes_per_user, config.pagination_size, since).await {
                 Ok(likes) => {
                     info!("Fetched {} likes for user {}", likes.len(), user_id);
+                    let newest_like = likes.iter().filter_map(|like| like.liked_at()).max();
+                    let state = self.user_state_mut(user_id);
+                    state.last_likes_polled = Some(Utc::now());
+                    if newest_like > state.last_like_date {
+                        state.last_like_date = newest_like;
+                    }
                     
                     // Extract tracks from likes
                     let liked_tracks = crate::soundcloud::extract_tracks_from_likes(&likes);
@@ -473,42 +1167,154 @@ impl TrackDatabase {
             }
         }
         
-        // Check which tracks are new
-        let track_ids: Vec<String> = all_tracks.iter().map(|t| t.id.clone()).collect();
+        // Snapshot the counts of tracks we've already archived from the listings we just fetched
+        if config.stats_snapshot_interval_hours > 0 {
+            let interval = chrono::Duration::hours(config.stats_snapshot_interval_hours as i64);
+            let mut recorded = 0;
+            for track in &all_tracks[fresh_from..] {
+                if self.has_track(&track.id) && self.record_stats(track, interval) {
+                    recorded += 1;
+                }
+            }
+            if recorded > 0 {
+                debug!("Recorded stats for {} tracks of user {}", recorded, user_id);
+            }
+        }
         
-        // Get new track IDs without adding to database yet
-        let new_track_ids: Vec<String> = track_ids.iter()
-            .filter(|id| !self.has_track(id))
-            .cloned()
-            .collect::<Vec<String>>();
+        // Post edits to tracks we've already archived
+        crate::edits::check_track_edits(self, &all_tracks[fresh_from..], config).await;
         
-        if new_track_ids.is_empty() {
+        // New albums are posted as a whole, so their tracks aren't posted one by one
+        let post_processing = crate::soundcloud::PostProcessing::from_config(config).with_api(api.clone());
+        let album_tracks = crate::albums::check_albums(self, user_id, config, &post_processing, processing_semaphore, discord_semaphore).await;
+        
+        // Tracks found before but never posted (e.g. the archiver was stopped mid-poll) are
+        // tried again, even if they've dropped out of the listings since
+        let listed: std::collections::HashSet<String> = all_tracks.iter().map(|t| t.id.clone()).collect();
+        for pending in self.pending_tracks(user_id) {
+            if listed.contains(&pending.track.id) {
+                continue;
+            }
+            debug!("Retrying track {} ({}) found for user {} on {}", pending.track.id, pending.track.title, user_id,
+                   pending.discovered_at.format("%Y-%m-%d %H:%M"));
+            if pending.provenance == crate::soundcloud::Provenance::Upload {
+                upload_ids.insert(pending.track.id.clone());
+            }
+            all_tracks.push(pending.track);
+        }
+        
+        // Keep only tracks we haven't seen yet, without adding them to the database yet.
+        // A track can show up both as an upload and a like, so dedupe by ID too. Tracks
+        // waiting in the Discord queue are added once they're posted from there.
+        let mut seen_ids = std::collections::HashSet::new();
+        let new_tracks: Vec<crate::soundcloud::Track> = all_tracks.into_iter()
+            .filter(|t| !self.has_track(&t.id) && !post_processing.outbox.contains(&t.id) && !album_tracks.contains(&t.id)
+                && seen_ids.insert(t.id.clone()))
+            .collect();
+        
+        // Tracks on the ignore list are never posted, but every skip is recorded
+        let new_tracks: Vec<crate::soundcloud::Track> = new_tracks.into_iter()
+            .filter(|t| match post_processing.ignore_list.check(t) {
+                Ok(()) => true,
+                Err(ignored) => {
+                    self.record_ignored(&ignored);
+                    false
+                }
+            })
+            .collect();
+        
+        // Skip tracks another poller is already processing. The claims are held until
+        // the posted tracks are added to the database below.
+        let mut claims = Vec::new();
+        let new_tracks: Vec<crate::soundcloud::Track> = new_tracks.into_iter()
+            .filter(|t| match self.claim(&t.id) {
+                Some(claim) => {
+                    claims.push(claim);
+                    true
+                },
+                None => {
+                    debug!("Track {} is already being processed elsewhere, skipping it for user {}", t.id, user_id);
+                    false
+                }
+            })
+            .collect();
+        
+        if new_tracks.is_empty() {
             return Ok(0); // No new tracks
         }
         
+        let provenance_of = |track: &crate::soundcloud::Track| if upload_ids.contains(&track.id) {
+            crate::soundcloud::Provenance::Upload
+        } else {
+            crate::soundcloud::Provenance::Like
+        };
+        let pending: Vec<_> = new_tracks.iter().map(|t| (t.clone(), provenance_of(t))).collect();
+        
+        // A deleted webhook can't take any posts, so the tracks wait as pending until it's
+        // replaced. The likes cursor has already moved past them, so they must be kept.
+        if let Err(e) = crate::discord::check_webhook(&config.discord_webhook_url) {
+            warn!("Not posting {} new tracks of user {} yet: {}", new_tracks.len(), user_id, e);
+            if self.add_pending_tracks(user_id, &pending) {
+                if let Err(e) = self.save() {
+                    warn!("Failed to save database with the pending tracks of user {}: {}", user_id, e);
+                }
+            }
+            return Ok(0);
+        }
+        
+        // A newly watched user's tracks can be posted in batches rather than one by one
+        if first_poll {
+            if let Some(posted) = crate::backfill::post_backfill(self, user_id, &new_tracks, &upload_ids, config, &post_processing, discord_semaphore).await {
+                drop(claims);
+                return Ok(posted);
+            }
+        }
+        
+        // Remember the tracks until they're posted, in case the archiver stops before then
+        if self.add_pending_tracks(user_id, &pending) {
+            if let Err(e) = self.save() {
+                warn!("Failed to save database with the pending tracks of user {}: {}", user_id, e);
+            }
+        }
+        
+        // Fetch the details of public tracks together rather than with a request each.
+        // Private tracks need their secret token, so they're fetched one by one.
+        let batch_ids: Vec<String> = pending.iter()
+            .filter(|(t, _)| t.secret_token.is_none())
+            .map(|(t, _)| t.id.clone())
+            .collect();
+        let mut batched: HashMap<String, crate::soundcloud::Track> = HashMap::new();
+        if batch_ids.len() > 1 {
+            match api.tracks_details(&batch_ids).await {
+                Ok(tracks) => batched.extend(tracks.into_iter().map(|t| (t.id.clone(), t))),
+                Err(e) => warn!("Failed to fetch the details of user {}'s {} new tracks at once, fetching them one by one: {}",
+                                user_id, batch_ids.len(), e),
+            }
+        }
+        
         // Process new tracks in parallel with resource limits
         let mut tasks = Vec::new();
-        let successful_tracks: Arc<Mutex<Vec<(String, Option<String>, Option<String>)>>> = Arc::new(Mutex::new(Vec::new()));
+        let successful_tracks: PostedTracks = Arc::new(Mutex::new(Vec::new()));
+        let failed_tracks: FailedTracks = Arc::new(Mutex::new(Vec::new()));
+        let ignored_tracks: IgnoredTracks = Arc::new(Mutex::new(Vec::new()));
         
-        for track_id in &new_track_ids {
-            // Find the track in our collection
-            let track = match all_tracks.iter().find(|t| &t.id == track_id) {
-                Some(t) => t.clone(),
-                None => {
-                    warn!("Could not find track {} in fetched tracks - skipping", track_id);
-                    continue;
-                }
-            };
-            
+        for (track, provenance) in pending {
+            crate::metrics::increment_queue_depth();
             let processing_semaphore = Arc::clone(processing_semaphore);
             let discord_semaphore = Arc::clone(discord_semaphore);
             let successful_tracks = Arc::clone(&successful_tracks);
+            let failed_tracks = Arc::clone(&failed_tracks);
+            let ignored_tracks = Arc::clone(&ignored_tracks);
             
             // Spawn a task to process this track
             let webhook_url = config.discord_webhook_url.clone();
             let temp_dir = config.temp_dir.clone();
-            let _user_id_clone = user_id.to_string();
-            let task = tokio::spawn(async move {
+            let user_id_clone = user_id.to_string();
+            let post_processing = post_processing.clone();
+            let api = api.clone();
+            let batched_details = batched.remove(&track.id);
+            let span = tracing::info_span!("track", track_id = %track.id);
+            let task = spawn_supervised(format!("track {}", track.id), async move {
                 // Acquire semaphore to limit concurrent ffmpeg processes
                 let _permit = match processing_semaphore.acquire().await {
                     Ok(permit) => permit,
@@ -518,39 +1324,53 @@ impl TrackDatabase {
                     }
                 };
                 
+                // Tracks that haven't started yet are left for the next run
+                if crate::shutdown::is_requested() {
+                    debug!("Shutdown requested, not starting processing of track {}", track.id);
+                    return;
+                }
+                
                 debug!("Processing new track: {} (ID: {})", track.title, track.id);
                 
-                // Get full track details
-                let track_details = match crate::soundcloud::get_track_details(&track.id).await {
-                    Ok(t) => t,
-                    Err(e) => {
-                        error!("Failed to get track details for {}: {}", track.id, e);
-                        return;
-                    }
+                // Fetch the full track details, so the listing entry doesn't need to carry
+                // them, then process and post the track with both semaphores. Batched
+                // details only need their stream URLs resolved, which expire quickly.
+                let details = match batched_details {
+                    Some(details) => api.resolve_track_media(details).await,
+                    None => api.track_details(&track.id, track.secret_token.as_deref()).await,
                 };
-                
-                // Process and post the track with both semaphores
-                match crate::soundcloud::process_and_post_track(
-                    &track.id,
-                    &webhook_url,
-                    temp_dir.as_deref(),
-                    Some(&discord_semaphore)
-                ).await {
-                    Ok((_track_id, _user_id, response)) => {
+                let posted = match details {
+                    Ok(details) => crate::soundcloud::post_track(
+                        details,
+                        &webhook_url,
+                        temp_dir.as_deref(),
+                        Some(&discord_semaphore),
+                        &post_processing,
+                        Some(provenance)
+                    ).await,
+                    Err(e) => Err(e),
+                };
+                match posted {
+                    Ok(posted) => {
                         info!("Successfully sent webhook for track: {} by {} (Discord message ID: {})", 
-                              track_details.title, track_details.user.username, response.message_id);
-                        let mut tracks = successful_tracks.lock().unwrap();
-                        tracks.push((
-                            track.id.clone(),
-                            Some(response.message_id),
-                            response.channel_id
-                        ));
+                              track.title, track.user.username, posted.response.message_id);
+                        successful_tracks.lock().unwrap().push(posted);
+                    },
+                    Err(e) if e.is::<crate::outbox::Queued>() => warn!("{}", e),
+                    // Left for the next poll, the webhook's deletion was reported once already
+                    Err(e) if e.is::<crate::discord::WebhookGone>() => warn!("Not posting track {}: {}", track.id, e),
+                    Err(e) if e.is::<crate::ignore::Ignored>() => {
+                        if let Ok(ignored) = e.downcast::<crate::ignore::Ignored>() {
+                            ignored_tracks.lock().unwrap().push(*ignored);
+                        }
                     },
                     Err(e) => {
                         error!("Failed to process and post track {}: {}", track.id, e);
+                        post_processing.hooks.error(&e.to_string(), Some(&user_id_clone), Some(&track.id));
+                        failed_tracks.lock().unwrap().push((track.clone(), e.to_string()));
                     }
                 };
-            });
+            }.instrument(span));
             
             tasks.push(task);
         }
@@ -559,34 +1379,21 @@ impl TrackDatabase {
         let mut new_tracks_processed = 0;
         
         for task in tasks {
-            match task.await {
-                Ok(()) => {
-                    new_tracks_processed += 1;
-                },
-                Err(e) => {
-                    error!("Error in track processing task: {}", e);
-                    crate::loghandler::increment_error_count();
-                }
+            let result = task.join().await;
+            crate::metrics::decrement_queue_depth();
+            if result.is_ok() {
+                new_tracks_processed += 1;
             }
+            // Panicked tracks are already logged by the supervisor; they aren't added to
+            // the database below, so they're picked up again on the next poll
         }
         
         // Add successful tracks to database with Discord info
         let successful_tracks_guard = successful_tracks.lock().unwrap();
         if !successful_tracks_guard.is_empty() {
             // Add successful tracks to the database with Discord message info
-            for (track_id, message_id, channel_id) in successful_tracks_guard.iter() {
-                if let Some(discord_id) = message_id {
-                    // Add with Discord message info
-                    self.add_track_with_discord_info(
-                        track_id, 
-                        discord_id.clone(), 
-                        channel_id.clone(),
-                        Some(user_id.to_string())
-                    );
-                } else {
-                    // Just add the track without Discord info
-                    self.tracks.insert(track_id.clone(), None);
-                }
+            for posted in successful_tracks_guard.iter() {
+                self.add_posted_track(posted);
             }
             
             // Save the database
@@ -595,10 +1402,164 @@ impl TrackDatabase {
             } else {
                 info!("Database saved with {} tracks including Discord message IDs", 
                      successful_tracks_guard.len());
-                crate::loghandler::increment_total_tracks(successful_tracks_guard.len() as u64);
+                crate::metrics::increment_total_tracks(successful_tracks_guard.len() as u64);
             }
         }
         
+        // Failed tracks are retried on the next poll, keep track of why they failed meanwhile
+        for (track, error) in failed_tracks.lock().unwrap().iter() {
+            self.record_skip(&track.id, SkipReason::Failed, error, Some(&track.user.id), Some(&track.user.username));
+        }
+        for ignored in ignored_tracks.lock().unwrap().iter() {
+            self.record_ignored(ignored);
+        }
+        
+        drop(claims);
         Ok(new_tracks_processed)
     }
-} 
\ No newline at end of file
+}
+
+/// Fetch the IDs of a user's uploaded tracks, and liked tracks if enabled
+async fn fetch_user_track_ids(
+    api: &dyn SoundCloudApi,
+    user_id: &str,
+    max_tracks_per_user: usize,
+    pagination_size: usize,
+    scrape_likes: bool,
+    max_likes_per_user: usize,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    info!("Fetching tracks for user {}", user_id);
+    
+    // Get uploaded tracks
+    let mut all_tracks = api.user_tracks(user_id, max_tracks_per_user, pagination_size).await?.tracks;
+    info!("Found {} uploaded tracks for user {}", all_tracks.len(), user_id);
+    
+    // If enabled, get liked tracks too
+    if scrape_likes {
+        info!("Fetching likes for user {} (enabled in config)", user_id);
+        match api.user_likes(user_id, max_likes_per_user, pagination_size, None).await {
+            Ok(likes) => {
+                let liked_tracks = crate::soundcloud::extract_tracks_from_likes(&likes);
+                info!("Found {} liked tracks for user {}", liked_tracks.len(), user_id);
+                all_tracks.extend(liked_tracks);
+            },
+            Err(e) => {
+                warn!("Failed to fetch likes for user {}: {}", user_id, e);
+            }
+        }
+    }
+    
+    let track_ids: Vec<String> = all_tracks.iter().map(|t| t.id.clone()).collect();
+    info!("Total tracks for user {}: {}", user_id, track_ids.len());
+    Ok(track_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use serde_json::json;
+
+    use super::*;
+    use crate::soundcloud_api::{FixtureApi, FixtureUser};
+
+    /// Answer every post like a Discord webhook, counting them
+    async fn start_mock_webhook() -> (String, Arc<AtomicUsize>) {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let counter = posts.clone();
+        let app = axum::Router::new().fallback(move || {
+            let counter = counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                axum::Json(json!({ "id": n.to_string(), "channel_id": "42" }))
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/webhooks/1/token", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, posts)
+    }
+
+    fn fixture_track(id: &str, user_id: &str) -> crate::soundcloud::Track {
+        serde_json::from_value(json!({
+            "id": id,
+            "title": format!("Track {}", id),
+            "permalink_url": format!("https://soundcloud.com/someone/track-{}", id),
+            "artwork_url": null,
+            "description": null,
+            "user": {
+                "id": user_id,
+                "username": "someone",
+                "permalink_url": "https://soundcloud.com/someone",
+                "avatar_url": null
+            },
+            "created_at": "2026-01-02T03:04:05Z",
+            "duration": 1000,
+            "stream_url": null,
+            "hls_url": null,
+            "download_url": null,
+            "playback_count": null,
+            "likes_count": null,
+            "reposts_count": null,
+            "comment_count": null,
+            "genre": null,
+            "tag_list": null,
+            "downloadable": null
+        })).unwrap()
+    }
+
+    #[test]
+    fn claims_are_kept_per_database() {
+        let first = TrackDatabase::new("claims_first.json".to_string());
+        let second = TrackDatabase::new("claims_second.json".to_string());
+
+        let claim = first.claim("claimed-track").expect("an unclaimed track can be claimed");
+        assert!(first.claim("claimed-track").is_none());
+        // Another profile's database still gets to process the track
+        let other = second.claim("claimed-track").expect("each database claims tracks on its own");
+        assert!(second.claim("claimed-track").is_none());
+
+        drop(claim);
+        assert!(first.claim("claimed-track").is_some());
+        drop(other);
+        assert!(second.claim("claimed-track").is_some());
+    }
+
+    #[tokio::test]
+    async fn poll_user_posts_new_fixture_tracks() {
+        let dir = std::env::temp_dir().join(format!("archiver_webhook_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (webhook_url, posts) = start_mock_webhook().await;
+
+        let mut fixture = FixtureApi::default();
+        fixture.users.insert("1".to_string(), FixtureUser {
+            details: json!({ "id": 1, "username": "someone" }),
+            tracks: vec![fixture_track("100", "1")],
+            ..Default::default()
+        });
+        let api: Arc<dyn SoundCloudApi> = Arc::new(fixture);
+
+        let config = crate::config::Config {
+            discord_webhook_url: webhook_url,
+            tracks_file: dir.join("tracks.json").to_string_lossy().to_string(),
+            temp_dir: Some(dir.join("temp").to_string_lossy().to_string()),
+            metadata_only: true,
+            scrape_user_likes: false,
+            ..Default::default()
+        };
+        let mut db = TrackDatabase::load_or_create(config.tracks_file.clone()).unwrap();
+        let processing = Arc::new(tokio::sync::Semaphore::new(1));
+        let discord = Arc::new(tokio::sync::Semaphore::new(1));
+
+        let posted = db.poll_user(&api, "1", &config, &processing, &discord).await.unwrap();
+        assert_eq!(posted, 1);
+        assert!(db.has_track("100"));
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+
+        // Nothing is new on the next poll
+        let posted = db.poll_user(&api, "1", &config, &processing, &discord).await.unwrap();
+        assert_eq!(posted, 0);
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::Duration;
+use log::{info, warn, debug};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::db::TrackDatabase;
+use crate::soundcloud_api::SoundCloudApi;
+
+/// Check a batch of archived tracks for deletion if `deleted_check_interval_hours` is set
+///
+/// Each track is checked at most once per interval, never-checked tracks first, and at most
+/// `deleted_check_batch_size` tracks per call. Tracks that SoundCloud answers with 404 for are
+/// marked as deleted and, with `notify_deleted_tracks`, get a follow-up post that links to
+/// their original message. Returns the number of tracks found deleted.
+pub async fn check_deleted_tracks(db: &Mutex<TrackDatabase>, api: &dyn SoundCloudApi, config: &Config) -> usize {
+    if config.deleted_check_interval_hours == 0 {
+        return 0;
+    }
+    let interval = Duration::hours(config.deleted_check_interval_hours as i64);
+    let due = db.lock().await.tracks_due_for_availability_check(interval, config.deleted_check_batch_size);
+    if due.is_empty() {
+        return 0;
+    }
+    debug!("Checking {} archived tracks for deletion", due.len());
+
+    let mut guild_id = None;
+    let mut deleted = 0;
+    let mut checked = 0;
+    for track_id in due {
+        if crate::shutdown::is_requested() {
+            break;
+        }
+        let exists = match api.track_exists(&track_id).await {
+            Ok(exists) => exists,
+            Err(e) => {
+                // Try again on the next pass rather than guessing
+                warn!("Failed to check whether track {} still exists: {}", track_id, e);
+                continue;
+            }
+        };
+
+        if !exists {
+            let (archived, message) = {
+                let db = db.lock().await;
+                (db.archived_track(&track_id).cloned(), db.get_discord_info(&track_id))
+            };
+            info!("Track {} ({}) was removed from SoundCloud", track_id,
+                  archived.as_ref().map(|t| t.title.as_str()).unwrap_or("unknown title"));
+
+            // Tracks that were never posted (e.g. recorded on first run) have nothing to follow up on
+            if let (true, Some(message)) = (config.notify_deleted_tracks, &message) {
+                if guild_id.is_none() {
+                    guild_id = crate::discord::get_webhook_guild_id(&config.discord_webhook_url).await
+                        .unwrap_or_else(|e| {
+                            debug!("Failed to look up the webhook's server: {}", e);
+                            None
+                        });
+                }
+                if let Err(e) = crate::discord::send_track_deleted(
+                    &config.discord_webhook_url, &track_id, archived.as_ref(), message, guild_id.as_deref()
+                ).await {
+                    // Leave the track unchecked so the notice is posted on the next pass
+                    warn!("Failed to post the removal of track {}: {}", track_id, e);
+                    continue;
+                }
+            }
+            deleted += 1;
+        }
+
+        db.lock().await.record_availability(&track_id, exists);
+        checked += 1;
+    }
+
+    if checked > 0 {
+        debug!("Checked {} archived tracks, {} were removed from SoundCloud", checked, deleted);
+        if let Err(e) = db.lock().await.save() {
+            warn!("Failed to save database after checking for deleted tracks: {}", e);
+        }
+    }
+    deleted
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use log::{info, warn, debug};
+use tokio::sync::Mutex;
+
+use crate::db::TrackDatabase;
+use crate::discord::{DiscordUnavailable, WebhookGone};
+use crate::soundcloud::PostProcessing;
+
+/// Most tracks retried per poll, so a destination that was down for long catches up gradually
+const RETRY_BATCH_SIZE: usize = 20;
+
+/// Post archived tracks to the `extra_webhooks` destinations they're still missing from
+///
+/// Tracks end up here when posting to a destination failed, and for posts that only go to
+/// the main webhook (backfill batches, albums and tracks from the Discord queue). The
+/// downloads are gone by then, so the track's embed is posted without audio. A destination
+/// that's unavailable or deleted is skipped for the rest of the pass. Destinations that
+/// were removed from the config are forgotten, and so are tracks that can't be fetched
+/// publicly anymore. Returns the number of posts made.
+pub async fn retry_pending_destinations(db: &Mutex<TrackDatabase>, post_processing: &PostProcessing) -> usize {
+    let due = db.lock().await.tracks_with_pending_destinations(RETRY_BATCH_SIZE);
+    if due.is_empty() {
+        return 0;
+    }
+    debug!("Posting {} tracks to the destinations they're missing from", due.len());
+
+    // Public tracks are fetched together; deleted and private ones are left out
+    let ids: Vec<String> = due.iter().map(|(id, _)| id.clone()).collect();
+    let mut tracks: HashMap<String, crate::soundcloud::Track> = match post_processing.api.tracks_details(&ids).await {
+        Ok(tracks) => tracks.into_iter().map(|t| (t.id.clone(), t)).collect(),
+        Err(e) => {
+            warn!("Failed to fetch the tracks still to be posted to other destinations: {}", e);
+            return 0;
+        }
+    };
+
+    let mut unavailable: Vec<String> = Vec::new();
+    let mut posted = 0;
+    let mut changed = false;
+    for (track_id, names) in due {
+        if crate::shutdown::is_requested() {
+            break;
+        }
+        let (removed, names): (Vec<String>, Vec<String>) = names.into_iter()
+            .partition(|name| !post_processing.extra_webhooks.iter().any(|(n, _)| n == name));
+        if !removed.is_empty() {
+            debug!("Forgetting destinations {} of track {}, they're no longer configured", removed.join(", "), track_id);
+            db.lock().await.remove_pending_destinations(&track_id, &removed);
+            changed = true;
+        }
+        let targets: Vec<(&String, &String)> = post_processing.extra_webhooks.iter()
+            .filter(|(name, url)| names.contains(name) && !unavailable.contains(name) && crate::discord::check_webhook(url).is_ok())
+            .map(|(name, url)| (name, url))
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+
+        let Some(track) = tracks.remove(&track_id) else {
+            warn!("Track {} can't be fetched publicly anymore, not posting it to destinations {}", track_id, names.join(", "));
+            db.lock().await.remove_pending_destinations(&track_id, &names);
+            changed = true;
+            continue;
+        };
+        let (embed, _) = match crate::discord::prepare_track_post(
+            &*post_processing.api, &track, None, None, true, post_processing.embed_fields, &post_processing.plugins
+        ).await {
+            Ok(post) => post,
+            Err(e) => {
+                warn!("Failed to prepare the embed of track {}: {}", track_id, e);
+                continue;
+            }
+        };
+        let content = post_processing.post_content.as_deref().map(|template| crate::discord::render_post_content(
+            template, &track.user.username, &track.title, &track.permalink_url));
+
+        let mut responses = Vec::new();
+        for (name, url) in targets {
+            match crate::discord::send_track_post(
+                url, &track, embed.clone(), content.as_deref(), Vec::new(), post_processing.webhook_retry
+            ).await {
+                Ok(response) => {
+                    info!("Posted track {} to destination {}, message ID: {}", track_id, name, response.message_id);
+                    responses.push((name.clone(), response));
+                },
+                Err(e) if e.is::<DiscordUnavailable>() || e.is::<WebhookGone>() => {
+                    warn!("Not posting to destination {} for now: {}", name, e);
+                    unavailable.push(name.clone());
+                },
+                Err(e) => warn!("Failed to post track {} to destination {}, retrying on the next poll: {}", track_id, name, e),
+            }
+        }
+        if !responses.is_empty() {
+            posted += responses.len();
+            db.lock().await.set_destination_messages(&track_id, &responses);
+            changed = true;
+        }
+    }
+
+    if changed {
+        if let Err(e) = db.lock().await.save() {
+            warn!("Failed to save database after posting to missing destinations: {}", e);
+        }
+    }
+    posted
+}
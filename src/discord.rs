@@ -1,58 +1,496 @@
 use reqwest::{Client, multipart};
 use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use log::{info, warn, error, debug};
-use crate::soundcloud::Track;
+use crate::metrics::Service;
+use crate::profile::ProfileItem;
+use crate::soundcloud::{Track, TrackUser};
 
 /// Response data from a Discord webhook
 #[derive(Debug, Clone)]
 pub struct WebhookResponse {
     pub message_id: String,
     pub channel_id: Option<String>,
+    /// Files that couldn't be uploaded with the message and still need to be posted
+    pub unattached_files: Vec<(String, String)>,
+}
+
+/// Largest file Discord accepts as a regular webhook attachment
+pub const MAX_DISCORD_UPLOAD_SIZE: u64 = 8 * 1024 * 1024; // 8MB per file
+
+/// Most embeds Discord shows in one message
+pub const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+
+/// Discord couldn't be reached or failed with a server error, so the post can be retried later
+#[derive(Debug)]
+pub struct DiscordUnavailable(pub String);
+
+impl std::fmt::Display for DiscordUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Discord is unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for DiscordUnavailable {}
+
+/// Discord error codes for a webhook that was deleted (Unknown Webhook) or whose token
+/// no longer matches (Invalid Webhook Token)
+const WEBHOOK_GONE_CODES: [u64; 2] = [10015, 50027];
+
+/// How often a webhook that was found deleted is checked again
+const WEBHOOK_RECHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How often the webhook monitor raises alerts and rechecks deleted webhooks
+const WEBHOOK_MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Discord says the webhook was deleted or its token is invalid, so no post to it can
+/// succeed until it's replaced in the config
+#[derive(Debug, Clone)]
+pub struct WebhookGone {
+    /// Discord's error code, see [`WEBHOOK_GONE_CODES`]
+    pub code: u64,
+    pub message: String,
+}
+
+impl WebhookGone {
+    /// Recognize a deleted or invalid webhook from the body of an error response
+    fn from_response(body: &str) -> Option<Self> {
+        let json: Value = serde_json::from_str(body).ok()?;
+        let code = json.get("code")?.as_u64().filter(|code| WEBHOOK_GONE_CODES.contains(code))?;
+        Some(WebhookGone {
+            code,
+            message: json.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for WebhookGone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Discord webhook is deleted or invalid ({}: {}), posting to it is paused", self.code, self.message)
+    }
+}
+
+impl std::error::Error for WebhookGone {}
+
+/// A webhook found deleted, kept until it works again
+#[derive(Debug)]
+struct GoneWebhook {
+    error: WebhookGone,
+    /// When it was last checked, by a post or by the monitor
+    checked: Instant,
+    /// Whether the monitor posted an alert about it yet
+    alerted: bool,
+}
+
+/// Discord allows about 30 messages per minute through each webhook
+const WEBHOOK_REQUESTS_PER_WINDOW: usize = 30;
+const WEBHOOK_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+lazy_static::lazy_static! {
+    /// Send times of recent (and already scheduled) posts, per webhook
+    static ref WEBHOOK_SENDS: Mutex<HashMap<String, VecDeque<Instant>>> = Mutex::new(HashMap::new());
+    /// Webhooks Discord said were deleted or invalid, by URL without the query
+    static ref GONE_WEBHOOKS: Mutex<HashMap<String, GoneWebhook>> = Mutex::new(HashMap::new());
+}
+
+/// The webhook URL without its query, so `?wait=true` doesn't make it a different webhook
+fn webhook_key(webhook_url: &str) -> &str {
+    webhook_url.split('?').next().unwrap_or(webhook_url)
+}
+
+/// A webhook's ID, to name it in logs and alerts without giving away its token
+fn webhook_label(webhook_url: &str) -> String {
+    let mut segments = webhook_key(webhook_url).split('/');
+    segments.by_ref().find(|segment| *segment == "webhooks");
+    match segments.next() {
+        Some(id) if !id.is_empty() => format!("webhook {}", id),
+        _ => "webhook".to_string(),
+    }
+}
+
+/// Fail with [`WebhookGone`] if posting to the webhook is paused because it was deleted
+///
+/// Checking is cheap and doesn't contact Discord, so it's done before downloading a track
+/// as well as before every request.
+pub fn check_webhook(webhook_url: &str) -> Result<(), WebhookGone> {
+    match GONE_WEBHOOKS.lock().unwrap_or_else(|e| e.into_inner()).get(webhook_key(webhook_url)) {
+        Some(gone) => Err(gone.error.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Number of webhooks posting is paused for
+pub fn gone_webhook_count() -> usize {
+    GONE_WEBHOOKS.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+/// Pause posting to a webhook Discord said was deleted, telling the operator how to fix it
+/// the first time
+fn mark_webhook_gone(webhook_url: &str, gone: &WebhookGone) {
+    let mut webhooks = GONE_WEBHOOKS.lock().unwrap_or_else(|e| e.into_inner());
+    if webhooks.contains_key(webhook_key(webhook_url)) {
+        return;
+    }
+    webhooks.insert(webhook_key(webhook_url).to_string(), GoneWebhook {
+        error: gone.clone(),
+        checked: Instant::now(),
+        alerted: false,
+    });
+    drop(webhooks);
+    error!("{}", webhook_gone_guidance(webhook_url, gone));
+}
+
+/// What happened to a deleted webhook and how to get posting going again
+fn webhook_gone_guidance(webhook_url: &str, gone: &WebhookGone) -> String {
+    format!("Discord says {} was deleted or its token is invalid ({}: {}). Posting to it is paused and new tracks are left for later instead of being retried one by one. \
+             To fix this, create a new webhook in the channel's settings (Integrations > Webhooks) and put its URL in place of the old one \
+             (discord_webhook_url, extra_webhooks or a user override), then restart. If the webhook comes back, posting resumes within {} minutes.",
+            webhook_label(webhook_url), gone.code, gone.message, WEBHOOK_RECHECK_INTERVAL.as_secs() / 60)
+}
+
+/// Ask Discord whether a webhook works, without posting anything
+///
+/// Returns `Ok(false)` only when Discord says it's still deleted or invalid.
+async fn probe_webhook(webhook_url: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let response = Client::new().get(webhook_key(webhook_url)).send().await?;
+    if response.status().is_success() {
+        return Ok(true);
+    }
+    let status = response.status();
+    let body = response.text().await?;
+    match WebhookGone::from_response(&body) {
+        Some(_) => Ok(false),
+        None => Err(format!("{} - {}", status, body).into()),
+    }
+}
+
+/// Spawn a task that looks after webhooks found deleted
+///
+/// Newly deleted webhooks are posted to `alert_webhook_url`, if set, and each one is checked
+/// again every few minutes; once it works, posting resumes and a note follows. Stops when a
+/// shutdown is requested.
+pub fn spawn_webhook_monitor(alert_webhook_url: Option<String>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(WEBHOOK_MONITOR_INTERVAL) => {},
+                _ = crate::shutdown::requested() => return,
+            }
+
+            let (new, due): (Vec<(String, WebhookGone)>, Vec<String>) = {
+                let mut webhooks = GONE_WEBHOOKS.lock().unwrap_or_else(|e| e.into_inner());
+                let new = webhooks.iter_mut()
+                    .filter(|(_, gone)| !gone.alerted)
+                    .map(|(url, gone)| {
+                        gone.alerted = true;
+                        (url.clone(), gone.error.clone())
+                    })
+                    .collect();
+                let due = webhooks.iter_mut()
+                    .filter(|(_, gone)| gone.checked.elapsed() >= WEBHOOK_RECHECK_INTERVAL)
+                    .map(|(url, gone)| {
+                        gone.checked = Instant::now();
+                        url.clone()
+                    })
+                    .collect();
+                (new, due)
+            };
+
+            let mut notices = Vec::new();
+            for (url, gone) in new {
+                notices.push((url.clone(), true, webhook_gone_guidance(&url, &gone)));
+            }
+            for url in due {
+                match probe_webhook(&url).await {
+                    Ok(true) => {
+                        GONE_WEBHOOKS.lock().unwrap_or_else(|e| e.into_inner()).remove(&url);
+                        let description = format!("Discord {} works again, posting to it resumes", webhook_label(&url));
+                        info!("{}", description);
+                        notices.push((url, false, description));
+                    },
+                    Ok(false) => debug!("Discord {} is still deleted or invalid", webhook_label(&url)),
+                    Err(e) => debug!("Failed to check whether Discord {} works again: {}", webhook_label(&url), e),
+                }
+            }
+
+            let Some(alert_webhook_url) = &alert_webhook_url else {
+                continue;
+            };
+            for (url, gone, description) in notices {
+                // An alert about the alert webhook itself has nowhere to go
+                if webhook_key(&url) == webhook_key(alert_webhook_url) {
+                    continue;
+                }
+                if let Err(e) = send_webhook_notice(alert_webhook_url, gone, &description).await {
+                    warn!("Failed to post the status of Discord {} to the alert webhook: {}", webhook_label(&url), e);
+                }
+            }
+        }
+    });
+}
+
+/// Wait until a post to the webhook fits in Discord's per-webhook rate limit
+///
+/// Reserves a send slot before returning, so concurrent posts to the same webhook
+/// are spread out instead of all waiting for the same slot.
+async fn wait_for_webhook_slot(webhook_url: &str) {
+    let slot = {
+        let mut sends = WEBHOOK_SENDS.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let times = sends.entry(webhook_url.to_string()).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) >= WEBHOOK_RATE_WINDOW) {
+            times.pop_front();
+        }
+        let slot = if times.len() < WEBHOOK_REQUESTS_PER_WINDOW {
+            now
+        } else {
+            (times[times.len() - WEBHOOK_REQUESTS_PER_WINDOW] + WEBHOOK_RATE_WINDOW).max(now)
+        };
+        times.push_back(slot);
+        slot
+    };
+
+    let wait = slot.saturating_duration_since(Instant::now());
+    if !wait.is_zero() {
+        debug!("Webhook rate limit reached, waiting {:.1}s before posting", wait.as_secs_f64());
+        tokio::time::sleep(wait).await;
+    }
 }
 
 /// Send a track to Discord via webhook
 pub async fn send_track_webhook(
     webhook_url: &str, 
     track: &Track,
-    audio_files: Option<Vec<(String, String)>> // Vec of (file_path, file_name)
+    audio_files: Option<Vec<(String, String)>>, // Vec of (file_path, file_name)
+    plugins: &crate::plugins::Plugins
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let api = crate::soundcloud_api::SoundCloudClient;
+    let (embed, files) = prepare_track_post(&api, track, audio_files, None, false, EmbedFields::default(), plugins).await?;
+    send_track_post(webhook_url, track, embed, None, files, WebhookRetry::default()).await
+}
+
+/// Build the embed for a track and let plugins adjust it and the attachments
+///
+/// A `badge` (such as "New upload") is put in front of the footer text. With
+/// `metadata_only`, the footer says the audio wasn't archived.
+pub async fn prepare_track_post(
+    api: &dyn crate::soundcloud_api::SoundCloudApi,
+    track: &Track,
+    audio_files: Option<Vec<(String, String)>>, // Vec of (file_path, file_name)
+    badge: Option<&str>,
+    metadata_only: bool,
+    embed_fields: EmbedFields,
+    plugins: &crate::plugins::Plugins
+) -> Result<(Value, Vec<(String, String)>), Box<dyn std::error::Error + Send + Sync>> {
+    // Build the embed object
+    info!("Preparing Discord webhook for track '{}' (ID: {})", track.title, track.id);
+    let mut embed = build_track_embed(track, badge, metadata_only, embed_fields);
+    
+    // Without track artwork, show the artist's banner (if any) next to their avatar
+    if track.artwork_url.as_deref().is_none_or(|url| url.is_empty()) {
+        match api.user_banner_url(&track.user.id).await {
+            Ok(Some(banner)) => embed["image"] = json!({ "url": banner }),
+            Ok(None) => {},
+            Err(e) => debug!("Failed to look up banner of user {}: {}", track.user.id, e),
+        }
+    }
+    
+    // Let plugins adjust the embed and attachments
+    plugins.apply(track, embed, audio_files.unwrap_or_default()).await
+}
+
+/// Fill in a `post_content` template with `{artist}`, `{title}` and `{url}`
+///
+/// The result is cut to the 2000 characters Discord allows in a message.
+pub fn render_post_content(template: &str, artist: &str, title: &str, url: &str) -> String {
+    let content = template
+        .replace("{artist}", artist)
+        .replace("{title}", title)
+        .replace("{url}", url);
+    if content.chars().count() > 2000 {
+        format!("{}…", content.chars().take(1999).collect::<String>())
+    } else {
+        content
+    }
+}
+
+/// Which optional parts of a track embed are shown
+///
+/// The title, artist name, link and artwork are always shown, and so is the note on
+/// tracks whose audio SoundCloud restricts.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbedFields {
+    pub description: bool,
+    pub duration: bool,
+    pub genre: bool,
+    pub tags: bool,
+    /// Play, like, repost and comment counts at the time of posting
+    pub stats: bool,
+    /// The footer text; a provenance badge is still shown without it
+    pub footer: bool,
+    pub author_icon: bool,
+}
+
+impl Default for EmbedFields {
+    fn default() -> Self {
+        EmbedFields {
+            description: true,
+            duration: true,
+            genre: true,
+            tags: true,
+            stats: false,
+            footer: true,
+            author_icon: true,
+        }
+    }
+}
+
+impl EmbedFields {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        EmbedFields {
+            description: config.embed_description,
+            duration: config.embed_duration,
+            genre: config.embed_genre,
+            tags: config.embed_tags,
+            stats: config.embed_stats,
+            footer: config.embed_footer,
+            author_icon: config.embed_author_icon,
+        }
+    }
+}
+
+/// How often a track post is retried when Discord is briefly unavailable
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookRetry {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further one
+    pub base_delay: Duration,
+}
+
+impl Default for WebhookRetry {
+    fn default() -> Self {
+        WebhookRetry { retries: 3, base_delay: Duration::from_millis(1000) }
+    }
+}
+
+impl WebhookRetry {
+    /// Longest delay between two attempts
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        WebhookRetry {
+            retries: config.webhook_retries.min(u32::MAX as u64) as u32,
+            base_delay: Duration::from_millis(config.webhook_retry_base_ms),
+        }
+    }
+
+    /// Delay before the given retry (starting at 1), with 50% jitter either way so
+    /// posts that failed together don't all retry at the same moment
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(Self::MAX_DELAY);
+        let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+        backoff.mul_f64(0.5 + jitter)
+    }
+}
+
+/// Post a prepared embed and its attachments to Discord
+///
+/// `content` is shown as plain text above the embed (see [`render_post_content`]).
+/// Network errors and server errors are retried as configured in `retry`, with growing,
+/// jittered delays. Fails with [`DiscordUnavailable`] if Discord still couldn't be reached
+/// or had a server error after the last retry.
+#[tracing::instrument(name = "discord_post", skip_all, fields(track_id = %track.id))]
+pub async fn send_track_post(
+    webhook_url: &str,
+    track: &Track,
+    embed: Value,
+    content: Option<&str>,
+    files: Vec<(String, String)>, // Vec of (file_path, file_name)
+    retry: WebhookRetry
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let mut attempt = 0;
+    loop {
+        let result = try_send_track_post(webhook_url, track, embed.clone(), content, files.clone()).await;
+        match result {
+            Err(e) if e.is::<DiscordUnavailable>() && attempt < retry.retries => {
+                attempt += 1;
+                let delay = retry.delay(attempt);
+                warn!("Discord is unavailable, retrying track '{}' in {:.1}s (retry {}/{}): {}",
+                      track.title, delay.as_secs_f64(), attempt, retry.retries, e);
+                tokio::time::sleep(delay).await;
+            },
+            result => {
+                if attempt > 0 {
+                    match &result {
+                        Ok(_) => info!("Posted track '{}' after {} retries", track.title, attempt),
+                        Err(e) => warn!("Giving up on posting track '{}' after {} retries: {}", track.title, attempt, e),
+                    }
+                    crate::metrics::record_webhook_retry_outcome(result.is_ok());
+                }
+                return result;
+            }
+        }
+    }
+}
+
+/// Post a prepared embed and its attachments to Discord once
+async fn try_send_track_post(
+    webhook_url: &str,
+    track: &Track,
+    embed: Value,
+    content: Option<&str>,
+    files: Vec<(String, String)> // Vec of (file_path, file_name)
 ) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
     // Create the webhook client
     let client = Client::new();
     
+    // Stay under Discord's per-webhook limit so bursts of new tracks don't get 429s
+    wait_for_webhook_slot(webhook_url).await;
+    
     // Add wait=true parameter to webhook URL
+    let rate_limit_key = webhook_url;
     let webhook_url = if webhook_url.contains('?') {
         format!("{}&wait=true", webhook_url)
     } else {
         format!("{}?wait=true", webhook_url)
     };
-    
-    // Build the embed object
-    info!("Preparing Discord webhook for track '{}' (ID: {})", track.title, track.id);
-    let embed = build_track_embed(track);
-    
-    // Check audio files
-    let files_count = match &audio_files {
-        Some(files) => files.len(),
-        None => 0,
-    };
+    let files_count = files.len();
     
     // If we have audio files, we need to use multipart/form-data
     // Otherwise, we can just use a simple JSON post
-    let result = if let Some(files) = audio_files {
-        if files.is_empty() {
-            debug!("No audio files attached, sending embed only");
-            send_embed_only(client, &webhook_url, embed).await
-        } else {
-            debug!("Attaching {} audio files to webhook", files.len());
-            send_with_audio_files(client, &webhook_url, embed, files).await
-        }
+    let result = if files.is_empty() {
+        debug!("No audio files attached, sending embed only");
+        send_embed_only(client, &webhook_url, embed, content).await
     } else {
-        debug!("No audio files provided, sending embed only");
-        send_embed_only(client, &webhook_url, embed).await
+        debug!("Attaching {} audio files to webhook", files.len());
+        let mut payload = json!({
+            "embeds": [embed.clone()],
+            "username": crate::locale::get().username,
+        });
+        if let Some(content) = content {
+            payload["content"] = json!(content);
+        }
+        match send_with_audio_files(client.clone(), &webhook_url, payload, files.clone()).await {
+            // Rather than losing the whole track, post the embed alone and leave the files for a follow-up
+            Err(e) if !e.is::<DiscordUnavailable>() && !e.is::<WebhookGone>() => {
+                warn!("Failed to post track '{}' with its attachments, posting the embed alone: {}", track.title, e);
+                let mut embed = embed;
+                add_pending_files_note(&mut embed);
+                wait_for_webhook_slot(rate_limit_key).await;
+                send_embed_only(client, &webhook_url, embed, content).await
+                    .map(|response| WebhookResponse { unattached_files: files, ..response })
+            },
+            result => result,
+        }
     };
     
     // Log result
@@ -65,12 +503,517 @@ pub async fn send_track_webhook(
     result
 }
 
+/// Post the embeds of several tracks in one message, without attachments
+///
+/// Discord shows at most [`MAX_EMBEDS_PER_MESSAGE`] embeds per message. Retried like
+/// [`send_track_post`].
+pub async fn send_track_batch(
+    webhook_url: &str,
+    tracks: &[Track],
+    embeds: Vec<Value>,
+    retry: WebhookRetry
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    let mut attempt = 0;
+    loop {
+        wait_for_webhook_slot(webhook_url).await;
+        let result = send_embeds(Client::new(), &url, embeds.clone(), None).await;
+        match result {
+            Err(e) if e.is::<DiscordUnavailable>() && attempt < retry.retries => {
+                attempt += 1;
+                let delay = retry.delay(attempt);
+                warn!("Discord is unavailable, retrying batch of {} tracks in {:.1}s (retry {}/{}): {}",
+                      tracks.len(), delay.as_secs_f64(), attempt, retry.retries, e);
+                tokio::time::sleep(delay).await;
+            },
+            result => {
+                if attempt > 0 {
+                    crate::metrics::record_webhook_retry_outcome(result.is_ok());
+                }
+                match &result {
+                    Ok(response) => info!("Posted a batch of {} tracks, message ID: {}", tracks.len(), response.message_id),
+                    Err(e) => error!("Failed to post a batch of {} tracks: {}", tracks.len(), e),
+                }
+                return result;
+            }
+        }
+    }
+}
+
+/// Post files as follow-up messages, one file each, after the track's embed was posted without them
+///
+/// Files too large for Discord are left out. Returns the files that couldn't be posted;
+/// stops early if Discord is unavailable.
+pub async fn send_track_files(
+    webhook_url: &str,
+    track: &Track,
+    files: &[(String, String)] // Vec of (file_path, file_name)
+) -> Vec<(String, String)> {
+    let client = Client::new();
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    let locale = crate::locale::get();
+
+    let mut failed = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        if !oversized_files(std::slice::from_ref(file)).is_empty() {
+            warn!("Not posting {} for track '{}', it's over Discord's size limit", file.1, track.title);
+            continue;
+        }
+
+        wait_for_webhook_slot(webhook_url).await;
+        let payload = json!({
+            "content": format!("{} **{}**", locale.files_follow_up, track.title),
+            "username": locale.username,
+        });
+        match send_with_audio_files(client.clone(), &url, payload, vec![file.clone()]).await {
+            Ok(response) => debug!("Posted {} for track '{}', message ID: {}", file.1, track.title, response.message_id),
+            Err(e) if e.is::<DiscordUnavailable>() || e.is::<WebhookGone>() => {
+                warn!("Not posting the remaining files for track '{}': {}", track.title, e);
+                failed.extend(files[i..].iter().cloned());
+                break;
+            },
+            Err(e) => {
+                warn!("Failed to post {} for track '{}': {}", file.1, track.title, e);
+                failed.push(file.clone());
+            }
+        }
+    }
+    failed
+}
+
+/// Post an album as a whole: one embed with its artwork and tracklist, followed by its files
+///
+/// The files are spread over as many messages as Discord's attachment limits need, the
+/// first ones attached to the embed. Files too large for Discord are left out, and files
+/// whose follow-up message fails are only logged since the album itself was posted.
+pub async fn send_album_post(
+    webhook_url: &str,
+    album: &crate::albums::Album,
+    tracks: &[Track],
+    files: &[(String, String)], // Vec of (file_path, file_name)
+    content: Option<&str>,
+    metadata_only: bool
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    // Discord allows 10 attachments and 25MB per message, stay a bit below both
+    const MAX_ATTACHMENTS: usize = 8;
+    const MAX_MESSAGE_SIZE: u64 = 24 * 1024 * 1024;
+    let locale = crate::locale::get();
+
+    // Embed descriptions hold up to 4096 characters, leave out whatever doesn't fit
+    let mut tracklist = String::new();
+    for (i, track) in tracks.iter().enumerate() {
+        let duration_secs = track.duration / 1000;
+        let line = format!("{}. [{}]({}) ({}:{:02})",
+                           i + 1, track.title, track.permalink_url, duration_secs / 60, duration_secs % 60);
+        if tracklist.chars().count() + line.chars().count() + 1 > 4000 {
+            tracklist.push_str("\n…");
+            break;
+        }
+        if !tracklist.is_empty() {
+            tracklist.push('\n');
+        }
+        tracklist.push_str(&line);
+    }
+
+    let mut embed = json!({
+        "title": album.title,
+        "type": "rich",
+        "description": tracklist,
+        "url": album.permalink_url,
+        "color": 0xFF7700, // SoundCloud orange
+        "author": {
+            "name": album.user.username.clone(),
+            "url": album.user.permalink_url.clone(),
+            "icon_url": album.user.avatar_url.clone().unwrap_or_default()
+        },
+        "footer": {
+            "text": if metadata_only { &locale.footer_metadata_only } else { &locale.footer_album }
+        },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    if let Some(url) = &album.artwork_url {
+        embed["image"] = json!({ "url": url });
+    }
+
+    // Group the files into messages, in album order
+    let mut batches: Vec<Vec<(String, String)>> = Vec::new();
+    let mut batch_size = 0;
+    for (path, name) in files {
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                warn!("Failed to get file size for {}: {}", path, e);
+                continue;
+            }
+        };
+        if size > MAX_DISCORD_UPLOAD_SIZE {
+            warn!("Not posting {} for album '{}', it's over Discord's size limit", name, album.title);
+            continue;
+        }
+        match batches.last_mut() {
+            Some(batch) if batch.len() < MAX_ATTACHMENTS && batch_size + size <= MAX_MESSAGE_SIZE => {
+                batch.push((path.clone(), name.clone()));
+                batch_size += size;
+            },
+            _ => {
+                batches.push(vec![(path.clone(), name.clone())]);
+                batch_size = size;
+            }
+        }
+    }
+
+    let client = Client::new();
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    let mut batches = batches.into_iter();
+
+    wait_for_webhook_slot(webhook_url).await;
+    let response = match batches.next() {
+        Some(batch) => {
+            let mut payload = json!({
+                "embeds": [embed],
+                "username": locale.username,
+            });
+            if let Some(content) = content {
+                payload["content"] = json!(content);
+            }
+            send_with_audio_files(client.clone(), &url, payload, batch).await?
+        },
+        None => send_embed_only(client.clone(), &url, embed, content).await?,
+    };
+    info!("Posted album '{}' with {} tracks, message ID: {}", album.title, tracks.len(), response.message_id);
+
+    for batch in batches {
+        wait_for_webhook_slot(webhook_url).await;
+        let payload = json!({
+            "content": format!("{} **{}**", locale.files_follow_up, album.title),
+            "username": locale.username,
+        });
+        let names = batch.iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>().join(", ");
+        match send_with_audio_files(client.clone(), &url, payload, batch.clone()).await {
+            Ok(follow_up) => debug!("Posted {} for album '{}', message ID: {}", names, album.title, follow_up.message_id),
+            Err(e) => warn!("Failed to post {} for album '{}': {}", names, album.title, e),
+        }
+    }
+    Ok(response)
+}
+
+/// Post a note that a user changed the tracks and playlists pinned to their spotlight
+pub async fn send_spotlight_change(
+    webhook_url: &str,
+    user_id: &str,
+    user: Option<&TrackUser>,
+    before: &[ProfileItem],
+    after: &[ProfileItem]
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let locale = crate::locale::get();
+    let list = |items: Vec<&ProfileItem>| -> String {
+        // Embed fields hold up to 1024 characters, leave out whatever doesn't fit
+        let mut text = String::new();
+        for item in items {
+            let line = match &item.permalink_url {
+                Some(url) => format!("[{}]({})", item.title, url),
+                None => item.title.clone(),
+            };
+            if text.chars().count() + line.chars().count() + 1 > 1024 {
+                break;
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&line);
+        }
+        text
+    };
+    let pinned: Vec<&ProfileItem> = after.iter().filter(|i| !before.iter().any(|b| b.id == i.id)).collect();
+    let unpinned: Vec<&ProfileItem> = before.iter().filter(|i| !after.iter().any(|a| a.id == i.id)).collect();
+
+    let mut fields = Vec::new();
+    if !pinned.is_empty() {
+        fields.push(json!({ "name": locale.spotlight_pinned, "value": list(pinned), "inline": false }));
+    }
+    if !unpinned.is_empty() {
+        fields.push(json!({ "name": locale.spotlight_unpinned, "value": list(unpinned), "inline": false }));
+    }
+    let mut embed = json!({
+        "title": locale.spotlight_changed,
+        "color": 0xFF7700, // SoundCloud orange
+        "fields": fields,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    if !after.is_empty() {
+        embed["description"] = json!(list(after.iter().collect()));
+    }
+    match user {
+        Some(user) => {
+            embed["author"] = json!({ "name": user.username, "url": user.permalink_url });
+            if let Some(avatar) = user.avatar_url.as_deref().filter(|a| !a.is_empty()) {
+                embed["author"]["icon_url"] = json!(avatar);
+            }
+        },
+        None => embed["author"] = json!({ "name": format!("User {}", user_id) }),
+    }
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post a note that an archived track was removed from SoundCloud
+///
+/// Links to the track's original post when the server it was posted in is known.
+pub async fn send_track_deleted(
+    webhook_url: &str,
+    track_id: &str,
+    track: Option<&crate::db::ArchivedTrack>,
+    message: &crate::db::DiscordMessage,
+    guild_id: Option<&str>
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let locale = crate::locale::get();
+    let mut description = match track {
+        Some(track) => format!("**[{}]({})** by {}", track.title, track.permalink_url, track.username),
+        None => format!("Track {}", track_id),
+    };
+    if let Some(link) = original_post_link(message, guild_id) {
+        description.push_str(&format!("\n[{}]({})", locale.original_post, link));
+    }
+    let embed = json!({
+        "title": locale.track_removed,
+        "description": description,
+        "color": 0x99AAB5, // Grey, the track is gone
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post a note that a newer release of the archiver is available
+pub async fn send_update_notice(
+    webhook_url: &str,
+    release: &crate::updates::Release,
+    current_version: &str,
+    highlights: &[String]
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let locale = crate::locale::get();
+    let mut description = format!("**{}** (running v{})", release.tag_name, current_version);
+    for line in highlights {
+        description.push_str(&format!("\n• {}", line));
+    }
+    let embed = json!({
+        "title": locale.update_available,
+        "url": release.html_url,
+        "description": description,
+        "color": 0x5865F2, // Discord blurple, it's about the archiver rather than a track
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post a note that SoundCloud requests are failing because of the client ID, or that
+/// they work again
+pub async fn send_client_id_notice(
+    webhook_url: &str,
+    failing: bool,
+    description: &str
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let locale = crate::locale::get();
+    let embed = json!({
+        "title": if failing { &locale.client_id_failing } else { &locale.client_id_recovered },
+        "description": description,
+        "color": if failing { 0xED4245 } else { 0x57F287 }, // Discord red and green
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post a note that a webhook was deleted, with what to do about it, or that it works again
+pub async fn send_webhook_notice(
+    webhook_url: &str,
+    gone: bool,
+    description: &str
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let locale = crate::locale::get();
+    let embed = json!({
+        "title": if gone { &locale.webhook_gone } else { &locale.webhook_recovered },
+        "description": description,
+        "color": if gone { 0xED4245 } else { 0x57F287 }, // Discord red and green
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post a note that a watched user was deactivated, with how to reactivate them
+pub async fn send_user_deactivated_notice(
+    webhook_url: &str,
+    description: &str
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let embed = json!({
+        "title": crate::locale::get().user_deactivated,
+        "description": description,
+        "color": 0xED4245, // Discord red
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Post the changes an artist made to an archived track
+///
+/// Each changed part gets a field with its old and new value. Links to the track's
+/// original post when the server it was posted in is known.
+pub async fn send_track_edit(
+    webhook_url: &str,
+    track: &Track,
+    changes: &[(crate::edits::EditedField, Option<String>, Option<String>)],
+    message: Option<&crate::db::DiscordMessage>,
+    guild_id: Option<&str>
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    use crate::edits::EditedField;
+    let locale = crate::locale::get();
+    // Embed fields hold up to 1024 characters, so each side gets a bit less than half
+    let shorten = |value: &Option<String>| -> String {
+        match value {
+            Some(v) if v.chars().count() > 500 => format!("{}…", v.chars().take(500).collect::<String>()),
+            Some(v) => v.clone(),
+            None => "—".to_string(),
+        }
+    };
+    let fields: Vec<Value> = changes.iter().map(|(field, before, after)| {
+        let name = match field {
+            EditedField::Title => &locale.edit_title,
+            EditedField::Description => &locale.edit_description,
+            EditedField::Artwork => &locale.edit_artwork,
+            EditedField::Tags => &locale.tags,
+        };
+        json!({
+            "name": name,
+            "value": format!("~~{}~~\n{}", shorten(before), shorten(after)),
+            "inline": false,
+        })
+    }).collect();
+
+    let mut embed = json!({
+        "title": format!("{}: {}", locale.track_updated, track.title),
+        "url": track.permalink_url,
+        "color": 0xFF7700, // SoundCloud orange
+        "author": {
+            "name": track.user.username.clone(),
+            "url": track.user.permalink_url.clone(),
+            "icon_url": track.user.avatar_url.clone().unwrap_or_default()
+        },
+        "fields": fields,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    if let Some(link) = message.and_then(|m| original_post_link(m, guild_id)) {
+        embed["description"] = json!(format!("[{}]({})", locale.original_post, link));
+    }
+    if let Some(url) = crate::soundcloud::cover_art_url(track) {
+        embed["thumbnail"] = json!({ "url": url });
+    }
+
+    wait_for_webhook_slot(webhook_url).await;
+    let url = if webhook_url.contains('?') {
+        format!("{}&wait=true", webhook_url)
+    } else {
+        format!("{}?wait=true", webhook_url)
+    };
+    send_embed_only(Client::new(), &url, embed, None).await
+}
+
+/// Link to a posted message, if the server and channel it was posted in are known
+fn original_post_link(message: &crate::db::DiscordMessage, guild_id: Option<&str>) -> Option<String> {
+    let channel = message.channel_id.as_deref()?;
+    Some(format!("https://discord.com/channels/{}/{}/{}", guild_id?, channel, message.id))
+}
+
+/// Note in a track's embed that its files will follow in separate messages
+fn add_pending_files_note(embed: &mut Value) {
+    let locale = crate::locale::get();
+    let note = json!({
+        "name": locale.attachments,
+        "value": locale.attachments_pending,
+        "inline": false,
+    });
+    match embed.get_mut("fields").and_then(|f| f.as_array_mut()) {
+        Some(fields) => fields.push(note),
+        None => embed["fields"] = json!([note]),
+    }
+}
+
+/// Names of the files that are too large to attach to a Discord message
+pub fn oversized_files(files: &[(String, String)]) -> Vec<String> {
+    files.iter()
+        .filter(|(path, _)| fs::metadata(path).is_ok_and(|m| m.len() > MAX_DISCORD_UPLOAD_SIZE))
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+/// Look up the ID of the server a webhook posts to, needed to link to its messages
+pub async fn get_webhook_guild_id(webhook_url: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let webhook: Value = Client::new()
+        .get(webhook_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(webhook.get("guild_id").and_then(|g| g.as_str()).map(|g| g.to_string()))
+}
+
 /// Build a Discord embed for the track
-fn build_track_embed(track: &Track) -> Value {
+fn build_track_embed(track: &Track, badge: Option<&str>, metadata_only: bool, show: EmbedFields) -> Value {
     debug!("Building Discord embed for track '{}' (ID: {})", track.title, track.id);
     
     // Extract additional metadata from raw_data if available
-    let mut description = track.description.clone().unwrap_or_default();
+    let mut description = track.description.clone().filter(|_| show.description).unwrap_or_default();
     
     // Trim description to 2000 characters to avoid Discord payload size limits
     const MAX_DESCRIPTION_LENGTH: usize = 2000;
@@ -123,25 +1066,27 @@ fn build_track_embed(track: &Track) -> Value {
            play_count, likes_count, reposts_count, comment_count);
     
     // Build fields for the embed
+    let locale = crate::locale::get();
     let mut fields = vec![];
     
-    // Add duration if available
-    if track.duration > 0 {
-        let duration_secs = track.duration / 1000;
+    // Add duration if available, of the whole track rather than of its preview
+    let duration = crate::soundcloud::full_duration(track);
+    if duration > 0 && show.duration {
+        let duration_secs = duration / 1000;
         let minutes = duration_secs / 60;
         let seconds = duration_secs % 60;
         fields.push(json!({
-            "name": "Duration",
+            "name": locale.duration,
             "value": format!("{}:{:02}", minutes, seconds),
             "inline": true
         }));
     }
     
     // Add genre if available
-    if let Some(g) = genre {
+    if let Some(g) = genre.filter(|_| show.genre) {
         if !g.is_empty() {
             fields.push(json!({
-                "name": "Genre",
+                "name": locale.genre,
                 "value": g,
                 "inline": true
             }));
@@ -149,12 +1094,12 @@ fn build_track_embed(track: &Track) -> Value {
     }
     
     // Add tags as a separate field if available
-    if let Some(tag_list) = tags {
+    if let Some(tag_list) = tags.filter(|_| show.tags) {
         if !tag_list.is_empty() {
             let parsed_tags = parse_tags(&tag_list);
             if !parsed_tags.is_empty() {
                 fields.push(json!({
-                    "name": "Tags",
+                    "name": locale.tags,
                     "value": parsed_tags.join(", "),
                     "inline": false
                 }));
@@ -162,15 +1107,57 @@ fn build_track_embed(track: &Track) -> Value {
         }
     }
     
+    // Counts as of posting, leaving out the ones SoundCloud didn't send
+    if show.stats {
+        let stats: Vec<String> = [
+            (&locale.plays, play_count),
+            (&locale.likes, likes_count),
+            (&locale.reposts, reposts_count),
+            (&locale.comments, comment_count),
+        ].into_iter()
+            .filter_map(|(label, count)| count.map(|count| format!("{}: {}", label, count)))
+            .collect();
+        if !stats.is_empty() {
+            fields.push(json!({
+                "name": locale.stats,
+                "value": stats.join(" • "),
+                "inline": false
+            }));
+        }
+    }
+    
+    // Say why the audio is missing or cut short, unless it's left out on purpose
+    let restriction = crate::soundcloud::track_restriction(track).filter(|_| !metadata_only);
+    if let Some(restriction) = restriction {
+        fields.push(json!({
+            "name": locale.restricted,
+            "value": match restriction {
+                crate::soundcloud::Restriction::GeoBlocked => &locale.restricted_geo_blocked,
+                crate::soundcloud::Restriction::Snippet => &locale.restricted_snippet,
+            },
+            "inline": false
+        }));
+    }
+    
     debug!("Created {} embed fields for Discord message", fields.len());
     
-    // Get original high-resolution artwork URL if available
-    let artwork_url = track.artwork_url.clone()
-        .map(|url| crate::soundcloud::get_original_artwork_url(&url))
-        .unwrap_or_default();
+    // Artwork URL, falling back to the artist's avatar. Track details already point at the
+    // original size, or at whichever size could be fetched. Animated covers are shown
+    // instead where Discord can play them.
+    let animated_cover = crate::soundcloud::animated_cover(track).filter(|cover| cover.is_embeddable());
+    let artwork_url = animated_cover.as_ref().map(|cover| cover.url.as_str())
+        .or_else(|| crate::soundcloud::cover_art_url(track));
+    
+    let footer = if metadata_only { &locale.footer_metadata_only } else { &locale.footer };
+    let footer = match (badge, show.footer) {
+        (Some(badge), true) => Some(format!("{} • {}", badge, footer)),
+        (Some(badge), false) => Some(badge.to_string()),
+        (None, true) => Some(footer.clone()),
+        (None, false) => None,
+    };
     
     // Create the embed object
-    json!({
+    let mut embed = json!({
         "title": track.title,
         "type": "rich",
         "description": description,
@@ -179,17 +1166,20 @@ fn build_track_embed(track: &Track) -> Value {
         "color": 0xFF7700, // SoundCloud orange
         "author": {
             "name": track.user.username.clone(),
-            "url": track.user.permalink_url.clone(),
-            "icon_url": track.user.avatar_url.clone().unwrap_or_default()
-        },
-        "thumbnail": {
-            "url": artwork_url
+            "url": track.user.permalink_url.clone()
         },
-        "fields": fields,
-        "footer": {
-            "text": "SoundCloud Archiver • All available audio formats are attached"
-        }
-    })
+        "fields": fields
+    });
+    if show.author_icon {
+        embed["author"]["icon_url"] = json!(track.user.avatar_url.clone().unwrap_or_default());
+    }
+    if let Some(text) = footer {
+        embed["footer"] = json!({ "text": text });
+    }
+    if let Some(url) = artwork_url {
+        embed["thumbnail"] = json!({ "url": url });
+    }
+    embed
 }
 
 /// Parse a tag list string, respecting quoted tags
@@ -252,28 +1242,60 @@ fn parse_tags(tag_list: &str) -> Vec<String> {
 async fn send_embed_only(
     client: Client, 
     webhook_url: &str, 
-    embed: Value
+    embed: Value,
+    content: Option<&str>
+) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
+    send_embeds(client, webhook_url, vec![embed], content).await
+}
+
+/// Send one or more embeds in a single message without any files
+async fn send_embeds(
+    client: Client, 
+    webhook_url: &str, 
+    embeds: Vec<Value>,
+    content: Option<&str>
 ) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
-    debug!("Preparing embed-only Discord webhook request");
+    debug!("Preparing embed-only Discord webhook request with {} embeds", embeds.len());
     
-    let payload = json!({
-        "embeds": [embed],
-        "username": "SoundCloud Archiver",
+    let mut payload = json!({
+        "embeds": embeds,
+        "username": crate::locale::get().username,
     });
+    if let Some(content) = content {
+        payload["content"] = json!(content);
+    }
     
+    check_webhook(webhook_url)?;
     debug!("Sending webhook POST request to Discord");
-    let response = client
+    let started = Instant::now();
+    let result = client
         .post(webhook_url)
         .json(&payload)
         .send()
-        .await?;
+        .await;
+    crate::metrics::observe_webhook_duration(started.elapsed());
+    let response = match result {
+        Ok(r) => r,
+        Err(e) => {
+            crate::metrics::record_api_error(Service::Discord, None);
+            return Err(Box::new(DiscordUnavailable(e.to_string())));
+        }
+    };
     
     let status = response.status();
     debug!("Discord API response status: {}", status);
     
     if !status.is_success() {
+        crate::metrics::record_api_error(Service::Discord, Some(status.as_u16()));
         let error_text = response.text().await?;
         error!("Discord webhook error: {} - {}", status, error_text);
+        if let Some(gone) = WebhookGone::from_response(&error_text) {
+            mark_webhook_gone(webhook_url, &gone);
+            return Err(Box::new(gone));
+        }
+        if status.is_server_error() {
+            return Err(Box::new(DiscordUnavailable(format!("{} - {}", status, error_text))));
+        }
         return Err(format!("Discord webhook error: {} - {}", status, error_text).into());
     }
     
@@ -301,14 +1323,14 @@ async fn send_embed_only(
         .map(|s| s.to_string());
     
     debug!("Discord webhook sent successfully, message ID: {}", message_id);
-    Ok(WebhookResponse { message_id, channel_id })
+    Ok(WebhookResponse { message_id, channel_id, unattached_files: Vec::new() })
 }
 
-/// Send the embed with audio file attachments
+/// Send a message payload with audio file attachments
 async fn send_with_audio_files(
     client: Client,
     webhook_url: &str,
-    embed: Value,
+    mut payload: Value,
     files: Vec<(String, String)> // Vec of (file_path, file_name)
 ) -> Result<WebhookResponse, Box<dyn std::error::Error + Send + Sync>> {
     debug!("Preparing multipart request with {} audio files", files.len());
@@ -316,7 +1338,6 @@ async fn send_with_audio_files(
     // Discord limits: 
     // - Max 8MB per file for regular uploads 
     // - Max 10 attachments per message
-    const MAX_DISCORD_UPLOAD_SIZE: u64 = 8 * 1024 * 1024; // 8MB per file
     const MAX_ATTACHMENTS: usize = 8;
     
     // Filter files to respect Discord limits
@@ -408,12 +1429,21 @@ async fn send_with_audio_files(
              filtered_files.len(), file_sizes_len);
     }
     
+    // Show the uploaded artwork in the embed, so the post keeps it after SoundCloud's copy is gone
+    if let Some((_, file_name)) = filtered_files.iter_mut().find(|(_, name)| name.ends_with("_cover.jpg")) {
+        if let Some(thumbnail) = payload.pointer_mut("/embeds/0/thumbnail") {
+            // Discord only resolves attachment:// for plain file names
+            *file_name = file_name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+                .collect();
+            debug!("Using the attached artwork {} as the embed thumbnail", file_name);
+            *thumbnail = json!({ "url": format!("attachment://{}", file_name) });
+        }
+    }
+    
     // Create a multipart form
     let mut form = multipart::Form::new()
-        .text("payload_json", json!({
-            "embeds": [embed],
-            "username": "SoundCloud Archiver",
-        }).to_string());
+        .text("payload_json", payload.to_string());
     
     // Add each audio file
     for (i, (file_path, file_name)) in filtered_files.iter().enumerate() {
@@ -478,19 +1508,37 @@ async fn send_with_audio_files(
     }
     
     // Send the form
+    check_webhook(webhook_url)?;
     debug!("Sending multipart POST request to Discord webhook");
-    let response = client
+    let started = Instant::now();
+    let result = client
         .post(webhook_url)
         .multipart(form)
         .send()
-        .await?;
+        .await;
+    crate::metrics::observe_webhook_duration(started.elapsed());
+    let response = match result {
+        Ok(r) => r,
+        Err(e) => {
+            crate::metrics::record_api_error(Service::Discord, None);
+            return Err(Box::new(DiscordUnavailable(e.to_string())));
+        }
+    };
     
     let status = response.status();
     debug!("Discord API response status: {}", status);
     
     if !status.is_success() {
+        crate::metrics::record_api_error(Service::Discord, Some(status.as_u16()));
         let error_text = response.text().await?;
         error!("Discord webhook error: {} - {}", status, error_text);
+        if let Some(gone) = WebhookGone::from_response(&error_text) {
+            mark_webhook_gone(webhook_url, &gone);
+            return Err(Box::new(gone));
+        }
+        if status.is_server_error() {
+            return Err(Box::new(DiscordUnavailable(format!("{} - {}", status, error_text))));
+        }
         return Err(format!("Discord webhook error: {} - {}", status, error_text).into());
     }
     
@@ -518,5 +1566,5 @@ async fn send_with_audio_files(
         .map(|s| s.to_string());
     
     debug!("Discord webhook with files sent successfully, message ID: {}", message_id);
-    Ok(WebhookResponse { message_id, channel_id })
+    Ok(WebhookResponse { message_id, channel_id, unattached_files: Vec::new() })
 } 
\ No newline at end of file
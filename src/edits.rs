@@ -0,0 +1,169 @@
+use log::{info, warn, debug};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::db::TrackDatabase;
+use crate::soundcloud::Track;
+
+/// The parts of a track an artist can edit after uploading it, as last seen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSnapshot {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Artwork URL at its original size, so listings and track details compare equal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_list: Option<String>,
+}
+
+/// What changed in one part of a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditedField {
+    Title,
+    Description,
+    Artwork,
+    Tags,
+}
+
+impl TrackSnapshot {
+    pub fn from_track(track: &Track) -> Self {
+        // Empty and missing values mean the same to the reader
+        let text = |value: &Option<String>| value.as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string());
+        TrackSnapshot {
+            title: track.title.clone(),
+            description: text(&track.description),
+            artwork_url: text(&track.artwork_url).map(|url| crate::soundcloud::get_original_artwork_url(&url)),
+            tag_list: text(&track.tag_list),
+        }
+    }
+
+    /// The fields that differ from `newer`, with their old and new values
+    pub fn changes(&self, newer: &TrackSnapshot) -> Vec<(EditedField, Option<String>, Option<String>)> {
+        let mut changes = Vec::new();
+        if self.title != newer.title {
+            changes.push((EditedField::Title, Some(self.title.clone()), Some(newer.title.clone())));
+        }
+        if self.description != newer.description {
+            changes.push((EditedField::Description, self.description.clone(), newer.description.clone()));
+        }
+        if self.artwork_url != newer.artwork_url {
+            changes.push((EditedField::Artwork, self.artwork_url.clone(), newer.artwork_url.clone()));
+        }
+        if self.tag_list != newer.tag_list {
+            changes.push((EditedField::Tags, self.tag_list.clone(), newer.tag_list.clone()));
+        }
+        changes
+    }
+}
+
+/// Compare archived tracks in a fresh listing with their last seen snapshot
+///
+/// Posts an "updated" embed with the changes for every track whose title, description,
+/// artwork or tags changed. The first time an archived track is seen only its snapshot is
+/// recorded. If posting fails, the old snapshot is kept so the edit is posted next time.
+pub async fn check_track_edits(db: &mut TrackDatabase, tracks: &[Track], config: &Config) {
+    if !config.detect_track_edits {
+        return;
+    }
+
+    let mut guild_id = None;
+    let mut recorded = 0;
+    for track in tracks {
+        if !db.has_track(&track.id) {
+            continue;
+        }
+        let current = TrackSnapshot::from_track(track);
+        let Some(previous) = db.track_snapshot(&track.id).cloned() else {
+            db.set_track_snapshot(&track.id, current);
+            recorded += 1;
+            continue;
+        };
+        let changes = previous.changes(&current);
+        if changes.is_empty() {
+            continue;
+        }
+
+        info!("Track {} ({}) was edited: {}", track.id, track.title,
+              changes.iter().map(|(field, _, _)| format!("{:?}", field).to_lowercase()).collect::<Vec<_>>().join(", "));
+        let message = db.get_discord_info(&track.id);
+        if message.is_some() && guild_id.is_none() {
+            guild_id = crate::discord::get_webhook_guild_id(&config.discord_webhook_url).await
+                .unwrap_or_else(|e| {
+                    debug!("Failed to look up the webhook's server: {}", e);
+                    None
+                });
+        }
+        if let Err(e) = crate::discord::send_track_edit(
+            &config.discord_webhook_url, track, &changes, message.as_ref(), guild_id.as_deref()
+        ).await {
+            warn!("Failed to post the edit of track {}: {}", track.id, e);
+            continue;
+        }
+        db.set_track_snapshot(&track.id, current);
+    }
+    if recorded > 0 {
+        debug!("Recorded the metadata of {} archived tracks to notice edits", recorded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn track(title: &str, description: Option<&str>, artwork_url: Option<&str>) -> Track {
+        serde_json::from_value(json!({
+            "id": "123",
+            "title": title,
+            "permalink_url": "https://soundcloud.com/someone/track",
+            "artwork_url": artwork_url,
+            "description": description,
+            "user": {
+                "id": "1",
+                "username": "someone",
+                "permalink_url": "https://soundcloud.com/someone",
+                "avatar_url": null
+            },
+            "created_at": "2026-01-02T03:04:05Z",
+            "duration": 1000,
+            "tag_list": null
+        })).unwrap()
+    }
+
+    const ARTWORK_LARGE: &str = "https://i1.sndcdn.com/artworks-abc-large.jpg";
+
+    #[test]
+    fn reports_title_description_and_artwork_changes() {
+        let old = TrackSnapshot::from_track(&track("Demo", Some("Out now"), Some(ARTWORK_LARGE)));
+        let new = TrackSnapshot::from_track(&track("Demo (Final)", None, Some("https://i1.sndcdn.com/artworks-def-large.jpg")));
+
+        assert_eq!(old.changes(&new), vec![
+            (EditedField::Title, Some("Demo".to_string()), Some("Demo (Final)".to_string())),
+            (EditedField::Description, Some("Out now".to_string()), None),
+            (EditedField::Artwork,
+             Some("https://i1.sndcdn.com/artworks-abc-original.jpg".to_string()),
+             Some("https://i1.sndcdn.com/artworks-def-original.jpg".to_string())),
+        ]);
+        assert!(old.changes(&old).is_empty());
+    }
+
+    #[test]
+    fn artwork_size_is_not_a_change() {
+        let old = TrackSnapshot::from_track(&track("Demo", None, Some(ARTWORK_LARGE)));
+        let new = TrackSnapshot::from_track(&track("Demo", None, Some("https://i1.sndcdn.com/artworks-abc-t500x500.jpg")));
+        assert!(old.changes(&new).is_empty());
+    }
+
+    #[test]
+    fn blank_description_is_not_a_change() {
+        let old = TrackSnapshot::from_track(&track("Demo", None, None));
+        let new = TrackSnapshot::from_track(&track("Demo", Some("  "), None));
+        assert!(old.changes(&new).is_empty());
+    }
+}
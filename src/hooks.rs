@@ -0,0 +1,149 @@
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use log::{warn, debug};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::discord::WebhookResponse;
+use crate::soundcloud::Track;
+
+/// External commands run when tracks are archived or something fails
+///
+/// Each command is run through the shell (`sh -c` / `cmd /C`) with the event as JSON on
+/// stdin, and the most useful fields also set as `ARCHIVER_HOOK_*` environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    on_track_archived: Option<String>,
+    on_error: Option<String>,
+    timeout: Duration,
+}
+
+impl Hooks {
+    pub fn from_config(config: &Config) -> Self {
+        Hooks {
+            on_track_archived: config.on_track_archived.clone().filter(|c| !c.is_empty()),
+            on_error: config.on_error.clone().filter(|c| !c.is_empty()),
+            timeout: Duration::from_secs(config.hook_timeout_sec),
+        }
+    }
+
+    /// Run the `on_track_archived` hook and wait for it
+    ///
+    /// Called after the track was posted but before its temp files are deleted, so the
+    /// hook can copy the downloaded files listed in the payload.
+    pub async fn track_archived(&self, track: &Track, response: &WebhookResponse, files: &[(String, String)]) {
+        let Some(command) = &self.on_track_archived else {
+            return;
+        };
+
+        let payload = json!({
+            "event": "track_archived",
+            "track": track,
+            "discord": {
+                "message_id": response.message_id,
+                "channel_id": response.channel_id,
+            },
+            "files": files.iter().map(|(path, name)| json!({ "path": path, "name": name })).collect::<Vec<_>>(),
+        });
+        let env = vec![
+            ("ARCHIVER_HOOK_EVENT", "track_archived".to_string()),
+            ("ARCHIVER_HOOK_TRACK_ID", track.id.clone()),
+            ("ARCHIVER_HOOK_USER_ID", track.user.id.clone()),
+            ("ARCHIVER_HOOK_TITLE", track.title.clone()),
+            ("ARCHIVER_HOOK_URL", track.permalink_url.clone()),
+            ("ARCHIVER_HOOK_MESSAGE_ID", response.message_id.clone()),
+        ];
+
+        run_hook("on_track_archived", command, payload, env, self.timeout).await;
+    }
+
+    /// Run the `on_error` hook in the background
+    pub fn error(&self, error: &str, user_id: Option<&str>, track_id: Option<&str>) {
+        let Some(command) = self.on_error.clone() else {
+            return;
+        };
+
+        let payload = json!({
+            "event": "error",
+            "error": error,
+            "user_id": user_id,
+            "track_id": track_id,
+        });
+        let env = vec![
+            ("ARCHIVER_HOOK_EVENT", "error".to_string()),
+            ("ARCHIVER_HOOK_ERROR", error.to_string()),
+            ("ARCHIVER_HOOK_USER_ID", user_id.unwrap_or_default().to_string()),
+            ("ARCHIVER_HOOK_TRACK_ID", track_id.unwrap_or_default().to_string()),
+        ];
+
+        let timeout = self.timeout;
+        tokio::spawn(async move {
+            run_hook("on_error", &command, payload, env, timeout).await;
+        });
+    }
+}
+
+/// Run a hook command with the payload on stdin, logging (but otherwise ignoring) failures
+async fn run_hook(name: &str, command: &str, payload: Value, env: Vec<(&str, String)>, timeout: Duration) {
+    debug!("Running {} hook: {}", name, command);
+
+    match run_command(command, &payload.to_string(), env, timeout).await {
+        Ok(output) => {
+            if output.status.success() {
+                debug!("{} hook finished: {}", name, String::from_utf8_lossy(&output.stdout).trim());
+            } else {
+                warn!("{} hook exited with {}: {}", name, output.status, String::from_utf8_lossy(&output.stderr).trim());
+            }
+        },
+        Err(e) => warn!("{} hook '{}' failed: {}", name, command, e),
+    }
+}
+
+/// Run a command through the shell with `input` on stdin and collect its output
+///
+/// The command is killed if it runs longer than `timeout`, including while it hasn't read
+/// all of `input` yet.
+pub(crate) async fn run_command(command: &str, input: &str, env: Vec<(&str, String)>, timeout: Duration) -> Result<Output, String> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+
+    let mut child = cmd
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))?;
+
+    // Writing and waiting run together, so a command that doesn't read a payload larger than
+    // the pipe buffer can't hold up the write past the timeout
+    let stdin = child.stdin.take();
+    let write = async move {
+        // The command may not read stdin at all, so a failed write isn't an error
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes()).await;
+            // Dropped here, so the command sees the end of its input
+        }
+    };
+    let run = async { tokio::join!(write, child.wait_with_output()).1 };
+
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(format!("failed to wait for it: {}", e)),
+        // Dropping the child kills it
+        Err(_) => Err(format!("timed out after {} seconds and was killed", timeout.as_secs())),
+    }
+}
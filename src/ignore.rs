@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::soundcloud::Track;
+
+/// A track on the ignore list was about to be archived and was skipped instead
+#[derive(Debug)]
+pub struct Ignored {
+    pub track_id: String,
+    /// The uploader's user ID and name
+    pub user_id: String,
+    pub username: String,
+    /// The `ignored_tracks` entry that matched
+    pub entry: String,
+}
+
+impl std::fmt::Display for Ignored {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Track {} is on the ignore list ({}), not archiving it", self.track_id, self.entry)
+    }
+}
+
+impl std::error::Error for Ignored {}
+
+/// Tracks that must never be archived or posted, e.g. after a takedown request
+///
+/// Entries in `ignored_tracks` are track IDs or permalink URLs. URLs are compared without
+/// their query string, trailing slash or `www.`/`m.` prefix, and ignoring case, so a link
+/// copied from the browser or the app matches the track's permalink.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    entries: Arc<HashSet<String>>,
+}
+
+impl IgnoreList {
+    pub fn from_config(config: &Config) -> Self {
+        IgnoreList {
+            entries: Arc::new(config.ignored_tracks.iter()
+                .map(|entry| normalize(entry))
+                .filter(|entry| !entry.is_empty())
+                .collect()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry a track is ignored by, or `None` if it isn't on the list
+    pub fn matching_entry(&self, track: &Track) -> Option<String> {
+        [&track.id, &track.permalink_url].into_iter()
+            .map(|value| normalize(value))
+            .find(|value| !value.is_empty() && self.entries.contains(value))
+    }
+
+    /// Fail with [`Ignored`] if a track is on the list
+    pub fn check(&self, track: &Track) -> Result<(), Ignored> {
+        match self.matching_entry(track) {
+            Some(entry) => Err(Ignored {
+                track_id: track.id.clone(),
+                user_id: track.user.id.clone(),
+                username: track.user.username.clone(),
+                entry,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Bring a track ID or URL into the form entries are compared in
+fn normalize(entry: &str) -> String {
+    let entry = entry.trim();
+    if !entry.contains('/') {
+        return entry.to_string();
+    }
+    let url = entry.split(['?', '#']).next().unwrap_or(entry).trim_end_matches('/').to_lowercase();
+    let url = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(&url);
+    let url = url.strip_prefix("www.").or_else(|| url.strip_prefix("m.")).unwrap_or(url);
+    url.to_string()
+}
@@ -1,16 +1,50 @@
+pub mod admin;
+pub mod albums;
+pub mod archiver;
 pub mod audio;
+pub mod autotune;
+pub mod backfill;
+pub mod backoff;
+pub mod cli;
 pub mod config;
+pub mod dashboard;
+pub mod deletions;
+pub mod destinations;
 pub mod db;
 pub mod discord;
+pub mod edits;
+pub mod hooks;
+pub mod ignore;
+pub mod library;
+pub mod locale;
+pub mod shutdown;
 pub mod soundcloud;
+pub mod soundcloud_api;
+pub mod stats;
+pub mod supervisor;
 pub mod loghandler;
+pub mod metrics;
+pub mod musicbrainz;
+pub mod outbox;
+pub mod playlists;
+pub mod plugins;
+pub mod profile;
+pub mod reporting;
+pub mod telemetry;
+pub mod tempspace;
+pub mod timezone;
+pub mod updates;
+pub mod waveform;
 
 // Re-export key structs for convenience
+pub use archiver::{Archiver, ArchiverBuilder, PollSummary};
 pub use config::{Config, Users};
 pub use db::TrackDatabase;
-pub use soundcloud::Track;
+pub use soundcloud::{PostedTrack, Track};
 
 /// Initialize the application with the given config file
+///
+/// Returns the loaded parts; use [`Archiver::builder`] to get an archiver that can poll and post.
 pub async fn initialize(config_path: &str) -> Result<(Config, Users, db::TrackDatabase), Box<dyn std::error::Error + Send + Sync>> {
     // Check for ffmpeg
     if !audio::check_ffmpeg() {
@@ -22,10 +56,11 @@ pub async fn initialize(config_path: &str) -> Result<(Config, Users, db::TrackDa
     let config = config::Config::load(config_path)?;
     
     // Setup logging
-    loghandler::setup_logging(&config.log_file, &config.log_level)?;
+    loghandler::setup_logging(&config)?;
     
     // Set static ffmpeg output setting
     config::Config::set_show_ffmpeg_output(config.show_ffmpeg_output);
+    locale::init(config.locale_file.as_deref())?;
     
     // Load users
     let users = config::Users::load(&config.users_file)?;
@@ -0,0 +1,103 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use log::{info, warn, debug};
+
+use crate::config::Config;
+use crate::soundcloud::Track;
+
+/// Name of the playlist with every archived track, in the root of the archive directory
+const GLOBAL_PLAYLIST: &str = "all.m3u8";
+
+/// Extensions of the files that can be added to playlists
+const AUDIO_EXTENSIONS: &[&str] = &["flac", "wav", "opus", "ogg", "m4a", "mp3", "audio"];
+
+/// Serializes playlist updates from tracks processed in parallel
+static PLAYLIST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Local copy of every archived track, one folder per artist
+///
+/// Files are stored as `<archive_dir>/<artist>/<track id>_<file name>`. Each artist
+/// folder has an `<artist>.m3u8` playlist and the root has `all.m3u8`, so the
+/// archive can be opened directly in a media player.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    dir: Option<PathBuf>,
+}
+
+impl Library {
+    pub fn from_config(config: &Config) -> Self {
+        Library {
+            dir: config.archive_dir.as_deref().filter(|d| !d.is_empty()).map(PathBuf::from),
+        }
+    }
+
+    /// Copy a track's files into the archive and add it to the playlists
+    ///
+    /// Failures are logged but don't fail the track, since it's already been posted.
+    pub async fn add_track(&self, track: &Track, files: &[(String, String)]) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        let artist = crate::audio::sanitize_filename(&track.user.username);
+        let artist_dir = dir.join(&artist);
+        if let Err(e) = tokio::fs::create_dir_all(&artist_dir).await {
+            warn!("Failed to create archive directory {}: {}", artist_dir.display(), e);
+            return;
+        }
+
+        let mut playlist_file = None;
+        for (path, name) in files {
+            let file_name = format!("{}_{}", track.id, name);
+            if let Err(e) = tokio::fs::copy(path, artist_dir.join(&file_name)).await {
+                warn!("Failed to copy {} into the archive: {}", name, e);
+                continue;
+            }
+
+            // The first audio file is the preferred format, that's the one that goes in the playlists
+            let is_audio = Path::new(name).extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e));
+            if is_audio && playlist_file.is_none() {
+                playlist_file = Some(file_name);
+            }
+        }
+
+        let Some(file_name) = playlist_file else {
+            debug!("No audio files archived for track {}, not adding it to the playlists", track.id);
+            return;
+        };
+
+        let extinf = format!("#EXTINF:{},{} - {}", track.duration / 1000, track.user.username, track.title);
+        let _guard = PLAYLIST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let playlists = [
+            (artist_dir.join(format!("{}.m3u8", artist)), file_name.clone()),
+            (dir.join(GLOBAL_PLAYLIST), format!("{}/{}", artist, file_name)),
+        ];
+        for (playlist, entry) in playlists {
+            if let Err(e) = append_to_playlist(&playlist, &extinf, &entry) {
+                warn!("Failed to update playlist {}: {}", playlist.display(), e);
+            }
+        }
+
+        info!("Archived track {} to {}", track.id, artist_dir.display());
+    }
+}
+
+/// Append an entry to an extended M3U playlist, creating it if needed
+///
+/// Entries already in the playlist (e.g. from re-archiving a track) aren't added again.
+fn append_to_playlist(playlist: &Path, extinf: &str, entry: &str) -> std::io::Result<()> {
+    let is_new = !playlist.exists();
+    if !is_new && std::fs::read_to_string(playlist)?.lines().any(|line| line == entry) {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(playlist)?;
+    if is_new {
+        writeln!(file, "#EXTM3U")?;
+    }
+    writeln!(file, "{}", extinf)?;
+    writeln!(file, "{}", entry)
+}
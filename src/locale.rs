@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::OnceLock;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// User-facing text used in Discord posts
+///
+/// Loaded from the JSON file set in `locale_file`. Any string missing from the
+/// file falls back to the English default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Locale {
+    /// Name of the embed field with the track length
+    pub duration: String,
+    /// Name of the embed field with the track genre
+    pub genre: String,
+    /// Name of the embed field with the track tags
+    pub tags: String,
+    /// Footer text of every track embed
+    pub footer: String,
+    /// Footer text of track embeds posted without audio (`metadata_only`)
+    pub footer_metadata_only: String,
+    /// Footer of album posts
+    pub footer_album: String,
+    /// Shown before the footer of tracks posted because the user uploaded them
+    pub badge_upload: String,
+    /// Shown before the footer of tracks posted because the user liked them
+    pub badge_like: String,
+    /// Shown with the playlist's title before the footer of tracks added to a watched playlist
+    pub badge_playlist: String,
+    /// Name the webhook posts as
+    pub username: String,
+    /// Name of the embed field shown when the attachments couldn't be uploaded with the post
+    pub attachments: String,
+    /// Text of that field
+    pub attachments_pending: String,
+    /// Text before the track title in follow-up messages with the attachments
+    pub files_follow_up: String,
+    /// Title of the note posted when a user changes their profile spotlight
+    pub spotlight_changed: String,
+    /// Name of the field listing the newly pinned items
+    pub spotlight_pinned: String,
+    /// Name of the field listing the items no longer pinned
+    pub spotlight_unpinned: String,
+    /// Title of the note posted when an archived track is removed from SoundCloud
+    pub track_removed: String,
+    /// Text of the link to a track's original post
+    pub original_post: String,
+    /// Title of the note posted when an archived track is edited, before the track title
+    pub track_updated: String,
+    /// Name of the field with a changed title
+    pub edit_title: String,
+    /// Name of the field with a changed description
+    pub edit_description: String,
+    /// Name of the field with changed artwork
+    pub edit_artwork: String,
+    /// Title of the note posted to `alert_webhook_url` when a newer release is available
+    pub update_available: String,
+    /// Name of the field on tracks whose audio SoundCloud restricts
+    pub restricted: String,
+    /// Value of that field for tracks blocked in the archiver's region
+    pub restricted_geo_blocked: String,
+    /// Value of that field for tracks only available as a Go+ preview
+    pub restricted_snippet: String,
+    /// Name of the embed field with the track's play, like, repost and comment counts
+    pub stats: String,
+    /// Label of the play count in that field
+    pub plays: String,
+    /// Label of the like count in that field
+    pub likes: String,
+    /// Label of the repost count in that field
+    pub reposts: String,
+    /// Label of the comment count in that field
+    pub comments: String,
+    /// Title of the note posted to `alert_webhook_url` when the client ID stopped working
+    pub client_id_failing: String,
+    /// Title of the note posted to `alert_webhook_url` when the client ID works again
+    pub client_id_recovered: String,
+    /// Title of the note posted to `alert_webhook_url` when a webhook was deleted
+    pub webhook_gone: String,
+    /// Title of the note posted to `alert_webhook_url` when a deleted webhook works again
+    pub webhook_recovered: String,
+    /// Title of the note posted to `alert_webhook_url` when a watched user is deactivated
+    pub user_deactivated: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            duration: "Duration".to_string(),
+            genre: "Genre".to_string(),
+            tags: "Tags".to_string(),
+            footer: "SoundCloud Archiver • All available audio formats are attached".to_string(),
+            footer_metadata_only: "SoundCloud Archiver • Metadata only, audio not archived".to_string(),
+            footer_album: "SoundCloud Archiver • The best available format of each track is attached".to_string(),
+            badge_upload: "New upload".to_string(),
+            badge_like: "Liked track".to_string(),
+            badge_playlist: "Added to playlist".to_string(),
+            username: "SoundCloud Archiver".to_string(),
+            attachments: "Attachments".to_string(),
+            attachments_pending: "The files couldn't be uploaded with this post, they'll follow in separate messages".to_string(),
+            files_follow_up: "Files for".to_string(),
+            spotlight_changed: "Spotlight changed".to_string(),
+            spotlight_pinned: "Now pinned".to_string(),
+            spotlight_unpinned: "No longer pinned".to_string(),
+            track_removed: "Removed from SoundCloud".to_string(),
+            original_post: "Original post".to_string(),
+            track_updated: "Track updated".to_string(),
+            edit_title: "Title".to_string(),
+            edit_description: "Description".to_string(),
+            edit_artwork: "Artwork".to_string(),
+            update_available: "Update available".to_string(),
+            restricted: "Restricted".to_string(),
+            restricted_geo_blocked: "Geo-blocked, no audio could be archived from the archiver's region".to_string(),
+            restricted_snippet: "SoundCloud Go+ track, only the preview could be archived".to_string(),
+            stats: "Stats".to_string(),
+            plays: "Plays".to_string(),
+            likes: "Likes".to_string(),
+            reposts: "Reposts".to_string(),
+            comments: "Comments".to_string(),
+            client_id_failing: "SoundCloud client ID not working".to_string(),
+            client_id_recovered: "SoundCloud client ID working again".to_string(),
+            webhook_gone: "Discord webhook deleted".to_string(),
+            webhook_recovered: "Discord webhook working again".to_string(),
+            user_deactivated: "Watched user deactivated".to_string(),
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Load the locale file, if one is configured
+///
+/// The locale is process-wide, so only the first call has any effect.
+pub fn init(locale_file: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(path) = locale_file else {
+        return Ok(());
+    };
+
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open locale file {}: {}", path, e))?;
+    let locale: Locale = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("Invalid locale file {}: {}", path, e))?;
+
+    if LOCALE.set(locale).is_err() {
+        warn!("A locale is already loaded, ignoring {}", path);
+    } else {
+        info!("Loaded Discord post text from {}", path);
+    }
+    Ok(())
+}
+
+/// Get the current locale (English unless a locale file was loaded)
+pub fn get() -> &'static Locale {
+    LOCALE.get_or_init(Locale::default)
+}
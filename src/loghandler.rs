@@ -1,50 +1,175 @@
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
-use log::{LevelFilter, info, warn};
-use std::sync::atomic::{AtomicU64, AtomicU32, Ordering};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use chrono::NaiveDate;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use log::{info, warn, debug};
 use std::time::Duration;
 use tokio::time;
+use tracing::Level;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Layer, Registry};
 
-// Global stats
-static TOTAL_TRACKS: AtomicU64 = AtomicU64::new(0);
-static NEW_TRACKS: AtomicU64 = AtomicU64::new(0);
-static ERROR_COUNT: AtomicU32 = AtomicU32::new(0);
+use crate::config::Config;
+use crate::timezone::Timezone;
 
-/// Update the log level at runtime
-pub fn update_log_level(level_str: &str) {
-    let level_filter = match level_str.to_lowercase().as_str() {
-        "trace" => LevelFilter::Trace,
-        "debug" => LevelFilter::Debug,
-        "info" => LevelFilter::Info,
-        "warn" => LevelFilter::Warn,
-        "error" => LevelFilter::Error,
+/// A log output (console, file, OTLP export, ...) that can be added to the subscriber
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Reload handles for the console and file log filters
+struct LogFilters {
+    console: reload::Handle<EnvFilter, Registry>,
+    file: reload::Handle<EnvFilter, Registry>,
+    // Whether the file follows the console level (no log_file_level was set)
+    file_follows_console: bool,
+    // Console filter from startup, restored when debug logging is toggled off
+    configured: String,
+}
+
+static LOG_FILTERS: OnceLock<LogFilters> = OnceLock::new();
+
+/// Parse a log level name, falling back to info
+fn parse_level(level_str: &str) -> LevelFilter {
+    match level_str.to_lowercase().as_str() {
+        "trace" => LevelFilter::TRACE,
+        "debug" => LevelFilter::DEBUG,
+        "info" => LevelFilter::INFO,
+        "warn" => LevelFilter::WARN,
+        "error" => LevelFilter::ERROR,
         _ => {
             warn!("Invalid log level '{}', defaulting to info", level_str);
-            LevelFilter::Info
+            LevelFilter::INFO
         }
+    }
+}
+
+/// Update the log level at runtime
+///
+/// Changes the console level, and the file level too unless `log_file_level` was set.
+pub fn update_log_level(level_str: &str) {
+    let level = parse_level(level_str);
+    let Some(filters) = LOG_FILTERS.get() else {
+        return;
     };
     
-    // Set the log level
-    log::set_max_level(level_filter);
+    let _ = filters.console.reload(EnvFilter::new(level.to_string()));
+    if filters.file_follows_console {
+        let _ = filters.file.reload(EnvFilter::new(level.to_string()));
+    }
+    
+    sync_log_max_level(filters);
     info!("Log level set to {}", level_str);
 }
 
+/// Check whether a string is a valid log level name
+pub fn is_valid_log_level(level_str: &str) -> bool {
+    ["trace", "debug", "info", "warn", "error"].contains(&level_str.to_lowercase().as_str())
+}
+
+/// Get the current console and file log filters
+pub fn log_levels() -> Option<(String, String)> {
+    let filters = LOG_FILTERS.get()?;
+    let console = filters.console.with_current(|f| f.to_string()).ok()?;
+    let file = filters.file.with_current(|f| f.to_string()).ok()?;
+    Some((console, file))
+}
+
+/// Switch debug logging on (trace if debug is configured), or back to the configured level if it's already on
+pub fn toggle_debug_logging() {
+    let Some(filters) = LOG_FILTERS.get() else {
+        return;
+    };
+    
+    let current = filters.console.with_current(|f| f.to_string()).unwrap_or_default();
+    if current == filters.configured {
+        update_log_level(if filters.configured == "debug" { "trace" } else { "debug" });
+    } else {
+        info!("Restoring configured log level {}", filters.configured);
+        let _ = filters.console.reload(EnvFilter::new(&filters.configured));
+        if filters.file_follows_console {
+            let _ = filters.file.reload(EnvFilter::new(&filters.configured));
+        }
+        sync_log_max_level(filters);
+    }
+}
+
+/// Spawn a listener that toggles debug logging on SIGUSR2
+pub fn spawn_log_level_signal_listener() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        
+        let mut sigusr2 = match signal(SignalKind::user_defined2()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to set up SIGUSR2 handler, debug logging can't be toggled by signal: {}", e);
+                return;
+            }
+        };
+        
+        tokio::spawn(async move {
+            while sigusr2.recv().await.is_some() {
+                info!("Received SIGUSR2 signal, toggling debug logging");
+                toggle_debug_logging();
+            }
+        });
+        debug!("Send SIGUSR2 to toggle debug logging");
+    }
+}
+
+/// Let `log` records through up to the most verbose of the console and file levels
+///
+/// `log` records are filtered against their own max level before reaching tracing.
+fn sync_log_max_level(filters: &LogFilters) {
+    let console = filters.console.with_current(|f| f.max_level_hint()).ok().flatten().unwrap_or(LevelFilter::INFO);
+    let file = filters.file.with_current(|f| f.max_level_hint()).ok().flatten().unwrap_or(LevelFilter::INFO);
+    log::set_max_level(to_log_level(std::cmp::max(console, file)));
+}
+
+/// Convert a tracing level filter to the `log` crate's equivalent
+fn to_log_level(level: LevelFilter) -> log::LevelFilter {
+    match level.into_level() {
+        Some(Level::TRACE) => log::LevelFilter::Trace,
+        Some(Level::DEBUG) => log::LevelFilter::Debug,
+        Some(Level::INFO) => log::LevelFilter::Info,
+        Some(Level::WARN) => log::LevelFilter::Warn,
+        Some(Level::ERROR) => log::LevelFilter::Error,
+        None => log::LevelFilter::Off,
+    }
+}
+
+/// Build the console title from the current stats
+fn console_title() -> String {
+    let stats = crate::metrics::summary();
+    format!(
+        "SCArchive Webhook | Tracks: {} | New: {} | Errors: {}",
+        stats.total_tracks,
+        stats.new_tracks,
+        stats.errors
+    )
+}
+
 /// Update the console title with current stats
 pub fn update_console_title() {
+    set_console_title(&console_title());
+}
+
+/// Set the console window title
+///
+/// Uses the console API on Windows and the xterm title escape sequence elsewhere,
+/// which is only written when stdout is a terminal so redirected logs stay clean.
+fn set_console_title(title: &str) {
     #[cfg(windows)]
     {
         use winapi::um::wincon::SetConsoleTitleW;
         use std::ffi::OsStr;
         use std::os::windows::ffi::OsStrExt;
         
-        let title = format!(
-            "SCArchive Webhook | Tracks: {} | New: {} | Errors: {}",
-            TOTAL_TRACKS.load(Ordering::Relaxed),
-            NEW_TRACKS.load(Ordering::Relaxed),
-            ERROR_COUNT.load(Ordering::Relaxed)
-        );
-        
-        let wide: Vec<u16> = OsStr::new(&title)
+        let wide: Vec<u16> = OsStr::new(title)
             .encode_wide()
             .chain(Some(0))
             .collect();
@@ -53,112 +178,281 @@ pub fn update_console_title() {
             SetConsoleTitleW(wide.as_ptr());
         }
     }
+    
+    #[cfg(not(windows))]
+    {
+        use std::io::IsTerminal;
+        
+        let mut stdout = std::io::stdout();
+        if stdout.is_terminal() {
+            let _ = write!(stdout, "\x1b]0;{}\x07", title);
+            let _ = stdout.flush();
+        }
+    }
 }
 
 /// Start the console title update task
 pub fn start_console_title_updater() {
     tokio::spawn(async move {
         let mut interval = time::interval(Duration::from_secs(1));
+        let mut last_title = String::new();
         loop {
             interval.tick().await;
-            update_console_title();
+            
+            // Only touch the terminal when something changed
+            let title = console_title();
+            if title != last_title {
+                set_console_title(&title);
+                last_title = title;
+            }
         }
     });
 }
 
-/// Increment the total tracks counter
-pub fn increment_total_tracks(count: u64) {
-    TOTAL_TRACKS.fetch_add(count, Ordering::Relaxed);
+/// When and how the log file is rotated
+#[derive(Debug, Clone)]
+pub struct LogRotation {
+    /// Rotate once the file reaches this many bytes (0 disables size-based rotation)
+    pub max_size_bytes: u64,
+    /// Rotate when the date changes in `timezone`
+    pub daily: bool,
+    /// Number of rotated files to keep
+    pub retain: usize,
+    /// Gzip rotated files
+    pub compress: bool,
+    pub timezone: Timezone,
 }
 
-/// Increment the new tracks counter
-pub fn increment_new_tracks(count: u64) {
-    NEW_TRACKS.fetch_add(count, Ordering::Relaxed);
+impl LogRotation {
+    pub fn from_config(config: &Config) -> Self {
+        LogRotation {
+            max_size_bytes: config.log_max_size_mb.saturating_mul(1024 * 1024),
+            daily: config.log_rotate_daily,
+            retain: config.log_retain_files,
+            compress: config.log_compress,
+            timezone: Timezone::from_config(config),
+        }
+    }
 }
 
-/// Increment the error counter
-pub fn increment_error_count() {
-    ERROR_COUNT.fetch_add(1, Ordering::Relaxed);
+/// The open log file and what's needed to decide when to rotate it
+struct LogFile {
+    path: String,
+    rotation: LogRotation,
+    file: Option<File>,
+    size: u64,
+    date: NaiveDate,
 }
 
-/// Setup logging to console and file
-pub fn setup_logging(log_file: &str, log_level: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Configure the logger
-    let level = match log_level.to_lowercase().as_str() {
-        "trace" => LevelFilter::Trace,
-        "debug" => LevelFilter::Debug,
-        "info" => LevelFilter::Info,
-        "warn" => LevelFilter::Warn,
-        "error" => LevelFilter::Error,
-        _ => {
-            warn!("Invalid log level '{}' in config, using 'info'", log_level);
-            LevelFilter::Info
-        }
-    };
-    
-    // Initialize simple logger for console output
-    simple_logger::SimpleLogger::new()
-        .with_level(level)
-        .env()
-        .init()?;
+impl LogFile {
+    fn new(path: String, rotation: LogRotation) -> Self {
+        // Pick up where an existing log file left off so restarts don't reset rotation
+        let metadata = std::fs::metadata(&path).ok();
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let date = metadata
+            .and_then(|m| m.modified().ok())
+            .map(|t| rotation.timezone.convert(t.into()).date_naive())
+            .unwrap_or_else(|| rotation.timezone.now().date_naive());
+        
+        LogFile { path, rotation, file: None, size, date }
+    }
     
-    // Add a custom file logger hook (simple_logger doesn't support file output)
-    let orig_logger = log::logger();
-    let file_path = log_file.to_string();
+    fn write_line(&mut self, line: &str) {
+        let today = self.rotation.timezone.now().date_naive();
+        let too_big = self.rotation.max_size_bytes > 0 && self.size + line.len() as u64 > self.rotation.max_size_bytes && self.size > 0;
+        let new_day = self.rotation.daily && today != self.date && self.size > 0;
+        if too_big || new_day {
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate log file {}: {}", self.path, e);
+            }
+        }
+        self.date = today;
+        
+        if self.file.is_none() {
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path).ok();
+        }
+        if let Some(file) = &mut self.file {
+            if file.write_all(line.as_bytes()).is_ok() {
+                self.size += line.len() as u64;
+            }
+        }
+    }
     
-    struct FileLogger {
-        inner: Box<dyn log::Log>,
-        file_path: String,
+    /// Path of the nth rotated file
+    fn rotated_path(&self, n: usize) -> String {
+        if self.rotation.compress {
+            format!("{}.{}.gz", self.path, n)
+        } else {
+            format!("{}.{}", self.path, n)
+        }
     }
     
-    impl log::Log for FileLogger {
-        fn enabled(&self, metadata: &log::Metadata) -> bool {
-            self.inner.enabled(metadata)
+    /// Move the current file to `<path>.1`, shifting older files up and deleting the oldest
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        
+        if self.rotation.retain == 0 {
+            std::fs::remove_file(&self.path)?;
+            self.size = 0;
+            return Ok(());
         }
         
-        fn log(&self, record: &log::Record) {
-            // First, let the original logger handle it
-            self.inner.log(record);
-            
-            // Then write to file
-            if self.enabled(record.metadata()) {
-                if let Ok(mut file) = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&self.file_path) {
-                        
-                    let timestamp = chrono::Local::now()
-                        .format("%Y-%m-%d %H:%M:%S%.3f");
-                        
-                    let log_line = format!(
-                        "{} {} [{}] {}\n",
-                        timestamp,
-                        record.level(),
-                        record.target(),
-                        record.args()
-                    );
-                    
-                    let _ = file.write_all(log_line.as_bytes());
-                }
+        let _ = std::fs::remove_file(self.rotated_path(self.rotation.retain));
+        for n in (1..self.rotation.retain).rev() {
+            let from = self.rotated_path(n);
+            if Path::new(&from).exists() {
+                std::fs::rename(&from, self.rotated_path(n + 1))?;
             }
         }
         
-        fn flush(&self) {
-            self.inner.flush();
+        if self.rotation.compress {
+            let mut input = File::open(&self.path)?;
+            let output = File::create(self.rotated_path(1))?;
+            let mut encoder = GzEncoder::new(output, Compression::default());
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+            std::fs::remove_file(&self.path)?;
+        } else {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Writer handed to the file log layer, shared so rotation state survives between events
+#[derive(Clone)]
+struct LogFileWriter(Arc<Mutex<LogFile>>);
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The fmt layer writes each event as a single complete line
+        if let Ok(mut file) = self.0.lock() {
+            file.write_line(&String::from_utf8_lossy(buf));
         }
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Ok(mut file) = self.0.lock() {
+            if let Some(f) = &mut file.file {
+                f.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFileWriter {
+    type Writer = LogFileWriter;
+    
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Build an unfiltered layer writing to a rotated log file
+fn file_layer(path: &str, config: &Config) -> BoxedLayer {
+    let writer = LogFileWriter(Arc::new(Mutex::new(LogFile::new(path.to_string(), LogRotation::from_config(config)))));
+    let timer = Timezone::from_config(config);
+    if config.log_format == "json" {
+        fmt::layer().json().with_timer(timer).with_writer(writer).boxed()
+    } else {
+        fmt::layer().with_timer(timer).with_ansi(false).with_writer(writer).boxed()
+    }
+}
+
+/// Setup logging to console and file
+///
+/// Output goes through `tracing`: events are shown with the spans they happened in
+/// (e.g. the user and track being processed), and `log` records from this crate and its
+/// dependencies are forwarded into it. The console and the file each get their own
+/// level filter, and `RUST_LOG` overrides the console level with full filter directives.
+/// The OTLP exporter is added as another layer when configured.
+pub fn setup_logging(config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let level = parse_level(&config.log_level);
+    let file_level = config.log_file_level.as_deref().map(parse_level).unwrap_or(level);
+    let json = config.log_format == "json";
+    
+    let console_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level.to_string()));
+    let (console_filter, console_handle) = reload::Layer::new(console_filter);
+    let (file_filter, file_handle) = reload::Layer::new(EnvFilter::new(file_level.to_string()));
+    
+    let timer = Timezone::from_config(config);
+    let console_layer = if json {
+        fmt::layer().json().with_timer(timer).boxed()
+    } else {
+        fmt::layer().with_timer(timer).boxed()
+    };
+    
+    let mut layers: Vec<BoxedLayer> = vec![
+        console_layer.with_filter(console_filter).boxed(),
+        file_layer(&config.log_file, config).with_filter(file_filter).boxed(),
+    ];
+    
+    // Warnings and errors are also written to their own file if configured
+    if let Some(error_log_file) = &config.error_log_file {
+        layers.push(file_layer(error_log_file, config).with_filter(LevelFilter::WARN).boxed());
     }
     
-    let logger = FileLogger {
-        inner: Box::new(orig_logger),
-        file_path,
+    // Start exporting trace spans if configured
+    let otel_error = match crate::telemetry::layer(config) {
+        Ok(Some(layer)) => {
+            layers.push(layer);
+            None
+        },
+        Ok(None) => None,
+        Err(e) => Some(e),
     };
     
-    log::set_boxed_logger(Box::new(logger))?;
+    // Report errors to Sentry if configured
+    let sentry_error = match crate::reporting::layer(config) {
+        Ok(Some(layer)) => {
+            layers.push(layer);
+            None
+        },
+        Ok(None) => None,
+        Err(e) => Some(e),
+    };
     
-    info!("Logging initialized: level={}, file={}", log_level, log_file);
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()?;
+    
+    let configured = console_handle.with_current(|f| f.to_string()).unwrap_or_else(|_| level.to_string());
+    let _ = LOG_FILTERS.set(LogFilters {
+        console: console_handle,
+        file: file_handle,
+        file_follows_console: config.log_file_level.is_none(),
+        configured,
+    });
+    if let Some(filters) = LOG_FILTERS.get() {
+        sync_log_max_level(filters);
+    }
+    
+    info!("Logging initialized: level={}, file={}, timezone={}", config.log_level, config.log_file, timer);
+    if let Some(error_log_file) = &config.error_log_file {
+        debug!("Warnings and errors are also logged to {}", error_log_file);
+    }
+    if let Some(file_level) = &config.log_file_level {
+        debug!("Log file level: {}", file_level);
+    }
+    if config.log_max_size_mb > 0 || config.log_rotate_daily {
+        debug!("Log rotation: max_size={} MB, daily={}, retain={}, compress={}",
+               config.log_max_size_mb, config.log_rotate_daily, config.log_retain_files, config.log_compress);
+    }
+    if let Some(e) = otel_error {
+        warn!("Failed to initialize OTLP trace export: {}", e);
+    }
+    if let Some(e) = sentry_error {
+        warn!("Failed to initialize Sentry error reporting: {}", e);
+    }
     
     // Start the console title updater
-    start_console_title_updater();
+    if config.console_title {
+        start_console_title_updater();
+    }
     
     Ok(())
-} 
\ No newline at end of file
+}
@@ -1,20 +1,14 @@
 use std::sync::Arc;
-use std::time::Duration;
 use std::env;
 use log::{info, warn, error, debug};
-use tokio::sync::Mutex;
-use crate::loghandler::{increment_new_tracks, increment_error_count, setup_logging};
-
-mod audio;
-mod config;
-mod db;
-mod discord;
-mod soundcloud;
-mod loghandler;
-mod cli;
-
-use config::{Config, Users};
-use db::TrackDatabase;
+use tokio::sync::Notify;
+use archiver_webhook::loghandler::{self, setup_logging};
+use archiver_webhook::{admin, cli, discord, reporting, shutdown, soundcloud, telemetry, tempspace, updates};
+use archiver_webhook::archiver::Archiver;
+use archiver_webhook::autotune::ParallelismTuner;
+use archiver_webhook::soundcloud_api::{SoundCloudApi, SoundCloudClient};
+use archiver_webhook::supervisor::spawn_supervised;
+use archiver_webhook::config::Config;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -30,6 +24,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     debug!("Command line arguments: {:?}", args);
     
     if args.len() > 1 {
+        let api: Arc<dyn SoundCloudApi> = Arc::new(SoundCloudClient);
         match args[1].as_str() {
             "--resolve" if args.len() > 2 => {
                 info!("Running in URL resolution mode");
@@ -37,19 +32,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             },
             "--init-tracks" => {
                 info!("Running in database initialization mode");
-                return cli::initialize_tracks_database().await;
+                return cli::initialize_tracks_database(&api).await;
             },
             "--post-track" if args.len() > 2 => {
                 info!("Running in post-track mode");
-                return cli::post_single_track(&args[2]).await;
+                return cli::post_single_track(&api, &args[2]).await;
+            },
+            "--post-tracks" if args.len() > 3 && args[2] == "--ids-file" => {
+                info!("Running in post-tracks mode");
+                return cli::post_tracks_from_file(&api, &args[3]).await;
             },
             "--lookup-discord-id" if args.len() > 2 => {
                 info!("Running in Discord ID lookup mode");
-                return cli::lookup_by_discord_id(&args[2]).await;
+                return cli::lookup_by_discord_id(&api, &args[2]).await;
+            },
+            "--tag-track" if args.len() > 2 => {
+                info!("Running in tag-track mode");
+                return cli::tag_track(&api, &args[2], &args[3..]).await;
+            },
+            "--mark-seen" if args.len() > 3 => {
+                info!("Running in mark-seen mode");
+                return cli::mark_seen(&api, &args[2..]).await;
             },
             "--generate-config" if args.len() > 2 => {
                 info!("Running in config generation mode");
-                return cli::generate_config(&args[2]).await;
+                return cli::generate_config(&api, &args[2]).await;
+            },
+            "--user-status" => {
+                info!("Running in user status mode");
+                return cli::show_user_status().await;
+            },
+            "--show-config" => {
+                info!("Running in show config mode");
+                return cli::show_config();
+            },
+            "--export-users" if args.len() > 2 => {
+                info!("Running in user export mode");
+                return cli::export_users(&api, &args[2], args.get(3).map(|s| s.as_str())).await;
+            },
+            "--skipped-report" => {
+                info!("Running in skipped report mode");
+                return cli::skipped_report();
+            },
+            "--retry-restricted" => {
+                info!("Running in retry-restricted mode");
+                return cli::retry_restricted(&api).await;
+            },
+            "--stats-chart" if args.len() > 2 => {
+                info!("Running in stats chart mode");
+                return cli::stats_chart(&args[2], args.get(3).map(|s| s.as_str()));
             },
             "--help" | "-h" => {
                 info!("Showing help information");
@@ -64,14 +95,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
     }
 
-    // Check for ffmpeg
-    if !audio::check_ffmpeg() {
-        warn!("ffmpeg not found in PATH, audio transcoding will not work!");
-        warn!("Please install ffmpeg and make sure it's in your PATH");
-    } else {
-        info!("ffmpeg found in PATH");
-    }
-
     // Run in watcher mode (default)
     info!("Running in watcher mode");
     run_watcher_mode().await
@@ -79,17 +102,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
 /// Setup logger with appropriate configuration
 fn setup_logger() {
-    // Load config and initialize logging (console + file + console title updater)
+    // Load config and initialize logging (console + file + console title updater),
+    // falling back to the defaults if the config can't be loaded
     let config_path = "config.json";
-    if let Ok(cfg) = Config::load(config_path) {
-        if let Err(e) = setup_logging(&cfg.log_file, &cfg.log_level) {
-            eprintln!("Failed to initialize logger: {}", e);
-        }
-    } else {
-        // Fallback to defaults
-        if let Err(e) = setup_logging("latest.log", "info") {
-            eprintln!("Failed to initialize logger: {}", e);
-        }
+    let cfg = Config::load(config_path).unwrap_or_default();
+    if let Err(e) = setup_logging(&cfg) {
+        eprintln!("Failed to initialize logger: {}", e);
     }
 }
 
@@ -126,331 +144,202 @@ async fn run_watcher_mode() -> Result<(), Box<dyn std::error::Error + Send + Syn
     // Log system info now that logger is configured
     log_system_info();
 
-    // Load users
-    info!("Loading users from {}", config.users_file);
-    let mut users = match Users::load(&config.users_file) {
-        Ok(u) => {
-            info!("Loaded {} users", u.users.len());
-            u
-        },
+    // Each profile gets its own archiver; without profiles the top-level config is the only one
+    let profiles = match config.profiles() {
+        Ok(p) => p,
         Err(e) => {
-            error!("Failed to load users from {}: {}", config.users_file, e);
+            error!("Failed to load profiles: {}", e);
             return Err(e);
         }
     };
     
-    if users.users.is_empty() {
-        warn!("No users found in {}. Add some users to the file and restart!", config.users_file);
+    let mut archivers = Vec::new();
+    if profiles.is_empty() {
+        // Load users and the tracks database and initialize the SoundCloud client
+        match Archiver::builder().config(config.clone()).build().await {
+            Ok(a) => archivers.push(Arc::new(a)),
+            Err(e) => {
+                error!("Failed to initialize archiver: {}", e);
+                return Err(e);
+            }
+        }
     } else {
-        debug!("Loaded users: {:?}", users.users);
+        info!("Running {} profiles: {}", profiles.len(),
+              profiles.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "));
+        
+        // All profiles share one SoundCloud client, so they share its parallelism limit too
+        let soundcloud_tuner = Arc::new(ParallelismTuner::new(&config));
+        for (name, profile_config) in profiles {
+            info!("Initializing profile {}", name);
+            let builder = Archiver::builder()
+                .name(name.clone())
+                .config(profile_config)
+                .soundcloud_tuner(soundcloud_tuner.clone());
+            match builder.build().await {
+                Ok(a) => archivers.push(Arc::new(a)),
+                Err(e) => {
+                    error!("Failed to initialize profile {}: {}", name, e);
+                    return Err(e);
+                }
+            }
+        }
     }
     
-    // Initialize database
-    info!("Initializing tracks database");
-    let tracks_db_path = config.tracks_file.clone();
-    let db = Arc::new(Mutex::new(match TrackDatabase::load_or_create(tracks_db_path) {
-        Ok(d) => {
-            info!("Tracks database initialized from {} with {} tracks", 
-                 d.db_path, d.get_all_tracks().len());
-            d
-        },
-        Err(e) => {
-            error!("Failed to initialize tracks database: {}", e);
-            return Err(e);
-        }
-    }));
+    // Initialize signal handlers for graceful shutdown
+    shutdown::spawn_signal_listener();
     
-    // Initialize SoundCloud client
-    info!("Initializing SoundCloud client");
-    match soundcloud::initialize().await {
-        Ok(_) => info!("SoundCloud client initialized successfully"),
-        Err(e) => {
-            error!("Failed to initialize SoundCloud client: {}", e);
-            return Err(e);
-        }
-    }
+    // All profiles share one SoundCloud client, and with it the API rate limit
+    soundcloud::set_rate_limit(config.soundcloud_requests_per_minute);
+    soundcloud::set_api_retry(soundcloud::ApiRetry::from_config(&config));
+    soundcloud::set_conditional_requests(config.conditional_requests);
+    soundcloud::set_user_cache_ttl(std::time::Duration::from_secs(config.user_cache_ttl_sec));
+    tempspace::set_ceiling(config.max_temp_mb.saturating_mul(1024 * 1024));
     
-    // If auto-follow is enabled, check for new followings on startup
-    if config.auto_follow_source.is_some() {
-        info!("Auto-follow is enabled, checking for new followings on startup");
-        match update_followings_from_source(&config, &mut users).await {
-            Ok(count) => {
-                if count > 0 {
-                    info!("Added {} new users to watch from auto-follow source during startup", count);
-                } else {
-                    info!("No new followings found from auto-follow source during startup");
+    // Replace the SoundCloud client ID before it expires mid-poll (shared by all profiles)
+    soundcloud::spawn_client_id_refresher(std::time::Duration::from_secs(config.client_id_refresh_hours.saturating_mul(3600)));
+    soundcloud::spawn_client_id_monitor(
+        std::time::Duration::from_secs(config.client_id_alert_minutes.saturating_mul(60)),
+        config.alert_webhook_url.clone().filter(|u| !u.is_empty())
+    );
+    
+    // Pause posting to webhooks that were deleted, and tell the operator how to replace them
+    discord::spawn_webhook_monitor(config.alert_webhook_url.clone().filter(|u| !u.is_empty()));
+    
+    // Let the operator know if they're running a stale release
+    updates::spawn_update_check(&config);
+    
+    // Toggle debug logging on SIGUSR2
+    loghandler::spawn_log_level_signal_listener();
+    
+    // Listen for requests to poll immediately (SIGUSR1 / control pipe) and pass them on to every profile
+    let poll_trigger = Arc::new(Notify::new());
+    spawn_poll_trigger_listener(poll_trigger.clone());
+    {
+        let poll_trigger = poll_trigger.clone();
+        let targets: Vec<_> = archivers.iter().map(|a| a.poll_trigger()).collect();
+        tokio::spawn(async move {
+            loop {
+                poll_trigger.notified().await;
+                for target in &targets {
+                    target.notify_one();
                 }
-            },
-            Err(e) => {
-                warn!("Failed to update followings from source during startup: {}", e);
             }
-        }
+        });
     }
     
-    // Initialize signal handlers for clean shutdown
-    #[cfg(unix)]
-    let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
-        .expect("Failed to set up SIGINT handler");
-    #[cfg(unix)]
-    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-        .expect("Failed to set up SIGTERM handler");
+    // Start the admin API if enabled (user and track endpoints act on the first profile)
+    let admin_state = admin::AdminState {
+        config: archivers[0].config().clone(),
+        db: archivers[0].database(),
+        users: archivers[0].users(),
+        soundcloud_api: archivers[0].soundcloud_api(),
+        poll_trigger,
+        started_at: archivers[0].started_at(),
+    };
+    if let Err(e) = admin::start_admin_server(admin_state).await {
+        error!("Failed to start admin API: {}", e);
+        return Err(e);
+    }
     
-    // Create scheduler interval
-    let poll_interval = Duration::from_secs(config.poll_interval_sec);
-    let mut interval = tokio::time::interval(poll_interval);
+    // Run every archiver until shutdown
+    let tasks: Vec<_> = archivers.into_iter()
+        .map(|archiver| {
+            let name = archiver.name().unwrap_or("default").to_string();
+            spawn_supervised(format!("profile {}", name), async move { archiver.run().await })
+        })
+        .collect();
     
-    // Start main polling loop
-    info!("Starting polling loop with interval of {} seconds", config.poll_interval_sec);
+    let mut result = Ok(());
+    for task in tasks {
+        match task.join().await {
+            Ok(Ok(())) => {},
+            Ok(Err(e)) => {
+                error!("Archiver stopped with an error: {}", e);
+                result = Err(e);
+            },
+            Err(failure) => result = Err(failure.into()),
+        }
+    }
     
-    // Initialize counters
-    let mut total_polls = 0;
-    let mut follow_check_counter = 0;
-    let mut db_save_counter = 0;
-    let mut tracks_since_last_save = 0;
-    let mut db_needs_saving = false;
+    telemetry::shutdown();
+    reporting::shutdown();
+    result
+}
 
-    // Main polling loop
-    loop {
-        total_polls += 1;
-        info!("Starting poll #{}", total_polls);
-        
-        // Wait for either the next tick or a shutdown signal
-        #[cfg(unix)]
-        let should_shutdown = tokio::select! {
-            _ = interval.tick() => false,
-            _ = sigint.recv() => {
-                info!("Received SIGINT signal");
-                true
-            },
-            _ = sigterm.recv() => {
-                info!("Received SIGTERM signal");
-                true
-            },
-        };
+/// Name of the control pipe used to trigger an immediate poll on Windows
+#[cfg(windows)]
+const POLL_TRIGGER_PIPE: &str = r"\\.\pipe\archiver_webhook";
+
+/// Spawn a background listener that requests an immediate out-of-schedule poll
+///
+/// On Unix this is triggered by sending SIGUSR1 to the process. On Windows, where
+/// there is no equivalent signal, writing `poll` to the `\\.\pipe\archiver_webhook`
+/// named pipe does the same thing (e.g. `echo poll > \\.\pipe\archiver_webhook`), and
+/// writing `log-level <level>` changes the log level.
+fn spawn_poll_trigger_listener(trigger: Arc<Notify>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
         
-        #[cfg(not(unix))]
-        let should_shutdown = tokio::select! {
-            _ = interval.tick() => false,
-            result = tokio::signal::ctrl_c() => {
-                match result {
-                    Ok(()) => {
-                        info!("Received Ctrl+C signal");
-                        // Give some breathing room for signal handling
-                        tokio::time::sleep(Duration::from_millis(100)).await;
-                        true
-                    },
-                    Err(e) => {
-                        error!("Error handling Ctrl+C signal: {}", e);
-                        true
-                    }
-                }
-            },
+        let mut sigusr1 = match signal(SignalKind::user_defined1()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to set up SIGUSR1 handler, immediate polls are unavailable: {}", e);
+                return;
+            }
         };
         
-        if should_shutdown {
-            info!("Shutdown signal received, performing clean shutdown");
-            
-            // Set a reasonable timeout for shutdown operations
-            let shutdown_timeout = Duration::from_secs(5);
-            
-            // Create a timeout for the shutdown process
-            let shutdown_result = tokio::time::timeout(shutdown_timeout, async {
-                // Save the database
-                {
-                    let db_guard = db.lock().await;
-                    if let Err(e) = db_guard.shutdown() {
-                        error!("Error during database shutdown: {}", e);
-                    }
-                }
-                
-                // Small delay to ensure all resources are freed
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }).await;
-            
-            match shutdown_result {
-                Ok(_) => info!("Application shutdown completed successfully"),
-                Err(_) => warn!("Application shutdown timed out after {} seconds", shutdown_timeout.as_secs()),
+        tokio::spawn(async move {
+            while sigusr1.recv().await.is_some() {
+                info!("Received SIGUSR1 signal, triggering immediate poll");
+                trigger.notify_one();
             }
-            
-            break;
-        }
+        });
+        debug!("Send SIGUSR1 to trigger an immediate poll");
+    }
+    
+    #[cfg(windows)]
+    {
+        use tokio::io::AsyncReadExt;
+        use tokio::net::windows::named_pipe::ServerOptions;
         
-        // Check if it's time to update followings
-        if config.auto_follow_source.is_some() {
-            follow_check_counter += 1;
-            
-            if follow_check_counter >= config.auto_follow_interval {
-                info!("Auto-follow interval reached ({} polls), checking for new followings", 
-                      config.auto_follow_interval);
-                
-                match update_followings_from_source(&config, &mut users).await {
-                    Ok(count) => {
-                        if count > 0 {
-                            info!("Added {} new users to watch from auto-follow source", count);
-                        } else {
-                            debug!("No new followings found from auto-follow source");
-                        }
-                    },
+        tokio::spawn(async move {
+            loop {
+                let mut server = match ServerOptions::new().create(POLL_TRIGGER_PIPE) {
+                    Ok(s) => s,
                     Err(e) => {
-                        warn!("Failed to update followings from source: {}", e);
+                        warn!("Failed to create control pipe {}, immediate polls are unavailable: {}", 
+                              POLL_TRIGGER_PIPE, e);
+                        return;
                     }
+                };
+                
+                if let Err(e) = server.connect().await {
+                    warn!("Control pipe connection failed: {}", e);
+                    continue;
                 }
                 
-                // Reset counter
-                follow_check_counter = 0;
-            }
-        }
-        
-        // Process users in parallel batches
-        let users_vec = users.users.clone();
-        let mut users_processed = 0;
-        let mut total_new_tracks = 0;
-        
-        // Process users in batches with SoundCloud parallelism limit
-        while users_processed < users_vec.len() {
-            let batch_size = std::cmp::min(config.max_soundcloud_parallelism, users_vec.len() - users_processed);
-            let batch = &users_vec[users_processed..users_processed + batch_size];
-            
-            let mut tasks = Vec::new();
-            
-            // Create tasks for each user in the batch
-            for user_id in batch {
-                let config = config.clone();
-                let user_id = user_id.clone();
-                let db = db.clone();
+                let mut command = String::new();
+                if let Err(e) = server.read_to_string(&mut command).await {
+                    warn!("Failed to read from control pipe: {}", e);
+                    continue;
+                }
                 
-                let task = tokio::spawn(async move {
-                    match poll_user(&config, &user_id, &db).await {
-                        Ok(count) => {
-                            increment_new_tracks(count as u64);
-                            (user_id, Ok(count))
-                        },
-                        Err(e) => {
-                            error!("Error polling user {}: {}", user_id, e);
-                            increment_error_count();
-                            (user_id, Err(e))
-                        }
-                    }
-                });
-                
-                tasks.push(task);
-            }
-            
-            // Wait for all tasks in the batch to complete
-            for task in tasks {
-                match task.await {
-                    Ok((_user_id, Ok(count))) => {
-                        total_new_tracks += count;
-                        tracks_since_last_save += count;
-                        if count > 0 {
-                            db_needs_saving = true;
-                        }
-                    },
-                    Ok((_user_id, Err(_))) => {
-                        // Error already logged in poll_user
-                    },
-                    Err(e) => {
-                        error!("Task join error: {}", e);
-                        increment_error_count();
+                let command = command.trim();
+                if command.eq_ignore_ascii_case("poll") {
+                    info!("Received poll command on control pipe, triggering immediate poll");
+                    trigger.notify_one();
+                } else if let Some(level) = command.strip_prefix("log-level ") {
+                    if loghandler::is_valid_log_level(level.trim()) {
+                        loghandler::update_log_level(level.trim());
+                    } else {
+                        warn!("Invalid log level on control pipe: {}", level.trim());
                     }
+                } else {
+                    warn!("Unknown command on control pipe: {}", command);
                 }
             }
-            
-            users_processed += batch_size;
-        }
-
-        // Increment the database save counter
-        db_save_counter += 1;
-        
-        // Save the database if:
-        // 1. We found new tracks and reached the track threshold OR
-        // 2. It's time for a scheduled save based on poll cycles
-        let save_by_tracks = db_needs_saving && tracks_since_last_save >= config.db_save_tracks;
-        let save_by_interval = db_save_counter >= config.db_save_interval;
-        
-        if save_by_tracks || save_by_interval {
-            let save_reason = if save_by_tracks {
-                format!("processed {} new tracks (threshold: {})", 
-                       tracks_since_last_save, config.db_save_tracks)
-            } else {
-                format!("reached poll interval {} (current: {})",
-                       config.db_save_interval, db_save_counter)
-            };
-            
-            info!("Saving database: {}", save_reason);
-            
-            // Hold the mutex lock for the entire save operation
-            let db_guard = db.lock().await;
-            if let Err(e) = db_guard.save() {
-                error!("Failed to save tracks database: {}", e);
-            } else {
-                info!("Database saved successfully with {} tracks ({})", 
-                     db_guard.get_all_tracks().len(), save_reason);
-            }
-            
-            // Reset the counter and flag
-            db_save_counter = 0;
-            tracks_since_last_save = 0;
-            db_needs_saving = false;
-        }
-
-        if total_new_tracks > 0 {
-            info!("Poll #{} completed: {} new tracks found", total_polls, total_new_tracks);
-        } else {
-            debug!("Poll #{} completed: no new tracks", total_polls);
-        }
-        
-        // Sleep until next poll
-        tokio::time::sleep(std::time::Duration::from_secs(config.poll_interval_sec)).await;
+        });
+        debug!("Write 'poll' to {} to trigger an immediate poll", POLL_TRIGGER_PIPE);
     }
-    
-    Ok(())
-}
-
-/// Poll a user for new tracks, process them, and send to Discord
-async fn poll_user(
-    config: &Config,
-    user_id: &str,
-    db: &Arc<Mutex<TrackDatabase>>,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
-    // Create semaphores for limiting concurrency
-    let processing_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_processing_parallelism));
-    let discord_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_discord_parallelism));
-    
-    // Get mutable access to the database
-    let mut db_guard = db.lock().await;
-    
-    // Use the poll_user method with both semaphores
-    db_guard.poll_user(user_id, config, &processing_semaphore, &discord_semaphore).await
-}
-
-/// Check for new followings from a source user and add them to the watched users list
-///
-/// This function is used by the auto-follow feature, which automatically adds new users followed
-/// by a source user to the watch list. It's called both on startup and periodically during the
-/// application's run time according to the configured interval.
-///
-/// The function:
-/// 1. Resolves the source URL to a user ID if needed
-/// 2. Fetches all of the source user's followings
-/// 3. Compares with existing users to find new followings
-/// 4. Adds new followings to the watch list
-/// 5. Saves the updated users file
-///
-/// If a user is unfollowed by the source, they remain in the users list.
-async fn update_followings_from_source(
-    config: &Config,
-    users: &mut Users,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
-    // Return early if no auto-follow source is configured
-    let source = match &config.auto_follow_source {
-        Some(s) => s,
-        None => {
-            debug!("No auto-follow source configured, skipping followings update");
-            return Ok(0);
-        }
-    };
-    
-    // Use our new method to update followings
-    users.update_followings_from_source(source, &config.users_file).await
 }
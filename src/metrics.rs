@@ -0,0 +1,316 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Bucket upper bounds (seconds) for webhook request latency
+const WEBHOOK_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+/// Bucket upper bounds (seconds) for full poll cycle duration
+const POLL_BUCKETS: [f64; 10] = [1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+/// A fixed-bucket Prometheus histogram backed by atomics
+pub struct Histogram {
+    bounds: &'static [f64; 10],
+    buckets: [AtomicU64; 10],
+    count: AtomicU64,
+    // Sum stored as f64 bits
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    const fn new(bounds: &'static [f64; 10]) -> Self {
+        Self {
+            bounds,
+            buckets: [const { AtomicU64::new(0) }; 10],
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a single observation
+    pub fn observe(&self, value: Duration) {
+        let secs = value.as_secs_f64();
+        for (bound, bucket) in self.bounds.iter().zip(self.buckets.iter()) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self.sum.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + secs).to_bits())
+        });
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, bucket) in self.bounds.iter().zip(self.buckets.iter()) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, bucket.load(Ordering::Relaxed));
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(out, "{}_sum {}", name, f64::from_bits(self.sum.load(Ordering::Relaxed)));
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// External service an API call was made to, used as a metric label
+#[derive(Debug, Clone, Copy)]
+pub enum Service {
+    SoundCloud,
+    Discord,
+}
+
+// Counters
+static POLLS: AtomicU64 = AtomicU64::new(0);
+static SKIPPED_POLLS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_TRACKS: AtomicU64 = AtomicU64::new(0);
+static NEW_TRACKS: AtomicU64 = AtomicU64::new(0);
+static ERROR_COUNT: AtomicU64 = AtomicU64::new(0);
+static BYTES_DOWNLOADED: AtomicU64 = AtomicU64::new(0);
+static SOUNDCLOUD_API_ERRORS: AtomicU64 = AtomicU64::new(0);
+static DISCORD_API_ERRORS: AtomicU64 = AtomicU64::new(0);
+static SOUNDCLOUD_RATE_LIMITS: AtomicU64 = AtomicU64::new(0);
+static DISCORD_RATE_LIMITS: AtomicU64 = AtomicU64::new(0);
+// SoundCloud responses with HTTP 429 or 403, watched by parallelism autotuning
+static SOUNDCLOUD_THROTTLED: AtomicU64 = AtomicU64::new(0);
+static TASK_PANICS: AtomicU64 = AtomicU64::new(0);
+// Track posts that failed at first and were retried, by whether a retry got them through
+static WEBHOOK_RETRIES_RECOVERED: AtomicU64 = AtomicU64::new(0);
+static WEBHOOK_RETRIES_EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+// SoundCloud client ID refreshes by outcome, and responses that rejected the client ID
+static CLIENT_ID_REFRESHES_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static CLIENT_ID_REFRESHES_FAILED: AtomicU64 = AtomicU64::new(0);
+static CLIENT_ID_REJECTIONS: AtomicU64 = AtomicU64::new(0);
+static LISTINGS_NOT_MODIFIED: AtomicU64 = AtomicU64::new(0);
+static LISTINGS_SKIPPED: AtomicU64 = AtomicU64::new(0);
+static TEMP_SPACE_WAITS: AtomicU64 = AtomicU64::new(0);
+
+// Gauges
+// Unix timestamp of the last completed poll cycle (0 = none yet)
+static LAST_POLL_COMPLETED: AtomicI64 = AtomicI64::new(0);
+// Number of tracks queued or in-flight for processing
+static QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+// Number of users currently allowed to be polled from SoundCloud at once
+static SOUNDCLOUD_PARALLELISM: AtomicUsize = AtomicUsize::new(0);
+
+// Histograms
+static POLL_DURATION: Histogram = Histogram::new(&POLL_BUCKETS);
+static WEBHOOK_DURATION: Histogram = Histogram::new(&WEBHOOK_BUCKETS);
+
+/// Increment the total tracks counter
+pub fn increment_total_tracks(count: u64) {
+    TOTAL_TRACKS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Increment the new tracks counter
+pub fn increment_new_tracks(count: u64) {
+    NEW_TRACKS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Increment the error counter
+pub fn increment_error_count() {
+    ERROR_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Increment the counter of panicked processing tasks
+pub fn increment_task_panics() {
+    TASK_PANICS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Add to the number of bytes downloaded (audio and artwork)
+pub fn add_bytes_downloaded(bytes: u64) {
+    BYTES_DOWNLOADED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Record a failed API request to an external service
+///
+/// `status` is the HTTP status of the response, or `None` for network errors.
+/// HTTP 429 responses are also counted as rate-limit hits.
+pub fn record_api_error(service: Service, status: Option<u16>) {
+    let (errors, rate_limits) = match service {
+        Service::SoundCloud => (&SOUNDCLOUD_API_ERRORS, &SOUNDCLOUD_RATE_LIMITS),
+        Service::Discord => (&DISCORD_API_ERRORS, &DISCORD_RATE_LIMITS),
+    };
+    errors.fetch_add(1, Ordering::Relaxed);
+    if status == Some(429) {
+        rate_limits.fetch_add(1, Ordering::Relaxed);
+    }
+    if matches!(service, Service::SoundCloud) && matches!(status, Some(429) | Some(403)) {
+        SOUNDCLOUD_THROTTLED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Get the number of SoundCloud responses with HTTP 429 or 403 so far
+pub fn soundcloud_throttled() -> u64 {
+    SOUNDCLOUD_THROTTLED.load(Ordering::Relaxed)
+}
+
+/// Set the current SoundCloud parallelism limit
+pub fn set_soundcloud_parallelism(limit: usize) {
+    SOUNDCLOUD_PARALLELISM.store(limit, Ordering::Relaxed);
+}
+
+/// Record how long a Discord webhook request took
+pub fn observe_webhook_duration(duration: Duration) {
+    WEBHOOK_DURATION.observe(duration);
+}
+
+/// Record how a retried track post ended: posted by a retry, or failed after the last one
+pub fn record_webhook_retry_outcome(recovered: bool) {
+    let counter = if recovered { &WEBHOOK_RETRIES_RECOVERED } else { &WEBHOOK_RETRIES_EXHAUSTED };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record how a SoundCloud client ID refresh ended
+pub fn record_client_id_refresh(succeeded: bool) {
+    let counter = if succeeded { &CLIENT_ID_REFRESHES_SUCCEEDED } else { &CLIENT_ID_REFRESHES_FAILED };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Increment the number of SoundCloud responses that rejected the client ID (HTTP 401 or 403)
+pub fn increment_client_id_rejections() {
+    CLIENT_ID_REJECTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Increment the number of track listings SoundCloud answered with 304 Not Modified
+pub fn increment_listings_not_modified() {
+    LISTINGS_NOT_MODIFIED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Increment the number of track listings not fetched because the user's profile was unchanged
+pub fn increment_listings_skipped() {
+    LISTINGS_SKIPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Increment the number of tracks that waited for room in the temp directory
+pub fn increment_temp_space_waits() {
+    TEMP_SPACE_WAITS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Add to the number of scheduled polls skipped because a previous poll overran
+pub fn increment_skipped_polls(count: u64) {
+    SKIPPED_POLLS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Record that a poll cycle has completed and how long it took
+pub fn record_poll_completed(duration: Duration) {
+    POLLS.fetch_add(1, Ordering::Relaxed);
+    POLL_DURATION.observe(duration);
+    LAST_POLL_COMPLETED.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+/// Get the time the last poll cycle completed, if any
+pub fn last_poll_completed() -> Option<chrono::DateTime<chrono::Utc>> {
+    match LAST_POLL_COMPLETED.load(Ordering::Relaxed) {
+        0 => None,
+        ts => chrono::DateTime::from_timestamp(ts, 0),
+    }
+}
+
+/// Increment the number of tracks queued for processing
+pub fn increment_queue_depth() {
+    QUEUE_DEPTH.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Decrement the number of tracks queued for processing
+pub fn decrement_queue_depth() {
+    let _ = QUEUE_DEPTH.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |d| Some(d.saturating_sub(1)));
+}
+
+/// Get the number of tracks currently queued or in-flight for processing
+pub fn queue_depth() -> usize {
+    QUEUE_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Snapshot of the headline counters, used for the console title
+pub struct Summary {
+    pub total_tracks: u64,
+    pub new_tracks: u64,
+    pub errors: u64,
+}
+
+/// Get the headline counters
+pub fn summary() -> Summary {
+    Summary {
+        total_tracks: TOTAL_TRACKS.load(Ordering::Relaxed),
+        new_tracks: NEW_TRACKS.load(Ordering::Relaxed),
+        errors: ERROR_COUNT.load(Ordering::Relaxed),
+    }
+}
+
+fn render_simple(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn render_by_service(out: &mut String, name: &str, help: &str, soundcloud: &AtomicU64, discord: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{}{{service=\"soundcloud\"}} {}", name, soundcloud.load(Ordering::Relaxed));
+    let _ = writeln!(out, "{}{{service=\"discord\"}} {}", name, discord.load(Ordering::Relaxed));
+}
+
+/// Render all metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+
+    render_simple(&mut out, "archiver_polls_total", "counter",
+        "Completed poll cycles", POLLS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_skipped_polls_total", "counter",
+        "Scheduled polls skipped because the previous poll overran the interval", SKIPPED_POLLS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_tracks_processed_total", "counter",
+        "Tracks successfully processed and posted", TOTAL_TRACKS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_new_tracks_total", "counter",
+        "New tracks found while polling", NEW_TRACKS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_errors_total", "counter",
+        "Errors while polling users or processing tracks", ERROR_COUNT.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_task_panics_total", "counter",
+        "Per-user and per-track tasks that panicked", TASK_PANICS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_bytes_downloaded_total", "counter",
+        "Bytes of audio and artwork downloaded", BYTES_DOWNLOADED.load(Ordering::Relaxed));
+    render_by_service(&mut out, "archiver_api_errors_total",
+        "Failed requests to external APIs", &SOUNDCLOUD_API_ERRORS, &DISCORD_API_ERRORS);
+    render_by_service(&mut out, "archiver_rate_limit_hits_total",
+        "Rate-limited (HTTP 429) responses from external APIs", &SOUNDCLOUD_RATE_LIMITS, &DISCORD_RATE_LIMITS);
+    let _ = writeln!(out, "# HELP archiver_webhook_retries_total Track posts retried after Discord was unavailable, by outcome");
+    let _ = writeln!(out, "# TYPE archiver_webhook_retries_total counter");
+    let _ = writeln!(out, "archiver_webhook_retries_total{{outcome=\"recovered\"}} {}", WEBHOOK_RETRIES_RECOVERED.load(Ordering::Relaxed));
+    let _ = writeln!(out, "archiver_webhook_retries_total{{outcome=\"exhausted\"}} {}", WEBHOOK_RETRIES_EXHAUSTED.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_queue_depth", "gauge",
+        "Tracks queued or in-flight for processing", QUEUE_DEPTH.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_temp_bytes_reserved", "gauge",
+        "Temp directory space reserved by tracks being processed", crate::tempspace::reserved());
+    render_simple(&mut out, "archiver_temp_space_waits_total", "counter",
+        "Tracks that waited for room in the temp directory", TEMP_SPACE_WAITS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_soundcloud_parallelism", "gauge",
+        "Users currently allowed to be polled from SoundCloud at once", SOUNDCLOUD_PARALLELISM.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_client_id_valid", "gauge",
+        "Whether the SoundCloud client ID is currently valid", crate::soundcloud::client_id_valid() as u8);
+    render_simple(&mut out, "archiver_client_id_age_seconds", "gauge",
+        "Time since the SoundCloud client ID was generated",
+        crate::soundcloud::client_id_age().map(|age| age.as_secs()).unwrap_or(0));
+    render_simple(&mut out, "archiver_client_id_failing_seconds", "gauge",
+        "Time SoundCloud requests have been failing because of the client ID (0 = working)",
+        crate::soundcloud::client_id_failing().map(|(failing_for, _)| failing_for.as_secs()).unwrap_or(0));
+    render_simple(&mut out, "archiver_webhooks_gone", "gauge",
+        "Discord webhooks posting is paused for because they were deleted or are invalid", crate::discord::gone_webhook_count() as u64);
+    let _ = writeln!(out, "# HELP archiver_client_id_refreshes_total SoundCloud client ID refreshes, by outcome");
+    let _ = writeln!(out, "# TYPE archiver_client_id_refreshes_total counter");
+    let _ = writeln!(out, "archiver_client_id_refreshes_total{{outcome=\"success\"}} {}", CLIENT_ID_REFRESHES_SUCCEEDED.load(Ordering::Relaxed));
+    let _ = writeln!(out, "archiver_client_id_refreshes_total{{outcome=\"failure\"}} {}", CLIENT_ID_REFRESHES_FAILED.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_client_id_rejections_total", "counter",
+        "SoundCloud responses that rejected the client ID (HTTP 401 or 403)", CLIENT_ID_REJECTIONS.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_listings_not_modified_total", "counter",
+        "User track listings that were unchanged since the last poll (HTTP 304)", LISTINGS_NOT_MODIFIED.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_listings_skipped_total", "counter",
+        "User track listings not fetched because the user's track count and profile were unchanged", LISTINGS_SKIPPED.load(Ordering::Relaxed));
+    render_simple(&mut out, "archiver_last_poll_completed_timestamp_seconds", "gauge",
+        "Unix time the last poll cycle completed", LAST_POLL_COMPLETED.load(Ordering::Relaxed));
+    POLL_DURATION.render(&mut out, "archiver_poll_duration_seconds",
+        "Time taken by a full poll cycle");
+    WEBHOOK_DURATION.render(&mut out, "archiver_webhook_duration_seconds",
+        "Time taken by Discord webhook requests");
+
+    out
+}
@@ -0,0 +1,261 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use log::{info, warn, debug};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::soundcloud::Track;
+
+const MUSICBRAINZ_API: &str = "https://musicbrainz.org/ws/2";
+const ACOUSTID_API: &str = "https://api.acoustid.org/v2/lookup";
+
+/// MusicBrainz asks clients to identify themselves and stay under one request per second
+const USER_AGENT: &str = concat!("archiver_webhook/", env!("CARGO_PKG_VERSION"), " ( https://github.com/scarchives/archiver_webhook )");
+const MUSICBRAINZ_MIN_INTERVAL: Duration = Duration::from_millis(1100);
+
+/// Lowest MusicBrainz search score (0-100) accepted as a match
+const MIN_SEARCH_SCORE: u64 = 90;
+/// Lowest AcoustID fingerprint score (0-1) accepted as a match
+const MIN_ACOUSTID_SCORE: f64 = 0.8;
+/// How far a search result's length may be from the SoundCloud track's
+const MAX_LENGTH_DIFFERENCE_MS: u64 = 10_000;
+
+/// Extensions of the audio files that get tagged
+const TAGGABLE_EXTENSIONS: &[&str] = &["flac", "opus", "ogg", "m4a", "mp3"];
+
+/// When the last MusicBrainz request was sent, shared by all tracks
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+/// Canonical metadata for a track, as found on MusicBrainz
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackMetadata {
+    /// MusicBrainz recording ID
+    pub recording_id: String,
+    pub title: String,
+    /// Canonical artist credit
+    pub artist: String,
+    /// First release the recording appears on
+    #[serde(default)]
+    pub release: Option<String>,
+    #[serde(default)]
+    pub release_id: Option<String>,
+    #[serde(default)]
+    pub isrc: Option<String>,
+    /// How the recording was matched: "acoustid" or "search"
+    pub matched_by: String,
+}
+
+/// Looks up archived tracks on MusicBrainz (and AcoustID, if configured) to enrich their tags
+#[derive(Debug, Clone, Default)]
+pub struct Enrichment {
+    enabled: bool,
+    acoustid_api_key: Option<String>,
+}
+
+impl Enrichment {
+    pub fn from_config(config: &Config) -> Self {
+        Enrichment {
+            enabled: config.musicbrainz_enrichment,
+            acoustid_api_key: config.acoustid_api_key.clone().filter(|k| !k.is_empty()),
+        }
+    }
+
+    /// Find the track on MusicBrainz and tag its audio files with the result
+    ///
+    /// Tracks are matched by audio fingerprint when an AcoustID key is set and `fpcalc`
+    /// is installed, otherwise by searching for the title and uploader. Nothing is
+    /// returned if there's no confident match; failures are logged and never fail the track.
+    pub async fn enrich(&self, track: &Track, files: &[(String, String)]) -> Option<TrackMetadata> {
+        if !self.enabled {
+            return None;
+        }
+
+        let audio_files: Vec<&str> = files.iter()
+            .map(|(path, _)| path.as_str())
+            .filter(|path| Path::new(path).extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| TAGGABLE_EXTENSIONS.contains(&e)))
+            .collect();
+
+        let client = Client::new();
+        let mut metadata = None;
+        if let (Some(key), Some(file)) = (&self.acoustid_api_key, audio_files.first()) {
+            match lookup_by_fingerprint(&client, key, file).await {
+                Ok(m) => metadata = m,
+                Err(e) => warn!("AcoustID lookup failed for track {}: {}", track.id, e),
+            }
+        }
+        if metadata.is_none() {
+            match lookup_by_search(&client, track).await {
+                Ok(m) => metadata = m,
+                Err(e) => warn!("MusicBrainz search failed for track {}: {}", track.id, e),
+            }
+        }
+
+        let Some(metadata) = metadata else {
+            debug!("No MusicBrainz match for track {}", track.id);
+            return None;
+        };
+        info!("Matched track {} to MusicBrainz recording {} ({} - {}) by {}",
+              track.id, metadata.recording_id, metadata.artist, metadata.title, metadata.matched_by);
+
+        for file in audio_files {
+            if let Err(e) = tag_file(file, &metadata).await {
+                warn!("Failed to write MusicBrainz tags to {}: {}", file, e);
+            }
+        }
+
+        Some(metadata)
+    }
+}
+
+/// Fingerprint the file with `fpcalc` and look it up on AcoustID
+async fn lookup_by_fingerprint(client: &Client, api_key: &str, file: &str) -> Result<Option<TrackMetadata>, Box<dyn std::error::Error + Send + Sync>> {
+    let output = Command::new("fpcalc").arg("-json").arg(file).output().await
+        .map_err(|e| format!("failed to run fpcalc (is chromaprint installed?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!("fpcalc exited with {}", output.status).into());
+    }
+    let fingerprint: Value = serde_json::from_slice(&output.stdout)?;
+    let duration = fingerprint.get("duration").and_then(|d| d.as_f64()).ok_or("fpcalc returned no duration")?;
+    let fingerprint = fingerprint.get("fingerprint").and_then(|f| f.as_str()).ok_or("fpcalc returned no fingerprint")?;
+
+    let response: Value = client.post(ACOUSTID_API)
+        .form(&[
+            ("client", api_key),
+            ("meta", "recordingids"),
+            ("duration", &(duration as u64).to_string()),
+            ("fingerprint", fingerprint),
+        ])
+        .send().await?
+        .error_for_status()?
+        .json().await?;
+
+    let recording_id = response.get("results")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|r| r.get("score").and_then(|s| s.as_f64()).unwrap_or(0.0) >= MIN_ACOUSTID_SCORE)
+        .find_map(|r| r.get("recordings")?.as_array()?.first()?.get("id")?.as_str().map(|s| s.to_string()));
+    let Some(recording_id) = recording_id else {
+        return Ok(None);
+    };
+
+    let recording = musicbrainz_get(client, &format!("{}/recording/{}", MUSICBRAINZ_API, recording_id),
+                                    &[("inc", "artist-credits+releases+isrcs")]).await?;
+    Ok(parse_recording(&recording, "acoustid"))
+}
+
+/// Search MusicBrainz for a recording with the track's title by the uploader
+async fn lookup_by_search(client: &Client, track: &Track) -> Result<Option<TrackMetadata>, Box<dyn std::error::Error + Send + Sync>> {
+    let query = format!("recording:\"{}\" AND artist:\"{}\"", escape_query(&track.title), escape_query(&track.user.username));
+    let response = musicbrainz_get(client, &format!("{}/recording", MUSICBRAINZ_API), &[("query", &query), ("limit", "5")]).await?;
+
+    let result = response.get("recordings")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|r| r.get("score").and_then(|s| s.as_u64()).unwrap_or(0) >= MIN_SEARCH_SCORE)
+        .find(|r| match r.get("length").and_then(|l| l.as_u64()) {
+            Some(length) => length.abs_diff(track.duration) <= MAX_LENGTH_DIFFERENCE_MS,
+            None => true,
+        });
+
+    Ok(result.and_then(|r| parse_recording(r, "search")))
+}
+
+/// GET a MusicBrainz API endpoint as JSON, respecting its rate limit
+async fn musicbrainz_get(client: &Client, url: &str, query: &[(&str, &str)]) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let mut last_request = LAST_REQUEST.lock().await;
+    if let Some(last) = *last_request {
+        let elapsed = last.elapsed();
+        if elapsed < MUSICBRAINZ_MIN_INTERVAL {
+            tokio::time::sleep(MUSICBRAINZ_MIN_INTERVAL - elapsed).await;
+        }
+    }
+    *last_request = Some(Instant::now());
+
+    let response = client.get(url)
+        .header("User-Agent", USER_AGENT)
+        .query(query)
+        .query(&[("fmt", "json")])
+        .send().await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/// Build the metadata from a MusicBrainz recording object
+fn parse_recording(recording: &Value, matched_by: &str) -> Option<TrackMetadata> {
+    let artist = recording.get("artist-credit")?
+        .as_array()?
+        .iter()
+        .map(|credit| format!("{}{}",
+            credit.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+            credit.get("joinphrase").and_then(|j| j.as_str()).unwrap_or_default()))
+        .collect::<String>();
+    let release = recording.get("releases").and_then(|r| r.as_array()).and_then(|r| r.first());
+
+    Some(TrackMetadata {
+        recording_id: recording.get("id")?.as_str()?.to_string(),
+        title: recording.get("title")?.as_str()?.to_string(),
+        artist,
+        release: release.and_then(|r| r.get("title")?.as_str()).map(|s| s.to_string()),
+        release_id: release.and_then(|r| r.get("id")?.as_str()).map(|s| s.to_string()),
+        isrc: recording.get("isrcs")
+            .and_then(|i| i.as_array())
+            .and_then(|i| i.first())
+            .and_then(|i| i.as_str())
+            .map(|s| s.to_string()),
+        matched_by: matched_by.to_string(),
+    })
+}
+
+/// Strip characters that would break a quoted Lucene search term
+fn escape_query(term: &str) -> String {
+    term.chars().filter(|c| *c != '"' && *c != '\\').collect()
+}
+
+/// Rewrite an audio file's tags with ffmpeg, copying the streams unchanged
+async fn tag_file(path: &str, metadata: &TrackMetadata) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let source = Path::new(path);
+    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let tagged = source.with_extension(format!("tagged.{}", extension));
+
+    let mut tags = vec![
+        ("title", metadata.title.clone()),
+        ("artist", metadata.artist.clone()),
+        ("MUSICBRAINZ_TRACKID", metadata.recording_id.clone()),
+    ];
+    if let Some(release) = &metadata.release {
+        tags.push(("album", release.clone()));
+    }
+    if let Some(release_id) = &metadata.release_id {
+        tags.push(("MUSICBRAINZ_ALBUMID", release_id.clone()));
+    }
+    if let Some(isrc) = &metadata.isrc {
+        tags.push(("ISRC", isrc.clone()));
+    }
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y").arg("-loglevel").arg("error")
+        .arg("-i").arg(path)
+        .arg("-map").arg("0")
+        .arg("-c").arg("copy");
+    for (key, value) in &tags {
+        cmd.arg("-metadata").arg(format!("{}={}", key, value));
+    }
+    let output = cmd.arg(&tagged).output().await?;
+
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&tagged).await;
+        return Err(format!("ffmpeg exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+
+    tokio::fs::rename(&tagged, source).await?;
+    debug!("Tagged {} with MusicBrainz metadata", path);
+    Ok(())
+}
@@ -0,0 +1,368 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use log::{info, warn, error, debug};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::db::{SkipReason, TrackDatabase};
+use crate::discord::{DiscordUnavailable, WebhookGone};
+use crate::musicbrainz::TrackMetadata;
+use crate::soundcloud::{ArtworkSource, PostProcessing, PostedTrack, Track};
+
+/// Name of the payload file in each queued track's folder
+const PAYLOAD_FILE: &str = "post.json";
+/// Name of the file listing a posted track's attachments still to be uploaded
+const UPLOAD_FILE: &str = "upload.json";
+
+/// A track was processed while Discord was down and has been queued instead of posted
+#[derive(Debug)]
+pub struct Queued(pub String);
+
+impl std::fmt::Display for Queued {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Discord is unavailable, track {} was queued to be posted later", self.0)
+    }
+}
+
+impl std::error::Error for Queued {}
+
+/// A ready-to-post track waiting for Discord to come back
+#[derive(Debug, Serialize, Deserialize)]
+struct QueuedPost {
+    webhook_url: String,
+    track: Track,
+    embed: Value,
+    /// Plain text shown above the embed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    /// Attachment file names, stored next to the payload
+    files: Vec<String>,
+    #[serde(flatten)]
+    details: PostDetails,
+    queued_at: DateTime<Utc>,
+}
+
+/// What's recorded in the database along with a queued track once it's posted
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PostDetails {
+    #[serde(default)]
+    pub metadata: Option<TrackMetadata>,
+    #[serde(default)]
+    pub skip: Option<(SkipReason, String)>,
+    #[serde(default)]
+    pub artwork_source: Option<ArtworkSource>,
+}
+
+/// Attachments of an already posted track, waiting to be uploaded as follow-up messages
+#[derive(Debug, Serialize, Deserialize)]
+struct QueuedUpload {
+    webhook_url: String,
+    track: Track,
+    /// Attachment file names, stored next to this file
+    files: Vec<String>,
+    queued_at: DateTime<Utc>,
+}
+
+/// Tracks that couldn't be posted because Discord was unreachable, kept on disk
+///
+/// Each track gets a `<discord_queue_dir>/<track id>/` folder with its attachments
+/// and a `post.json` holding the embed. The queue is flushed, oldest first, before
+/// every poll; tracks are only recorded in the database once they've been posted.
+///
+/// Attachments that couldn't be uploaded along with a posted track are kept the same
+/// way, in an `upload.json` next to the files, and posted as follow-up messages.
+#[derive(Debug, Clone, Default)]
+pub struct Outbox {
+    dir: Option<PathBuf>,
+}
+
+impl Outbox {
+    pub fn from_config(config: &Config) -> Self {
+        Outbox {
+            dir: config.discord_queue_dir.as_deref().filter(|d| !d.is_empty()).map(PathBuf::from),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Whether a track is waiting in the queue
+    pub fn contains(&self, track_id: &str) -> bool {
+        self.dir.as_ref().is_some_and(|dir| dir.join(track_id).join(PAYLOAD_FILE).exists())
+    }
+
+    /// Copy a track's attachments into the queue along with its embed
+    pub async fn enqueue(
+        &self,
+        webhook_url: &str,
+        track: &Track,
+        embed: Value,
+        content: Option<&str>,
+        files: &[(String, String)],
+        details: PostDetails
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let dir = self.dir.as_ref().ok_or("discord_queue_dir isn't set")?;
+        let entry_dir = dir.join(&track.id);
+        tokio::fs::create_dir_all(&entry_dir).await?;
+
+        let mut names = Vec::new();
+        for (path, name) in files {
+            let name = crate::audio::sanitize_filename(name);
+            tokio::fs::copy(path, entry_dir.join(&name)).await
+                .map_err(|e| format!("failed to copy {} into the Discord queue: {}", path, e))?;
+            names.push(name);
+        }
+
+        let post = QueuedPost {
+            webhook_url: webhook_url.to_string(),
+            track: track.clone(),
+            embed,
+            content: content.map(|c| c.to_string()),
+            files: names,
+            details,
+            queued_at: Utc::now(),
+        };
+        // Written last, so a half-copied entry is never picked up
+        tokio::fs::write(entry_dir.join(PAYLOAD_FILE), serde_json::to_vec_pretty(&post)?).await?;
+
+        info!("Queued track {} in {} until Discord is reachable again", track.id, entry_dir.display());
+        Ok(())
+    }
+
+    /// Post the attachments of a track whose embed went out without them
+    ///
+    /// Files that can't be posted right away are queued for the next flush, if the
+    /// queue is enabled.
+    pub async fn upload_or_queue(&self, webhook_url: &str, track: &Track, files: &[(String, String)]) {
+        info!("Posting {} files for track {} as follow-up messages", files.len(), track.id);
+        let failed = crate::discord::send_track_files(webhook_url, track, files).await;
+        if failed.is_empty() {
+            return;
+        }
+
+        let Some(dir) = &self.dir else {
+            error!("Failed to post {} files for track {} and discord_queue_dir isn't set, they won't be retried",
+                   failed.len(), track.id);
+            return;
+        };
+        if let Err(e) = write_upload(&dir.join(&track.id), webhook_url, track, &failed).await {
+            error!("Failed to queue files for track {}: {}", track.id, e);
+        }
+    }
+
+    /// Post queued tracks, oldest first, recording each one in the database
+    ///
+    /// Stops at the first track that fails because Discord is still unavailable. Tracks
+    /// that fail for any other reason stay queued and are retried on the next flush.
+    /// Returns the number of tracks posted.
+    pub async fn flush(&self, db: &Mutex<TrackDatabase>, post_processing: &PostProcessing) -> usize {
+        let Some(dir) = &self.dir else {
+            return 0;
+        };
+
+        self.flush_uploads(dir).await;
+
+        let mut entries = match load_entries(dir) {
+            Ok(e) => e,
+            Err(e) => {
+                warn!("Failed to read Discord queue {}: {}", dir.display(), e);
+                return 0;
+            }
+        };
+        if entries.is_empty() {
+            return 0;
+        }
+        entries.sort_by_key(|(_, post)| post.queued_at);
+        info!("Posting {} tracks queued while Discord was unavailable", entries.len());
+
+        let mut posted_count = 0;
+        for (entry_dir, post) in entries {
+            let files: Vec<(String, String)> = post.files.iter()
+                .map(|name| (entry_dir.join(name).to_string_lossy().to_string(), name.clone()))
+                .collect();
+
+            let response = match crate::discord::send_track_post(
+                &post.webhook_url, &post.track, post.embed.clone(), post.content.as_deref(), files.clone(),
+                post_processing.webhook_retry
+            ).await {
+                Ok(r) => r,
+                Err(e) if e.is::<DiscordUnavailable>() => {
+                    warn!("Discord is still unavailable, keeping the remaining queued tracks: {}", e);
+                    break;
+                },
+                Err(e) if e.is::<WebhookGone>() => {
+                    warn!("Not posting queued track {}, it stays queued: {}", post.track.id, e);
+                    continue;
+                },
+                Err(e) => {
+                    error!("Failed to post queued track {}, it stays queued: {}", post.track.id, e);
+                    continue;
+                }
+            };
+
+            post_processing.library.add_track(&post.track, &files).await;
+            post_processing.hooks.track_archived(&post.track, &response, &files).await;
+            let unattached = response.unattached_files.clone();
+
+            let posted = PostedTrack {
+                track_id: post.track.id.clone(),
+                user_id: post.track.user.id.clone(),
+                response,
+                track: post.track,
+                metadata: post.details.metadata,
+                skip: post.details.skip,
+                artwork_source: post.details.artwork_source,
+                destinations: Vec::new(),
+                pending_destinations: post_processing.extra_webhook_names(),
+            };
+            {
+                let mut db = db.lock().await;
+                db.add_posted_track(&posted);
+                if let Err(e) = db.save() {
+                    warn!("Failed to save database after posting queued track {}: {}", posted.track_id, e);
+                }
+            }
+            crate::metrics::increment_total_tracks(1);
+            posted_count += 1;
+
+            if unattached.is_empty() {
+                if let Err(e) = tokio::fs::remove_dir_all(&entry_dir).await {
+                    warn!("Failed to remove {} from the Discord queue: {}", entry_dir.display(), e);
+                }
+            } else {
+                // The files are already in the entry's folder, keep them there for the follow-up
+                let failed = crate::discord::send_track_files(&post.webhook_url, &posted.track, &unattached).await;
+                let result = if failed.is_empty() {
+                    tokio::fs::remove_dir_all(&entry_dir).await.map_err(|e| e.into())
+                } else {
+                    write_upload(&entry_dir, &post.webhook_url, &posted.track, &failed).await
+                };
+                if let Err(e) = result {
+                    warn!("Failed to update {} in the Discord queue: {}", entry_dir.display(), e);
+                }
+            }
+            debug!("Posted queued track {}", posted.track_id);
+        }
+
+        if posted_count > 0 {
+            info!("Posted {} queued tracks", posted_count);
+        }
+        posted_count
+    }
+
+    /// Post queued follow-up attachments, keeping any that still fail
+    async fn flush_uploads(&self, dir: &Path) {
+        let mut uploads = match load_uploads(dir) {
+            Ok(u) => u,
+            Err(e) => {
+                warn!("Failed to read Discord queue {}: {}", dir.display(), e);
+                return;
+            }
+        };
+        uploads.sort_by_key(|(_, upload)| upload.queued_at);
+
+        for (entry_dir, upload) in uploads {
+            let files: Vec<(String, String)> = upload.files.iter()
+                .map(|name| (entry_dir.join(name).to_string_lossy().to_string(), name.clone()))
+                .collect();
+            info!("Posting {} queued files for track {}", files.len(), upload.track.id);
+
+            let failed = crate::discord::send_track_files(&upload.webhook_url, &upload.track, &files).await;
+            if failed.is_empty() {
+                if let Err(e) = tokio::fs::remove_dir_all(&entry_dir).await {
+                    warn!("Failed to remove {} from the Discord queue: {}", entry_dir.display(), e);
+                }
+                continue;
+            }
+            if let Err(e) = write_upload(&entry_dir, &upload.webhook_url, &upload.track, &failed).await {
+                warn!("Failed to update {} in the Discord queue: {}", entry_dir.display(), e);
+            }
+        }
+    }
+}
+
+/// Store a posted track's remaining attachments in its queue folder
+///
+/// Files already in the folder are kept in place; anything else is copied in.
+async fn write_upload(
+    entry_dir: &Path,
+    webhook_url: &str,
+    track: &Track,
+    files: &[(String, String)]
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    tokio::fs::create_dir_all(entry_dir).await?;
+
+    let mut names = Vec::new();
+    for (path, name) in files {
+        let name = crate::audio::sanitize_filename(name);
+        let target = entry_dir.join(&name);
+        if Path::new(path) != target {
+            tokio::fs::copy(path, &target).await
+                .map_err(|e| format!("failed to copy {} into the Discord queue: {}", path, e))?;
+        }
+        names.push(name);
+    }
+
+    let upload = QueuedUpload {
+        webhook_url: webhook_url.to_string(),
+        track: track.clone(),
+        files: names,
+        queued_at: Utc::now(),
+    };
+    tokio::fs::write(entry_dir.join(UPLOAD_FILE), serde_json::to_vec_pretty(&upload)?).await?;
+    // Only the follow-up is left once the track itself has been posted
+    let payload = entry_dir.join(PAYLOAD_FILE);
+    if payload.exists() {
+        tokio::fs::remove_file(payload).await?;
+    }
+
+    info!("Queued {} files for track {} until they can be posted", upload.files.len(), track.id);
+    Ok(())
+}
+
+/// Read every queued follow-up upload
+fn load_uploads(dir: &Path) -> std::io::Result<Vec<(PathBuf, QueuedUpload)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut uploads = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry_dir = entry?.path();
+        let file = entry_dir.join(UPLOAD_FILE);
+        if !file.is_file() {
+            continue;
+        }
+        match std::fs::read(&file).map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice::<QueuedUpload>(&data).map_err(|e| e.to_string())) {
+            Ok(upload) => uploads.push((entry_dir, upload)),
+            Err(e) => warn!("Skipping unreadable Discord queue entry {}: {}", file.display(), e),
+        }
+    }
+    Ok(uploads)
+}
+
+/// Read every complete entry in the queue directory
+fn load_entries(dir: &Path) -> std::io::Result<Vec<(PathBuf, QueuedPost)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry_dir = entry?.path();
+        let payload = entry_dir.join(PAYLOAD_FILE);
+        if !payload.is_file() {
+            continue;
+        }
+        match std::fs::read(&payload).map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice::<QueuedPost>(&data).map_err(|e| e.to_string())) {
+            Ok(post) => entries.push((entry_dir, post)),
+            Err(e) => warn!("Skipping unreadable Discord queue entry {}: {}", payload.display(), e),
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,199 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use log::{info, warn, error, debug};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::db::{SkipReason, TrackDatabase};
+use crate::soundcloud::{PostProcessing, Provenance, Track};
+
+/// A watched playlist, as returned by the API
+#[derive(Debug, Clone)]
+struct Playlist {
+    id: String,
+    title: String,
+    permalink_url: String,
+    /// Track IDs in playlist order
+    track_ids: Vec<String>,
+}
+
+impl Playlist {
+    fn from_json(playlist: &Value) -> Option<Self> {
+        let id = match playlist.get("id")? {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            _ => return None,
+        };
+        let str_field = |name: &str| playlist.get(name).and_then(|v| v.as_str()).map(|v| v.to_string());
+        // Only the first tracks are complete, the rest are just IDs, which is all that's needed
+        let track_ids = playlist.get("tracks")
+            .and_then(|t| t.as_array())
+            .map(|tracks| tracks.iter()
+                .filter_map(|t| t.get("id").and_then(|id| id.as_u64()))
+                .map(|id| id.to_string())
+                .collect())
+            .unwrap_or_default();
+        Some(Playlist {
+            id,
+            title: str_field("title").unwrap_or_else(|| "Untitled".to_string()),
+            permalink_url: str_field("permalink_url").unwrap_or_default(),
+            track_ids,
+        })
+    }
+}
+
+/// A watched playlist as of its last poll
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistState {
+    pub title: String,
+    pub permalink_url: String,
+    /// Tracks in the playlist that were archived or already known at the last poll;
+    /// anything else in it is new
+    pub track_ids: BTreeSet<String>,
+    pub last_polled: DateTime<Utc>,
+}
+
+/// Poll the playlists in the users file and post the tracks added to them
+///
+/// The first poll of a playlist only records what's in it: curators' playlists are mostly
+/// other people's catalogs, so only tracks added from then on are posted, with the playlist
+/// named in the embed footer. Tracks that were archived before (e.g. from a watched user)
+/// are only recorded. Tracks that fail are tried again on the next poll.
+///
+/// Returns the number of tracks posted.
+pub async fn poll_playlists(
+    db: &Arc<Mutex<TrackDatabase>>,
+    playlists: &[String],
+    config: &Config,
+    post_processing: &PostProcessing
+) -> usize {
+    let mut posted = 0;
+    for entry in playlists {
+        if crate::shutdown::is_requested() {
+            break;
+        }
+        let mut db = db.lock().await;
+        match poll_playlist(&mut db, entry, config, post_processing).await {
+            Ok(count) => posted += count,
+            Err(e) => {
+                error!("Error polling playlist {}: {}", entry, e);
+                crate::metrics::increment_error_count();
+                post_processing.hooks.error(&format!("Failed to poll playlist {}: {}", entry, e), None, None);
+            }
+        }
+    }
+    posted
+}
+
+/// Poll one playlist, returning the number of tracks posted
+///
+/// Added tracks are posted one at a time, after all users were polled.
+async fn poll_playlist(
+    db: &mut TrackDatabase,
+    entry: &str,
+    config: &Config,
+    post_processing: &PostProcessing
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let json = post_processing.api.playlist(entry).await?;
+    let playlist = Playlist::from_json(&json)
+        .ok_or_else(|| format!("Unexpected API response format for playlist {}", entry))?;
+
+    let Some(state) = db.playlist_state(&playlist.id) else {
+        info!("Now watching playlist {} ({}) with {} tracks, only tracks added from now on are posted",
+              playlist.id, playlist.title, playlist.track_ids.len());
+        db.set_playlist_state(&playlist.id, PlaylistState {
+            title: playlist.title.clone(),
+            permalink_url: playlist.permalink_url.clone(),
+            track_ids: playlist.track_ids.iter().cloned().collect(),
+            last_polled: Utc::now(),
+        });
+        db.save()?;
+        return Ok(0);
+    };
+
+    // Tracks that were archived before, or are waiting in the Discord queue, only need
+    // to be remembered
+    let added: Vec<String> = playlist.track_ids.iter()
+        .filter(|id| !state.track_ids.contains(*id))
+        .cloned()
+        .collect();
+    let mut known: BTreeSet<String> = playlist.track_ids.iter()
+        .filter(|id| state.track_ids.contains(*id) || db.has_track(id) || post_processing.outbox.contains(id))
+        .cloned()
+        .collect();
+    let new_ids: Vec<String> = added.iter().filter(|id| !known.contains(*id)).cloned().collect();
+    if !added.is_empty() {
+        debug!("{} tracks were added to playlist {} ({}), {} of them new", added.len(), playlist.id, playlist.title, new_ids.len());
+    }
+
+    let provenance = Provenance::Playlist { id: playlist.id.clone(), title: playlist.title.clone() };
+    let mut batched: HashMap<String, Track> = HashMap::new();
+    if new_ids.len() > 1 {
+        match post_processing.api.tracks_details(&new_ids).await {
+            Ok(tracks) => batched.extend(tracks.into_iter().map(|t| (t.id.clone(), t))),
+            Err(e) => warn!("Failed to fetch the details of playlist {}'s {} new tracks at once, fetching them one by one: {}",
+                            playlist.id, new_ids.len(), e),
+        }
+    }
+
+    let mut posted = 0;
+    for track_id in &new_ids {
+        if crate::shutdown::is_requested() {
+            break;
+        }
+        let Some(_claim) = db.claim(track_id) else {
+            debug!("Track {} is already being processed elsewhere, skipping it for playlist {}", track_id, playlist.id);
+            continue;
+        };
+
+        let details = match batched.remove(track_id) {
+            Some(details) => post_processing.api.resolve_track_media(details).await,
+            None => post_processing.api.track_details(track_id, None).await,
+        };
+        let result = match details {
+            Ok(details) => crate::soundcloud::post_track(
+                details,
+                &config.discord_webhook_url,
+                config.temp_dir.as_deref(),
+                None,
+                post_processing,
+                Some(provenance.clone())
+            ).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(track) => {
+                info!("Posted track {} ({}) added to playlist {}", track_id, track.track.title, playlist.title);
+                db.add_posted_track(&track);
+                known.insert(track_id.clone());
+                posted += 1;
+            },
+            Err(e) if e.is::<crate::outbox::Queued>() => {
+                warn!("{}", e);
+                known.insert(track_id.clone());
+            },
+            Err(e) => match e.downcast_ref::<crate::ignore::Ignored>() {
+                Some(ignored) => db.record_ignored(ignored),
+                None => {
+                    error!("Failed to archive track {} added to playlist {}: {}", track_id, playlist.id, e);
+                    post_processing.hooks.error(&e.to_string(), None, Some(track_id));
+                    db.record_skip(track_id, SkipReason::Failed, &e.to_string(), None, None);
+                }
+            },
+        }
+    }
+
+    db.set_playlist_state(&playlist.id, PlaylistState {
+        title: playlist.title,
+        permalink_url: playlist.permalink_url,
+        track_ids: known,
+        last_polled: Utc::now(),
+    });
+    db.save()?;
+    crate::metrics::increment_total_tracks(posted as u64);
+    Ok(posted)
+}
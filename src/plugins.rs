@@ -0,0 +1,107 @@
+use std::time::Duration;
+use log::{info, debug};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::soundcloud::Track;
+
+/// External executables that can change a track's embed and attachments before it's posted
+///
+/// Each plugin gets `{"track", "embed", "files"}` as JSON on stdin and may print a JSON
+/// object with a replacement `embed` and/or `files` list. Plugins run in the order
+/// they're configured, each seeing the previous plugin's output.
+#[derive(Debug, Clone, Default)]
+pub struct Plugins {
+    commands: Vec<String>,
+    timeout: Duration,
+}
+
+/// What a plugin prints on stdout; anything left out stays unchanged
+#[derive(Deserialize)]
+struct PluginOutput {
+    embed: Option<Value>,
+    files: Option<Vec<PluginFile>>,
+}
+
+#[derive(Deserialize)]
+struct PluginFile {
+    path: String,
+    name: Option<String>,
+}
+
+impl Plugins {
+    pub fn from_config(config: &Config) -> Self {
+        Plugins {
+            commands: config.plugins.iter().filter(|c| !c.is_empty()).cloned().collect(),
+            timeout: Duration::from_secs(config.hook_timeout_sec),
+        }
+    }
+
+    /// Run every plugin over the embed and file list
+    ///
+    /// A plugin that fails, times out or prints something that isn't valid output fails
+    /// the track, so it's retried on the next poll instead of being posted unfiltered.
+    pub async fn apply(
+        &self,
+        track: &Track,
+        mut embed: Value,
+        mut files: Vec<(String, String)>
+    ) -> Result<(Value, Vec<(String, String)>), Box<dyn std::error::Error + Send + Sync>> {
+        for command in &self.commands {
+            debug!("Running plugin for track {}: {}", track.id, command);
+
+            let input = json!({
+                "track": track,
+                "embed": embed,
+                "files": files.iter().map(|(path, name)| json!({ "path": path, "name": name })).collect::<Vec<_>>(),
+            });
+            let env = vec![
+                ("ARCHIVER_HOOK_EVENT", "plugin".to_string()),
+                ("ARCHIVER_HOOK_TRACK_ID", track.id.clone()),
+            ];
+
+            let output = crate::hooks::run_command(command, &input.to_string(), env, self.timeout).await
+                .map_err(|e| format!("Plugin '{}' failed: {}", command, e))?;
+            if !output.status.success() {
+                return Err(format!("Plugin '{}' exited with {}: {}", command, output.status,
+                                   String::from_utf8_lossy(&output.stderr).trim()).into());
+            }
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if stdout.trim().is_empty() {
+                debug!("Plugin '{}' left track {} unchanged", command, track.id);
+                continue;
+            }
+
+            let result: PluginOutput = serde_json::from_str(&stdout)
+                .map_err(|e| format!("Plugin '{}' printed invalid output: {}", command, e))?;
+            if let Some(new_embed) = result.embed {
+                if !new_embed.is_object() {
+                    return Err(format!("Plugin '{}' returned an embed that isn't an object", command).into());
+                }
+                embed = new_embed;
+            }
+            if let Some(new_files) = result.files {
+                let count = files.len();
+                files = new_files.into_iter()
+                    .map(|f| {
+                        let name = f.name.unwrap_or_else(|| {
+                            std::path::Path::new(&f.path)
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| f.path.clone())
+                        });
+                        (f.path, name)
+                    })
+                    .collect();
+                if files.len() != count {
+                    info!("Plugin '{}' changed the attachments of track {} from {} to {} files",
+                          command, track.id, count, files.len());
+                }
+            }
+        }
+
+        Ok((embed, files))
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{Duration, Utc};
+use log::{info, warn, debug};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::db::TrackDatabase;
+use crate::soundcloud::TrackUser;
+use crate::soundcloud_api::SoundCloudApi;
+
+/// A track or playlist featured on a user's profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileItem {
+    pub id: String,
+    /// "track" or "playlist"
+    pub kind: String,
+    pub title: String,
+    #[serde(default)]
+    pub permalink_url: Option<String>,
+}
+
+impl ProfileItem {
+    /// Read an item from a SoundCloud collection entry
+    fn from_json(item: &Value) -> Option<Self> {
+        // Spotlight entries may wrap the track or playlist instead of being one
+        let item = item.get("track")
+            .or_else(|| item.get("playlist"))
+            .filter(|i| i.is_object())
+            .unwrap_or(item);
+        let id = match item.get("id")? {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            _ => return None,
+        };
+        Some(ProfileItem {
+            id,
+            kind: item.get("kind").and_then(|k| k.as_str()).unwrap_or("track").to_string(),
+            title: item.get("title").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+            permalink_url: item.get("permalink_url").and_then(|u| u.as_str()).map(|u| u.to_string()),
+        })
+    }
+}
+
+/// Snapshot of what a user features on their profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    pub checked_at: chrono::DateTime<Utc>,
+    /// Tracks and playlists pinned to the profile spotlight, in order
+    #[serde(default)]
+    pub spotlight: Vec<ProfileItem>,
+    /// Most played tracks, as shown under "Popular tracks"
+    #[serde(default)]
+    pub popular_tracks: Vec<ProfileItem>,
+}
+
+/// Record a user's spotlight and popular tracks if `profile_check_interval_hours` has passed
+///
+/// Posts a note to Discord when the spotlight differs from the previous snapshot. The
+/// first snapshot of a user is only recorded.
+pub async fn check_profile(
+    db: &mut TrackDatabase,
+    api: &dyn SoundCloudApi,
+    user_id: &str,
+    user: Option<&TrackUser>,
+    config: &Config
+) {
+    if config.profile_check_interval_hours == 0 {
+        return;
+    }
+    let previous = db.user_state(user_id).and_then(|s| s.profile.clone());
+    let interval = Duration::hours(config.profile_check_interval_hours as i64);
+    if previous.as_ref().is_some_and(|p| Utc::now() - p.checked_at < interval) {
+        return;
+    }
+
+    debug!("Checking the profile spotlight and popular tracks of user {}", user_id);
+    let spotlight = match api.user_spotlight(user_id).await {
+        Ok(items) => items.iter().filter_map(ProfileItem::from_json).collect::<Vec<_>>(),
+        Err(e) => {
+            warn!("Failed to fetch the spotlight of user {}: {}", user_id, e);
+            return;
+        }
+    };
+    let popular_tracks = match api.user_top_tracks(user_id).await {
+        Ok(items) => items.iter().filter_map(ProfileItem::from_json).collect(),
+        Err(e) => {
+            warn!("Failed to fetch the popular tracks of user {}: {}", user_id, e);
+            previous.as_ref().map(|p| p.popular_tracks.clone()).unwrap_or_default()
+        }
+    };
+
+    if let Some(previous) = &previous {
+        if previous.spotlight != spotlight {
+            info!("User {} changed their spotlight ({} items, was {})", user_id, spotlight.len(), previous.spotlight.len());
+            if let Err(e) = crate::discord::send_spotlight_change(&config.discord_webhook_url, user_id, user, &previous.spotlight, &spotlight).await {
+                // Keep the old snapshot so the change is posted on the next check
+                warn!("Failed to post the spotlight change of user {}: {}", user_id, e);
+                return;
+            }
+        }
+    }
+
+    db.user_state_mut(user_id).profile = Some(ProfileSnapshot {
+        checked_at: Utc::now(),
+        spotlight,
+        popular_tracks,
+    });
+}
@@ -0,0 +1,50 @@
+use log::warn;
+
+use crate::config::Config;
+use crate::loghandler::BoxedLayer;
+
+#[cfg(feature = "sentry")]
+static SENTRY_GUARD: std::sync::OnceLock<sentry::ClientInitGuard> = std::sync::OnceLock::new();
+
+/// Build the layer that reports errors to Sentry, if `sentry_dsn` is configured
+///
+/// Every error-level log line becomes a Sentry event, with the fields of the spans it
+/// was logged in (such as `poll_user:user_id` and `track:track_id`) attached, and
+/// lower-level lines are kept as breadcrumbs. Panics are reported too.
+/// Requires the `sentry` cargo feature; otherwise this only warns if a DSN is set.
+pub fn layer(config: &Config) -> Result<Option<BoxedLayer>, Box<dyn std::error::Error + Send + Sync>> {
+    if config.sentry_dsn.as_deref().unwrap_or("").is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "sentry")]
+    {
+        use tracing_subscriber::Layer;
+
+        let mut options = sentry::ClientOptions::default();
+        options.dsn = config.sentry_dsn.as_deref().map(str::parse).transpose()?;
+        options.release = sentry::release_name!();
+        options.environment = config.sentry_environment.clone().map(Into::into);
+        let guard = sentry::init(options);
+        let _ = SENTRY_GUARD.set(guard);
+
+        log::info!("Reporting errors to Sentry");
+        Ok(Some(sentry::integrations::tracing::layer().boxed()))
+    }
+
+    #[cfg(not(feature = "sentry"))]
+    {
+        warn!("sentry_dsn is set but this build doesn't include the 'sentry' feature, errors will not be reported");
+        Ok(None)
+    }
+}
+
+/// Send any queued Sentry events
+pub fn shutdown() {
+    #[cfg(feature = "sentry")]
+    if let Some(client) = SENTRY_GUARD.get().and_then(|_| sentry::Hub::current().client()) {
+        if !client.flush(Some(std::time::Duration::from_secs(5))) {
+            warn!("Timed out sending queued Sentry events");
+        }
+    }
+}
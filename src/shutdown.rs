@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use log::info;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+// Set once a shutdown signal has been received
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+// When the shutdown was requested, used as the start of the drain period
+static REQUESTED_AT: OnceLock<Instant> = OnceLock::new();
+static SHUTDOWN_NOTIFY: Notify = Notify::const_new();
+
+/// Request a graceful shutdown
+///
+/// No new polls or track processing are started after this, but work that's
+/// already in flight is allowed to finish within the drain timeout.
+pub fn request() {
+    let _ = REQUESTED_AT.set(Instant::now());
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+    SHUTDOWN_NOTIFY.notify_waiters();
+}
+
+/// Check whether a shutdown has been requested
+pub fn is_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Wait until a shutdown is requested
+pub async fn requested() {
+    loop {
+        // Register interest before checking the flag so a request can't be missed
+        let notified = SHUTDOWN_NOTIFY.notified();
+        if is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Wait until a shutdown has been requested and the drain timeout has elapsed since
+pub async fn drain_expired(drain_timeout: Duration) {
+    requested().await;
+    let requested_at = REQUESTED_AT.get().copied().unwrap_or_else(Instant::now);
+    tokio::time::sleep_until(requested_at + drain_timeout).await;
+}
+
+/// Spawn a listener that requests a graceful shutdown on SIGINT/SIGTERM (Ctrl+C on Windows)
+pub fn spawn_signal_listener() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigint = signal(SignalKind::interrupt())
+            .expect("Failed to set up SIGINT handler");
+        let mut sigterm = signal(SignalKind::terminate())
+            .expect("Failed to set up SIGTERM handler");
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = sigint.recv() => info!("Received SIGINT signal"),
+                _ = sigterm.recv() => info!("Received SIGTERM signal"),
+            }
+            request();
+        });
+    }
+
+    #[cfg(not(unix))]
+    tokio::spawn(async move {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => info!("Received Ctrl+C signal"),
+            Err(e) => log::error!("Error handling Ctrl+C signal: {}", e),
+        }
+        request();
+    });
+}
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use log::{info, warn, error, debug};
 use regex::Regex;
 use reqwest::Client;
@@ -7,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::time::sleep;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::metrics::Service;
 
 // Global client ID cache
 lazy_static::lazy_static! {
@@ -18,8 +21,480 @@ lazy_static::lazy_static! {
         .unwrap();
     static ref SCRIPT_REGEX: Regex = Regex::new(r#"<script crossorigin src="(https://a-v2\.sndcdn\.com/assets/[^"]+)"></script>"#).unwrap();
     static ref CLIENT_ID_REGEX: Regex = Regex::new(r#"client_id:"([^"]+)"#).unwrap();
+    static ref OG_IMAGE_REGEX: Regex = Regex::new(r#"<meta[^>]+property="og:image"[^>]+content="([^"]+)""#).unwrap();
+    // Resolved CDN URLs by transcoding URL, with when they were resolved
+    static ref STREAM_URL_CACHE: Mutex<HashMap<String, (String, Instant)>> = Mutex::new(HashMap::new());
+    // When the client ID was last generated, so scheduled refreshes count from the latest one
+    static ref CLIENT_ID_REFRESHED: Mutex<Option<Instant>> = Mutex::new(None);
+    // Since when requests have been failing because of the client ID, and the latest failure
+    static ref CLIENT_ID_FAILING: Mutex<Option<(Instant, String)>> = Mutex::new(None);
+    // Shared budget for API requests, if a rate limit is set
+    static ref API_RATE_LIMIT: Mutex<Option<TokenBucket>> = Mutex::new(None);
+    // How failed API requests are retried, unless a call overrides it
+    static ref API_RETRY: Mutex<ApiRetry> = Mutex::new(ApiRetry::default());
+    // Users' track listings by user ID, with the validators to ask SoundCloud whether they changed
+    static ref LISTING_CACHE: Mutex<HashMap<String, CachedListing>> = Mutex::new(HashMap::new());
+    // Users' profile details by user ID, with when they were fetched
+    static ref USER_CACHE: Mutex<HashMap<String, (Value, Instant)>> = Mutex::new(HashMap::new());
+    // Users' profile banners by user ID (`None` if they have none), with when they were looked up
+    static ref BANNER_CACHE: Mutex<HashMap<String, (Option<String>, Instant)>> = Mutex::new(HashMap::new());
 }
 
+const API_BASE_URL: &str = "https://api-v2.soundcloud.com";
+
+/// How soon a failed scheduled client ID refresh is tried again
+const CLIENT_ID_REFRESH_RETRY: Duration = Duration::from_secs(600);
+
+/// How often the client ID monitor checks whether the client ID works
+const CLIENT_ID_MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a resolved stream URL is reused (CDN URLs stay valid for a while longer)
+const STREAM_URL_TTL: Duration = Duration::from_secs(300);
+
+/// Most tracks SoundCloud returns for one `/tracks?ids=` request
+pub const TRACK_BATCH_SIZE: usize = 50;
+
+/// Most users whose track listing or profile details are kept
+const LISTING_CACHE_SIZE: usize = 2000;
+
+/// How long a user's banner is reused for embeds, even with the user cache off
+const BANNER_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+// Whether user track listings are fetched with If-None-Match / If-Modified-Since
+static CONDITIONAL_REQUESTS: AtomicBool = AtomicBool::new(false);
+
+// How long cached profile details are used for, in seconds (0 = not cached)
+static USER_CACHE_TTL: AtomicU64 = AtomicU64::new(0);
+
+// Whether the cached client ID is believed to be valid (cleared while a refresh is pending or after it fails)
+static CLIENT_ID_VALID: AtomicBool = AtomicBool::new(false);
+
+/// Token bucket spreading API requests out to a steady rate, with short bursts allowed
+#[derive(Debug)]
+struct TokenBucket {
+    per_second: f64,
+    capacity: f64,
+    /// Tokens left; negative when requests are already waiting for later tokens
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Limit requests to the SoundCloud API to `requests_per_minute` across the whole process
+///
+/// Up to ten seconds' worth of requests can go out at once after a quiet period. 0 removes
+/// the limit. Applies to every API call (tracks, likes, followings, resolving URLs and
+/// stream URLs), not to audio and artwork downloads from the CDN.
+pub fn set_rate_limit(requests_per_minute: u64) {
+    let bucket = (requests_per_minute > 0).then(|| {
+        let per_second = requests_per_minute as f64 / 60.0;
+        let capacity = (per_second * 10.0).max(1.0);
+        TokenBucket { per_second, capacity, tokens: capacity, updated: Instant::now() }
+    });
+    *API_RATE_LIMIT.lock().unwrap() = bucket;
+    if requests_per_minute > 0 {
+        info!("Limiting SoundCloud API requests to {} per minute", requests_per_minute);
+    }
+}
+
+/// Wait until an API request fits in the rate limit
+///
+/// Takes its token before returning, so concurrent callers queue up behind each other
+/// instead of all waking for the same token.
+async fn wait_for_api_slot() {
+    let wait = {
+        let mut limit = API_RATE_LIMIT.lock().unwrap();
+        let Some(bucket) = limit.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * bucket.per_second;
+        bucket.tokens = (bucket.tokens + refill).min(bucket.capacity) - 1.0;
+        bucket.updated = now;
+        if bucket.tokens >= 0.0 {
+            return;
+        }
+        Duration::from_secs_f64(-bucket.tokens / bucket.per_second)
+    };
+    debug!("SoundCloud API rate limit reached, waiting {:.1}s before the next request", wait.as_secs_f64());
+    sleep(wait).await;
+}
+
+/// Send a GET request to the SoundCloud API once the rate limit allows it
+///
+/// With `validators`, the request is conditional and SoundCloud can answer 304 Not Modified.
+async fn api_get(url: reqwest::Url, validators: Option<&Validators>) -> reqwest::Result<reqwest::Response> {
+    wait_for_api_slot().await;
+    let mut request = HTTP_CLIENT.get(url);
+    if let Some(validators) = validators {
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    request.send().await
+}
+
+/// What a response said about its version, to ask for it conditionally next time
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    /// Read `ETag` and `Last-Modified` from a response, if it has either
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Option<Self> {
+        let header = |name| headers.get(name).and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok()).map(|v| v.to_string());
+        let validators = Validators {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
+        (validators.etag.is_some() || validators.last_modified.is_some()).then_some(validators)
+    }
+}
+
+/// Result of a conditional request
+enum Conditional<T> {
+    /// The resource changed, or there was nothing to compare it to
+    Modified(T, Option<Validators>),
+    /// SoundCloud answered 304, so the cached copy is current
+    NotModified,
+}
+
+/// What a user's profile says about their tracks, compared between polls
+#[derive(Debug, Clone, PartialEq)]
+struct ProfileSnapshot {
+    track_count: u64,
+    last_modified: String,
+}
+
+impl ProfileSnapshot {
+    fn from_details(details: &Value) -> Option<Self> {
+        Some(ProfileSnapshot {
+            track_count: details.get("track_count")?.as_u64()?,
+            last_modified: details.get("last_modified")?.as_str()?.to_string(),
+        })
+    }
+}
+
+/// A user's track listing as last fetched, reused when SoundCloud says it hasn't changed
+#[derive(Debug, Clone)]
+struct CachedListing {
+    /// Only kept with conditional requests enabled
+    validators: Option<Validators>,
+    /// The user's profile when the listing was fetched
+    profile: Option<ProfileSnapshot>,
+    /// Limit and page size the listing was fetched with, as a different first page
+    /// can't be compared
+    limit: usize,
+    page_size: usize,
+    tracks: Vec<Track>,
+    stored: Instant,
+}
+
+/// Enable or disable conditional requests for users' track listings
+///
+/// When enabled, the ETag or Last-Modified of the first page of each user's tracks is kept
+/// with the parsed listing, and sent with the next poll. A 304 Not Modified then returns the
+/// kept listing without fetching or parsing any more pages. Disabling drops the kept listings,
+/// unless the user cache keeps them (see [`set_user_cache_ttl`]).
+pub fn set_conditional_requests(enabled: bool) {
+    CONDITIONAL_REQUESTS.store(enabled, Ordering::Relaxed);
+    if !listing_cache_enabled() {
+        LISTING_CACHE.lock().unwrap().clear();
+    }
+}
+
+/// Set how long users' profile details are cached
+///
+/// Within this time a user whose track count and last-modified date haven't changed since
+/// their listing was fetched gets the kept listing without a tracks request, and profile
+/// banners are looked up from the cache. Once it's up, the listing is fetched again to catch
+/// edits the profile doesn't reflect. Zero turns the cache off.
+pub fn set_user_cache_ttl(ttl: Duration) {
+    USER_CACHE_TTL.store(ttl.as_secs(), Ordering::Relaxed);
+    if ttl.is_zero() {
+        USER_CACHE.lock().unwrap().clear();
+        if !listing_cache_enabled() {
+            LISTING_CACHE.lock().unwrap().clear();
+        }
+    }
+}
+
+fn user_cache_ttl() -> Duration {
+    Duration::from_secs(USER_CACHE_TTL.load(Ordering::Relaxed))
+}
+
+/// Whether users' track listings are kept, for conditional requests or the profile check
+fn listing_cache_enabled() -> bool {
+    CONDITIONAL_REQUESTS.load(Ordering::Relaxed) || !user_cache_ttl().is_zero()
+}
+
+/// The kept listing of a user's tracks, if it was fetched the same way
+fn cached_listing(user_id: &str, limit: usize, page_size: usize) -> Option<CachedListing> {
+    if !listing_cache_enabled() {
+        return None;
+    }
+    LISTING_CACHE.lock().unwrap().get(user_id)
+        .filter(|cached| cached.limit == limit && cached.page_size == page_size)
+        .cloned()
+}
+
+/// Keep a user's track listing for the next conditional request, dropping the oldest
+/// listing when the cache is full
+fn store_listing(user_id: &str, listing: CachedListing) {
+    if !listing_cache_enabled() {
+        return;
+    }
+    let mut cache = LISTING_CACHE.lock().unwrap();
+    if cache.len() >= LISTING_CACHE_SIZE && !cache.contains_key(user_id) {
+        let oldest = cache.iter().min_by_key(|(_, cached)| cached.stored).map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(user_id.to_string(), listing);
+}
+
+/// How often a failed SoundCloud API request is retried
+#[derive(Debug, Clone, Copy)]
+pub struct ApiRetry {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further one
+    pub base_delay: Duration,
+    /// Longest delay between two attempts, also for a `Retry-After` asking for longer
+    pub max_delay: Duration,
+}
+
+impl Default for ApiRetry {
+    fn default() -> Self {
+        ApiRetry { retries: 2, base_delay: Duration::from_millis(2000), max_delay: Duration::from_secs(30) }
+    }
+}
+
+impl ApiRetry {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        ApiRetry {
+            retries: config.soundcloud_retries.min(u32::MAX as u64) as u32,
+            base_delay: Duration::from_millis(config.soundcloud_retry_base_ms),
+            max_delay: Duration::from_millis(config.soundcloud_retry_max_ms),
+        }
+    }
+
+    /// The same policy with a different number of retries
+    pub fn with_retries(self, retries: u32) -> Self {
+        ApiRetry { retries, ..self }
+    }
+
+    /// Delay before the given retry (starting at 1), with 50% jitter either way so
+    /// requests that failed together don't all retry at the same moment
+    ///
+    /// A `Retry-After` from the failed response is waited out instead, up to `max_delay`.
+    fn delay(&self, retry: u32, retry_after: Option<&str>) -> Duration {
+        let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+        self.delay_at(retry, retry_after, chrono::Utc::now(), jitter)
+    }
+
+    /// [`ApiRetry::delay`] at `now`, with `jitter` from 0 (half the backoff) to 1 (one and a
+    /// half times it)
+    fn delay_at(&self, retry: u32, retry_after: Option<&str>, now: chrono::DateTime<chrono::Utc>, jitter: f64) -> Duration {
+        if let Some(delay) = retry_after.and_then(|v| parse_retry_after(v, now)) {
+            return delay.min(self.max_delay);
+        }
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        backoff.mul_f64(0.5 + jitter.clamp(0.0, 1.0)).min(self.max_delay)
+    }
+}
+
+/// How long a `Retry-After` header asks to wait from `now`, given in seconds or as an HTTP date
+///
+/// A date in the past means retrying right away.
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.to_utc() - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Set how failed API requests are retried across the whole process
+pub fn set_api_retry(retry: ApiRetry) {
+    *API_RETRY.lock().unwrap() = retry;
+}
+
+/// How failed API requests are retried, as set with [`set_api_retry`]
+fn api_retry() -> ApiRetry {
+    *API_RETRY.lock().unwrap()
+}
+
+/// Fetch and parse JSON from the API, retrying failures as set in `retry`
+///
+/// `url` builds the request for the current client ID; after a 401 or 403 the client ID
+/// is refreshed and the request built again. `on_status` gets the status of every failed
+/// response, and whether the client ID was refreshed already, and can end the request with
+/// a result of its own. Network errors, other error statuses and unparseable responses
+/// are retried after a growing, jittered delay, or after as long as `Retry-After` asks.
+async fn retry_request<T: serde::de::DeserializeOwned>(
+    what: &str,
+    retry: ApiRetry,
+    url: impl Fn(&str) -> Result<reqwest::Url, Box<dyn std::error::Error + Send + Sync>>,
+    on_status: impl Fn(reqwest::StatusCode, bool) -> Option<Result<T, Box<dyn std::error::Error + Send + Sync>>>
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    match conditional_request(what, retry, None, url, on_status).await? {
+        Conditional::Modified(result, _) => Ok(result),
+        Conditional::NotModified => Err(format!("Unexpected 304 Not Modified for {}", what).into()),
+    }
+}
+
+/// [`retry_request`], sending `validators` along so SoundCloud can answer 304 Not Modified
+///
+/// Successful responses come with their own validators, if they have any.
+async fn conditional_request<T: serde::de::DeserializeOwned>(
+    what: &str,
+    retry: ApiRetry,
+    validators: Option<&Validators>,
+    url: impl Fn(&str) -> Result<reqwest::Url, Box<dyn std::error::Error + Send + Sync>>,
+    on_status: impl Fn(reqwest::StatusCode, bool) -> Option<Result<T, Box<dyn std::error::Error + Send + Sync>>>
+) -> Result<Conditional<T>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut client_id = match get_client_id() {
+        Some(id) => id,
+        None => refresh_client_id().await?,
+    };
+    let mut refreshed = false;
+    let mut retry_after = None;
+    let attempts = retry.retries.saturating_add(1);
+    
+    for attempt in 1..=attempts {
+        if attempt > 1 {
+            let delay = retry.delay(attempt - 1, retry_after.take().as_deref());
+            debug!("Retrying {} in {:.1}s (attempt {}/{})", what, delay.as_secs_f64(), attempt, attempts);
+            sleep(delay).await;
+        }
+        
+        let response = match api_get(url(&client_id)?, validators).await {
+            Ok(res) => res,
+            Err(e) => {
+                crate::metrics::record_api_error(Service::SoundCloud, None);
+                warn!("Network error when fetching {}: {}", what, e);
+                continue;
+            }
+        };
+        
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED && validators.is_some() {
+            record_client_id_working();
+            return Ok(Conditional::NotModified);
+        }
+        if !status.is_success() {
+            match on_status(status, refreshed) {
+                Some(Ok(result)) => return Ok(Conditional::Modified(result, None)),
+                Some(Err(e)) => {
+                    crate::metrics::record_api_error(Service::SoundCloud, Some(status.as_u16()));
+                    return Err(e);
+                },
+                None => crate::metrics::record_api_error(Service::SoundCloud, Some(status.as_u16())),
+            }
+            // Check for auth error and refresh client ID
+            if status.as_u16() == 401 || status.as_u16() == 403 {
+                warn!("Auth error ({}), refreshing client ID", status);
+                crate::metrics::increment_client_id_rejections();
+                record_client_id_failure(&format!("HTTP {} when fetching {}", status, what));
+                client_id = refresh_client_id().await?;
+                refreshed = true;
+                continue;
+            }
+            
+            retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+            warn!("API error: HTTP {} when fetching {}", status, what);
+            continue;
+        }
+        
+        record_client_id_working();
+        let response_validators = Validators::from_headers(response.headers());
+        match response.json::<T>().await {
+            Ok(json) => return Ok(Conditional::Modified(json, response_validators)),
+            Err(e) => warn!("JSON parse error for {}: {}", what, e),
+        }
+    }
+    
+    Err(format!("Failed to fetch {} after {} attempts", what, attempts).into())
+}
+
+/// Build an API v2 URL from path segments and query parameters, percent-encoding both
+fn api_url(segments: &[&str], params: &[(&str, &str)]) -> reqwest::Url {
+    let mut url = reqwest::Url::parse(API_BASE_URL).expect("API base URL is valid");
+    url.path_segments_mut().expect("API base URL can have a path").extend(segments);
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+    url
+}
+
+/// Add the client ID to a URL the API handed out (`next_href`, transcodings), keeping its
+/// own query parameters
+fn with_client_id(url: &str, client_id: &str) -> Result<reqwest::Url, Box<dyn std::error::Error + Send + Sync>> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| format!("Invalid API URL {}: {}", url, e))?;
+    url.query_pairs_mut().append_pair("client_id", client_id);
+    Ok(url)
+}
+
+/// Add a private track's secret token to one of its API URLs (such as a transcoding), unless
+/// it's already there
+pub fn with_secret_token(url: &str, secret_token: Option<&str>) -> String {
+    let Some(token) = secret_token else {
+        return url.to_string();
+    };
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if !parsed.query_pairs().any(|(key, _)| key == "secret_token") => {
+            parsed.query_pairs_mut().append_pair("secret_token", token);
+            parsed.to_string()
+        },
+        _ => url.to_string(),
+    }
+}
+
+/// The secret token in a private track's share link, either as `?secret_token=s-...` or as
+/// the third path segment (`soundcloud.com/artist/track/s-...`)
+///
+/// A public track whose own slug starts with `s-` (`soundcloud.com/artist/s-club-remix`)
+/// has no token.
+pub fn secret_token_from_url(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    if let Some((_, token)) = parsed.query_pairs().find(|(key, _)| key == "secret_token") {
+        return Some(token.into_owned()).filter(|t| !t.is_empty());
+    }
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+    match segments.as_slice() {
+        [_artist, track, token] if *track != "sets" && token.starts_with("s-") && token.len() > 2 => Some(token.to_string()),
+        _ => None,
+    }
+}
+
+/// SoundCloud says a user doesn't exist (404), or refuses to show them even with a fresh client ID (403)
+///
+/// Usually means the account was deleted, deactivated or banned.
+#[derive(Debug)]
+pub struct UserUnavailable {
+    pub user_id: String,
+    pub status: u16,
+}
+
+impl std::fmt::Display for UserUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "User {} is unavailable on SoundCloud (HTTP {})", self.user_id, self.status)
+    }
+}
+
+impl std::error::Error for UserUnavailable {}
+
 /// Track metadata returned from the SoundCloud API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Track {
@@ -44,7 +519,13 @@ pub struct Track {
     pub genre: Option<String>,
     pub tag_list: Option<String>,
     pub downloadable: Option<bool>,
-    // Raw JSON data
+    /// Token that grants access to a private track, from its share link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<String>,
+    /// The waveform SoundCloud shows for the track (only populated by get_track_details)
+    #[serde(skip)]
+    pub waveform: Option<crate::waveform::Waveform>,
+    // Raw JSON data (only populated by get_track_details, listings leave it empty to save memory)
     #[serde(skip)]
     pub raw_data: Option<Value>,
 }
@@ -65,6 +546,106 @@ pub struct Like {
     pub track: Track,
 }
 
+impl Like {
+    /// When the track was liked, if SoundCloud said
+    pub fn liked_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.created_at).ok().map(|d| d.to_utc())
+    }
+}
+
+/// A page of a paginated SoundCloud API listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiCollection<T> {
+    pub collection: Vec<T>,
+    /// The next page, without the client ID
+    pub next_href: Option<String>,
+}
+
+/// A user as the SoundCloud API returns it, on its own or as a track's uploader
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiUser {
+    pub id: Option<u64>,
+    pub username: Option<String>,
+    pub permalink_url: Option<String>,
+    pub avatar_url: Option<String>,
+    pub track_count: Option<u64>,
+    pub followers_count: Option<u64>,
+}
+
+impl From<ApiUser> for TrackUser {
+    fn from(user: ApiUser) -> Self {
+        TrackUser {
+            id: user.id.map(|id| id.to_string()).unwrap_or_default(),
+            username: user.username.unwrap_or_else(|| "Unknown Artist".to_string()),
+            permalink_url: user.permalink_url.unwrap_or_default(),
+            avatar_url: user.avatar_url.as_deref().map(get_original_artwork_url),
+        }
+    }
+}
+
+/// A track as the SoundCloud API returns it
+///
+/// Only the fields the archiver uses are read; the full JSON is kept in [`Track::raw_data`]
+/// by [`get_track_details`] alone.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiTrack {
+    pub id: Option<u64>,
+    pub title: Option<String>,
+    pub permalink_url: Option<String>,
+    pub artwork_url: Option<String>,
+    pub description: Option<String>,
+    pub user: Option<ApiUser>,
+    pub created_at: Option<String>,
+    pub duration: Option<u64>,
+    pub stream_url: Option<String>,
+    pub download_url: Option<String>,
+    pub playback_count: Option<u64>,
+    pub likes_count: Option<u64>,
+    pub reposts_count: Option<u64>,
+    pub comment_count: Option<u64>,
+    pub genre: Option<String>,
+    pub tag_list: Option<String>,
+    pub downloadable: Option<bool>,
+    pub secret_token: Option<String>,
+}
+
+impl ApiTrack {
+    /// Convert to a [`Track`], or `None` if the API left out the track's ID
+    pub fn into_track(self) -> Option<Track> {
+        Some(Track {
+            id: self.id?.to_string(),
+            title: self.title.unwrap_or_else(|| "Untitled".to_string()),
+            permalink_url: self.permalink_url.unwrap_or_default(),
+            artwork_url: self.artwork_url,
+            description: self.description,
+            user: self.user.unwrap_or_default().into(),
+            created_at: self.created_at.unwrap_or_default(),
+            duration: self.duration.unwrap_or(0),
+            stream_url: self.stream_url,
+            hls_url: None, // Will be populated when needed
+            download_url: self.download_url,
+            playback_count: self.playback_count,
+            likes_count: self.likes_count,
+            reposts_count: self.reposts_count,
+            comment_count: self.comment_count,
+            genre: self.genre,
+            tag_list: self.tag_list,
+            downloadable: self.downloadable,
+            secret_token: self.secret_token,
+            waveform: None,
+            raw_data: None, // Only attached for new tracks by get_track_details
+        })
+    }
+}
+
+/// An entry of a user's likes, which is either a track or a playlist
+#[derive(Debug, Clone, Deserialize)]
+struct ApiLike {
+    created_at: Option<String>,
+    kind: Option<String>,
+    track: Option<ApiTrack>,
+}
+
 /// Initialize the SoundCloud client
 pub async fn initialize() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Generate the initial client ID
@@ -74,6 +655,8 @@ pub async fn initialize() -> Result<(), Box<dyn std::error::Error + Send + Sync>
     // Store it in the global cache
     let mut client_id = CLIENT_ID.lock().unwrap();
     *client_id = Some(initial_id.clone());
+    CLIENT_ID_VALID.store(true, Ordering::Relaxed);
+    *CLIENT_ID_REFRESHED.lock().unwrap() = Some(Instant::now());
     
     info!("Generated initial SoundCloud client ID: {}", initial_id);
     Ok(())
@@ -85,15 +668,58 @@ pub fn get_client_id() -> Option<String> {
     client_id.clone()
 }
 
+/// Check whether we have a client ID that hasn't been rejected by the API
+pub fn client_id_valid() -> bool {
+    get_client_id().is_some() && CLIENT_ID_VALID.load(Ordering::Relaxed)
+}
+
+/// How long ago the current client ID was generated
+pub fn client_id_age() -> Option<Duration> {
+    CLIENT_ID_REFRESHED.lock().unwrap().map(|at| at.elapsed())
+}
+
+/// How long requests have been failing because of the client ID, and the latest failure,
+/// or `None` if the last request that got an answer went through
+///
+/// A rejected ID that's replaced right away recovers with the next request; this only
+/// grows while SoundCloud keeps rejecting new IDs, or no new ID can be scraped at all.
+pub fn client_id_failing() -> Option<(Duration, String)> {
+    CLIENT_ID_FAILING.lock().unwrap().as_ref().map(|(since, error)| (since.elapsed(), error.clone()))
+}
+
+/// Note a request that failed because of the client ID
+fn record_client_id_failure(error: &str) {
+    let mut failing = CLIENT_ID_FAILING.lock().unwrap();
+    let since = failing.as_ref().map(|(since, _)| *since).unwrap_or_else(Instant::now);
+    *failing = Some((since, error.to_string()));
+}
+
+/// Note a request that the client ID got through
+fn record_client_id_working() {
+    CLIENT_ID_FAILING.lock().unwrap().take();
+}
+
 /// Refresh the SoundCloud client ID
 pub async fn refresh_client_id() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let new_id = generate_client_id().await?;
+    // A refresh means the current ID was rejected (or missing); it stays invalid until we get a new one
+    CLIENT_ID_VALID.store(false, Ordering::Relaxed);
+    let new_id = match generate_client_id().await {
+        Ok(id) => id,
+        Err(e) => {
+            crate::metrics::record_client_id_refresh(false);
+            record_client_id_failure(&format!("Client ID refresh failed: {}", e));
+            return Err(e);
+        }
+    };
+    crate::metrics::record_client_id_refresh(true);
     
     // Update the global cache
     {
         let old_id = get_client_id();
         let mut client_id = CLIENT_ID.lock().unwrap();
         *client_id = Some(new_id.clone());
+        CLIENT_ID_VALID.store(true, Ordering::Relaxed);
+        *CLIENT_ID_REFRESHED.lock().unwrap() = Some(Instant::now());
         
         if let Some(old) = old_id {
             info!("Refreshed SoundCloud client ID: {} -> {}", old, new_id);
@@ -105,6 +731,96 @@ pub async fn refresh_client_id() -> Result<String, Box<dyn std::error::Error + S
     Ok(new_id)
 }
 
+/// Spawn a task that replaces the client ID before it expires, every `interval`
+///
+/// The interval counts from the latest refresh, so an ID that was just replaced after
+/// being rejected isn't refreshed again right away. Unlike [`refresh_client_id`], the
+/// current ID stays in use while the new one is fetched, and is kept if that fails.
+/// Stops when a shutdown is requested; does nothing if `interval` is zero.
+pub fn spawn_client_id_refresher(interval: Duration) {
+    if interval.is_zero() {
+        return;
+    }
+    info!("Refreshing the SoundCloud client ID every {} hours", interval.as_secs_f64() / 3600.0);
+    tokio::spawn(async move {
+        let mut wait = interval;
+        loop {
+            tokio::select! {
+                _ = sleep(wait) => {},
+                _ = crate::shutdown::requested() => return,
+            }
+
+            // Wait out the rest of the interval if the ID was refreshed in the meantime
+            let age = CLIENT_ID_REFRESHED.lock().unwrap().map(|at| at.elapsed()).unwrap_or(interval);
+            if age < interval {
+                wait = interval - age;
+                continue;
+            }
+
+            debug!("Client ID is {} minutes old, refreshing it", age.as_secs() / 60);
+            match generate_client_id().await {
+                Ok(new_id) => {
+                    crate::metrics::record_client_id_refresh(true);
+                    let old_id = CLIENT_ID.lock().unwrap().replace(new_id.clone());
+                    CLIENT_ID_VALID.store(true, Ordering::Relaxed);
+                    *CLIENT_ID_REFRESHED.lock().unwrap() = Some(Instant::now());
+                    info!("Refreshed SoundCloud client ID on schedule: {} -> {}", old_id.unwrap_or_default(), new_id);
+                    wait = interval;
+                },
+                Err(e) => {
+                    crate::metrics::record_client_id_refresh(false);
+                    warn!("Scheduled client ID refresh failed, keeping the current one: {}", e);
+                    wait = CLIENT_ID_REFRESH_RETRY.min(interval);
+                }
+            }
+        }
+    });
+}
+
+/// Spawn a task that raises an alert when requests have been failing because of the
+/// client ID for `alert_after`, e.g. because SoundCloud changed its site and scraping broke
+///
+/// The alert is logged as an error and, with an `alert_webhook_url`, posted there, once per
+/// outage; a note follows when requests go through again. Stops when a shutdown is
+/// requested; does nothing if `alert_after` is zero.
+pub fn spawn_client_id_monitor(alert_after: Duration, alert_webhook_url: Option<String>) {
+    if alert_after.is_zero() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut alerted = false;
+        loop {
+            tokio::select! {
+                _ = sleep(CLIENT_ID_MONITOR_INTERVAL) => {},
+                _ = crate::shutdown::requested() => return,
+            }
+
+            let description = match client_id_failing() {
+                Some((failing_for, error)) if !alerted && failing_for >= alert_after => {
+                    let description = format!("SoundCloud requests have been failing because of the client ID for {} minutes, nothing can be archived until it works again. Latest error: {}",
+                                              failing_for.as_secs() / 60, error);
+                    error!("{}", description);
+                    alerted = true;
+                    description
+                },
+                None if alerted => {
+                    let description = "SoundCloud requests go through with the client ID again".to_string();
+                    info!("{}", description);
+                    alerted = false;
+                    description
+                },
+                _ => continue,
+            };
+
+            if let Some(webhook_url) = &alert_webhook_url {
+                if let Err(e) = crate::discord::send_client_id_notice(webhook_url, alerted, &description).await {
+                    warn!("Failed to post the client ID status to the alert webhook: {}", e);
+                }
+            }
+        }
+    });
+}
+
 /// Generate a new SoundCloud client ID by scraping the website
 async fn generate_client_id() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let client = &HTTP_CLIENT;
@@ -141,6 +857,7 @@ async fn generate_client_id() -> Result<String, Box<dyn std::error::Error + Send
             {
                 Ok(res) => {
                     if !res.status().is_success() {
+                        crate::metrics::record_api_error(Service::SoundCloud, Some(res.status().as_u16()));
                         warn!("Script fetch returned status {}: {}", res.status(), script_url.as_str());
                         continue;
                     }
@@ -166,13 +883,25 @@ async fn generate_client_id() -> Result<String, Box<dyn std::error::Error + Send
     Err("Could not find client_id in any script".into())
 }
 
+/// A user's uploads as returned by [`get_user_tracks`]
+#[derive(Debug, Clone, Default)]
+pub struct UserTracks {
+    pub tracks: Vec<Track>,
+    /// The tracks were reused from an earlier fetch because the user's profile didn't
+    /// change, so their metadata and stats may be up to `user_cache_ttl_sec` old
+    pub reused: bool,
+}
+
 /// Get tracks for a SoundCloud user
+///
+/// Follows `next_href` through pages of `pagination_size` tracks until `limit` tracks
+/// have been fetched or the user has no more.
+#[tracing::instrument(name = "fetch_user_tracks", skip_all, fields(user_id = %user_id))]
 pub async fn get_user_tracks(
     user_id: &str, 
     limit: usize,
-    _pagination_size: usize, // Keep parameter for backward compatibility
-) -> Result<Vec<Track>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
+    pagination_size: usize,
+) -> Result<UserTracks, Box<dyn std::error::Error + Send + Sync>> {
     let mut tracks = Vec::new();
     let mut seen_track_ids = std::collections::HashSet::new();
     
@@ -180,8 +909,11 @@ pub async fn get_user_tracks(
     let user_data = match get_user_details(user_id).await {
         Ok(data) => data,
         Err(e) => {
+            if e.is::<UserUnavailable>() {
+                return Err(e);
+            }
             warn!("Failed to get user details for {}: {}. Using configured limit.", user_id, e);
-            return Ok(Vec::new());
+            return Ok(UserTracks::default());
         }
     };
     
@@ -189,7 +921,7 @@ pub async fn get_user_tracks(
         Some(count) => count as usize,
         None => {
             warn!("Could not determine track count for user {}, using configured limit", user_id);
-            return Ok(Vec::new());
+            return Ok(UserTracks::default());
         }
     };
     
@@ -199,399 +931,369 @@ pub async fn get_user_tracks(
     let effective_limit = limit;
     info!("Will fetch up to {} tracks", effective_limit);
     
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => {
-            debug!("Using cached client ID: {}", id);
-            id
-        },
-        None => {
-            debug!("No cached client ID, generating new one");
-            refresh_client_id().await?
-        },
-    };
-    
-    // Try to fetch all tracks in one go with a large limit
-    let url = format!(
-        "https://api-v2.soundcloud.com/users/{}/tracks?client_id={}&limit={}&linked_partitioning=1",
-        user_id, client_id, effective_limit
-    );
-    
-    debug!("Attempting to fetch all {} tracks in one request", effective_limit);
+    // Walk the pages until we have enough tracks or SoundCloud runs out of them
+    let page_size = pagination_size.clamp(1, 200).min(effective_limit.max(1));
+    let mut next_url = Some(api_url(
+        &["users", user_id, "tracks"],
+        &[("limit", &page_size.to_string()), ("linked_partitioning", "1")]
+    ).to_string());
+    let mut pages = 0;
+    let cached = cached_listing(user_id, effective_limit, page_size);
+    let profile = ProfileSnapshot::from_details(&user_data);
+    
+    // The same track count and profile date as when the listing was fetched suggest nothing
+    // was uploaded or deleted since, so the tracks aren't asked for at all. Edits and play
+    // counts don't show on the profile, so the kept tracks may be behind on those and are
+    // marked as reused.
+    if let (Some(cached), Some(profile)) = (&cached, &profile) {
+        if cached.profile.as_ref() == Some(profile) && cached.stored.elapsed() < user_cache_ttl() {
+            info!("Profile of user {} is unchanged since their tracks were fetched, reusing {} tracks", user_id, cached.tracks.len());
+            crate::metrics::increment_listings_skipped();
+            return Ok(UserTracks { tracks: cached.tracks.clone(), reused: true });
+        }
+    }
     
-    // Make the request with retry logic
-    let mut response_json = None;
-    let max_retries = 3;
+    // Only the first page is asked for conditionally: if it's unchanged, nothing was
+    // uploaded, deleted or edited since, and the kept listing is still current
+    let conditional = CONDITIONAL_REQUESTS.load(Ordering::Relaxed);
+    let mut first_page_validators = None;
     
-    for retry in 0..max_retries {
-        if retry > 0 {
-            debug!("Retrying tracks fetch (attempt {}/{}) for user {}", 
-                  retry + 1, max_retries, user_id);
-            sleep(Duration::from_secs(2 * retry as u64)).await;
+    while let Some(page_url) = next_url.take() {
+        if tracks.len() >= effective_limit {
+            break;
         }
+        pages += 1;
+        debug!("Fetching tracks page {} for user {} ({} tracks so far)", pages, user_id, tracks.len());
         
-        let response = match client.get(&url).send().await {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    // Check for auth error and refresh client ID
-                    if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                        warn!("Auth error ({}), refreshing client ID", res.status());
-                        client_id = refresh_client_id().await?;
-                        continue;
-                    }
-                    
-                    warn!("API error: HTTP {} when fetching tracks for user {}", res.status(), user_id);
-                    continue;
+        // next_href doesn't carry the client ID, so it's added to every page
+        let validators = if pages == 1 && conditional { cached.as_ref().and_then(|c| c.validators.as_ref()) } else { None };
+        let page: ApiCollection<ApiTrack> = match conditional_request(
+            &format!("tracks for user {}", user_id),
+            api_retry(),
+            validators,
+            |client_id| with_client_id(&page_url, client_id),
+            |_, _| None
+        ).await? {
+            Conditional::Modified(page, page_validators) => {
+                if pages == 1 {
+                    first_page_validators = page_validators;
                 }
-                res
-            }
-            Err(e) => {
-                warn!("Network error when fetching tracks for user {}: {}", user_id, e);
-                continue;
-            }
+                page
+            },
+            Conditional::NotModified => {
+                let Some(cached) = cached else {
+                    return Ok(UserTracks::default());
+                };
+                info!("Tracks of user {} are unchanged since the last poll, reusing {} tracks", user_id, cached.tracks.len());
+                crate::metrics::increment_listings_not_modified();
+                // The listing is current as of now, so the profile check can rely on it again
+                let tracks = cached.tracks.clone();
+                store_listing(user_id, CachedListing { profile, stored: Instant::now(), ..cached });
+                return Ok(UserTracks { tracks, reused: false });
+            },
         };
         
-        match response.json::<Value>().await {
-            Ok(json) => {
-                response_json = Some(json);
-                break;
-            }
-            Err(e) => {
-                warn!("JSON parse error for tracks response: {}", e);
-                if retry == max_retries - 1 {
-                    return Err(format!("Failed to parse JSON after {} retries", max_retries).into());
-                }
-            }
-        }
-    }
-    
-    let json = match response_json {
-        Some(j) => j,
-        None => {
-            error!("Failed to fetch tracks for user {} after {} retries", user_id, max_retries);
-            return Err(format!("Failed to fetch tracks for user {} after {} retries", 
-                              user_id, max_retries).into());
-        }
-    };
-    
-    // Extract the collection of tracks
-    let collection = match json.get("collection") {
-        Some(Value::Array(arr)) => arr,
-        _ => {
-            error!("Unexpected API response format for user {}: missing 'collection' array", user_id);
-            return Err(format!("Unexpected API response format for user {}", user_id).into());
+        if page.collection.is_empty() {
+            debug!("No more tracks found for user {} on page {}", user_id, pages);
+            break;
         }
-    };
-    
-    if collection.is_empty() {
-        debug!("No tracks found for user {}", user_id);
-        return Ok(Vec::new());
-    }
-    
-    debug!("Processing {} tracks from response", collection.len());
-    
-    // Parse the tracks
-    let mut batch_count = 0;
-    for track_json in collection {
-        // Extract basic fields
-        if let Some(id) = track_json.get("id").and_then(Value::as_u64) {
-            let track_id = id.to_string();
+        
+        debug!("Processing {} tracks from response", page.collection.len());
+        
+        // Parse the tracks
+        let mut batch_count = 0;
+        for api_track in page.collection {
+            let Some(track) = api_track.into_track() else {
+                warn!("Track missing ID in API response - skipping");
+                continue;
+            };
             
             // Skip if we've already seen this track
-            if !seen_track_ids.insert(track_id.clone()) {
-                debug!("Skipping duplicate track ID: {}", track_id);
+            if !seen_track_ids.insert(track.id.clone()) {
+                debug!("Skipping duplicate track ID: {}", track.id);
                 continue;
             }
             
-            let title = track_json.get("title")
-                .and_then(Value::as_str)
-                .unwrap_or("Untitled")
-                .to_string();
-            
-            debug!("Processing track: {} (ID: {})", title, id);
-            
-            let track = Track {
-                id: track_id,
-                title,
-                permalink_url: track_json.get("permalink_url")
-                    .and_then(Value::as_str)
-                    .unwrap_or("")
-                    .to_string(),
-                artwork_url: track_json.get("artwork_url")
-                    .and_then(Value::as_str)
-                    .map(String::from),
-                description: track_json.get("description")
-                    .and_then(Value::as_str)
-                    .map(String::from),
-                user: parse_track_user(track_json),
-                created_at: track_json.get("created_at")
-                    .and_then(Value::as_str)
-                    .unwrap_or("")
-                    .to_string(),
-                duration: track_json.get("duration")
-                    .and_then(Value::as_u64)
-                    .unwrap_or(0),
-                stream_url: track_json.get("stream_url")
-                    .and_then(Value::as_str)
-                    .map(String::from),
-                hls_url: None, // Will be populated when needed
-                download_url: track_json.get("download_url")
-                    .and_then(Value::as_str)
-                    .map(String::from),
-                // Stats
-                playback_count: track_json.get("playback_count").and_then(Value::as_u64),
-                likes_count: track_json.get("likes_count").and_then(Value::as_u64),
-                reposts_count: track_json.get("reposts_count").and_then(Value::as_u64),
-                comment_count: track_json.get("comment_count").and_then(Value::as_u64),
-                // Additional metadata
-                genre: track_json.get("genre").and_then(Value::as_str).map(String::from),
-                tag_list: track_json.get("tag_list").and_then(Value::as_str).map(String::from),
-                downloadable: track_json.get("downloadable").and_then(Value::as_bool),
-                raw_data: Some(track_json.clone()),
-            };
+            debug!("Processing track: {} (ID: {})", track.title, track.id);
             tracks.push(track);
             batch_count += 1;
-        } else {
-            warn!("Track missing ID in API response - skipping");
+            if tracks.len() >= effective_limit {
+                break;
+            }
+        }
+        
+        debug!("Added {} tracks from page {}, total: {}", batch_count, pages, tracks.len());
+        
+        // A page with nothing new means the cursor isn't moving
+        if batch_count == 0 {
+            warn!("Page {} of user {}'s tracks had no new tracks, stopping", pages, user_id);
+            break;
+        }
+        
+        next_url = page.next_href;
+        if next_url.is_none() {
+            debug!("No next_href found, this is the last page");
         }
     }
     
-    debug!("Added {} tracks from batch, total: {}", batch_count, tracks.len());
-    
     info!("Successfully fetched {} tracks for user {}", tracks.len(), user_id);
-    Ok(tracks)
+    let validators = first_page_validators.filter(|_| conditional);
+    if validators.is_some() || profile.is_some() {
+        store_listing(user_id, CachedListing {
+            validators,
+            profile,
+            limit: effective_limit,
+            page_size,
+            tracks: tracks.clone(),
+            stored: Instant::now(),
+        });
+    }
+    Ok(UserTracks { tracks, reused: false })
 }
 
 /// Get user details from SoundCloud
-async fn get_user_details(user_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
-    
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => id,
-        None => refresh_client_id().await?,
-    };
-    
-    let max_retries = 3;
-    let url = format!(
-        "https://api-v2.soundcloud.com/users/{}?client_id={}",
-        user_id, client_id
-    );
-    
+///
+/// Fails with [`UserUnavailable`] if the user doesn't exist, or is still refused after
+/// the client ID was refreshed.
+pub async fn get_user_details(user_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
     debug!("Fetching user details for user ID: {}", user_id);
     
-    for retry in 0..max_retries {
-        if retry > 0 {
-            debug!("Retrying user details fetch (attempt {}/{}) for user {}", 
-                  retry + 1, max_retries, user_id);
-            sleep(Duration::from_secs(2 * retry as u64)).await;
+    let json = retry_request(
+        &format!("user details for {}", user_id),
+        api_retry(),
+        |client_id| Ok(api_url(&["users", user_id], &[("client_id", client_id)])),
+        |status, refreshed| {
+            let status = status.as_u16();
+            (status == 404 || (status == 403 && refreshed)).then(|| {
+                Err(Box::new(UserUnavailable { user_id: user_id.to_string(), status }).into())
+            })
         }
-        
-        let response = match client.get(&url).send().await {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    // Check for auth error and refresh client ID
-                    if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                        warn!("Auth error ({}), refreshing client ID", res.status());
-                        client_id = refresh_client_id().await?;
-                        continue;
-                    }
-                    
-                    warn!("API error: HTTP {} when fetching user details for {}", res.status(), user_id);
-                    continue;
-                }
-                res
-            }
-            Err(e) => {
-                warn!("Network error when fetching user details for {}: {}", user_id, e);
-                continue;
-            }
-        };
-        
-        match response.json::<Value>().await {
-            Ok(json) => {
-                debug!("Successfully fetched user details for user {}", user_id);
-                return Ok(json);
-            }
-            Err(e) => {
-                warn!("JSON parse error for user details: {}", e);
-                if retry == max_retries - 1 {
-                    return Err(format!("Failed to parse JSON after {} retries", max_retries).into());
-                }
+    ).await?;
+    debug!("Successfully fetched user details for user {}", user_id);
+    cache_user_details(user_id, &json);
+    Ok(json)
+}
+
+/// Get user details, reusing ones fetched within the user cache TTL
+///
+/// For details that don't need to be current, like a user's banner.
+pub async fn get_cached_user_details(user_id: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let ttl = user_cache_ttl();
+    let cached = USER_CACHE.lock().unwrap().get(user_id)
+        .filter(|(_, fetched)| fetched.elapsed() < ttl)
+        .map(|(details, _)| details.clone());
+    match cached {
+        Some(details) => {
+            debug!("Using cached user details for user {}", user_id);
+            Ok(details)
+        },
+        None => get_user_details(user_id).await,
+    }
+}
+
+/// Keep a user's details for [`get_cached_user_details`], dropping expired ones when the
+/// cache is full
+fn cache_user_details(user_id: &str, details: &Value) {
+    let ttl = user_cache_ttl();
+    if ttl.is_zero() {
+        return;
+    }
+    let mut cache = USER_CACHE.lock().unwrap();
+    if cache.len() >= LISTING_CACHE_SIZE && !cache.contains_key(user_id) {
+        cache.retain(|_, (_, fetched)| fetched.elapsed() < ttl);
+        if cache.len() >= LISTING_CACHE_SIZE {
+            let oldest = cache.iter().min_by_key(|(_, (_, fetched))| *fetched).map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
             }
         }
     }
-    
-    Err(format!("Failed to fetch user details for {} after {} retries", user_id, max_retries).into())
+    cache.insert(user_id.to_string(), (details.clone(), Instant::now()));
 }
 
 // Parse user info from track JSON
-fn parse_track_user(track_json: &Value) -> TrackUser {
-    if let Some(user) = track_json.get("user") {
-        TrackUser {
-            id: user.get("id")
-                .and_then(Value::as_u64)
-                .map(|id| id.to_string())
-                .unwrap_or_default(),
-            username: user.get("username")
-                .and_then(Value::as_str)
-                .unwrap_or("Unknown Artist")
-                .to_string(),
-            permalink_url: user.get("permalink_url")
-                .and_then(Value::as_str)
-                .unwrap_or("")
-                .to_string(),
-            avatar_url: user.get("avatar_url")
-                .and_then(Value::as_str)
-            .map(|url| get_original_artwork_url(url)),
-        }
-    } else {
-        // Default user if not found
-        TrackUser {
-            id: String::new(),
-            username: "Unknown Artist".to_string(),
-            permalink_url: String::new(),
-            avatar_url: None,
-        }
-    }
+pub(crate) fn parse_track_user(track_json: &Value) -> TrackUser {
+    track_json.get("user")
+        .and_then(|user| ApiUser::deserialize(user).ok())
+        .unwrap_or_default()
+        .into()
+}
+
+/// Check whether a track is still on SoundCloud
+///
+/// Returns `false` only when SoundCloud answers 404; other failures are errors, so a
+/// flaky connection doesn't make tracks look deleted.
+pub async fn track_exists(track_id: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    // Only the status matters, the body is skipped
+    let found: Option<serde::de::IgnoredAny> = retry_request(
+        &format!("track {}", track_id),
+        api_retry(),
+        |client_id| Ok(api_url(&["tracks", track_id], &[("client_id", client_id)])),
+        |status, _| (status.as_u16() == 404).then_some(Ok(None))
+    ).await?;
+    Ok(found.is_some())
 }
 
 /// Get detailed information for a track including stream URLs
+///
+/// Private tracks need the `secret_token` from their share link (see [`resolve_track`]).
+#[tracing::instrument(name = "fetch_track_details", skip_all, fields(track_id = %track_id))]
 pub async fn get_track_details(
-    track_id: &str
+    track_id: &str,
+    secret_token: Option<&str>
 ) -> Result<Track, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
-    
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => id,
-        None => refresh_client_id().await?,
-    };
+    let json: Value = retry_request(
+        &format!("details for track {}", track_id),
+        api_retry(),
+        |client_id| {
+            let mut params = vec![("client_id", client_id)];
+            if let Some(token) = secret_token {
+                params.push(("secret_token", token));
+            }
+            Ok(api_url(&["tracks", track_id], &params))
+        },
+        |_, _| None
+    ).await?;
     
-    let max_retries = 3;
-    let mut json_response = None;
+    let mut track = track_from_json(json, secret_token)
+        .map_err(|e| format!("{} for track {}", e, track_id))?;
+    info!("Fetched details for track {} - {}", track_id, track.title);
     
-    for retry in 0..max_retries {
-        if retry > 0 {
-            debug!("Retrying track details fetch (attempt {}/{}) for track {}", 
-                  retry + 1, max_retries, track_id);
-            sleep(Duration::from_secs(2 * retry as u64)).await;
-        }
-        
-        let url = format!(
-            "https://api-v2.soundcloud.com/tracks/{}?client_id={}",
-            track_id, client_id
-        );
-        
-        let response = match client.get(&url).send().await {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    // Check for auth error and refresh client ID
-                    if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                        warn!("Auth error ({}), refreshing client ID", res.status());
-                        client_id = refresh_client_id().await?;
-                        continue;
-                    }
-                    
-                    warn!("API error: HTTP {} for track {}", res.status(), track_id);
-                    continue;
-                }
-                res
-            }
-            Err(e) => {
-                warn!("Request error for track {}: {}", track_id, e);
-                continue;
-            }
-        };
+    resolve_track_media(&mut track).await;
+    Ok(track)
+}
+
+/// Get a playlist by ID or URL, with the IDs of all its tracks
+///
+/// Only the first few entries of `tracks` are complete, the rest just carry their ID.
+pub async fn get_playlist(id_or_url: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let playlist = if id_or_url.starts_with("http") {
+        resolve_url(id_or_url).await?
+    } else {
+        retry_request(
+            &format!("playlist {}", id_or_url),
+            api_retry(),
+            |client_id| Ok(api_url(&["playlists", id_or_url], &[("client_id", client_id)])),
+            |_, _| None
+        ).await?
+    };
+    match playlist.get("kind").and_then(Value::as_str) {
+        Some("playlist") | Some("system-playlist") => Ok(playlist),
+        kind => Err(format!("{} is not a playlist (it's a {})", id_or_url, kind.unwrap_or("unknown")).into()),
+    }
+}
+
+/// Get the details of several public tracks with as few requests as possible
+///
+/// Asks for up to [`TRACK_BATCH_SIZE`] tracks per request. Tracks SoundCloud doesn't return,
+/// e.g. private or deleted ones, are left out, so callers should fall back to
+/// [`get_track_details`] for those. Unlike [`get_track_details`], the waveform and HLS
+/// playlist aren't fetched: the playlist URL expires after a few minutes, so call
+/// [`resolve_track_media`] right before processing each track.
+#[tracing::instrument(name = "fetch_track_details_batch", skip_all, fields(tracks = track_ids.len()))]
+pub async fn get_tracks_details_batch(track_ids: &[String]) -> Result<Vec<Track>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut tracks = Vec::with_capacity(track_ids.len());
+    
+    for chunk in track_ids.chunks(TRACK_BATCH_SIZE) {
+        let ids = chunk.join(",");
+        let batch: Vec<Value> = retry_request(
+            &format!("details for {} tracks", chunk.len()),
+            api_retry(),
+            |client_id| Ok(api_url(&["tracks"], &[("ids", &ids), ("client_id", client_id)])),
+            |_, _| None
+        ).await?;
         
-        match response.json::<Value>().await {
-            Ok(json) => {
-                json_response = Some(json);
-                break;
-            }
-            Err(e) => {
-                warn!("JSON parse error for track {}: {}", track_id, e);
+        let returned = batch.len();
+        for json in batch {
+            match track_from_json(json, None) {
+                Ok(track) => tracks.push(track),
+                Err(e) => warn!("Skipping a track in a batch response: {}", e),
             }
         }
+        debug!("Fetched details for {} of {} tracks in one request", returned, chunk.len());
     }
     
-    let json = match json_response {
-        Some(j) => j,
-        None => return Err(format!("Failed to fetch details for track {} after {} retries", 
-                                  track_id, max_retries).into()),
-    };
-    
-    // Basic track info
-    let track = Track {
-        id: track_id.to_string(),
-        title: json.get("title")
-            .and_then(Value::as_str)
-            .unwrap_or("Untitled")
-            .to_string(),
-        permalink_url: json.get("permalink_url")
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .to_string(),
-        artwork_url: json.get("artwork_url")
-            .and_then(Value::as_str)
-            .map(|url| get_original_artwork_url(url)),
-        description: json.get("description")
-            .and_then(Value::as_str)
-            .map(String::from),
-        user: parse_track_user(&json),
-        created_at: json.get("created_at")
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .to_string(),
-        duration: json.get("duration")
-            .and_then(Value::as_u64)
-            .unwrap_or(0),
-        stream_url: json.get("stream_url")
-            .and_then(Value::as_str)
-            .map(String::from),
-        download_url: json.get("download_url")
-            .and_then(Value::as_str)
-            .map(String::from),
-        hls_url: None, // Populate this below if available
-        // Stats
-        playback_count: json.get("playback_count").and_then(Value::as_u64),
-        likes_count: json.get("likes_count").and_then(Value::as_u64),
-        reposts_count: json.get("reposts_count").and_then(Value::as_u64),
-        comment_count: json.get("comment_count").and_then(Value::as_u64),
-        // Additional metadata
-        genre: json.get("genre").and_then(Value::as_str).map(String::from),
-        tag_list: json.get("tag_list").and_then(Value::as_str).map(String::from),
-        downloadable: json.get("downloadable").and_then(Value::as_bool),
-        raw_data: Some(json.clone()),
+    Ok(tracks)
+}
+
+/// Read a track from its details in the API, keeping the full JSON for the metadata file
+fn track_from_json(json: Value, secret_token: Option<&str>) -> Result<Track, Box<dyn std::error::Error + Send + Sync>> {
+    let api_track = ApiTrack::deserialize(&json)
+        .map_err(|e| format!("Unexpected API response format: {}", e))?;
+    let mut track = api_track.into_track()
+        .ok_or("Missing track ID in the API response")?;
+    track.artwork_url = track.artwork_url.as_deref().map(get_original_artwork_url);
+    track.secret_token = track.secret_token.or_else(|| secret_token.map(String::from));
+    track.raw_data = Some(json);
+    Ok(track)
+}
+
+/// Fetch a track's waveform and resolve its HLS playlist, from the details in `raw_data`
+///
+/// Failures are logged and leave the fields empty; tracks without details are left as they are.
+pub async fn resolve_track_media(track: &mut Track) {
+    let Some(json) = &track.raw_data else {
+        return;
     };
+    let waveform_url = json.get("waveform_url").and_then(Value::as_str).filter(|url| !url.is_empty()).map(String::from);
+    let hls_url = find_hls_transcoding(json).map(|url| with_secret_token(url, track.secret_token.as_deref()));
     
-    info!("Fetched details for track {} - {}", track_id, track.title);
-    
-    // Try to extract HLS stream URL for the track
-    if let Some(media) = json.get("media") {
-        if let Some(transcodings) = media.get("transcodings").and_then(Value::as_array) {
-            for transcoding in transcodings {
-                let format = transcoding.get("format").and_then(|f| f.get("protocol")).and_then(Value::as_str);
-                
-                // Look for HLS streams specifically
-                if let (Some("hls"), Some(url)) = (format, transcoding.get("url").and_then(Value::as_str)) {
-                    debug!("Found HLS URL for track {}", track_id);
-                    // TODO: Actually resolve the HLS URL by making another API call with client_id
-                    // For now just return the original URL
-                    let mut track = track.clone();
-                    track.hls_url = Some(url.to_string());
-                    return Ok(track);
-                }
-            }
+    if let Some(url) = waveform_url {
+        match get_waveform(&url).await {
+            Ok(waveform) => track.waveform = Some(waveform),
+            Err(e) => debug!("Failed to fetch the waveform of track {}: {}", track.id, e),
         }
     }
     
-    Ok(track)
+    // Resolve the HLS transcoding to its playlist URL, so downloads can use it directly
+    if let Some(url) = hls_url {
+        debug!("Found HLS transcoding for track {}, resolving its playlist URL", track.id);
+        match get_stream_url(&url).await {
+            Ok(playlist_url) => track.hls_url = Some(playlist_url),
+            Err(e) => warn!("Failed to resolve HLS URL for track {}: {}", track.id, e),
+        }
+    }
+}
+
+/// The API URL of a track's HLS transcoding, preferring AAC and Opus streams over the
+/// deprecated `audio/mpegurl` one, which often 404s
+fn find_hls_transcoding(json: &Value) -> Option<&str> {
+    let transcodings = json.get("media")?.get("transcodings")?.as_array()?;
+    let hls = transcodings.iter()
+        .filter(|t| t.get("format").and_then(|f| f.get("protocol")).and_then(Value::as_str) == Some("hls"))
+        .filter_map(|t| {
+            let mime_type = t.get("format").and_then(|f| f.get("mime_type")).and_then(Value::as_str).unwrap_or("");
+            t.get("url").and_then(Value::as_str).map(|url| (mime_type, url))
+        })
+        .collect::<Vec<_>>();
+    hls.iter()
+        .find(|(mime_type, _)| !mime_type.contains("audio/mpegurl"))
+        .or_else(|| hls.first())
+        .map(|(_, url)| *url)
+}
+
+/// Fetch a track's waveform from its `waveform_url`
+///
+/// The waveform is served from SoundCloud's CDN, so this doesn't count towards the API rate limit.
+async fn get_waveform(url: &str) -> Result<crate::waveform::Waveform, Box<dyn std::error::Error + Send + Sync>> {
+    let response = HTTP_CLIENT.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP error {}", response.status()).into());
+    }
+    Ok(response.json().await?)
 }
 
 /// Resolve the actual download/stream URL for a track
+///
+/// Resolved URLs are cached for a few minutes, so retries and fallbacks for the
+/// same transcoding don't resolve it again.
 pub async fn get_stream_url(url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
+    if let Some((resolved, at)) = STREAM_URL_CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(url) {
+        if at.elapsed() < STREAM_URL_TTL {
+            debug!("Using cached stream URL for {}", url);
+            return Ok(resolved.clone());
+        }
+    }
     
     // Get the current client ID or refresh it
     let client_id = match get_client_id() {
@@ -599,18 +1301,13 @@ pub async fn get_stream_url(url: &str) -> Result<String, Box<dyn std::error::Err
         None => refresh_client_id().await?,
     };
     
-    // Add client_id to URL
-    let full_url = if url.contains('?') {
-        format!("{}&client_id={}", url, client_id)
-    } else {
-        format!("{}?client_id={}", url, client_id)
-    };
-    
-    let response = client.get(&full_url).send().await?;
+    let response = api_get(with_client_id(url, &client_id)?, None).await?;
     
     if !response.status().is_success() {
+        crate::metrics::record_api_error(Service::SoundCloud, Some(response.status().as_u16()));
         return Err(format!("HTTP error {}", response.status()).into());
     }
+    record_client_id_working();
     
     #[derive(Deserialize)]
     struct StreamResponse {
@@ -618,69 +1315,138 @@ pub async fn get_stream_url(url: &str) -> Result<String, Box<dyn std::error::Err
     }
     
     let stream_response: StreamResponse = response.json().await?;
+    
+    let mut cache = STREAM_URL_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.retain(|_, (_, at)| at.elapsed() < STREAM_URL_TTL);
+    cache.insert(url.to_string(), (stream_response.url.clone(), Instant::now()));
+    
     Ok(stream_response.url)
 }
 
+/// Drop a cached stream URL, e.g. after the CDN rejected it
+pub fn forget_stream_url(url: &str) {
+    STREAM_URL_CACHE.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+}
+
 /// Resolve a SoundCloud URL to a track/user ID
+///
+/// Private share links work too: a `?secret_token=` is moved into the path, the form the
+/// API resolves.
 pub async fn resolve_url(url: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
+    let share_url = private_share_url(url);
+    let url = share_url.as_deref().unwrap_or(url);
+    
+    let json = retry_request(
+        &format!("URL {}", url),
+        api_retry(),
+        |client_id| Ok(api_url(&["resolve"], &[("url", url), ("client_id", client_id)])),
+        |_, _| None
+    ).await?;
+    info!("Successfully resolved URL: {}", url);
+    Ok(json)
+}
+
+/// Resolve a user ID or profile URL to a numeric SoundCloud user ID
+/// 
+/// Plain IDs are returned unchanged; URLs are resolved through the API and
+/// must point to a user.
+pub async fn resolve_user_id(id_or_url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if !(id_or_url.contains("soundcloud.com") || id_or_url.contains("http")) {
+        // It's already an ID
+        return Ok(id_or_url.to_string());
+    }
     
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => id,
-        None => refresh_client_id().await?,
+    // It's a URL, resolve it
+    info!("Resolving URL to user ID: {}", id_or_url);
+    let data = match resolve_url(id_or_url).await {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to resolve URL {}: {}", id_or_url, e);
+            return Err(e);
+        }
     };
     
-    let max_retries = 3;
+    match data.get("kind").and_then(|v| v.as_str()) {
+        Some("user") => match data.get("id").and_then(|v| v.as_u64()) {
+            Some(id) => Ok(id.to_string()),
+            None => {
+                error!("Could not extract user ID from resolved URL data");
+                Err("Missing user ID in resolved data".into())
+            }
+        },
+        Some(kind) => {
+            error!("URL resolved to non-user kind: {}", kind);
+            Err(format!("URL resolved to non-user kind: {}", kind).into())
+        },
+        None => {
+            error!("URL resolved to object with missing kind");
+            Err("URL resolved to object with missing kind".into())
+        }
+    }
+}
+
+/// A private share link with its `?secret_token=` query moved into the path, or `None` if
+/// the URL doesn't have one
+fn private_share_url(url: &str) -> Option<String> {
+    let mut parsed = reqwest::Url::parse(url).ok()?;
+    let token = parsed.query_pairs().find(|(key, _)| key == "secret_token")?.1.into_owned();
+    parsed.set_query(None);
+    parsed.path_segments_mut().ok()?.pop_if_empty().push(&token);
+    Some(parsed.to_string())
+}
+
+/// Resolve a track ID or track URL to a numeric SoundCloud track ID
+/// 
+/// Plain IDs are returned unchanged; URLs are resolved through the API and
+/// must point to a track. A private track's secret token is dropped, use
+/// [`resolve_track`] to keep it.
+pub async fn resolve_track_id(id_or_url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    resolve_track(id_or_url).await.map(|(track_id, _)| track_id)
+}
+
+/// Resolve a track ID or track URL to a numeric SoundCloud track ID and, for a private
+/// track's share link, its secret token
+pub async fn resolve_track(id_or_url: &str) -> Result<(String, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    if !id_or_url.starts_with("http") {
+        // Assume this is a track ID
+        return Ok((id_or_url.to_string(), None));
+    }
     
-    for retry in 0..max_retries {
-        if retry > 0 {
-            debug!("Retrying URL resolution (attempt {}/{}) for {}", 
-                  retry + 1, max_retries, url);
-            sleep(Duration::from_secs(2 * retry as u64)).await;
+    // This is a URL, resolve it
+    info!("Resolving SoundCloud URL: {}", id_or_url);
+    let resolved = match resolve_url(id_or_url).await {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to resolve URL: {}", e);
+            return Err(e);
         }
-        
-        let resolve_url = format!(
-            "https://api-v2.soundcloud.com/resolve?url={}&client_id={}",
-            url, client_id
-        );
-        
-        let response = match client.get(&resolve_url).send().await {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    // Check for auth error and refresh client ID
-                    if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                        warn!("Auth error ({}), refreshing client ID", res.status());
-                        client_id = refresh_client_id().await?;
-                        continue;
-                    }
-                    
-                    warn!("API error: HTTP {} for URL {}", res.status(), url);
-                    continue;
-                }
-                res
-            }
-            Err(e) => {
-                warn!("Request error for URL {}: {}", url, e);
-                continue;
-            }
-        };
-        
-        match response.json::<Value>().await {
-            Ok(json) => {
-                info!("Successfully resolved URL: {}", url);
-                return Ok(json);
-            }
-            Err(e) => {
-                warn!("JSON parse error for URL {}: {}", url, e);
-                if retry == max_retries - 1 {
-                    return Err(format!("Failed to parse JSON after {} retries", max_retries).into());
-                }
+    };
+    
+    match resolved.get("kind").and_then(|v| v.as_str()) {
+        Some("track") => match resolved.get("id").and_then(|v| v.as_u64()) {
+            Some(id) => {
+                let track_id = id.to_string();
+                info!("URL resolved to track ID: {}", track_id);
+                let secret_token = resolved.get("secret_token")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+                    .or_else(|| secret_token_from_url(id_or_url));
+                Ok((track_id, secret_token))
+            },
+            None => {
+                error!("Could not extract track ID from resolved URL");
+                Err("Could not extract track ID from resolved URL".into())
             }
+        },
+        Some(kind) => {
+            error!("URL does not point to a track, but to a {}", kind);
+            Err(format!("URL points to a {}, not a track", kind).into())
+        },
+        None => {
+            error!("Could not determine object type from resolved URL");
+            Err("Could not determine object type from resolved URL".into())
         }
     }
-    
-    Err(format!("Failed to resolve URL {} after {} retries", url, max_retries).into())
 }
 
 /// Convert artwork URL to get the original high-resolution version
@@ -695,17 +1461,285 @@ pub fn get_original_artwork_url(artwork_url: &str) -> String {
     if artwork_url.contains("-t500x500.jpg") {
         return artwork_url.replace("-t500x500.jpg", "-original.jpg");
     }
-    
-    // Return the original URL if it doesn't match the expected pattern
-    artwork_url.to_string()
+    
+    // Return the original URL if it doesn't match the expected pattern
+    artwork_url.to_string()
+}
+
+/// The artwork URL in each size SoundCloud serves, largest first
+fn artwork_url_variants(artwork_url: &str) -> Vec<String> {
+    let mut variants = vec![get_original_artwork_url(artwork_url)];
+    for size in ["-t500x500.jpg", "-large.jpg"] {
+        let variant = variants[0].replace("-original.jpg", size);
+        if !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
+
+/// Where a track's cover art came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtworkSource {
+    /// The track's own artwork
+    Artwork,
+    /// The `og:image` of the track's page, because none of the artwork sizes could be fetched
+    PageImage,
+    /// The uploader's avatar, because the track has no artwork
+    Avatar,
+}
+
+/// Why a track was picked up while polling a watched user or playlist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provenance {
+    /// The user uploaded it
+    Upload,
+    /// The user liked it
+    Like,
+    /// It was added to a watched playlist
+    Playlist { id: String, title: String },
+}
+
+impl Provenance {
+    /// The badge shown in the embed footer
+    pub fn badge(&self) -> String {
+        let locale = crate::locale::get();
+        match self {
+            Provenance::Upload => locale.badge_upload.clone(),
+            Provenance::Like => locale.badge_like.clone(),
+            Provenance::Playlist { title, .. } => format!("{}: {}", locale.badge_playlist, title),
+        }
+    }
+}
+
+/// Make sure the track's artwork URL can be fetched before it's downloaded and posted
+///
+/// Tries each artwork size in turn, then the `og:image` of the track's page. If nothing
+/// works, the artwork URL is cleared so the uploader's avatar is used instead, if they
+/// have one. Returns where the cover art now comes from.
+pub async fn resolve_cover_art(track: &mut Track) -> Option<ArtworkSource> {
+    let Some(artwork_url) = track.artwork_url.clone().filter(|url| !url.is_empty()) else {
+        return cover_art_url(track).map(|_| ArtworkSource::Avatar);
+    };
+    
+    for variant in artwork_url_variants(&artwork_url) {
+        match HTTP_CLIENT.head(&variant).header("User-Agent", "Mozilla/5.0").send().await {
+            Ok(res) if res.status().is_success() => {
+                if variant != artwork_url {
+                    info!("Artwork of track {} isn't available at the usual size, using {}", track.id, variant);
+                }
+                track.artwork_url = Some(variant);
+                return Some(ArtworkSource::Artwork);
+            },
+            Ok(res) => debug!("Artwork {} returned HTTP {}", variant, res.status()),
+            Err(e) => debug!("Failed to check artwork {}: {}", variant, e),
+        }
+    }
+    
+    match get_page_image_url(&track.permalink_url).await {
+        Ok(Some(image)) => {
+            warn!("No artwork size of track {} could be fetched, using the page's og:image instead", track.id);
+            track.artwork_url = Some(image);
+            return Some(ArtworkSource::PageImage);
+        },
+        Ok(None) => debug!("Track page {} has no og:image", track.permalink_url),
+        Err(e) => debug!("Failed to fetch track page {}: {}", track.permalink_url, e),
+    }
+    
+    warn!("No artwork of track {} could be fetched, falling back to the uploader's avatar", track.id);
+    track.artwork_url = None;
+    cover_art_url(track).map(|_| ArtworkSource::Avatar)
+}
+
+/// Fetch a public SoundCloud page as a browser would see it
+async fn get_page_html(page_url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let html = HTTP_CLIENT
+        .get(page_url)
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(html)
+}
+
+/// Scrape the `og:image` meta tag of a SoundCloud page
+async fn get_page_image_url(page_url: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let html = get_page_html(page_url).await?;
+    Ok(OG_IMAGE_REGEX.captures(&html)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().replace("&amp;", "&")))
+}
+
+/// The track's artwork URL, or the uploader's avatar if the track has no artwork
+///
+/// SoundCloud's placeholder avatar isn't used, so this can still be `None`.
+pub fn cover_art_url(track: &Track) -> Option<&str> {
+    track.artwork_url.as_deref()
+        .filter(|url| !url.is_empty())
+        .or_else(|| track.user.avatar_url.as_deref()
+            .filter(|url| !url.is_empty() && !url.contains("default_avatar")))
+}
+
+/// Why SoundCloud won't serve a track's full audio to the archiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restriction {
+    /// Blocked in the archiver's region
+    GeoBlocked,
+    /// Only a preview, usually 30 seconds, is available without SoundCloud Go+
+    Snippet,
+}
+
+impl std::fmt::Display for Restriction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Restriction::GeoBlocked => "geo-blocked",
+            Restriction::Snippet => "preview only",
+        })
+    }
+}
+
+/// Whether SoundCloud restricts the track's audio, going by its `policy` and by a
+/// `full_duration` longer than the `duration` that can be streamed
+///
+/// Only known for track details, since listings leave out the raw API data.
+pub fn track_restriction(track: &Track) -> Option<Restriction> {
+    let raw = track.raw_data.as_ref()?;
+    match raw.get("policy").and_then(Value::as_str) {
+        Some("BLOCK") => return Some(Restriction::GeoBlocked),
+        Some("SNIP") => return Some(Restriction::Snippet),
+        _ => {}
+    }
+    // Allow for rounding between the two durations
+    let full_duration = raw.get("full_duration").and_then(Value::as_u64).unwrap_or(0);
+    (full_duration > track.duration + 1000).then_some(Restriction::Snippet)
+}
+
+/// The length of the whole track, which for snippets is longer than its `duration`
+pub fn full_duration(track: &Track) -> u64 {
+    track.raw_data.as_ref()
+        .and_then(|raw| raw.get("full_duration"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+        .max(track.duration)
+}
+
+/// An animated cover a track shows in its `visuals`, in place of the static artwork
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimatedCover {
+    pub url: String,
+    /// "gif", "mp4" or "webm"
+    pub extension: String,
+}
+
+impl AnimatedCover {
+    /// Whether Discord can show the cover in an embed (only GIFs, videos are attachments only)
+    pub fn is_embeddable(&self) -> bool {
+        self.extension == "gif"
+    }
+}
+
+/// The track's animated cover, if its `visuals` have one
+///
+/// Visuals can also be static images, which the artwork already covers, so only GIFs and
+/// videos count. Only known for track details, since listings leave out the raw API data.
+pub fn animated_cover(track: &Track) -> Option<AnimatedCover> {
+    const ANIMATED_EXTENSIONS: [&str; 3] = ["gif", "mp4", "webm"];
+    
+    track.raw_data.as_ref()?
+        .get("visuals")?
+        .get("visuals")?
+        .as_array()?
+        .iter()
+        .filter_map(|visual| visual.get("visual_url").and_then(Value::as_str))
+        .find_map(|url| {
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            let extension = path.rsplit_once('.')?.1.to_lowercase();
+            ANIMATED_EXTENSIONS.contains(&extension.as_str()).then(|| AnimatedCover {
+                url: url.to_string(),
+                extension,
+            })
+        })
+}
+
+/// Get the URL of a user's profile banner, if they have one
+///
+/// Every track without artwork shows its uploader's banner, so banners (and users having
+/// none) are remembered for [`BANNER_CACHE_TTL`] rather than looked up per track.
+pub async fn get_user_banner_url(user_id: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let cached = BANNER_CACHE.lock().unwrap().get(user_id)
+        .filter(|(_, looked_up)| looked_up.elapsed() < BANNER_CACHE_TTL)
+        .map(|(banner, _)| banner.clone());
+    if let Some(banner) = cached {
+        return Ok(banner);
+    }
+    
+    let banner = banner_url(&get_cached_user_details(user_id).await?);
+    let mut cache = BANNER_CACHE.lock().unwrap();
+    if cache.len() >= LISTING_CACHE_SIZE && !cache.contains_key(user_id) {
+        cache.retain(|_, (_, looked_up)| looked_up.elapsed() < BANNER_CACHE_TTL);
+    }
+    if cache.len() < LISTING_CACHE_SIZE || cache.contains_key(user_id) {
+        cache.insert(user_id.to_string(), (banner.clone(), Instant::now()));
+    }
+    Ok(banner)
+}
+
+/// Read the URL of the profile banner from a user's details
+pub fn banner_url(user: &Value) -> Option<String> {
+    user.get("visuals")
+        .and_then(|v| v.get("visuals"))
+        .and_then(|v| v.as_array())
+        .and_then(|v| v.first())
+        .and_then(|v| v.get("visual_url"))
+        .and_then(|u| u.as_str())
+        .map(|u| u.to_string())
+}
+
+/// Get the tracks and playlists a user has pinned to their profile spotlight
+pub async fn get_user_spotlight(user_id: &str) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
+    get_user_collection(user_id, "spotlight", "spotlight").await
+}
+
+/// Get a user's most played tracks, as shown under "Popular tracks" on their profile
+pub async fn get_user_top_tracks(user_id: &str) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
+    get_user_collection(user_id, "toptracks", "popular tracks").await
+}
+
+/// Get a user's most recent albums, EPs and other sets marked as releases
+pub async fn get_user_albums(user_id: &str) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
+    get_user_collection(user_id, "albums", "albums").await
+}
+
+/// Fetch the first page of one of a user's collections, such as `spotlight`
+async fn get_user_collection(
+    user_id: &str,
+    endpoint: &str,
+    what: &str
+) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
+    debug!("Fetching {} for user ID: {}", what, user_id);
+    
+    // These only decorate posts, so they get a single retry rather than holding up a poll
+    let json: Value = retry_request(
+        &format!("{} for user {}", what, user_id),
+        api_retry().with_retries(1),
+        |client_id| Ok(api_url(&["users", user_id, endpoint], &[("client_id", client_id), ("limit", "10")])),
+        |_, _| None
+    ).await?;
+    match json.get("collection") {
+        Some(Value::Array(items)) => Ok(items.clone()),
+        _ => Err(format!("Unexpected API response format for {} of user {}", what, user_id).into()),
+    }
 }
 
 /// Get a list of users that a SoundCloud user is following
 pub async fn get_user_followings(
     user_id: &str, 
     limit: Option<usize>
-) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
+) -> Result<Vec<ApiUser>, Box<dyn std::error::Error + Send + Sync>> {
     let mut followings = Vec::new();
     let mut offset = 0;
     // API has a max limit of 200 per request
@@ -714,18 +1748,6 @@ pub async fn get_user_followings(
     
     info!("Fetching followings for user {}", user_id);
     
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => {
-            debug!("Using cached client ID: {}", id);
-            id
-        },
-        None => {
-            debug!("No cached client ID, generating new one");
-            refresh_client_id().await?
-        },
-    };
-    
     loop {
         // Break if we've reached the requested limit
         if followings.len() >= max_limit {
@@ -733,100 +1755,42 @@ pub async fn get_user_followings(
         }
         
         let current_limit = std::cmp::min(chunk_size, max_limit - followings.len());
-        let url = format!(
-            "https://api-v2.soundcloud.com/users/{}/followings?client_id={}&limit={}&offset={}&linked_partitioning=1",
-            user_id, client_id, current_limit, offset
-        );
-        
         debug!("Fetching followings batch: offset={}, limit={}", offset, current_limit);
         
-        // Make the request with retry logic
-        let mut response_json = None;
-        let max_retries = 3;
-        
-        for retry in 0..max_retries {
-            if retry > 0 {
-                debug!("Retrying followings fetch (attempt {}/{}) for user {}", 
-                      retry + 1, max_retries, user_id);
-                sleep(Duration::from_secs(2 * retry as u64)).await;
-            }
-            
-            let response = match client.get(&url).send().await {
-                Ok(res) => {
-                    if !res.status().is_success() {
-                        // Check for auth error and refresh client ID
-                        if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                            warn!("Auth error ({}), refreshing client ID", res.status());
-                            client_id = refresh_client_id().await?;
-                            continue;
-                        }
-                        
-                        warn!("API error: HTTP {} when fetching followings for user {}", res.status(), user_id);
-                        continue;
-                    }
-                    res
-                }
-                Err(e) => {
-                    warn!("Network error when fetching followings for user {}: {}", user_id, e);
-                    continue;
-                }
-            };
-            
-            match response.json::<Value>().await {
-                Ok(json) => {
-                    response_json = Some(json);
-                    break;
-                }
-                Err(e) => {
-                    warn!("JSON parse error for followings response: {}", e);
-                    if retry == max_retries - 1 {
-                        return Err(format!("Failed to parse JSON after {} retries", max_retries).into());
-                    }
-                }
-            }
-        }
-        
-        let json = match response_json {
-            Some(j) => j,
-            None => {
-                error!("Failed to fetch followings for user {} after {} retries", user_id, max_retries);
-                return Err(format!("Failed to fetch followings for user {} after {} retries", 
-                                  user_id, max_retries).into());
-            }
-        };
-        
-        // Extract the collection of followings
-        let collection = match json.get("collection") {
-            Some(Value::Array(arr)) => arr,
-            _ => {
-                error!("Unexpected API response format for user {}: missing 'collection' array", user_id);
-                return Err(format!("Unexpected API response format for user {}", user_id).into());
-            }
-        };
+        let page: ApiCollection<ApiUser> = retry_request(
+            &format!("followings for user {}", user_id),
+            api_retry(),
+            |client_id| Ok(api_url(&["users", user_id, "followings"], &[
+                ("client_id", client_id),
+                ("limit", &current_limit.to_string()),
+                ("offset", &offset.to_string()),
+                ("linked_partitioning", "1"),
+            ])),
+            |_, _| None
+        ).await?;
         
-        if collection.is_empty() {
+        if page.collection.is_empty() {
             debug!("No more followings found for user {} at offset {}", user_id, offset);
             break; // No more followings
         }
         
-        debug!("Processing {} followings from response", collection.len());
+        let batch_count = page.collection.len();
+        debug!("Processing {} followings from response", batch_count);
         
         // Add followings to our collection
-        for following in collection {
-            followings.push(following.clone());
-        }
+        followings.extend(page.collection);
         
-        debug!("Added {} followings from batch, total: {}", collection.len(), followings.len());
+        debug!("Added {} followings from batch, total: {}", batch_count, followings.len());
         
         // Check if there are more pages
-        if let Some(next_href) = json.get("next_href").and_then(Value::as_str) {
+        if let Some(next_href) = page.next_href.as_deref() {
             // Extract offset from next_href
             if let Some(new_offset) = extract_offset_from_url(next_href) {
                 offset = new_offset;
                 debug!("Next page available, offset: {}", offset);
             } else {
                 // Can't extract offset, so just increment by collection size
-                offset += collection.len();
+                offset += batch_count;
                 debug!("Couldn't extract offset from next_href, incrementing by collection size");
             }
         } else {
@@ -839,6 +1803,77 @@ pub async fn get_user_followings(
     Ok(followings)
 }
 
+/// A comment on a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: String,
+    pub body: String,
+    pub created_at: String,
+    /// Position in the track the comment was left at, in milliseconds
+    pub timestamp: Option<u64>,
+    pub user: TrackUser,
+}
+
+/// Most comment pages fetched for one track (200 comments each)
+const MAX_COMMENT_PAGES: usize = 50;
+
+/// Get all comments on a track, oldest first
+#[tracing::instrument(name = "fetch_track_comments", skip_all, fields(track_id = %track_id))]
+pub async fn get_track_comments(track_id: &str, secret_token: Option<&str>) -> Result<Vec<Comment>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut comments = Vec::new();
+    
+    debug!("Fetching comments for track {}", track_id);
+    
+    let mut next_url = Some(with_secret_token(api_url(
+        &["tracks", track_id, "comments"],
+        &[("threaded", "0"), ("filter_replies", "0"), ("limit", "200"), ("linked_partitioning", "1")]
+    ).as_str(), secret_token));
+    let mut pages = 0;
+    while let Some(page_url) = next_url.take() {
+        if pages >= MAX_COMMENT_PAGES {
+            warn!("Track {} has more comments than fit in {} pages, only archiving the first {}", track_id, MAX_COMMENT_PAGES, comments.len());
+            break;
+        }
+        pages += 1;
+        
+        // next_href doesn't carry the client ID, so it's added to every page
+        let json: Value = retry_request(
+            &format!("comments for track {}", track_id),
+            api_retry(),
+            |client_id| with_client_id(&page_url, client_id),
+            |_, _| None
+        ).await?;
+        let collection = match json.get("collection") {
+            Some(Value::Array(arr)) => arr,
+            _ => return Err(format!("Unexpected API response format for comments of track {}", track_id).into()),
+        };
+        
+        for comment in collection {
+            let id = match comment.get("id") {
+                Some(Value::Number(n)) => n.to_string(),
+                Some(Value::String(s)) => s.clone(),
+                _ => continue,
+            };
+            comments.push(Comment {
+                id,
+                body: comment.get("body").and_then(Value::as_str).unwrap_or_default().to_string(),
+                created_at: comment.get("created_at").and_then(Value::as_str).unwrap_or_default().to_string(),
+                timestamp: comment.get("timestamp").and_then(Value::as_u64),
+                user: parse_track_user(comment),
+            });
+        }
+        
+        next_url = json.get("next_href")
+            .and_then(Value::as_str)
+            .filter(|_| !collection.is_empty())
+            .map(|href| with_secret_token(href, secret_token));
+    }
+    
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    info!("Fetched {} comments for track {}", comments.len(), track_id);
+    Ok(comments)
+}
+
 /// Extract offset parameter from a SoundCloud API URL
 fn extract_offset_from_url(url: &str) -> Option<usize> {
     if let Some(query) = url.split('?').nth(1) {
@@ -854,195 +1889,119 @@ fn extract_offset_from_url(url: &str) -> Option<usize> {
 }
 
 /// Get likes for a SoundCloud user
+///
+/// Follows `next_href` through pages of `pagination_size` likes, newest first, until
+/// `limit` likes have been fetched or the user has no more. With `since` set, stops at the
+/// first like older than that, so a poll only fetches what was liked since the last one
+/// instead of all `limit` likes again.
+#[tracing::instrument(name = "fetch_user_likes", skip_all, fields(user_id = %user_id))]
 pub async fn get_user_likes(
     user_id: &str, 
     limit: usize,
-    _pagination_size: usize, // Keep parameter for backward compatibility
+    pagination_size: usize,
+    since: Option<chrono::DateTime<chrono::Utc>>,
 ) -> Result<Vec<Like>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = &HTTP_CLIENT;
     let mut likes = Vec::new();
     let mut seen_like_ids = std::collections::HashSet::new();
     
-    info!("Fetching up to {} likes for user {}", limit, user_id);
-    
-    // Get the current client ID or refresh it
-    let mut client_id = match get_client_id() {
-        Some(id) => {
-            debug!("Using cached client ID: {}", id);
-            id
-        },
-        None => {
-            debug!("No cached client ID, generating new one");
-            refresh_client_id().await?
-        },
-    };
-    
-    // Try to fetch all likes in one go with a large limit
-    let url = format!(
-        "https://api-v2.soundcloud.com/users/{}/likes?client_id={}&limit={}&linked_partitioning=1",
-        user_id, client_id, limit
-    );
-    
-    debug!("Attempting to fetch all {} likes in one request", limit);
+    match since {
+        Some(since) => info!("Fetching up to {} likes for user {} since {}", limit, user_id, since.to_rfc3339()),
+        None => info!("Fetching up to {} likes for user {}", limit, user_id),
+    }
     
-    // Make the request with retry logic
-    let mut response_json = None;
-    let max_retries = 3;
+    let page_size = pagination_size.clamp(1, 200).min(limit.max(1));
+    let mut next_url = Some(api_url(
+        &["users", user_id, "likes"],
+        &[("limit", &page_size.to_string()), ("linked_partitioning", "1")]
+    ).to_string());
+    let mut pages = 0;
+    let mut caught_up = false;
     
-    for retry in 0..max_retries {
-        if retry > 0 {
-            debug!("Retrying likes fetch (attempt {}/{}) for user {}", 
-                  retry + 1, max_retries, user_id);
-            sleep(Duration::from_secs(2 * retry as u64)).await;
+    while let Some(page_url) = next_url.take() {
+        if likes.len() >= limit {
+            break;
         }
+        pages += 1;
+        debug!("Fetching likes page {} for user {} ({} likes so far)", pages, user_id, likes.len());
         
-        let response = match client.get(&url).send().await {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    // Check for auth error and refresh client ID
-                    if res.status().as_u16() == 401 || res.status().as_u16() == 403 {
-                        warn!("Auth error ({}), refreshing client ID", res.status());
-                        client_id = refresh_client_id().await?;
-                        continue;
-                    }
-                    
-                    warn!("API error: HTTP {} when fetching likes for user {}", res.status(), user_id);
-                    continue;
+        // next_href doesn't carry the client ID, so it's added to every page
+        let page: ApiCollection<ApiLike> = retry_request(
+            &format!("likes for user {}", user_id),
+            api_retry(),
+            |client_id| with_client_id(&page_url, client_id),
+            |_, _| None
+        ).await?;
+        
+        if page.collection.is_empty() {
+            debug!("No more likes found for user {} on page {}", user_id, pages);
+            break;
+        }
+        
+        debug!("Processing {} likes from response", page.collection.len());
+        
+        // Parse the likes
+        let mut batch_count = 0;
+        for like in page.collection {
+            // Everything from here on was liked before the last poll
+            let liked_at = like.created_at.as_deref()
+                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok());
+            if let (Some(since), Some(liked_at)) = (since, liked_at) {
+                if liked_at < since {
+                    caught_up = true;
+                    break;
                 }
-                res
             }
-            Err(e) => {
-                warn!("Network error when fetching likes for user {}: {}", user_id, e);
+            
+            // Likes of playlists don't carry a track
+            let (Some(kind), Some(api_track)) = (like.kind, like.track) else {
+                continue;
+            };
+            if kind != "like" {
                 continue;
             }
-        };
-        
-        match response.json::<Value>().await {
-            Ok(json) => {
-                response_json = Some(json);
-                break;
+            let Some(track) = api_track.into_track() else {
+                warn!("Liked track missing ID in API response - skipping");
+                continue;
+            };
+            
+            // Skip if we've already seen this like
+            if !seen_like_ids.insert(track.id.clone()) {
+                debug!("Skipping duplicate like for track ID: {}", track.id);
+                continue;
             }
-            Err(e) => {
-                warn!("JSON parse error for likes response: {}", e);
-                if retry == max_retries - 1 {
-                    return Err(format!("Failed to parse JSON after {} retries", max_retries).into());
-                }
+            
+            debug!("Processing liked track: {} (ID: {})", track.title, track.id);
+            likes.push(Like {
+                created_at: like.created_at.unwrap_or_default(),
+                kind,
+                track,
+            });
+            batch_count += 1;
+            if likes.len() >= limit {
+                break;
             }
         }
-    }
-    
-    let json = match response_json {
-        Some(j) => j,
-        None => {
-            error!("Failed to fetch likes for user {} after {} retries", user_id, max_retries);
-            return Err(format!("Failed to fetch likes for user {} after {} retries", 
-                              user_id, max_retries).into());
+        
+        debug!("Added {} likes from page {}, total: {}", batch_count, pages, likes.len());
+        
+        if caught_up {
+            debug!("Reached likes from before {} on page {}, stopping", since.map(|s| s.to_rfc3339()).unwrap_or_default(), pages);
+            break;
         }
-    };
-    
-    // Extract the collection of likes
-    let collection = match json.get("collection") {
-        Some(Value::Array(arr)) => arr,
-        _ => {
-            error!("Unexpected API response format for user {}: missing 'collection' array", user_id);
-            return Err(format!("Unexpected API response format for user {}", user_id).into());
+        
+        // Pages of liked playlists add nothing, so only a repeated page means the cursor
+        // isn't moving
+        if page.next_href.as_deref() == Some(page_url.as_str()) {
+            warn!("Page {} of user {}'s likes links to itself, stopping", pages, user_id);
+            break;
         }
-    };
-    
-    if collection.is_empty() {
-        debug!("No likes found for user {}", user_id);
-        return Ok(Vec::new());
-    }
-    
-    debug!("Processing {} likes from response", collection.len());
-    
-    // Parse the likes
-    let mut batch_count = 0;
-    for like_json in collection {
-        // Each like contains a track
-        if let Some(track_json) = like_json.get("track") {
-            if let Some(kind) = like_json.get("kind").and_then(Value::as_str) {
-                if kind == "like" {
-                    // Parse the created_at date
-                    let created_at = like_json.get("created_at")
-                        .and_then(Value::as_str)
-                        .unwrap_or("")
-                        .to_string();
-                    
-                    // Extract track
-                    if let Some(id) = track_json.get("id").and_then(Value::as_u64) {
-                        let track_id = id.to_string();
-                        
-                        // Skip if we've already seen this like
-                        if !seen_like_ids.insert(track_id.clone()) {
-                            debug!("Skipping duplicate like for track ID: {}", track_id);
-                            continue;
-                        }
-                        
-                        let title = track_json.get("title")
-                            .and_then(Value::as_str)
-                            .unwrap_or("Untitled")
-                            .to_string();
-                        
-                        debug!("Processing liked track: {} (ID: {})", title, id);
-                        
-                        let track = Track {
-                            id: track_id,
-                            title,
-                            permalink_url: track_json.get("permalink_url")
-                                .and_then(Value::as_str)
-                                .unwrap_or("")
-                                .to_string(),
-                            artwork_url: track_json.get("artwork_url")
-                                .and_then(Value::as_str)
-                                .map(String::from),
-                            description: track_json.get("description")
-                                .and_then(Value::as_str)
-                                .map(String::from),
-                            user: parse_track_user(track_json),
-                            created_at: track_json.get("created_at")
-                                .and_then(Value::as_str)
-                                .unwrap_or("")
-                                .to_string(),
-                            duration: track_json.get("duration")
-                                .and_then(Value::as_u64)
-                                .unwrap_or(0),
-                            stream_url: track_json.get("stream_url")
-                                .and_then(Value::as_str)
-                                .map(String::from),
-                            hls_url: None, // Will be populated when needed
-                            download_url: track_json.get("download_url")
-                                .and_then(Value::as_str)
-                                .map(String::from),
-                            // Stats
-                            playback_count: track_json.get("playback_count").and_then(Value::as_u64),
-                            likes_count: track_json.get("likes_count").and_then(Value::as_u64),
-                            reposts_count: track_json.get("reposts_count").and_then(Value::as_u64),
-                            comment_count: track_json.get("comment_count").and_then(Value::as_u64),
-                            // Additional metadata
-                            genre: track_json.get("genre").and_then(Value::as_str).map(String::from),
-                            tag_list: track_json.get("tag_list").and_then(Value::as_str).map(String::from),
-                            downloadable: track_json.get("downloadable").and_then(Value::as_bool),
-                            raw_data: Some(track_json.clone()),
-                        };
-                        
-                        // Create the like structure
-                        let like = Like {
-                            created_at,
-                            kind: kind.to_string(),
-                            track,
-                        };
-                        
-                        likes.push(like);
-                        batch_count += 1;
-                    }
-                }
-            }
+        
+        next_url = page.next_href;
+        if next_url.is_none() {
+            debug!("No next_href found, this is the last page");
         }
     }
     
-    debug!("Added {} likes from batch, total: {}", batch_count, likes.len());
-    
     info!("Successfully fetched {} likes for user {}", likes.len(), user_id);
     Ok(likes)
 }
@@ -1090,7 +2049,11 @@ pub async fn display_soundcloud_info(url: &str) -> Result<(), Box<dyn std::error
                 
                 // Get detailed track info
                 debug!("Fetching detailed track information");
-                let track = get_track_details(&track_id).await?;
+                let secret_token = resolved.get("secret_token")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+                    .or_else(|| secret_token_from_url(url));
+                let track = get_track_details(&track_id, secret_token.as_deref()).await?;
                 
                 // Print track details
                 println!("\nTrack Information:");
@@ -1173,55 +2136,211 @@ pub async fn display_soundcloud_info(url: &str) -> Result<(), Box<dyn std::error
     Ok(())
 }
 
+/// Optional steps run around posting each track, all configured in config.json
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessing {
+    pub hooks: crate::hooks::Hooks,
+    pub plugins: crate::plugins::Plugins,
+    pub library: crate::library::Library,
+    pub enrichment: crate::musicbrainz::Enrichment,
+    pub outbox: crate::outbox::Outbox,
+    /// Tracks that are never archived
+    pub ignore_list: crate::ignore::IgnoreList,
+    /// Attach the track's comments as comments.json
+    pub archive_comments: bool,
+    /// Attach a snapshot of the track's page as page.html
+    pub archive_page_html: bool,
+    /// Attach the track's animated cover, if it has one
+    pub archive_animated_artwork: bool,
+    /// Show why a polled track was posted in its embed footer
+    pub provenance_badge: bool,
+    /// Stop downloading further formats of a track past this many bytes
+    pub max_download_bytes: Option<u64>,
+    /// Post tracks with their artwork and metadata but without downloading audio
+    pub metadata_only: bool,
+    /// Template for the plain text line above each embed
+    pub post_content: Option<String>,
+    /// How posts are retried while Discord is briefly unavailable
+    pub webhook_retry: crate::discord::WebhookRetry,
+    /// Also save the track's waveform as an SVG image
+    pub render_waveform: bool,
+    /// Tag downloaded audio with its ReplayGain
+    pub replaygain: bool,
+    /// Which optional parts of track embeds are shown
+    pub embed_fields: crate::discord::EmbedFields,
+    /// More webhooks tracks are posted to after the main one, as (name, URL)
+    pub extra_webhooks: Vec<(String, String)>,
+    /// Where track details, comments and banners are fetched from
+    pub api: crate::soundcloud_api::SharedApi,
+}
+
+impl PostProcessing {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        PostProcessing {
+            hooks: crate::hooks::Hooks::from_config(config),
+            plugins: crate::plugins::Plugins::from_config(config),
+            library: crate::library::Library::from_config(config),
+            enrichment: crate::musicbrainz::Enrichment::from_config(config),
+            outbox: crate::outbox::Outbox::from_config(config),
+            ignore_list: crate::ignore::IgnoreList::from_config(config),
+            archive_comments: config.archive_comments,
+            archive_page_html: config.archive_page_html,
+            archive_animated_artwork: config.archive_animated_artwork,
+            provenance_badge: config.provenance_badge,
+            max_download_bytes: Some(config.max_track_download_mb.saturating_mul(1024 * 1024)).filter(|max| *max > 0),
+            metadata_only: config.metadata_only,
+            post_content: config.post_content.clone().filter(|c| !c.trim().is_empty()),
+            webhook_retry: crate::discord::WebhookRetry::from_config(config),
+            render_waveform: config.render_waveform,
+            replaygain: config.replaygain,
+            embed_fields: crate::discord::EmbedFields::from_config(config),
+            extra_webhooks: config.extra_webhooks.iter().map(|(name, url)| (name.clone(), url.clone())).collect(),
+            api: Default::default(),
+        }
+    }
+
+    /// Names of all `extra_webhooks` destinations
+    pub fn extra_webhook_names(&self) -> Vec<String> {
+        self.extra_webhooks.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Fetch from `api` instead of the live SoundCloud API
+    pub fn with_api(mut self, api: Arc<dyn crate::soundcloud_api::SoundCloudApi>) -> Self {
+        self.api = crate::soundcloud_api::SharedApi(api);
+        self
+    }
+}
+
+/// Fetch a track's comments and save them as comments.json next to its downloaded files
+///
+/// Returns the file as (path, name), or `None` if there's no download folder to save it in.
+async fn save_track_comments(
+    api: &dyn crate::soundcloud_api::SoundCloudApi,
+    track: &Track,
+    files: &[(String, String)]
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(dir) = files.first().and_then(|(path, _)| std::path::Path::new(path).parent()) else {
+        return Ok(None);
+    };
+    
+    let comments = api.track_comments(&track.id, track.secret_token.as_deref()).await?;
+    let path = dir.join("comments.json");
+    let json = serde_json::json!({
+        "track_id": track.id,
+        "fetched_at": chrono::Utc::now().to_rfc3339(),
+        "comments": comments,
+    });
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&json)?).await?;
+    info!("Saved {} comments of track {}", comments.len(), track.id);
+    
+    Ok(Some((path.to_string_lossy().to_string(), "comments.json".to_string())))
+}
+
+/// Save the track's public page as page.html next to its downloaded files
+///
+/// The page keeps the description formatting, buy links and layout that the API JSON
+/// doesn't have. Returns the file as (path, name), or `None` if there's no download
+/// folder to save it in.
+async fn save_track_page(
+    track: &Track,
+    files: &[(String, String)]
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(dir) = files.first().and_then(|(path, _)| std::path::Path::new(path).parent()) else {
+        return Ok(None);
+    };
+    if track.permalink_url.is_empty() {
+        return Err("Track has no page URL".into());
+    }
+    
+    let html = get_page_html(&track.permalink_url).await?;
+    let path = dir.join("page.html");
+    tokio::fs::write(&path, &html).await?;
+    info!("Saved page of track {} ({} bytes)", track.id, html.len());
+    
+    Ok(Some((path.to_string_lossy().to_string(), "page.html".to_string())))
+}
+
+/// Download the track's animated cover as cover_animated.gif (or .mp4/.webm) next to its
+/// downloaded files
+///
+/// Returns the file as (path, name), or `None` if there's no download folder to save it in.
+async fn save_animated_cover(
+    track: &Track,
+    cover: &AnimatedCover,
+    files: &[(String, String)]
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(dir) = files.first().and_then(|(path, _)| std::path::Path::new(path).parent()) else {
+        return Ok(None);
+    };
+    
+    let response = HTTP_CLIENT.get(&cover.url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {} for {}", response.status(), cover.url).into());
+    }
+    let bytes = response.bytes().await?;
+    crate::metrics::add_bytes_downloaded(bytes.len() as u64);
+    
+    let name = format!("cover_animated.{}", cover.extension);
+    let path = dir.join(&name);
+    tokio::fs::write(&path, &bytes).await?;
+    info!("Saved animated cover of track {} ({} bytes)", track.id, bytes.len());
+    
+    Ok(Some((path.to_string_lossy().to_string(), name)))
+}
+
+/// Delete a track's downloaded files
+async fn delete_temp_files(files: &[(String, String)]) {
+    for (path, _) in files {
+        if let Err(e) = crate::audio::delete_temp_file(path).await {
+            warn!("Failed to clean up temp file {}: {}", path, e);
+        }
+    }
+}
+
+/// A track that was archived and posted by [`process_and_post_track`]
+#[derive(Debug, Clone)]
+pub struct PostedTrack {
+    pub track_id: String,
+    /// The uploader's user ID
+    pub user_id: String,
+    pub response: crate::discord::WebhookResponse,
+    /// The track's details as fetched for posting
+    pub track: Track,
+    /// MusicBrainz metadata, if enrichment is enabled and the track was matched
+    pub metadata: Option<crate::musicbrainz::TrackMetadata>,
+    /// Why the track's audio wasn't (fully) archived, if it wasn't
+    pub skip: Option<(crate::db::SkipReason, String)>,
+    /// Where the cover art came from, if the track has any
+    pub artwork_source: Option<ArtworkSource>,
+    /// The responses of the `extra_webhooks` destinations the track was posted to, by name
+    pub destinations: Vec<(String, crate::discord::WebhookResponse)>,
+    /// The `extra_webhooks` destinations the track couldn't be posted to yet
+    pub pending_destinations: Vec<String>,
+}
+
 /// Process and post a single track to Discord
 /// 
 /// Takes either a track ID or URL, resolves it, processes the audio, and posts to Discord.
 /// Returns the Discord message ID and track ID for further processing.
+///
+/// `provenance` says why the track was picked up, for tracks found by polling a user.
+/// A private track needs its `secret_token`, unless `id_or_url` is its share link.
+#[tracing::instrument(name = "process_track", skip_all, fields(track = %id_or_url))]
 pub async fn process_and_post_track(
     id_or_url: &str,
+    secret_token: Option<&str>,
     discord_webhook_url: &str,
     temp_dir: Option<&str>,
-    discord_semaphore: Option<&Arc<tokio::sync::Semaphore>>
-) -> Result<(String, String, crate::discord::WebhookResponse), Box<dyn std::error::Error + Send + Sync>> {
-    // Check if this is a URL or an ID
-    let track_id = if id_or_url.starts_with("http") {
-        // This is a URL, resolve it
-        info!("Resolving SoundCloud URL: {}", id_or_url);
-        let resolved = match resolve_url(id_or_url).await {
-            Ok(data) => data,
-            Err(e) => {
-                error!("Failed to resolve URL: {}", e);
-                return Err(e);
-            }
-        };
-        
-        // Check if it's a track
-        if let Some(kind) = resolved.get("kind").and_then(|v| v.as_str()) {
-            if kind == "track" {
-                if let Some(id) = resolved.get("id").and_then(|v| v.as_u64()) {
-                    let track_id = id.to_string();
-                    info!("URL resolved to track ID: {}", track_id);
-                    track_id
-                } else {
-                    error!("Could not extract track ID from resolved URL");
-                    return Err("Could not extract track ID from resolved URL".into());
-                }
-            } else {
-                error!("URL does not point to a track, but to a {}", kind);
-                return Err(format!("URL points to a {}, not a track", kind).into());
-            }
-        } else {
-            error!("Could not determine object type from resolved URL");
-            return Err("Could not determine object type from resolved URL".into());
-        }
-    } else {
-        // Assume this is a track ID
-        id_or_url.to_string()
-    };
+    discord_semaphore: Option<&Arc<tokio::sync::Semaphore>>,
+    post_processing: &PostProcessing,
+    provenance: Option<Provenance>
+) -> Result<PostedTrack, Box<dyn std::error::Error + Send + Sync>> {
+    let (track_id, resolved_token) = resolve_track(id_or_url).await?;
+    let secret_token = secret_token.map(String::from).or(resolved_token);
     
     // Get track details
     info!("Fetching track details for ID: {}", track_id);
-    let track_details = match get_track_details(&track_id).await {
+    let track_details = match get_track_details(&track_id, secret_token.as_deref()).await {
         Ok(t) => {
             info!("Successfully fetched track: {} by {}", t.title, t.user.username);
             t
@@ -1232,10 +2351,46 @@ pub async fn process_and_post_track(
         }
     };
     
+    post_track(track_details, discord_webhook_url, temp_dir, discord_semaphore, post_processing, provenance).await
+}
+
+/// Process and post a track whose details were already fetched
+///
+/// Does everything [`process_and_post_track`] does after fetching the track's details,
+/// for callers that fetch them through a [`crate::soundcloud_api::SoundCloudApi`].
+#[tracing::instrument(name = "post_track", skip_all, fields(track_id = %track_details.id))]
+pub async fn post_track(
+    mut track_details: Track,
+    discord_webhook_url: &str,
+    temp_dir: Option<&str>,
+    discord_semaphore: Option<&Arc<tokio::sync::Semaphore>>,
+    post_processing: &PostProcessing,
+    provenance: Option<Provenance>
+) -> Result<PostedTrack, Box<dyn std::error::Error + Send + Sync>> {
+    let track_id = track_details.id.clone();
+    post_processing.ignore_list.check(&track_details)?;
+    // Don't download anything for a webhook that was deleted
+    crate::discord::check_webhook(discord_webhook_url)?;
+    
+    // Find artwork that can actually be fetched, for both the download and the embed
+    let artwork_source = resolve_cover_art(&mut track_details).await;
+    
+    // Hold the track back while the temp directory is as full as allowed
+    let estimate = if post_processing.metadata_only {
+        0
+    } else {
+        crate::tempspace::estimate(full_duration(&track_details), post_processing.max_download_bytes)
+    };
+    let mut temp_space = crate::tempspace::Reservation::acquire(estimate).await;
+    
     // Download and process audio
     info!("Processing audio and artwork for track");
-    let processing_result = match crate::audio::process_track_audio(&track_details, temp_dir).await {
-        Ok((audio_files, artwork, json)) => {
+    let mut audio_count = 0;
+    let mut processing_result = match crate::audio::process_track_audio(
+        &track_details, temp_dir, post_processing.max_download_bytes, !post_processing.metadata_only, post_processing.render_waveform, post_processing.replaygain
+    ).await {
+        Ok((audio_files, artwork, json, waveform)) => {
+            audio_count = audio_files.len();
             let mut files = Vec::new();
             
             // Process all audio files
@@ -1275,6 +2430,17 @@ pub async fn process_and_post_track(
                 files.push((file_path, filename));
             }
             
+            for path in waveform {
+                let filename = std::path::Path::new(&path)
+                    .file_name()
+                    .unwrap_or_else(|| std::ffi::OsStr::new("waveform.json"))
+                    .to_string_lossy()
+                    .to_string();
+                
+                info!("Saved waveform: {}", filename);
+                files.push((path, filename));
+            }
+            
             files
         },
         Err(e) => {
@@ -1283,11 +2449,42 @@ pub async fn process_and_post_track(
         }
     };
     
+    // Keep the comments next to the other files, they're context the audio doesn't capture
+    if post_processing.archive_comments {
+        match save_track_comments(&*post_processing.api, &track_details, &processing_result).await {
+            Ok(Some(file)) => processing_result.push(file),
+            Ok(None) => debug!("No downloaded files for track {}, not archiving its comments", track_id),
+            Err(e) => warn!("Failed to archive comments of track {}: {}", track_id, e),
+        }
+    }
+    if post_processing.archive_page_html {
+        match save_track_page(&track_details, &processing_result).await {
+            Ok(Some(file)) => processing_result.push(file),
+            Ok(None) => debug!("No downloaded files for track {}, not archiving its page", track_id),
+            Err(e) => warn!("Failed to archive page of track {}: {}", track_id, e),
+        }
+    }
+    if let Some(cover) = animated_cover(&track_details).filter(|_| post_processing.archive_animated_artwork) {
+        match save_animated_cover(&track_details, &cover, &processing_result).await {
+            Ok(Some(file)) => processing_result.push(file),
+            Ok(None) => debug!("No downloaded files for track {}, not archiving its animated cover", track_id),
+            Err(e) => warn!("Failed to archive animated cover of track {}: {}", track_id, e),
+        }
+    }
+    
+    temp_space.resize(processing_result.iter()
+        .filter_map(|(path, _)| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum());
+    
+    // Look the track up on MusicBrainz and tag the files before they're uploaded
+    let metadata = post_processing.enrichment.enrich(&track_details, &processing_result).await;
+    
     // Send to Discord
     info!("Sending webhook for track: {} by {}", track_details.title, track_details.user.username);
     
     // Acquire Discord semaphore if provided
-    let _discord_permit = if let Some(semaphore) = discord_semaphore {
+    let discord_permit = if let Some(semaphore) = discord_semaphore {
         match semaphore.acquire().await {
             Ok(permit) => Some(permit),
             Err(e) => {
@@ -1299,7 +2496,25 @@ pub async fn process_and_post_track(
         None
     };
     
-    let webhook_response = match crate::discord::send_track_webhook(discord_webhook_url, &track_details, Some(processing_result.clone())).await {
+    // Tracks from playlists are usually someone else's, so they always say where they came from
+    let badge = provenance
+        .filter(|p| post_processing.provenance_badge || matches!(p, Provenance::Playlist { .. }))
+        .map(|p| p.badge());
+    let (embed, files) = crate::discord::prepare_track_post(&*post_processing.api, &track_details, Some(processing_result.clone()), badge.as_deref(), post_processing.metadata_only, post_processing.embed_fields, &post_processing.plugins).await?;
+    // Leaving the audio out on purpose isn't a skip
+    let skip = if post_processing.metadata_only {
+        None
+    } else {
+        audio_skip_reason(&track_details, audio_count, &files)
+    };
+    if let Some((reason, detail)) = &skip {
+        warn!("Track {} audio skipped ({}): {}", track_id, reason, detail);
+    }
+    let content = post_processing.post_content.as_deref().map(|template| crate::discord::render_post_content(
+        template, &track_details.user.username, &track_details.title, &track_details.permalink_url));
+    let webhook_response = match crate::discord::send_track_post(
+        discord_webhook_url, &track_details, embed.clone(), content.as_deref(), files.clone(), post_processing.webhook_retry
+    ).await {
         Ok(response) => {
             info!("Successfully sent webhook for track with message ID: {}", response.message_id);
             println!("Track successfully posted to Discord: {} by {}", 
@@ -1307,18 +2522,313 @@ pub async fn process_and_post_track(
             println!("Discord message ID: {}", response.message_id);
             response
         },
+        Err(e) if e.is::<crate::discord::DiscordUnavailable>() && post_processing.outbox.is_enabled() => {
+            // Keep the finished downloads so the track can be posted once Discord is back
+            drop(discord_permit);
+            let details = crate::outbox::PostDetails { metadata, skip, artwork_source };
+            post_processing.outbox.enqueue(discord_webhook_url, &track_details, embed, content.as_deref(), &files, details).await?;
+            delete_temp_files(&processing_result).await;
+            drop(temp_space);
+            return Err(Box::new(crate::outbox::Queued(track_id)));
+        },
         Err(e) => {
             error!("Failed to send webhook: {}", e);
             return Err(e);
         }
     };
+    // Files that didn't make it into the post follow in their own messages
+    if !webhook_response.unattached_files.is_empty() {
+        post_processing.outbox.upload_or_queue(discord_webhook_url, &track_details, &webhook_response.unattached_files).await;
+    }
     
-    // Clean up temp files
-    for (path, _) in processing_result.clone() {
-        if let Err(e) = crate::audio::delete_temp_file(&path).await {
-            warn!("Failed to clean up temp file {}: {}", path, e);
+    // The same post goes to every other destination. The track counts as archived once the
+    // main webhook has it, so a destination that fails is retried on later polls.
+    let mut destinations = Vec::new();
+    let mut pending_destinations = Vec::new();
+    for (name, url) in &post_processing.extra_webhooks {
+        match crate::discord::send_track_post(
+            url, &track_details, embed.clone(), content.as_deref(), files.clone(), post_processing.webhook_retry
+        ).await {
+            Ok(response) => {
+                info!("Posted track {} to destination {}, message ID: {}", track_id, name, response.message_id);
+                if !response.unattached_files.is_empty() {
+                    post_processing.outbox.upload_or_queue(url, &track_details, &response.unattached_files).await;
+                }
+                destinations.push((name.clone(), response));
+            },
+            Err(e) if e.is::<crate::discord::WebhookGone>() => {
+                debug!("Not posting track {} to destination {} yet: {}", track_id, name, e);
+                pending_destinations.push(name.clone());
+            },
+            Err(e) => {
+                error!("Failed to post track {} to destination {}, retrying on the next poll: {}", track_id, name, e);
+                post_processing.hooks.error(&format!("Failed to post to destination {}: {}", name, e), Some(&track_details.user.id), Some(&track_id));
+                pending_destinations.push(name.clone());
+            }
         }
     }
+    drop(discord_permit);
+    
+    // Keep a local copy and run the hook before cleanup, while the downloaded files still exist
+    post_processing.library.add_track(&track_details, &processing_result).await;
+    post_processing.hooks.track_archived(&track_details, &webhook_response, &processing_result).await;
+    
+    // Clean up temp files
+    delete_temp_files(&processing_result).await;
+    drop(temp_space);
+    
+    Ok(PostedTrack {
+        track_id,
+        user_id: track_details.user.id.clone(),
+        response: webhook_response,
+        track: track_details,
+        metadata,
+        skip,
+        artwork_source,
+        destinations,
+        pending_destinations,
+    })
+}
+
+/// Why some or all of a track's audio isn't being archived, if that's the case
+fn audio_skip_reason(track: &Track, audio_count: usize, attached: &[(String, String)]) -> Option<(crate::db::SkipReason, String)> {
+    use crate::db::SkipReason;
+    
+    let format_duration = |ms: u64| format!("{}:{:02}", ms / 1000 / 60, (ms / 1000) % 60);
+    match track_restriction(track) {
+        Some(Restriction::GeoBlocked) => return Some((SkipReason::GeoBlocked, "SoundCloud blocks this track in the archiver's region".to_string())),
+        Some(Restriction::Snippet) => {
+            let monetization = track.raw_data.as_ref()
+                .and_then(|d| d.get("monetization_model"))
+                .and_then(Value::as_str)
+                .filter(|m| !m.is_empty() && *m != "NOT_APPLICABLE");
+            let detail = format!("only a {} preview of the {} track is available without SoundCloud Go+{}",
+                                 format_duration(track.duration), format_duration(full_duration(track)),
+                                 monetization.map(|m| format!(" (monetization model {})", m)).unwrap_or_default());
+            return Some((SkipReason::PremiumOnly, detail));
+        },
+        None => {}
+    }
+    
+    if audio_count == 0 {
+        return Some((SkipReason::Failed, "no audio could be downloaded".to_string()));
+    }
+    
+    let oversized = crate::discord::oversized_files(attached);
+    if !oversized.is_empty() {
+        return Some((SkipReason::TooLarge, format!("not attached, over Discord's size limit: {}", oversized.join(", "))));
+    }
     
-    Ok((track_id, track_details.user.id.clone(), webhook_response))
-} 
\ No newline at end of file
+    None
+} 
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_secret_token_from_query() {
+        assert_eq!(
+            secret_token_from_url("https://soundcloud.com/artist/track?secret_token=s-AbC123&utm_source=clipboard").as_deref(),
+            Some("s-AbC123")
+        );
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/track?secret_token="), None);
+    }
+
+    #[test]
+    fn reads_secret_token_from_path() {
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/track/s-AbC123").as_deref(), Some("s-AbC123"));
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/track/s-AbC123/").as_deref(), Some("s-AbC123"));
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/track/s-"), None);
+    }
+
+    #[test]
+    fn public_s_slug_is_not_a_secret_token() {
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/s-club-remix"), None);
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/sets/s-playlist"), None);
+        assert_eq!(secret_token_from_url("https://soundcloud.com/artist/track"), None);
+    }
+
+    fn test_retry() -> ApiRetry {
+        ApiRetry { retries: 5, base_delay: Duration::from_secs(2), max_delay: Duration::from_secs(30) }
+    }
+
+    fn test_now() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().to_utc()
+    }
+
+    #[test]
+    fn retry_delay_stays_within_jitter_bounds() {
+        let retry = test_retry();
+        for (attempt, backoff) in [(1, 2), (2, 4), (3, 8), (4, 16)] {
+            let backoff = Duration::from_secs(backoff);
+            assert_eq!(retry.delay_at(attempt, None, test_now(), 0.0), backoff / 2);
+            assert_eq!(retry.delay_at(attempt, None, test_now(), 0.5), backoff);
+            assert!(retry.delay_at(attempt, None, test_now(), 1.0) <= backoff.mul_f64(1.5));
+        }
+        // Later retries and full jitter never go past the maximum delay
+        for jitter in [0.0, 0.5, 1.0] {
+            assert!(retry.delay_at(5, None, test_now(), jitter) <= retry.max_delay);
+            assert!(retry.delay_at(u32::MAX, None, test_now(), jitter) <= retry.max_delay);
+        }
+        assert_eq!(retry.delay_at(u32::MAX, None, test_now(), 0.0), retry.max_delay / 2);
+    }
+
+    #[test]
+    fn retry_delay_follows_retry_after_seconds() {
+        let retry = test_retry();
+        assert_eq!(retry.delay_at(1, Some("7"), test_now(), 1.0), Duration::from_secs(7));
+        assert_eq!(retry.delay_at(1, Some(" 0 "), test_now(), 1.0), Duration::ZERO);
+        assert_eq!(retry.delay_at(1, Some("3600"), test_now(), 0.0), retry.max_delay);
+        // Unparseable values fall back to the backoff
+        assert_eq!(retry.delay_at(1, Some("soon"), test_now(), 0.5), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retry_delay_follows_retry_after_date() {
+        let retry = test_retry();
+        assert_eq!(retry.delay_at(1, Some("Wed, 21 Oct 2015 07:28:10 GMT"), test_now(), 1.0), Duration::from_secs(10));
+        assert_eq!(retry.delay_at(1, Some("Wed, 21 Oct 2015 08:28:00 GMT"), test_now(), 0.0), retry.max_delay);
+        assert_eq!(retry.delay_at(1, Some("Wed, 21 Oct 2015 07:27:00 GMT"), test_now(), 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn parses_v2_track_details() {
+        let json = json!({
+            "id": 123456789,
+            "kind": "track",
+            "title": "Some Track",
+            "permalink_url": "https://soundcloud.com/someone/some-track",
+            "artwork_url": "https://i1.sndcdn.com/artworks-abc-large.jpg",
+            "description": "Out now",
+            "user": {
+                "id": 42,
+                "kind": "user",
+                "username": "someone",
+                "permalink_url": "https://soundcloud.com/someone",
+                "avatar_url": "https://i1.sndcdn.com/avatars-xyz-large.jpg",
+                "followers_count": 1000
+            },
+            "created_at": "2026-01-02T03:04:05Z",
+            "duration": 215000,
+            "full_duration": 215000,
+            "stream_url": null,
+            "download_url": null,
+            "playback_count": 1500,
+            "likes_count": 20,
+            "reposts_count": 3,
+            "comment_count": 4,
+            "genre": "Electronic",
+            "tag_list": "\"deep house\" ambient",
+            "downloadable": false,
+            "media": { "transcodings": [] },
+            "waveform_url": "https://wave.sndcdn.com/abc_m.json"
+        });
+
+        let track = track_from_json(json.clone(), Some("s-token")).unwrap();
+        assert_eq!(track.id, "123456789");
+        assert_eq!(track.title, "Some Track");
+        assert_eq!(track.artwork_url.as_deref(), Some("https://i1.sndcdn.com/artworks-abc-original.jpg"));
+        assert_eq!(track.user.id, "42");
+        assert_eq!(track.user.username, "someone");
+        assert_eq!(track.user.avatar_url.as_deref(), Some("https://i1.sndcdn.com/avatars-xyz-original.jpg"));
+        assert_eq!(track.duration, 215000);
+        assert_eq!(track.playback_count, Some(1500));
+        assert_eq!(track.stream_url, None);
+        assert_eq!(track.downloadable, Some(false));
+        assert_eq!(track.secret_token.as_deref(), Some("s-token"));
+        assert_eq!(track.raw_data, Some(json));
+    }
+
+    #[test]
+    fn fills_in_missing_and_null_track_fields() {
+        let api_track: ApiTrack = serde_json::from_value(json!({
+            "id": 5,
+            "title": null,
+            "user": null,
+            "duration": null
+        })).unwrap();
+        let track = api_track.into_track().unwrap();
+        assert_eq!(track.id, "5");
+        assert_eq!(track.title, "Untitled");
+        assert_eq!(track.permalink_url, "");
+        assert_eq!(track.user.id, "");
+        assert_eq!(track.user.username, "Unknown Artist");
+        assert_eq!(track.user.avatar_url, None);
+        assert_eq!(track.created_at, "");
+        assert_eq!(track.duration, 0);
+        assert_eq!(track.playback_count, None);
+        assert_eq!(track.secret_token, None);
+    }
+
+    #[test]
+    fn rejects_tracks_without_an_id() {
+        let api_track: ApiTrack = serde_json::from_value(json!({ "title": "No ID" })).unwrap();
+        assert!(api_track.into_track().is_none());
+        assert!(track_from_json(json!({ "id": null, "title": "No ID" }), None).is_err());
+        assert!(track_from_json(json!({ "id": "not a number" }), None).is_err());
+    }
+
+    #[test]
+    fn parses_a_user_page() {
+        let page: ApiCollection<ApiUser> = serde_json::from_value(json!({
+            "collection": [
+                {
+                    "id": 42,
+                    "kind": "user",
+                    "username": "someone",
+                    "permalink_url": "https://soundcloud.com/someone",
+                    "avatar_url": null,
+                    "track_count": 12,
+                    "followers_count": 1000,
+                    "visuals": null
+                },
+                { "id": 43 }
+            ],
+            "next_href": null,
+            "query_urn": null
+        })).unwrap();
+        assert_eq!(page.next_href, None);
+        assert_eq!(page.collection.len(), 2);
+        assert_eq!(page.collection[0].track_count, Some(12));
+
+        let user: TrackUser = page.collection[1].clone().into();
+        assert_eq!(user.id, "43");
+        assert_eq!(user.username, "Unknown Artist");
+        assert_eq!(user.permalink_url, "");
+    }
+
+    #[test]
+    fn parses_a_likes_page() {
+        let page: ApiCollection<ApiLike> = serde_json::from_value(json!({
+            "collection": [
+                {
+                    "created_at": "2026-03-04T05:06:07Z",
+                    "kind": "like",
+                    "track": { "id": 7, "title": "Liked", "user": { "id": 8, "username": "artist" } }
+                },
+                {
+                    "created_at": "2026-03-03T00:00:00Z",
+                    "kind": "like",
+                    "playlist": { "id": 9, "title": "A playlist" }
+                },
+                { "kind": "like", "track": null }
+            ],
+            "next_href": "https://api-v2.soundcloud.com/users/1/likes?offset=abc"
+        })).unwrap();
+        assert_eq!(page.next_href.as_deref(), Some("https://api-v2.soundcloud.com/users/1/likes?offset=abc"));
+        assert_eq!(page.collection.len(), 3);
+
+        let first = &page.collection[0];
+        assert_eq!(first.kind.as_deref(), Some("like"));
+        let track = first.track.clone().unwrap().into_track().unwrap();
+        assert_eq!(track.id, "7");
+        assert_eq!(track.user.id, "8");
+
+        // Playlist likes and likes of removed tracks carry no track
+        assert!(page.collection[1].track.is_none());
+        assert!(page.collection[2].track.is_none());
+        assert!(page.collection[2].created_at.is_none());
+    }
+}
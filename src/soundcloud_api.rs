@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::soundcloud::{self, ApiUser, Comment, Like, Track, UserTracks, UserUnavailable};
+
+/// Future returned by [`SoundCloudApi`] methods
+pub type ApiFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>> + Send + 'a>>;
+
+/// The SoundCloud requests the polling pipeline and the CLI make
+///
+/// [`SoundCloudClient`] talks to the live API; [`FixtureApi`] answers from fixture data
+/// instead, so a poll can be run without network access.
+pub trait SoundCloudApi: Send + Sync {
+    /// Get a client ID, if this API needs one
+    fn initialize(&self) -> ApiFuture<'_, ()>;
+
+    /// Resolve a track ID or URL to the track ID and, for private tracks, its secret token
+    fn resolve_track<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, (String, Option<String>)>;
+
+    /// Resolve a user ID or profile URL to the user ID
+    fn resolve_user_id<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, String>;
+
+    /// Get everything about a track, including its raw JSON
+    fn track_details<'a>(&'a self, track_id: &'a str, secret_token: Option<&'a str>) -> ApiFuture<'a, Track>;
+
+    /// Get the details of several public tracks at once, leaving out any that weren't found
+    ///
+    /// The waveform and HLS playlist aren't resolved, see
+    /// [`soundcloud::get_tracks_details_batch`].
+    fn tracks_details<'a>(&'a self, track_ids: &'a [String]) -> ApiFuture<'a, Vec<Track>>;
+
+    /// Resolve the stream URLs of a track from [`SoundCloudApi::tracks_details`]
+    fn resolve_track_media(&self, track: Track) -> ApiFuture<'_, Track>;
+
+    /// Check whether a track is still on SoundCloud
+    fn track_exists<'a>(&'a self, track_id: &'a str) -> ApiFuture<'a, bool>;
+
+    /// Get all comments on a track, oldest first
+    fn track_comments<'a>(&'a self, track_id: &'a str, secret_token: Option<&'a str>) -> ApiFuture<'a, Vec<Comment>>;
+
+    /// Get a playlist by ID or URL as JSON
+    fn playlist<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, Value>;
+
+    /// Get a user's profile as JSON
+    fn user_details<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Value>;
+
+    /// Get the URL of a user's profile banner, if they have one
+    fn user_banner_url<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Option<String>>;
+
+    /// Get the tracks and playlists a user has pinned to their profile spotlight
+    fn user_spotlight<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>>;
+
+    /// Get a user's most played tracks
+    fn user_top_tracks<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>>;
+
+    /// Get a user's most recent albums, EPs and other sets marked as releases
+    fn user_albums<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>>;
+
+    /// Get up to `limit` of a user's uploads
+    fn user_tracks<'a>(&'a self, user_id: &'a str, limit: usize, pagination_size: usize) -> ApiFuture<'a, UserTracks>;
+
+    /// Get up to `limit` of a user's likes, newest first, leaving out those older than `since`
+    fn user_likes<'a>(&'a self, user_id: &'a str, limit: usize, pagination_size: usize, since: Option<DateTime<Utc>>) -> ApiFuture<'a, Vec<Like>>;
+
+    /// Get the users a user follows
+    fn user_followings<'a>(&'a self, user_id: &'a str, limit: Option<usize>) -> ApiFuture<'a, Vec<ApiUser>>;
+}
+
+/// The live SoundCloud API, through the functions in [`crate::soundcloud`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoundCloudClient;
+
+impl SoundCloudApi for SoundCloudClient {
+    fn initialize(&self) -> ApiFuture<'_, ()> {
+        Box::pin(soundcloud::initialize())
+    }
+
+    fn resolve_track<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, (String, Option<String>)> {
+        Box::pin(soundcloud::resolve_track(id_or_url))
+    }
+
+    fn resolve_user_id<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, String> {
+        Box::pin(soundcloud::resolve_user_id(id_or_url))
+    }
+
+    fn track_details<'a>(&'a self, track_id: &'a str, secret_token: Option<&'a str>) -> ApiFuture<'a, Track> {
+        Box::pin(soundcloud::get_track_details(track_id, secret_token))
+    }
+
+    fn tracks_details<'a>(&'a self, track_ids: &'a [String]) -> ApiFuture<'a, Vec<Track>> {
+        Box::pin(soundcloud::get_tracks_details_batch(track_ids))
+    }
+
+    fn resolve_track_media(&self, mut track: Track) -> ApiFuture<'_, Track> {
+        Box::pin(async move {
+            soundcloud::resolve_track_media(&mut track).await;
+            Ok(track)
+        })
+    }
+
+    fn track_exists<'a>(&'a self, track_id: &'a str) -> ApiFuture<'a, bool> {
+        Box::pin(soundcloud::track_exists(track_id))
+    }
+
+    fn track_comments<'a>(&'a self, track_id: &'a str, secret_token: Option<&'a str>) -> ApiFuture<'a, Vec<Comment>> {
+        Box::pin(soundcloud::get_track_comments(track_id, secret_token))
+    }
+
+    fn playlist<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, Value> {
+        Box::pin(soundcloud::get_playlist(id_or_url))
+    }
+
+    fn user_details<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Value> {
+        Box::pin(soundcloud::get_user_details(user_id))
+    }
+
+    fn user_banner_url<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Option<String>> {
+        Box::pin(soundcloud::get_user_banner_url(user_id))
+    }
+
+    fn user_spotlight<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(soundcloud::get_user_spotlight(user_id))
+    }
+
+    fn user_top_tracks<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(soundcloud::get_user_top_tracks(user_id))
+    }
+
+    fn user_albums<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(soundcloud::get_user_albums(user_id))
+    }
+
+    fn user_tracks<'a>(&'a self, user_id: &'a str, limit: usize, pagination_size: usize) -> ApiFuture<'a, UserTracks> {
+        Box::pin(soundcloud::get_user_tracks(user_id, limit, pagination_size))
+    }
+
+    fn user_likes<'a>(&'a self, user_id: &'a str, limit: usize, pagination_size: usize, since: Option<DateTime<Utc>>) -> ApiFuture<'a, Vec<Like>> {
+        Box::pin(soundcloud::get_user_likes(user_id, limit, pagination_size, since))
+    }
+
+    fn user_followings<'a>(&'a self, user_id: &'a str, limit: Option<usize>) -> ApiFuture<'a, Vec<ApiUser>> {
+        Box::pin(soundcloud::get_user_followings(user_id, limit))
+    }
+}
+
+/// A [`SoundCloudApi`] that can be kept in shared settings, the live API by default
+#[derive(Clone)]
+pub struct SharedApi(pub Arc<dyn SoundCloudApi>);
+
+impl Default for SharedApi {
+    fn default() -> Self {
+        SharedApi(Arc::new(SoundCloudClient))
+    }
+}
+
+impl std::fmt::Debug for SharedApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedApi")
+    }
+}
+
+impl std::ops::Deref for SharedApi {
+    type Target = dyn SoundCloudApi;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+/// A user in a [`FixtureApi`]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FixtureUser {
+    /// The profile returned by `user_details`
+    pub details: Value,
+    pub tracks: Vec<Track>,
+    pub likes: Vec<Like>,
+    pub followings: Vec<ApiUser>,
+    /// Spotlight entries as the API returns them
+    pub spotlight: Vec<Value>,
+    /// Popular tracks as the API returns them
+    pub top_tracks: Vec<Value>,
+    /// Albums as the API returns them
+    pub albums: Vec<Value>,
+}
+
+/// SoundCloud data served from memory instead of the live API
+///
+/// Users that aren't in the fixture are reported as [`UserUnavailable`]. Track details
+/// come from `tracks`, falling back to the users' uploads and likes, and tracks found in
+/// neither are reported as deleted. Media needs no resolving. Can be loaded from a JSON
+/// file shaped like the struct:
+///
+/// ```json
+/// { "users": { "123": { "details": { "id": 123, "username": "someone" }, "tracks": [], "likes": [] } } }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FixtureApi {
+    pub users: HashMap<String, FixtureUser>,
+    pub tracks: HashMap<String, Track>,
+    /// Playlists as the API returns them, by ID or URL
+    pub playlists: HashMap<String, Value>,
+    /// Comments by track ID
+    pub comments: HashMap<String, Vec<Comment>>,
+}
+
+impl FixtureApi {
+    /// Load fixture data from a JSON file
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| format!("Failed to open fixture file {}: {}", path, e))?;
+        let fixture: FixtureApi = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| format!("Invalid fixture file {}: {}", path, e))?;
+        info!("Loaded SoundCloud fixture with {} users and {} tracks from {}", fixture.users.len(), fixture.tracks.len(), path);
+        Ok(fixture)
+    }
+
+    fn user(&self, user_id: &str) -> Result<&FixtureUser, Box<dyn std::error::Error + Send + Sync>> {
+        self.users.get(user_id)
+            .ok_or_else(|| Box::new(UserUnavailable { user_id: user_id.to_string(), status: 404 }).into())
+    }
+
+    fn find_track(&self, matches: impl Fn(&Track) -> bool) -> Option<&Track> {
+        self.tracks.values()
+            .chain(self.users.values().flat_map(|u| u.tracks.iter().chain(u.likes.iter().map(|l| &l.track))))
+            .find(|t| matches(t))
+    }
+}
+
+impl SoundCloudApi for FixtureApi {
+    fn initialize(&self) -> ApiFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn resolve_track<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, (String, Option<String>)> {
+        Box::pin(async move {
+            let url = id_or_url.split('?').next().unwrap_or(id_or_url);
+            match self.find_track(|t| t.id == id_or_url || t.permalink_url == url) {
+                Some(track) => Ok((track.id.clone(), track.secret_token.clone())),
+                None if !id_or_url.starts_with("http") => Ok((id_or_url.to_string(), None)),
+                None => Err(format!("No track in the fixture for {}", id_or_url).into()),
+            }
+        })
+    }
+
+    fn resolve_user_id<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, String> {
+        Box::pin(async move {
+            if !id_or_url.starts_with("http") {
+                return Ok(id_or_url.to_string());
+            }
+            self.users.iter()
+                .find(|(_, user)| user.details.get("permalink_url").and_then(|v| v.as_str()) == Some(id_or_url))
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| format!("No user in the fixture for {}", id_or_url).into())
+        })
+    }
+
+    fn track_details<'a>(&'a self, track_id: &'a str, _secret_token: Option<&'a str>) -> ApiFuture<'a, Track> {
+        Box::pin(async move {
+            self.find_track(|t| t.id == track_id)
+                .cloned()
+                .ok_or_else(|| format!("No track {} in the fixture", track_id).into())
+        })
+    }
+
+    fn tracks_details<'a>(&'a self, track_ids: &'a [String]) -> ApiFuture<'a, Vec<Track>> {
+        Box::pin(async move {
+            Ok(track_ids.iter().filter_map(|id| self.find_track(|t| &t.id == id).cloned()).collect())
+        })
+    }
+
+    fn resolve_track_media(&self, track: Track) -> ApiFuture<'_, Track> {
+        Box::pin(async move { Ok(track) })
+    }
+
+    fn track_exists<'a>(&'a self, track_id: &'a str) -> ApiFuture<'a, bool> {
+        Box::pin(async move { Ok(self.find_track(|t| t.id == track_id).is_some()) })
+    }
+
+    fn track_comments<'a>(&'a self, track_id: &'a str, _secret_token: Option<&'a str>) -> ApiFuture<'a, Vec<Comment>> {
+        Box::pin(async move { Ok(self.comments.get(track_id).cloned().unwrap_or_default()) })
+    }
+
+    fn playlist<'a>(&'a self, id_or_url: &'a str) -> ApiFuture<'a, Value> {
+        Box::pin(async move {
+            self.playlists.get(id_or_url)
+                .cloned()
+                .ok_or_else(|| format!("No playlist in the fixture for {}", id_or_url).into())
+        })
+    }
+
+    fn user_details<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Value> {
+        Box::pin(async move { Ok(self.user(user_id)?.details.clone()) })
+    }
+
+    fn user_banner_url<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(soundcloud::banner_url(&self.user(user_id)?.details)) })
+    }
+
+    fn user_spotlight<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(async move { Ok(self.user(user_id)?.spotlight.clone()) })
+    }
+
+    fn user_top_tracks<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(async move { Ok(self.user(user_id)?.top_tracks.clone()) })
+    }
+
+    fn user_albums<'a>(&'a self, user_id: &'a str) -> ApiFuture<'a, Vec<Value>> {
+        Box::pin(async move { Ok(self.user(user_id)?.albums.clone()) })
+    }
+
+    fn user_tracks<'a>(&'a self, user_id: &'a str, limit: usize, _pagination_size: usize) -> ApiFuture<'a, UserTracks> {
+        Box::pin(async move {
+            Ok(UserTracks { tracks: self.user(user_id)?.tracks.iter().take(limit).cloned().collect(), reused: false })
+        })
+    }
+
+    fn user_likes<'a>(&'a self, user_id: &'a str, limit: usize, _pagination_size: usize, since: Option<DateTime<Utc>>) -> ApiFuture<'a, Vec<Like>> {
+        Box::pin(async move {
+            Ok(self.user(user_id)?.likes.iter()
+                .take_while(|like| since.is_none_or(|since| like.liked_at().is_none_or(|at| at >= since)))
+                .take(limit)
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn user_followings<'a>(&'a self, user_id: &'a str, limit: Option<usize>) -> ApiFuture<'a, Vec<ApiUser>> {
+        Box::pin(async move {
+            Ok(self.user(user_id)?.followings.iter().take(limit.unwrap_or(usize::MAX)).cloned().collect())
+        })
+    }
+}
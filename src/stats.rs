@@ -0,0 +1,136 @@
+use std::collections::{BTreeSet, HashSet};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::db::{StatsSnapshot, TrackDatabase};
+
+const CHART_WIDTH: f64 = 800.0;
+const PANEL_HEIGHT: f64 = 150.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const TITLE_HEIGHT: f64 = 40.0;
+const PANEL_GAP: f64 = 30.0;
+
+/// A chart panel: label, line color and the count it shows
+type Panel = (&'static str, &'static str, fn(&StatsSnapshot) -> u64);
+
+/// Stats history of a track or of all of a user's tracks, ready to chart
+pub struct Series {
+    pub title: String,
+    pub snapshots: Vec<StatsSnapshot>,
+}
+
+/// Find the stats history for a track ID, or the combined history of a user's tracks
+pub fn series_for(db: &TrackDatabase, id: &str) -> Option<Series> {
+    let snapshots = db.track_stats(id);
+    if !snapshots.is_empty() {
+        let title = db.archived_track(id)
+            .map(|t| format!("{} - {}", t.username, t.title))
+            .unwrap_or_else(|| format!("Track {}", id));
+        return Some(Series { title, snapshots: snapshots.to_vec() });
+    }
+
+    let mut track_ids: HashSet<String> = db.find_tracks_by_user(id).into_iter().collect();
+    track_ids.extend(db.archived_tracks().iter()
+        .filter(|(_, t)| t.user_id == id)
+        .map(|(track_id, _)| track_id.clone()));
+    let snapshots = user_totals(db, &track_ids);
+    if snapshots.is_empty() {
+        return None;
+    }
+
+    let title = db.archived_tracks().values()
+        .find(|t| t.user_id == id)
+        .map(|t| format!("{} (all tracks)", t.username))
+        .unwrap_or_else(|| format!("User {} (all tracks)", id));
+    Some(Series { title, snapshots })
+}
+
+/// Sum the tracks' counts per day, carrying each track's last known counts forward
+fn user_totals(db: &TrackDatabase, track_ids: &HashSet<String>) -> Vec<StatsSnapshot> {
+    let histories: Vec<&[StatsSnapshot]> = track_ids.iter()
+        .map(|id| db.track_stats(id))
+        .filter(|s| !s.is_empty())
+        .collect();
+    let days: BTreeSet<NaiveDate> = histories.iter()
+        .flat_map(|h| h.iter().map(|s| s.at.date_naive()))
+        .collect();
+
+    days.into_iter()
+        .map(|day| {
+            let end_of_day = day.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc();
+            let mut total = StatsSnapshot { at: end_of_day, plays: 0, likes: 0, reposts: 0, comments: 0 };
+            for history in &histories {
+                if let Some(latest) = history.iter().rev().find(|s| s.at <= end_of_day) {
+                    total.plays += latest.plays;
+                    total.likes += latest.likes;
+                    total.reposts += latest.reposts;
+                    total.comments += latest.comments;
+                }
+            }
+            total
+        })
+        .collect()
+}
+
+/// Render plays, likes and reposts over time as an SVG with one panel each
+pub fn render_svg(series: &Series) -> String {
+    let panels: [Panel; 3] = [
+        ("Plays", "#f50", |s| s.plays),
+        ("Likes", "#e0245e", |s| s.likes),
+        ("Reposts", "#1da1f2", |s| s.reposts),
+    ];
+    let height = TITLE_HEIGHT + panels.len() as f64 * (PANEL_HEIGHT + PANEL_GAP);
+    let first = series.snapshots.first().map(|s| s.at).unwrap_or_else(Utc::now);
+    let last = series.snapshots.last().map(|s| s.at).unwrap_or_else(Utc::now);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n\
+         <text x=\"{x}\" y=\"24\" font-size=\"16\" font-weight=\"bold\">{title}</text>\n",
+        w = CHART_WIDTH, h = height, x = MARGIN_LEFT, title = escape(&series.title));
+
+    for (i, (label, color, value)) in panels.iter().enumerate() {
+        let top = TITLE_HEIGHT + i as f64 * (PANEL_HEIGHT + PANEL_GAP);
+        let bottom = top + PANEL_HEIGHT;
+        let values: Vec<u64> = series.snapshots.iter().map(value).collect();
+        let min = values.iter().copied().min().unwrap_or(0);
+        let max = values.iter().copied().max().unwrap_or(0).max(min + 1);
+
+        let points: Vec<String> = series.snapshots.iter().zip(&values)
+            .map(|(s, v)| format!("{:.1},{:.1}",
+                x_position(s.at, first, last),
+                bottom - (v - min) as f64 / (max - min) as f64 * PANEL_HEIGHT))
+            .collect();
+
+        svg.push_str(&format!(
+            "<text x=\"{x}\" y=\"{ty:.1}\" font-weight=\"bold\">{label}</text>\n\
+             <line x1=\"{x}\" y1=\"{bottom:.1}\" x2=\"{right}\" y2=\"{bottom:.1}\" stroke=\"#ccc\"/>\n\
+             <text x=\"{lx}\" y=\"{top_label:.1}\" text-anchor=\"end\">{max}</text>\n\
+             <text x=\"{lx}\" y=\"{bottom:.1}\" text-anchor=\"end\">{min}</text>\n\
+             <polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"2\" points=\"{points}\"/>\n",
+            x = MARGIN_LEFT, ty = top - 6.0, right = CHART_WIDTH - MARGIN_RIGHT, lx = MARGIN_LEFT - 8.0,
+            top_label = top + 10.0, bottom = bottom, label = label, max = max, min = min,
+            color = color, points = points.join(" ")));
+    }
+
+    svg.push_str(&format!(
+        "<text x=\"{x}\" y=\"{y}\">{first}</text>\n<text x=\"{right}\" y=\"{y}\" text-anchor=\"end\">{last}</text>\n</svg>\n",
+        x = MARGIN_LEFT, right = CHART_WIDTH - MARGIN_RIGHT, y = height - 8.0,
+        first = first.format("%Y-%m-%d"), last = last.format("%Y-%m-%d")));
+    svg
+}
+
+/// Horizontal position of a point in time between the first and last snapshot
+fn x_position(at: DateTime<Utc>, first: DateTime<Utc>, last: DateTime<Utc>) -> f64 {
+    let span = (last - first).num_seconds().max(1) as f64;
+    let width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    MARGIN_LEFT + (at - first).num_seconds() as f64 / span * width
+}
+
+/// Escape text for use in SVG
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
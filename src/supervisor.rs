@@ -0,0 +1,88 @@
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use log::{error, warn};
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Why a supervised task didn't produce a result
+#[derive(Debug, Clone)]
+pub enum TaskFailure {
+    /// The task panicked, with the panic message if one was available
+    Panicked(String),
+    /// The task was cancelled (e.g. the runtime is shutting down)
+    Cancelled,
+}
+
+impl fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskFailure::Panicked(msg) => write!(f, "task panicked: {}", msg),
+            TaskFailure::Cancelled => write!(f, "task was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for TaskFailure {}
+
+/// A spawned task whose panics are caught and reported as failures
+pub struct SupervisedTask<T> {
+    name: String,
+    handle: JoinHandle<T>,
+}
+
+impl<T> SupervisedTask<T> {
+    /// Abort the task
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Get a handle that can abort the task while it's being joined
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle.abort_handle()
+    }
+
+    /// Wait for the task to finish
+    ///
+    /// A panic is logged and counted as an error here rather than propagating, so
+    /// callers can treat it like any other failure (e.g. leave the track out of the
+    /// database so it's retried on the next poll) and the watcher keeps running.
+    pub async fn join(self) -> Result<T, TaskFailure> {
+        match self.handle.await {
+            Ok(value) => Ok(value),
+            Err(e) if e.is_panic() => {
+                let message = panic_message(e.into_panic());
+                error!("Task '{}' panicked: {}", self.name, message);
+                crate::metrics::increment_error_count();
+                crate::metrics::increment_task_panics();
+                Err(TaskFailure::Panicked(message))
+            }
+            Err(_) => {
+                warn!("Task '{}' was cancelled", self.name);
+                Err(TaskFailure::Cancelled)
+            }
+        }
+    }
+}
+
+/// Spawn a task under supervision
+pub fn spawn_supervised<F>(name: impl Into<String>, future: F) -> SupervisedTask<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    SupervisedTask {
+        name: name.into(),
+        handle: tokio::spawn(future),
+    }
+}
+
+/// Extract a readable message from a panic payload
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
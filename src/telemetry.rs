@@ -0,0 +1,61 @@
+use log::warn;
+
+use crate::config::Config;
+use crate::loghandler::BoxedLayer;
+
+#[cfg(feature = "otel")]
+static TRACER_PROVIDER: std::sync::OnceLock<opentelemetry_sdk::trace::SdkTracerProvider> = std::sync::OnceLock::new();
+
+/// Build the layer that exports tracing spans over OTLP, if `otlp_endpoint` is configured
+///
+/// The poll, fetch, download and Discord post stages are instrumented with spans, so
+/// per-track timings can be inspected in any OTLP-compatible tracing backend.
+/// Requires the `otel` cargo feature; otherwise this only warns if an endpoint is set.
+pub fn layer(config: &Config) -> Result<Option<BoxedLayer>, Box<dyn std::error::Error + Send + Sync>> {
+    let endpoint = match &config.otlp_endpoint {
+        Some(e) if !e.is_empty() => e,
+        _ => return Ok(None),
+    };
+
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::trace::TracerProvider;
+        use opentelemetry_otlp::WithExportConfig;
+        use tracing_subscriber::Layer;
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()?;
+
+        let resource = opentelemetry_sdk::Resource::builder()
+            .with_service_name(config.otlp_service_name.clone())
+            .build();
+
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build();
+
+        let tracer = provider.tracer("archiver_webhook");
+        let _ = TRACER_PROVIDER.set(provider);
+        log::info!("Exporting trace spans via OTLP to {} as service '{}'", endpoint, config.otlp_service_name);
+        Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer).boxed()))
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        warn!("otlp_endpoint is set to {} but this build doesn't include the 'otel' feature, spans will not be exported", endpoint);
+        Ok(None)
+    }
+}
+
+/// Flush any buffered spans to the OTLP endpoint
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            warn!("Failed to flush trace spans: {}", e);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+use log::{info, debug};
+use tokio::sync::Notify;
+
+/// Space in the temp directory taken by tracks being processed, and how much may be taken
+#[derive(Debug)]
+struct Usage {
+    /// Most bytes tracks in flight may reserve, 0 = no limit
+    ceiling: u64,
+    reserved: u64,
+}
+
+static USAGE: Mutex<Usage> = Mutex::new(Usage { ceiling: 0, reserved: 0 });
+static RELEASED: Notify = Notify::const_new();
+
+/// Rough size of a track's downloads per second of audio, all formats together (~384 kbps)
+const ESTIMATED_BYTES_PER_SECOND: u64 = 48_000;
+
+/// Limit how much of the temp directory tracks in flight may take, across the whole process
+///
+/// 0 removes the limit.
+pub fn set_ceiling(bytes: u64) {
+    USAGE.lock().unwrap().ceiling = bytes;
+    RELEASED.notify_waiters();
+    if bytes > 0 {
+        info!("Limiting temp directory usage to {} MB", bytes / 1024 / 1024);
+    }
+}
+
+/// Bytes of the temp directory currently reserved by tracks in flight
+pub fn reserved() -> u64 {
+    USAGE.lock().unwrap().reserved
+}
+
+/// Guess how much a track's downloads will take from its duration, at most `max_download_bytes`
+pub fn estimate(duration_ms: u64, max_download_bytes: Option<u64>) -> u64 {
+    let estimate = duration_ms / 1000 * ESTIMATED_BYTES_PER_SECOND;
+    max_download_bytes.map_or(estimate, |max| estimate.min(max))
+}
+
+/// Temp directory space held for one track, given back when dropped
+#[derive(Debug)]
+pub struct Reservation {
+    bytes: u64,
+}
+
+impl Reservation {
+    /// Wait until `bytes` more fit under the ceiling, then reserve them
+    ///
+    /// A track is always let through when nothing else is reserved, so one that's larger
+    /// than the ceiling on its own still gets processed.
+    pub async fn acquire(bytes: u64) -> Self {
+        let mut waited = false;
+        loop {
+            // Register interest before checking so a release can't be missed
+            let notified = RELEASED.notified();
+            {
+                let mut usage = USAGE.lock().unwrap();
+                if usage.ceiling == 0 || usage.reserved == 0 || usage.reserved.saturating_add(bytes) <= usage.ceiling {
+                    usage.reserved += bytes;
+                    return Reservation { bytes };
+                }
+                if !waited {
+                    debug!("Temp directory usage at {} of {} MB, waiting to process a track needing about {} MB",
+                           usage.reserved / 1024 / 1024, usage.ceiling / 1024 / 1024, bytes / 1024 / 1024);
+                    crate::metrics::increment_temp_space_waits();
+                    waited = true;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Replace the estimate with what the track's files actually take
+    ///
+    /// Going over the ceiling this way only holds back tracks that haven't started yet.
+    pub fn resize(&mut self, bytes: u64) {
+        let mut usage = USAGE.lock().unwrap();
+        usage.reserved = usage.reserved.saturating_sub(self.bytes) + bytes;
+        if bytes < self.bytes {
+            RELEASED.notify_waiters();
+        }
+        self.bytes = bytes;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let mut usage = USAGE.lock().unwrap();
+        usage.reserved = usage.reserved.saturating_sub(self.bytes);
+        drop(usage);
+        RELEASED.notify_waiters();
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+
+use crate::config::Config;
+
+/// Timestamp format used for log lines
+const LOG_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// The timezone times of day are shown and scheduled in
+///
+/// `Local` follows the host (and `TZ`), which in containers is usually UTC whatever the
+/// operator's own timezone is. A fixed offset doesn't follow daylight saving time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Timezone {
+    #[default]
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// Parse `local`, `UTC` or an offset from UTC like `+02:00`, `-0530` or `UTC+1`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid timezone '{}', use \"local\", \"UTC\" or an offset like \"+02:00\"", value);
+        let lower = value.trim().to_lowercase();
+        match lower.as_str() {
+            "" | "local" => return Ok(Timezone::Local),
+            "utc" | "gmt" | "z" => return Ok(Timezone::Fixed(FixedOffset::east_opt(0).expect("zero offset is valid"))),
+            _ => {}
+        }
+
+        let offset = lower.strip_prefix("utc").or_else(|| lower.strip_prefix("gmt")).unwrap_or(&lower);
+        let (sign, offset) = match offset.split_at_checked(1) {
+            Some(("+", rest)) => (1, rest),
+            Some(("-", rest)) => (-1, rest),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = match offset.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if offset.len() > 2 => offset.split_at(offset.len() - 2),
+            None => (offset, "0"),
+        };
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 14 || minutes > 59 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Timezone::Fixed)
+            .ok_or_else(invalid)
+    }
+
+    /// The configured `timezone`, or the host's if it isn't valid (which validation reports)
+    pub fn from_config(config: &Config) -> Self {
+        Timezone::parse(&config.timezone).unwrap_or_default()
+    }
+
+    /// The current time in this timezone
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.convert(Utc::now())
+    }
+
+    /// A point in time in this timezone
+    pub fn convert(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Timezone::Local => time.with_timezone(&Local).fixed_offset(),
+            Timezone::Fixed(offset) => time.with_timezone(offset),
+        }
+    }
+}
+
+impl std::fmt::Display for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timezone::Local => f.write_str("local"),
+            Timezone::Fixed(offset) if offset.local_minus_utc() == 0 => f.write_str("UTC"),
+            Timezone::Fixed(offset) => write!(f, "UTC{}", offset),
+        }
+    }
+}
+
+/// Log timestamps in the timezone
+impl FormatTime for Timezone {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        write!(w, "{}", self.now().format(LOG_TIME_FORMAT))
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+use log::{info, warn, debug};
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/scarchives/archiver_webhook/releases/latest";
+/// GitHub rejects API requests without a user agent
+const USER_AGENT: &str = concat!("archiver_webhook/", env!("CARGO_PKG_VERSION"), " ( https://github.com/scarchives/archiver_webhook )");
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How many changelog lines to show with a new release, and how long each may be
+const MAX_HIGHLIGHTS: usize = 5;
+const MAX_HIGHLIGHT_CHARS: usize = 200;
+
+/// The latest release, as returned by the GitHub API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl Release {
+    /// The release's version without the `v` prefix of its tag
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// The first bullet points of the release notes
+    pub fn highlights(&self) -> Vec<String> {
+        self.body.as_deref().unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")))
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| if line.chars().count() > MAX_HIGHLIGHT_CHARS {
+                format!("{}…", line.chars().take(MAX_HIGHLIGHT_CHARS).collect::<String>())
+            } else {
+                line.to_string()
+            })
+            .take(MAX_HIGHLIGHTS)
+            .collect()
+    }
+}
+
+/// Check for a newer release in the background if `update_check` is enabled
+///
+/// A newer release is logged as a warning with its changelog highlights and, with
+/// `alert_webhook_url` set, posted there too. Failures are only logged; the check never
+/// holds up startup.
+pub fn spawn_update_check(config: &Config) {
+    if !config.update_check {
+        return;
+    }
+    let alert_webhook_url = config.alert_webhook_url.clone().filter(|u| !u.is_empty());
+    tokio::spawn(async move {
+        let release = match get_latest_release().await {
+            Ok(release) => release,
+            Err(e) => {
+                warn!("Failed to check for a newer release: {}", e);
+                return;
+            }
+        };
+
+        let current = env!("CARGO_PKG_VERSION");
+        if !is_newer(release.version(), current) {
+            debug!("Running the latest release (v{}, latest is {})", current, release.tag_name);
+            return;
+        }
+
+        let highlights = release.highlights();
+        warn!("A newer release is available: {} (running v{}), see {}", release.tag_name, current, release.html_url);
+        for line in &highlights {
+            info!("  - {}", line);
+        }
+
+        if let Some(webhook_url) = alert_webhook_url {
+            if let Err(e) = crate::discord::send_update_notice(&webhook_url, &release, current, &highlights).await {
+                warn!("Failed to post the new release to the alert webhook: {}", e);
+            }
+        }
+    });
+}
+
+/// Fetch the latest release from GitHub
+async fn get_latest_release() -> Result<Release, Box<dyn std::error::Error + Send + Sync>> {
+    let response = Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?
+        .get(RELEASES_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(format!("GitHub returned HTTP {}", response.status()).into());
+    }
+    Ok(response.json::<Release>().await?)
+}
+
+/// Whether `latest` is a higher version than `current`
+///
+/// Versions are compared number by number (`0.10.0` is newer than `0.9.1`); anything after
+/// a `-` or `+` is ignored, and a version that isn't numeric is never newer.
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Option<Vec<u64>> {
+        version.split(['-', '+']).next()?
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect()
+    };
+    match (parse(latest), parse(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
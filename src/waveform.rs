@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+const IMAGE_WIDTH: f64 = 1800.0;
+const IMAGE_HEIGHT: f64 = 140.0;
+
+/// The waveform SoundCloud shows for a track, as served from its `waveform_url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waveform {
+    pub width: u32,
+    /// Largest possible sample value
+    pub height: u32,
+    /// One peak per column, from 0 to `height`
+    pub samples: Vec<u32>,
+}
+
+impl Waveform {
+    /// Render the waveform as an SVG, one bar per sample, the way SoundCloud draws it
+    pub fn render_svg(&self) -> String {
+        let max = self.height.max(1) as f64;
+        let step = IMAGE_WIDTH / self.samples.len().max(1) as f64;
+        let bars: String = self.samples.iter().enumerate()
+            .map(|(i, sample)| {
+                let top = IMAGE_HEIGHT - (*sample as f64).min(max) / max * IMAGE_HEIGHT;
+                format!("M{:.1} {:.1}V{:.1}", i as f64 * step + step / 2.0, IMAGE_HEIGHT, top)
+            })
+            .collect();
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n\
+             <path d=\"{bars}\" stroke=\"#f50\" stroke-width=\"{stroke:.2}\"/>\n\
+             </svg>\n",
+            w = IMAGE_WIDTH, h = IMAGE_HEIGHT, bars = bars, stroke = (step * 0.7).max(0.5))
+    }
+}